@@ -0,0 +1,92 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use mdbook::book::{Book, Chapter};
+use mdbook::preprocess::{Preprocessor, PreprocessorContext};
+use mdbook::Config;
+use mdbook_metadata::metadata::{metadata_to_html, parse_metadata, Metadata};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+mod synthetic;
+
+use synthetic::synthetic_chapter;
+
+/// Generates a flat book of `chapters` synthetic chapters.
+fn synthetic_book(chapters: usize, paragraphs: usize) -> Book {
+    let mut book = Book::new();
+    for i in 0..chapters {
+        book.push_item(Chapter::new(
+            &format!("Chapter {}", i),
+            synthetic_chapter(i, paragraphs),
+            format!("chapter-{}.md", i),
+            Vec::new(),
+        ));
+    }
+    book
+}
+
+fn context(book_toml: &str) -> PreprocessorContext {
+    let config = Config::from_str(book_toml).unwrap();
+    serde_json::from_value(serde_json::json!({
+        "root": ".",
+        "config": config,
+        "renderer": "html",
+        "mdbook_version": mdbook::MDBOOK_VERSION,
+    }))
+    .unwrap()
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_metadata");
+    for paragraphs in [10, 1_000, 10_000] {
+        let content = synthetic_chapter(0, paragraphs);
+        group.throughput(Throughput::Bytes(content.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(content.len()),
+            &content,
+            |b, content| b.iter(|| parse_metadata(black_box(content), true).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+fn bench_emit(c: &mut Criterion) {
+    let mut group = c.benchmark_group("metadata_to_html");
+    for keys in [5, 50, 500] {
+        let metadata: HashMap<String, String> = (0..keys)
+            .map(|i| (format!("key-{}", i), format!("<value> & \"{}\"", i)))
+            .collect();
+        let defaults = HashMap::from([("author".to_string(), "Jane Doe".to_string())]);
+        group.throughput(Throughput::Elements(keys as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(keys), &metadata, |b, m| {
+            b.iter(|| metadata_to_html(black_box(m), &defaults))
+        });
+    }
+    group.finish();
+}
+
+fn bench_run(c: &mut Criterion) {
+    let ctx = context(
+        r#"
+[preprocessor.metadata]
+default-author = "Jane Doe"
+
+[preprocessor.metadata.computed]
+full-title = "{title} | {author}"
+"#,
+    );
+    let preprocessor = Metadata::new(&ctx);
+
+    let mut group = c.benchmark_group("run");
+    group.sample_size(20);
+    for chapters in [10, 100, 1_000] {
+        let book = synthetic_book(chapters, 50);
+        group.throughput(Throughput::Elements(chapters as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(chapters), &book, |b, book| {
+            b.iter(|| preprocessor.run(&ctx, book.clone()).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse, bench_emit, bench_run);
+criterion_main!(benches);
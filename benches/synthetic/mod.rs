@@ -0,0 +1,20 @@
+//! Synthetic chapters shared by the benchmarks and the `synthetic_book`
+//! example.
+
+/// Generates a chapter with a metadata block followed by `paragraphs` paragraphs.
+pub fn synthetic_chapter(index: usize, paragraphs: usize) -> String {
+    let mut content = format!(
+        "---\ntitle: Chapter {index}\nauthor: Jane Doe\nkeywords: rust, mdbook, bench\n\
+         date: 2024-01-{day:02}\ndescription: Synthetic chapter {index}\n---\n\n# Chapter {index}\n\n",
+        index = index,
+        day = index % 28 + 1
+    );
+    for p in 0..paragraphs {
+        content.push_str(&format!(
+            "Paragraph {} of chapter {}. Lorem ipsum dolor sit amet, consectetur adipiscing \
+             elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.\n\n",
+            p, index
+        ));
+    }
+    content
+}
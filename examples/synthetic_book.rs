@@ -0,0 +1,51 @@
+//! Writes the synthetic book used by the benchmarks to a directory, to time
+//! `mdbook build` end to end or profile the preprocessor on it:
+//!
+//! ```sh
+//! cargo run --example synthetic_book -- target/synthetic-book 1000 50
+//! ```
+
+use std::fs;
+use std::path::PathBuf;
+
+#[path = "../benches/synthetic/mod.rs"]
+mod synthetic;
+
+use synthetic::synthetic_chapter;
+
+const BOOK_TOML: &str = r#"[book]
+title = "Synthetic book"
+
+[preprocessor.metadata]
+default-author = "Jane Doe"
+
+[preprocessor.metadata.computed]
+full-title = "{title} | {author}"
+"#;
+
+fn main() -> std::io::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let Some(dir) = args.next().map(PathBuf::from) else {
+        eprintln!("usage: synthetic_book <dir> [chapters] [paragraphs]");
+        std::process::exit(2);
+    };
+    let mut count = |default: usize| args.next().and_then(|a| a.parse().ok()).unwrap_or(default);
+    let chapters = count(100);
+    let paragraphs = count(50);
+
+    let src = dir.join("src");
+    fs::create_dir_all(&src)?;
+    fs::write(dir.join("book.toml"), BOOK_TOML)?;
+    let mut summary = String::from("# Summary\n\n");
+    for i in 0..chapters {
+        summary.push_str(&format!("- [Chapter {}](chapter-{}.md)\n", i, i));
+        fs::write(
+            src.join(format!("chapter-{}.md", i)),
+            synthetic_chapter(i, paragraphs),
+        )?;
+    }
+    fs::write(src.join("SUMMARY.md"), summary)?;
+
+    println!("Wrote {} chapters to {}", chapters, dir.display());
+    Ok(())
+}
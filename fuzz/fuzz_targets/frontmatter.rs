@@ -0,0 +1,24 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mdbook_metadata::metadata::{metadata_to_html, parse_metadata};
+use std::collections::HashMap;
+
+// Well-formed blocks with adversarial keys, values and body
+fuzz_target!(|input: (Vec<(String, String)>, String)| {
+    let (pairs, body) = input;
+    let mut content = String::from("---\n");
+    for (key, value) in &pairs {
+        content.push_str(&format!("{}: {}\n", key, value));
+    }
+    content.push_str("---\n");
+    content.push_str(&body);
+
+    if let Ok((metadata, _)) = parse_metadata(&content, true) {
+        let html = metadata_to_html(&metadata, &HashMap::new());
+        // Keys and values must never break out of the tag attributes
+        for line in html.lines().filter(|l| l.starts_with("<meta ")) {
+            assert_eq!(line.matches('"').count(), 4, "{}", line);
+        }
+    }
+});
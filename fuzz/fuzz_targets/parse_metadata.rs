@@ -0,0 +1,25 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mdbook_metadata::frontmatter;
+use mdbook_metadata::metadata::{metadata_to_html, parse_list, parse_metadata};
+use std::collections::HashMap;
+
+// Arbitrary bytes, as found in untrusted chapter sources
+fuzz_target!(|data: &[u8]| {
+    let Ok(content) = std::str::from_utf8(data) else {
+        return;
+    };
+    let _ = parse_metadata(content, false);
+    if let Ok((metadata, rest)) = parse_metadata(content, true) {
+        // Skipped comments are kept above the content after the closing fence
+        match frontmatter::find(content) {
+            Some(block) => assert!(rest.ends_with(content[block.end..].trim_start())),
+            None => assert_eq!(rest.as_ref(), content),
+        }
+        for value in metadata.values() {
+            parse_list(value);
+        }
+        metadata_to_html(&metadata, &HashMap::new());
+    }
+});
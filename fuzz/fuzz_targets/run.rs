@@ -0,0 +1,44 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mdbook::book::{Book, Chapter};
+use mdbook::preprocess::{Preprocessor, PreprocessorContext};
+use mdbook::Config;
+use mdbook_metadata::metadata::Metadata;
+use std::str::FromStr;
+
+const BOOK_TOML: &str = r#"
+[book]
+title = "Fuzz"
+
+[preprocessor.metadata]
+default-description = "{title | truncate(20)} - {book.title}"
+
+[preprocessor.metadata.computed]
+age-days = "now() - date"
+slug = "{title | slugify}"
+published = "{date | date(\"%B %Y\")}"
+
+[preprocessor.metadata.decorate]
+"#;
+
+// A whole preprocessor run over a book with one untrusted chapter
+fuzz_target!(|content: &str| {
+    let config = Config::from_str(BOOK_TOML).unwrap();
+    let ctx: PreprocessorContext = serde_json::from_value(serde_json::json!({
+        "root": ".",
+        "config": config,
+        "renderer": "html",
+        "mdbook_version": mdbook::MDBOOK_VERSION,
+    }))
+    .unwrap();
+
+    let mut book = Book::new();
+    book.push_item(Chapter::new(
+        "Chapter",
+        content.to_string(),
+        "chapter.md",
+        Vec::new(),
+    ));
+    let _ = Metadata::new(&ctx).run(&ctx, book);
+});
@@ -0,0 +1,177 @@
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use toml::value::Table;
+
+use crate::aggregate::relative_href;
+use crate::metadata::{parse_list, MetadataError};
+use crate::tags::Tag;
+
+/// The key holding a plain-language summary of a chapter's accessibility.
+pub const SUMMARY_KEY: &str = "a11y-summary";
+
+/// The key listing a chapter's schema.org `accessibilityFeature` values.
+pub const FEATURES_KEY: &str = "accessibility-features";
+
+/// The `accessibilityFeature` vocabulary of schema.org.
+const FEATURES: [&str; 41] = [
+    "alternativeText",
+    "annotations",
+    "ARIA",
+    "audioDescription",
+    "bookmarks",
+    "braille",
+    "captions",
+    "ChemML",
+    "closedCaptions",
+    "describedMath",
+    "displayTransformability",
+    "fullRubyAnnotations",
+    "highContrastAudio",
+    "highContrastDisplay",
+    "horizontalWriting",
+    "index",
+    "largePrint",
+    "latex",
+    "longDescription",
+    "MathML",
+    "none",
+    "openCaptions",
+    "pageBreakMarkers",
+    "pageNavigation",
+    "printPageNumbers",
+    "readingOrder",
+    "rubyAnnotations",
+    "signLanguage",
+    "structuralNavigation",
+    "synchronizedAudioText",
+    "tableOfContents",
+    "tactileGraphic",
+    "tactileObject",
+    "taggedPDF",
+    "timingControl",
+    "transcript",
+    "ttsMarkup",
+    "unlocked",
+    "verticalWriting",
+    "withAdditionalWordSegmentation",
+    "withoutAdditionalWordSegmentation",
+];
+
+/// Accessibility declarations (`[preprocessor.metadata.accessibility]`).
+#[derive(Debug, Clone)]
+pub struct Accessibility {
+    pub statement: Option<String>,
+}
+
+impl Accessibility {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            statement: table
+                .get("statement")
+                .and_then(|v| v.as_str())
+                .map(String::from),
+        }
+    }
+
+    /// Returns the link to the accessibility statement: a URL, or a chapter
+    /// path relative to `src`, linked relative to the chapter at `path`.
+    pub fn statement_tag(&self, path: &Path) -> Option<Tag> {
+        let statement = self.statement.as_ref()?;
+        let href = if statement.contains("://") {
+            statement.clone()
+        } else {
+            relative_href(path, Path::new(statement))
+        };
+        Some(Tag::link("accessibility-statement", &href))
+    }
+
+    /// Takes the accessibility keys out of `metadata`, returning their meta
+    /// tags and adding `accessibilitySummary` and `accessibilityFeature` to the
+    /// JSON-LD `properties`. Features are matched case-insensitively and
+    /// emitted in their schema.org spelling.
+    pub fn tags(
+        &self,
+        metadata: &mut HashMap<String, String>,
+        properties: &mut Map<String, Value>,
+    ) -> Result<Vec<Tag>, MetadataError> {
+        let mut tags = Vec::new();
+        if let Some(summary) = metadata.remove(SUMMARY_KEY) {
+            tags.push(Tag::meta(SUMMARY_KEY, &summary));
+            properties.insert("accessibilitySummary".to_string(), summary.into());
+        }
+        if let Some(value) = metadata.remove(FEATURES_KEY) {
+            let features = parse_list(&value)
+                .iter()
+                .map(|feature| {
+                    FEATURES
+                        .iter()
+                        .find(|f| f.eq_ignore_ascii_case(feature))
+                        .map(|f| f.to_string())
+                        .ok_or_else(|| MetadataError::InvalidValue {
+                            key: FEATURES_KEY.to_string(),
+                            message: format!(
+                                "'{}' isn't a schema.org accessibilityFeature",
+                                feature
+                            ),
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if !features.is_empty() {
+                tags.push(Tag::meta(FEATURES_KEY, &features.join(", ")));
+                properties.insert("accessibilityFeature".to_string(), features.into());
+            }
+        }
+        Ok(tags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::to_html;
+
+    #[test]
+    fn tags_normalize_features() {
+        let accessibility = Accessibility::from_config(
+            &toml::from_str("statement = \"about/accessibility.md\"").unwrap(),
+        );
+        let mut metadata = HashMap::from([
+            (
+                SUMMARY_KEY.to_string(),
+                "Diagrams have text alternatives.".to_string(),
+            ),
+            (
+                FEATURES_KEY.to_string(),
+                "[alternativetext, MathML]".to_string(),
+            ),
+        ]);
+        let mut properties = Map::new();
+
+        let tags = accessibility.tags(&mut metadata, &mut properties).unwrap();
+
+        assert!(metadata.is_empty());
+        assert_eq!(
+            to_html(&tags),
+            "<meta name=\"a11y-summary\" content=\"Diagrams have text alternatives.\">\n\
+             <meta name=\"accessibility-features\" content=\"alternativeText, MathML\">\n"
+        );
+        assert_eq!(
+            Value::Object(properties),
+            serde_json::json!({
+                "accessibilitySummary": "Diagrams have text alternatives.",
+                "accessibilityFeature": ["alternativeText", "MathML"]
+            })
+        );
+        assert_eq!(
+            accessibility
+                .statement_tag(Path::new("guide/intro.md"))
+                .unwrap()
+                .content,
+            "../about/accessibility.html"
+        );
+
+        let mut invalid = HashMap::from([(FEATURES_KEY.to_string(), "subtitles".to_string())]);
+        assert!(accessibility.tags(&mut invalid, &mut Map::new()).is_err());
+    }
+}
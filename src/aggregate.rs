@@ -0,0 +1,254 @@
+use mdbook::book::{Book, BookItem, Chapter};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use toml::Value;
+
+use crate::metadata::{parse_list, MetadataError};
+
+/// Metadata collected for a single chapter during the first pass over the book.
+#[derive(Debug, Clone)]
+pub struct ChapterEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub source_path: Option<PathBuf>,
+    pub metadata: HashMap<String, String>,
+}
+
+impl ChapterEntry {
+    pub fn new(chapter: &Chapter, path: &Path, metadata: HashMap<String, String>) -> Self {
+        Self {
+            name: chapter.name.clone(),
+            path: path.to_path_buf(),
+            source_path: chapter.source_path.clone(),
+            metadata,
+        }
+    }
+
+    /// The chapter title, preferring the `title` metadata key over the SUMMARY name.
+    pub fn title(&self) -> &str {
+        self.metadata
+            .get("title")
+            .map(String::as_str)
+            .unwrap_or(&self.name)
+    }
+}
+
+/// Resolves a reference to another chapter. A reference is either the chapter's
+/// path relative to the source directory (the `.md` extension is optional), the
+/// value of its `slug` metadata key, or its file name when that is unambiguous.
+pub fn resolve_reference<'a>(
+    chapters: &'a [ChapterEntry],
+    reference: &str,
+) -> Option<&'a ChapterEntry> {
+    let reference = reference.trim().trim_start_matches("./");
+    let path = if reference.ends_with(".md") {
+        PathBuf::from(reference)
+    } else {
+        PathBuf::from(format!("{}.md", reference))
+    };
+
+    chapters
+        .iter()
+        .find(|e| e.path == path || e.source_path.as_ref() == Some(&path))
+        .or_else(|| {
+            chapters
+                .iter()
+                .find(|e| e.metadata.get("slug").map(String::as_str) == Some(reference))
+        })
+        .or_else(|| {
+            let mut by_stem = chapters
+                .iter()
+                .filter(|e| e.path.file_stem().and_then(|s| s.to_str()) == Some(reference));
+            match (by_stem.next(), by_stem.next()) {
+                (Some(entry), None) => Some(entry),
+                _ => None,
+            }
+        })
+}
+
+/// Resolves every reference listed in `entry`'s `key`, or returns an error for
+/// each reference that doesn't resolve to a chapter of the book.
+pub fn resolve_references<'a>(
+    entry: &ChapterEntry,
+    key: &str,
+    chapters: &'a [ChapterEntry],
+) -> Result<Vec<&'a ChapterEntry>, Vec<MetadataError>> {
+    let mut resolved = Vec::new();
+    let mut errors = Vec::new();
+    for reference in entry
+        .metadata
+        .get(key)
+        .map(|v| parse_list(v))
+        .unwrap_or_default()
+    {
+        match resolve_reference(chapters, &reference) {
+            Some(target) => resolved.push(target),
+            None => errors.push(MetadataError::UnresolvedReference {
+                chapter: entry.name.clone(),
+                key: key.to_string(),
+                reference,
+            }),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(resolved)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Where a generated chapter is inserted among the book's top-level items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    Start,
+    End,
+    Index(usize),
+}
+
+impl Position {
+    pub fn from_toml(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(s) if s == "start" => Some(Position::Start),
+            Value::String(s) if s == "end" => Some(Position::End),
+            Value::Integer(i) if *i >= 0 => Some(Position::Index(*i as usize)),
+            _ => None,
+        }
+    }
+}
+
+/// Inserts a generated chapter into the book at the given position.
+pub fn insert_chapter(book: &mut Book, chapter: Chapter, position: Position) {
+    let index = match position {
+        Position::Start => 0,
+        Position::End => book.sections.len(),
+        Position::Index(i) => i.min(book.sections.len()),
+    };
+    book.sections.insert(index, BookItem::Chapter(chapter));
+}
+
+/// Builds a chapter that has no file on disk.
+pub fn generated_chapter(name: &str, content: String, path: &Path) -> Chapter {
+    let mut chapter = Chapter::new(name, content, path, Vec::new());
+    chapter.source_path = None;
+    chapter
+}
+
+/// Computes a markdown link from the chapter at `from` to the chapter at `to`,
+/// both relative to the book's source directory.
+pub fn relative_link(from: &Path, to: &Path) -> String {
+    let from_dir: Vec<Component> = from
+        .parent()
+        .map(|p| p.components().collect())
+        .unwrap_or_default();
+    let to_components: Vec<Component> = to.components().collect();
+
+    let common = from_dir
+        .iter()
+        .zip(to_components.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut parts: Vec<String> = vec!["..".to_string(); from_dir.len() - common];
+    parts.extend(
+        to_components[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+    );
+    parts.join("/").replace(' ', "%20")
+}
+
+/// Computes the `href` of the rendered HTML page of chapter `to`, relative to the
+/// rendered page of chapter `from`.
+pub fn relative_href(from: &Path, to: &Path) -> String {
+    relative_link(from, &to.with_extension("html"))
+}
+
+/// Builds the entry of a chapter named after its path, for tests.
+#[cfg(test)]
+pub(crate) fn entry(path: &str, metadata: &[(&str, &str)]) -> ChapterEntry {
+    named_entry(path, path, metadata)
+}
+
+/// Builds the entry of a chapter, for tests.
+#[cfg(test)]
+pub(crate) fn named_entry(name: &str, path: &str, metadata: &[(&str, &str)]) -> ChapterEntry {
+    let chapter = Chapter::new(name, String::new(), path, Vec::new());
+    ChapterEntry::new(&chapter, Path::new(path), self::metadata(metadata))
+}
+
+/// Builds a chapter's metadata map from key/value pairs, for tests.
+#[cfg(test)]
+pub(crate) fn metadata(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_link_between_directories() {
+        assert_eq!(
+            relative_link(Path::new("whats-new.md"), Path::new("a/b/c.md")),
+            "a/b/c.md"
+        );
+        assert_eq!(
+            relative_link(Path::new("a/x.md"), Path::new("a/b/c.md")),
+            "b/c.md"
+        );
+        assert_eq!(
+            relative_link(Path::new("d/e/x.md"), Path::new("a/c.md")),
+            "../../a/c.md"
+        );
+    }
+
+    #[test]
+    fn resolve_reference_by_path_slug_and_stem() {
+        let chapters = vec![
+            entry("guide/setup.md", &[("slug", "getting-started")]),
+            entry("guide/intro.md", &[]),
+            entry("reference/intro.md", &[]),
+        ];
+
+        let resolved = |r: &str| resolve_reference(&chapters, r).map(|e| e.path.clone());
+
+        assert_eq!(resolved("guide/setup.md"), Some("guide/setup.md".into()));
+        assert_eq!(resolved("./guide/setup"), Some("guide/setup.md".into()));
+        assert_eq!(resolved("getting-started"), Some("guide/setup.md".into()));
+        assert_eq!(resolved("setup"), Some("guide/setup.md".into()));
+        assert_eq!(
+            resolved("intro"),
+            None,
+            "Ambiguous file names don't resolve"
+        );
+        assert_eq!(resolved("missing"), None);
+    }
+
+    #[test]
+    fn insert_chapter_clamps_index() {
+        let mut book = Book::new();
+        insert_chapter(
+            &mut book,
+            generated_chapter("One", String::new(), Path::new("one.md")),
+            Position::Index(10),
+        );
+        insert_chapter(
+            &mut book,
+            generated_chapter("Zero", String::new(), Path::new("zero.md")),
+            Position::Start,
+        );
+
+        let names: Vec<_> = book
+            .iter()
+            .filter_map(|item| match item {
+                BookItem::Chapter(chap) => Some(chap.name.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, vec!["Zero", "One"]);
+    }
+}
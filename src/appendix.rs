@@ -0,0 +1,137 @@
+use mdbook::book::Chapter;
+use std::fmt::Write;
+use std::path::PathBuf;
+use toml::value::Table;
+
+use crate::aggregate::{generated_chapter, relative_link, ChapterEntry, Position};
+
+// Sorts the rows of the table by the text of a column when its header is
+// clicked, toggling the direction on every click
+const SORT_SCRIPT: &str = r#"<script>
+document.querySelectorAll(".metadata-appendix table").forEach((table) => {
+  table.querySelectorAll("th").forEach((th, column) => {
+    th.style.cursor = "pointer";
+    th.addEventListener("click", () => {
+      const body = table.tBodies[0];
+      const ascending = th.dataset.sort !== "asc";
+      table.querySelectorAll("th").forEach((other) => delete other.dataset.sort);
+      th.dataset.sort = ascending ? "asc" : "desc";
+      const text = (row) => row.cells[column].textContent.trim();
+      [...body.rows]
+        .sort((a, b) => text(a).localeCompare(text(b), undefined, { numeric: true }) * (ascending ? 1 : -1))
+        .forEach((row) => body.appendChild(row));
+    });
+  });
+});
+</script>"#;
+
+/// The generated appendix listing every chapter with some of its metadata, a
+/// table sortable by column (`appendix-columns`).
+#[derive(Debug, Clone)]
+pub struct Appendix {
+    pub columns: Vec<String>,
+    pub title: String,
+    pub path: PathBuf,
+    pub position: Position,
+}
+
+impl Appendix {
+    /// Reads the `appendix-*` options, returning `None` without
+    /// `appendix-columns`.
+    pub fn from_config(table: &Table) -> Option<Self> {
+        let columns: Vec<String> = table
+            .get("appendix-columns")?
+            .as_array()?
+            .iter()
+            .filter_map(|c| c.as_str().map(String::from))
+            .collect();
+        let string = |key: &str, default: &str| {
+            table
+                .get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or(default)
+                .to_string()
+        };
+        Some(Self {
+            columns,
+            title: string("appendix-title", "Chapter metadata"),
+            path: string("appendix-path", "metadata-appendix.md").into(),
+            position: table
+                .get("appendix-position")
+                .and_then(Position::from_toml)
+                .unwrap_or(Position::End),
+        })
+    }
+
+    /// Builds the appendix: a row per chapter, with the chapter's title linked
+    /// in the `title` column and its values in the others.
+    pub fn chapter(&self, chapters: &[ChapterEntry]) -> Chapter {
+        let cell = |value: &str| value.trim().replace('|', "\\|").replace('\n', " ");
+        let mut content = format!("# {}\n\n<div class=\"metadata-appendix\">\n\n", self.title);
+        let headers: Vec<String> = self.columns.iter().map(|c| header(c)).collect();
+        let _ = writeln!(content, "| {} |", headers.join(" | "));
+        let _ = writeln!(content, "|{}", "---|".repeat(self.columns.len()));
+        for entry in chapters {
+            let cells: Vec<String> = self
+                .columns
+                .iter()
+                .map(|column| match column.as_str() {
+                    "title" => format!(
+                        "[{}]({})",
+                        cell(entry.title()),
+                        relative_link(&self.path, &entry.path)
+                    ),
+                    key => entry.metadata.get(key).map(|v| cell(v)).unwrap_or_default(),
+                })
+                .collect();
+            let _ = writeln!(content, "| {} |", cells.join(" | "));
+        }
+        let _ = write!(content, "\n</div>\n\n{}\n", SORT_SCRIPT);
+        generated_chapter(&self.title, content, &self.path)
+    }
+}
+
+/// Returns the header of a column: `review-date` becomes `Review date`.
+fn header(column: &str) -> String {
+    let mut chars = column.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+        .unwrap_or_default()
+        .replace(['-', '_'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::named_entry;
+
+    #[test]
+    fn appendix_lists_every_chapter() {
+        let appendix = Appendix::from_config(
+            &toml::from_str("appendix-columns = [\"title\", \"owner\", \"status\"]").unwrap(),
+        )
+        .unwrap();
+        let chapters = vec![
+            named_entry(
+                "Intro",
+                "intro.md",
+                &[("owner", "docs"), ("status", "draft | wip")],
+            ),
+            named_entry("Retries", "guide/retries.md", &[("owner", "platform")]),
+        ];
+
+        let chapter = appendix.chapter(&chapters);
+
+        assert_eq!(chapter.path, Some(PathBuf::from("metadata-appendix.md")));
+        assert!(chapter.content.starts_with(
+            "# Chapter metadata\n\n<div class=\"metadata-appendix\">\n\n\
+             | Title | Owner | Status |\n\
+             |---|---|---|\n\
+             | [Intro](intro.md) | docs | draft \\| wip |\n\
+             | [Retries](guide/retries.md) | platform |  |\n\
+             \n</div>\n\n<script>"
+        ));
+        assert!(Appendix::from_config(&Table::new()).is_none());
+    }
+}
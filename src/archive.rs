@@ -0,0 +1,224 @@
+use chrono::{Datelike, NaiveDate};
+use mdbook::book::{BookItem, Chapter};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+use toml::value::Table;
+
+use crate::aggregate::{generated_chapter, relative_link, ChapterEntry, Position};
+use crate::dates::parse_date;
+
+/// How dated chapters are grouped into archive pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    Year,
+    Month,
+}
+
+/// Configuration of the generated archive chapters
+/// (`[preprocessor.metadata.archive]`).
+#[derive(Debug, Clone)]
+pub struct Archive {
+    pub title: String,
+    pub dir: PathBuf,
+    pub position: Position,
+    pub date_key: String,
+    pub granularity: Granularity,
+}
+
+impl Archive {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            title: table
+                .get("title")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Archive")
+                .to_string(),
+            dir: table
+                .get("path")
+                .and_then(|v| v.as_str())
+                .unwrap_or("archive")
+                .into(),
+            position: table
+                .get("position")
+                .and_then(Position::from_toml)
+                .unwrap_or(Position::End),
+            date_key: table
+                .get("date-key")
+                .and_then(|v| v.as_str())
+                .unwrap_or("date")
+                .to_string(),
+            granularity: match table.get("granularity").and_then(|v| v.as_str()) {
+                Some("year") => Granularity::Year,
+                _ => Granularity::Month,
+            },
+        }
+    }
+
+    /// Builds the archive index chapter, with one nested chapter per period.
+    pub fn chapter(&self, chapters: &[ChapterEntry]) -> Chapter {
+        let mut periods: BTreeMap<(i32, u32), Vec<(NaiveDate, &ChapterEntry)>> = BTreeMap::new();
+        for entry in chapters {
+            let Some(date) = entry
+                .metadata
+                .get(&self.date_key)
+                .and_then(|d| parse_date(d))
+            else {
+                continue;
+            };
+            let month = match self.granularity {
+                Granularity::Year => 0,
+                Granularity::Month => date.month(),
+            };
+            periods
+                .entry((date.year(), month))
+                .or_default()
+                .push((date, entry));
+        }
+
+        let index_path = self.dir.join("index.md");
+        let mut index_content = format!("# {}\n\n", self.title);
+        let mut sub_items = Vec::new();
+
+        for ((year, month), mut entries) in periods.into_iter().rev() {
+            let (name, path) = match self.granularity {
+                Granularity::Year => (year.to_string(), self.dir.join(year.to_string())),
+                Granularity::Month => (
+                    NaiveDate::from_ymd_opt(year, month, 1)
+                        .map(|d| d.format("%B %Y").to_string())
+                        .unwrap_or_default(),
+                    self.dir
+                        .join(year.to_string())
+                        .join(format!("{:02}", month)),
+                ),
+            };
+            let path = path.join("index.md");
+
+            entries.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.title().cmp(b.1.title())));
+            let mut content = format!("# {}\n\n", name);
+            for (date, entry) in &entries {
+                let _ = writeln!(
+                    content,
+                    "- {} — [{}]({})",
+                    date.format("%Y-%m-%d"),
+                    entry.title(),
+                    relative_link(&path, &entry.path)
+                );
+            }
+
+            let _ = writeln!(
+                index_content,
+                "- [{}]({}) ({})",
+                name,
+                relative_link(&index_path, &path),
+                entries.len()
+            );
+
+            let mut period = generated_chapter(&name, content, &path);
+            period.parent_names = vec![self.title.clone()];
+            sub_items.push(BookItem::Chapter(period));
+        }
+
+        let mut index = generated_chapter(&self.title, index_content, &index_path);
+        index.sub_items = sub_items;
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::named_entry;
+
+    fn entry(name: &str, path: &str, date: &str) -> ChapterEntry {
+        named_entry(name, path, &[("date", date)])
+    }
+
+    fn sub_chapters(chapter: &Chapter) -> Vec<&Chapter> {
+        chapter
+            .sub_items
+            .iter()
+            .filter_map(|item| match item {
+                BookItem::Chapter(chap) => Some(chap),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn archive_groups_by_month() {
+        let archive = Archive::from_config(&Table::new());
+        let chapters = vec![
+            entry("First", "blog/first.md", "2024-05-03"),
+            entry("Second", "blog/second.md", "2024-06-10"),
+            entry("Third", "blog/third.md", "2024-06-01"),
+        ];
+
+        let index = archive.chapter(&chapters);
+        let periods = sub_chapters(&index);
+
+        assert_eq!(index.path, Some(PathBuf::from("archive/index.md")));
+        assert_eq!(periods.len(), 2);
+        assert_eq!(periods[0].name, "June 2024");
+        assert_eq!(
+            periods[0].path,
+            Some(PathBuf::from("archive/2024/06/index.md"))
+        );
+        assert_eq!(
+            periods[0].content,
+            "# June 2024\n\n\
+             - 2024-06-10 — [Second](../../../blog/second.md)\n\
+             - 2024-06-01 — [Third](../../../blog/third.md)\n"
+        );
+        assert!(index
+            .content
+            .contains("- [June 2024](2024/06/index.md) (2)"));
+        assert!(index.content.contains("- [May 2024](2024/05/index.md) (1)"));
+    }
+
+    #[test]
+    fn archive_groups_by_year_with_custom_date_key() {
+        let mut table = Table::new();
+        table.insert("granularity".to_string(), "year".into());
+        table.insert("date-key".to_string(), "published".into());
+        let archive = Archive::from_config(&table);
+        let chapters = vec![
+            named_entry("Post", "post.md", &[("published", "2023-02-01")]),
+            entry("Ignored", "ignored.md", "2024-01-01"),
+        ];
+
+        let index = archive.chapter(&chapters);
+        let periods = sub_chapters(&index);
+
+        assert_eq!(periods.len(), 1);
+        assert_eq!(
+            periods[0].path,
+            Some(PathBuf::from("archive/2023/index.md"))
+        );
+        assert!(periods[0].content.contains("[Post](../../post.md)"));
+    }
+
+    #[test]
+    fn archive_pages_snapshot() {
+        let archive = Archive::from_config(&Table::new());
+        let chapters = vec![
+            entry("First", "blog/first.md", "2023-12-03"),
+            entry("Second", "blog/second.md", "2024-06-10"),
+            entry("Third", "blog/third.md", "2024-06-01"),
+        ];
+
+        let index = archive.chapter(&chapters);
+        let pages: Vec<String> = std::iter::once(&index)
+            .chain(sub_chapters(&index))
+            .map(|chap| {
+                format!(
+                    "=== {}\n{}",
+                    chap.path.as_ref().unwrap().display(),
+                    chap.content
+                )
+            })
+            .collect();
+
+        insta::assert_snapshot!(pages.join("\n"));
+    }
+}
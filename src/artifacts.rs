@@ -0,0 +1,37 @@
+use anyhow::Context;
+use log::info;
+use mdbook::errors::Error as MdBookError;
+use std::fs;
+use std::path::Path;
+
+/// Writes a generated file (graph exports, JSON data, ...) into the artifacts
+/// directory, creating it when needed.
+pub fn write_artifact(dir: &Path, name: &str, contents: &str) -> Result<(), MdBookError> {
+    let path = dir.join(name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+    }
+    fs::write(&path, contents)
+        .with_context(|| format!("Failed to write artifact '{}'", path.display()))?;
+    info!("Wrote artifact: {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_artifact_creates_missing_directories() {
+        let dir = std::env::temp_dir().join(format!("mdbook-metadata-{}", std::process::id()));
+
+        write_artifact(&dir, "nested/out.txt", "contents").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dir.join("nested/out.txt")).unwrap(),
+            "contents"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
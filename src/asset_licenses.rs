@@ -0,0 +1,168 @@
+use mdbook::book::Chapter;
+use serde::Serialize;
+use std::fmt::Write;
+use std::path::PathBuf;
+use toml::value::Table;
+
+use crate::aggregate::{generated_chapter, relative_link, ChapterEntry, Position};
+use crate::links::parse_maps;
+use crate::metadata::MetadataError;
+
+/// The key listing the third-party assets of a chapter.
+pub const ASSET_LICENSES_KEY: &str = "asset-licenses";
+
+/// Configuration of the generated "Third-party content" chapter and license
+/// manifest (`[preprocessor.metadata.asset-licenses]`).
+#[derive(Debug, Clone)]
+pub struct AssetLicenses {
+    pub title: String,
+    pub path: PathBuf,
+    pub position: Position,
+    pub file: String,
+}
+
+/// A third-party asset used by a chapter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AssetLicense {
+    pub file: String,
+    pub license: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attribution: Option<String>,
+    pub chapter: PathBuf,
+    #[serde(skip)]
+    pub chapter_title: String,
+}
+
+impl AssetLicenses {
+    pub fn from_config(table: &Table) -> Self {
+        let string = |key: &str, default: &str| {
+            table
+                .get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or(default)
+                .to_string()
+        };
+        Self {
+            title: string("title", "Third-party content"),
+            path: string("path", "third-party.md").into(),
+            position: table
+                .get("position")
+                .and_then(Position::from_toml)
+                .unwrap_or(Position::End),
+            file: string("file", "asset-licenses.json"),
+        }
+    }
+
+    /// Collects the `asset-licenses` entries of every chapter, in book order.
+    /// Entries need a `file` and a `license`; others are errors.
+    pub fn collect(&self, chapters: &[ChapterEntry]) -> (Vec<AssetLicense>, Vec<MetadataError>) {
+        let mut assets = Vec::new();
+        let mut errors = Vec::new();
+        for entry in chapters {
+            let Some(value) = entry.metadata.get(ASSET_LICENSES_KEY) else {
+                continue;
+            };
+            let maps = match parse_maps(ASSET_LICENSES_KEY, value) {
+                Ok(maps) => maps,
+                Err(e) => {
+                    errors.push(e);
+                    continue;
+                }
+            };
+            for mut map in maps {
+                let (Some(file), Some(license)) = (map.remove("file"), map.remove("license"))
+                else {
+                    errors.push(MetadataError::InvalidValue {
+                        key: ASSET_LICENSES_KEY.to_string(),
+                        message: format!(
+                            "every asset needs a file and a license ('{}')",
+                            entry.path.display()
+                        ),
+                    });
+                    continue;
+                };
+                assets.push(AssetLicense {
+                    file,
+                    license,
+                    attribution: map.remove("attribution"),
+                    chapter: entry.path.clone(),
+                    chapter_title: entry.title().to_string(),
+                });
+            }
+        }
+        (assets, errors)
+    }
+
+    /// Builds the "Third-party content" chapter, a table of the assets and the
+    /// chapters using them.
+    pub fn chapter(&self, assets: &[AssetLicense]) -> Chapter {
+        let mut content = format!("# {}\n", self.title);
+        if assets.is_empty() {
+            content.push_str("\nNo third-party content.\n");
+        } else {
+            content.push_str("\n| File | License | Attribution | Used in |\n|---|---|---|---|\n");
+            for asset in assets {
+                let cell = |value: &str| value.replace('|', "\\|");
+                let _ = writeln!(
+                    content,
+                    "| `{}` | {} | {} | [{}]({}) |",
+                    asset.file.replace('`', ""),
+                    cell(&asset.license),
+                    cell(asset.attribution.as_deref().unwrap_or("-")),
+                    cell(&asset.chapter_title),
+                    relative_link(&self.path, &asset.chapter)
+                );
+            }
+        }
+        generated_chapter(&self.title, content, &self.path)
+    }
+
+    /// Returns the machine-readable manifest of the assets.
+    pub fn artifact(&self, assets: &[AssetLicense]) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(assets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    #[test]
+    fn assets_are_listed_with_their_chapters() {
+        let asset_licenses = AssetLicenses::from_config(&Table::new());
+        let chapter = Chapter::new("Setup", String::new(), "guide/setup.md", Vec::new());
+        let metadata = HashMap::from([(
+            ASSET_LICENSES_KEY.to_string(),
+            "[{file: img/diagram.png, license: CC-BY-4.0, attribution: Jane Doe}, {file: logo.svg}]"
+                .to_string(),
+        )]);
+        let chapters = vec![ChapterEntry::new(
+            &chapter,
+            Path::new("guide/setup.md"),
+            metadata,
+        )];
+
+        let (assets, errors) = asset_licenses.collect(&chapters);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            asset_licenses.chapter(&assets).content,
+            "# Third-party content\n\n\
+             | File | License | Attribution | Used in |\n\
+             |---|---|---|---|\n\
+             | `img/diagram.png` | CC-BY-4.0 | Jane Doe | [Setup](guide/setup.md) |\n"
+        );
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&asset_licenses.artifact(&assets).unwrap())
+                .unwrap(),
+            serde_json::json!([{
+                "file": "img/diagram.png",
+                "license": "CC-BY-4.0",
+                "attribution": "Jane Doe",
+                "chapter": "guide/setup.md"
+            }])
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use toml::value::Table;
+
+use crate::metadata::{parse_list, MetadataError};
+use crate::tags::Tag;
+
+/// The key listing the audiences of a chapter.
+pub const AUDIENCE_KEY: &str = "audience";
+
+/// The key holding the reading level of a chapter.
+pub const READING_LEVEL_KEY: &str = "reading-level";
+
+/// Audience and reading-level declarations
+/// (`[preprocessor.metadata.audience]`).
+#[derive(Debug, Clone)]
+pub struct Audience {
+    pub audiences: Vec<String>,
+    pub reading_levels: Vec<String>,
+}
+
+impl Audience {
+    pub fn from_config(table: &Table) -> Self {
+        let strings = |key: &str| {
+            table
+                .get(key)
+                .and_then(|v| v.as_array())
+                .map(|values| {
+                    values
+                        .iter()
+                        .filter_map(|v| v.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        Self {
+            audiences: strings("audiences"),
+            reading_levels: strings("reading-levels"),
+        }
+    }
+
+    /// Takes the audience keys out of `metadata`, returning their meta tags and
+    /// adding `audience` and `educationalLevel` to the JSON-LD `properties`.
+    /// Values are checked against the allowed values, when configured.
+    pub fn tags(
+        &self,
+        metadata: &mut HashMap<String, String>,
+        properties: &mut Map<String, Value>,
+    ) -> Result<Vec<Tag>, MetadataError> {
+        let mut tags = Vec::new();
+        if let Some(value) = metadata.remove(AUDIENCE_KEY) {
+            let audiences = parse_list(&value)
+                .iter()
+                .map(|audience| allowed(AUDIENCE_KEY, audience, &self.audiences))
+                .collect::<Result<Vec<_>, _>>()?;
+            if !audiences.is_empty() {
+                tags.push(Tag::meta(AUDIENCE_KEY, &audiences.join(", ")));
+                let audience_type = match audiences.as_slice() {
+                    [audience] => Value::from(audience.as_str()),
+                    audiences => Value::from(audiences.to_vec()),
+                };
+                properties.insert(
+                    "audience".to_string(),
+                    serde_json::json!({ "@type": "Audience", "audienceType": audience_type }),
+                );
+            }
+        }
+        if let Some(value) = metadata.remove(READING_LEVEL_KEY) {
+            let level = allowed(READING_LEVEL_KEY, value.trim(), &self.reading_levels)?;
+            tags.push(Tag::meta(READING_LEVEL_KEY, &level));
+            properties.insert("educationalLevel".to_string(), level.into());
+        }
+        Ok(tags)
+    }
+}
+
+/// Returns the allowed spelling of `value`, matched case-insensitively. Any
+/// value is allowed when none are configured.
+fn allowed(key: &str, value: &str, values: &[String]) -> Result<String, MetadataError> {
+    if values.is_empty() {
+        return Ok(value.to_string());
+    }
+    values
+        .iter()
+        .find(|v| v.eq_ignore_ascii_case(value))
+        .cloned()
+        .ok_or_else(|| MetadataError::InvalidValue {
+            key: key.to_string(),
+            message: format!(
+                "'{}' isn't allowed, expected one of: {}",
+                value,
+                values.join(", ")
+            ),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::to_html;
+
+    #[test]
+    fn tags_validate_allowed_values() {
+        let audience = Audience::from_config(
+            &toml::from_str(
+                r#"
+audiences = ["developers", "administrators"]
+reading-levels = ["beginner", "advanced"]
+"#,
+            )
+            .unwrap(),
+        );
+        let mut metadata = HashMap::from([
+            (
+                AUDIENCE_KEY.to_string(),
+                "[Developers, administrators]".to_string(),
+            ),
+            (READING_LEVEL_KEY.to_string(), "Beginner".to_string()),
+        ]);
+        let mut properties = Map::new();
+
+        let tags = audience.tags(&mut metadata, &mut properties).unwrap();
+
+        assert_eq!(
+            to_html(&tags),
+            "<meta name=\"audience\" content=\"developers, administrators\">\n\
+             <meta name=\"reading-level\" content=\"beginner\">\n"
+        );
+        assert_eq!(
+            Value::Object(properties),
+            serde_json::json!({
+                "audience": {
+                    "@type": "Audience",
+                    "audienceType": ["developers", "administrators"]
+                },
+                "educationalLevel": "beginner"
+            })
+        );
+
+        let mut invalid = HashMap::from([(READING_LEVEL_KEY.to_string(), "expert".to_string())]);
+        assert!(audience.tags(&mut invalid, &mut Map::new()).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use toml::value::Table;
+
+use crate::metadata::{parse_list, MetadataError};
+use crate::tags;
+
+/// Keys holding URLs unless `url-keys` is configured.
+const DEFAULT_URL_KEYS: &[&str] = &[
+    "url",
+    "canonical",
+    "image",
+    "banner",
+    "og:url",
+    "og:image*",
+    "og:video*",
+    "og:audio*",
+    "twitter:image",
+    "citation_pdf_url",
+    "citation_abstract_html_url",
+];
+
+/// URL schemes rejected unless `blocked-schemes` is configured.
+const DEFAULT_BLOCKED_SCHEMES: &[&str] = &["javascript", "vbscript", "data"];
+
+/// Rejects suspicious metadata values before any tag is built, for books
+/// accepting chapters from outside contributors
+/// (`[preprocessor.metadata.audit]`): values with control characters, values
+/// longer than `max-length` characters, and URLs with a blocked scheme in
+/// the `url-keys`.
+#[derive(Debug, Clone)]
+pub struct Audit {
+    pub max_length: usize,
+    pub url_keys: Vec<String>,
+    pub blocked_schemes: Vec<String>,
+}
+
+impl Audit {
+    pub fn from_config(table: &Table) -> Self {
+        let strings = |key: &str, default: &[&str]| -> Vec<String> {
+            match table.get(key).and_then(|v| v.as_array()) {
+                Some(values) => values
+                    .iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect(),
+                None => default.iter().map(|s| s.to_string()).collect(),
+            }
+        };
+        Self {
+            max_length: table
+                .get("max-length")
+                .and_then(|v| v.as_integer())
+                .and_then(|n| usize::try_from(n).ok())
+                .unwrap_or(1000),
+            url_keys: strings("url-keys", DEFAULT_URL_KEYS),
+            blocked_schemes: strings("blocked-schemes", DEFAULT_BLOCKED_SCHEMES)
+                .into_iter()
+                .map(|scheme| scheme.to_lowercase())
+                .collect(),
+        }
+    }
+
+    /// Removes the values failing the audit from the metadata, returning an
+    /// error for each.
+    pub fn apply(&self, metadata: &mut HashMap<String, String>) -> Vec<MetadataError> {
+        let mut errors = Vec::new();
+        let mut keys: Vec<&String> = metadata.keys().collect();
+        keys.sort();
+        for key in keys {
+            if let Some(message) = self.check(key, &metadata[key]) {
+                errors.push(MetadataError::InvalidValue {
+                    key: key.clone(),
+                    message,
+                });
+            }
+        }
+        for e in &errors {
+            if let MetadataError::InvalidValue { key, .. } = e {
+                metadata.remove(key);
+            }
+        }
+        errors
+    }
+
+    /// Returns why a value fails the audit, if it does.
+    fn check(&self, key: &str, value: &str) -> Option<String> {
+        if let Some(c) = value.chars().find(|c| is_disallowed(*c)) {
+            return Some(format!("contains the control character U+{:04X}", c as u32));
+        }
+        let length = value.chars().count();
+        if length > self.max_length {
+            return Some(format!(
+                "is {} characters long, more than max-length ({})",
+                length, self.max_length
+            ));
+        }
+        if tags::matches_key(&self.url_keys, key) {
+            for url in parse_list(value) {
+                if let Some(scheme) = scheme(&url) {
+                    if self.blocked_schemes.contains(&scheme) {
+                        return Some(format!("uses the blocked URL scheme '{}:'", scheme));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Control characters other than line breaks and tabs, and the bidirectional
+/// overrides that make text display differently from how it reads.
+fn is_disallowed(c: char) -> bool {
+    (c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+        || matches!(c, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Returns the lowercase scheme of a URL, ignoring the whitespace browsers
+/// skip (`java\tscript:`).
+fn scheme(url: &str) -> Option<String> {
+    let url: String = url.chars().filter(|c| !c.is_whitespace()).collect();
+    let (scheme, _) = url.split_once(':')?;
+    let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    valid.then(|| scheme.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn audit_rejects_suspicious_values() {
+        let audit = Audit::from_config(&toml::from_str("max-length = 30").unwrap());
+        let mut metadata: HashMap<String, String> = [
+            ("title", "Intro"),
+            ("description", "Retries\u{0007}"),
+            ("keywords", "a rather long list of many keywords"),
+            ("og:image", "JavaScript:alert(1)"),
+            ("canonical", "https://example.com/"),
+            ("image", "[cover.png, \"data:,AAAA\"]"),
+            ("author", "java\tscript:not a url"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        let errors: Vec<String> = audit
+            .apply(&mut metadata)
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+
+        assert_eq!(
+            errors,
+            vec![
+                "Invalid value of 'description': contains the control character U+0007",
+                "Invalid value of 'image': uses the blocked URL scheme 'data:'",
+                "Invalid value of 'keywords': is 35 characters long, more than max-length (30)",
+                "Invalid value of 'og:image': uses the blocked URL scheme 'javascript:'",
+            ]
+        );
+        let mut kept: Vec<&str> = metadata.keys().map(String::as_str).collect();
+        kept.sort();
+        assert_eq!(kept, vec!["author", "canonical", "title"]);
+    }
+}
@@ -0,0 +1,142 @@
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use toml::value::Table;
+
+use crate::metadata::parse_list;
+
+/// The key listing the authors of a chapter.
+pub const AUTHOR_KEY: &str = "author";
+
+/// An author of the registry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Person {
+    pub name: String,
+    pub url: Option<String>,
+    pub same_as: Vec<String>,
+}
+
+impl Person {
+    /// Returns the schema.org `Person` of the author.
+    pub fn json_ld(&self) -> Value {
+        let mut person = Map::new();
+        person.insert("@type".to_string(), "Person".into());
+        person.insert("name".to_string(), self.name.as_str().into());
+        if let Some(url) = &self.url {
+            person.insert("url".to_string(), url.as_str().into());
+        }
+        if !self.same_as.is_empty() {
+            person.insert("sameAs".to_string(), self.same_as.clone().into());
+        }
+        Value::Object(person)
+    }
+}
+
+/// The author registry (`[preprocessor.metadata.authors.<id>]`), so chapters
+/// can name their authors by id and get full `Person` objects in JSON-LD.
+#[derive(Debug, Clone, Default)]
+pub struct Authors {
+    pub people: HashMap<String, Person>,
+}
+
+impl Authors {
+    pub fn from_config(table: &Table) -> Self {
+        let people = table
+            .iter()
+            .filter_map(|(id, author)| {
+                let author = author.as_table()?;
+                let name = author.get("name").and_then(|v| v.as_str()).unwrap_or(id);
+                let person = Person {
+                    name: name.to_string(),
+                    url: author.get("url").and_then(|v| v.as_str()).map(String::from),
+                    same_as: author
+                        .get("same-as")
+                        .and_then(|v| v.as_array())
+                        .map(|links| {
+                            links
+                                .iter()
+                                .filter_map(|l| l.as_str().map(String::from))
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                };
+                Some((id.clone(), person))
+            })
+            .collect();
+        Self { people }
+    }
+
+    /// Looks up an author by id or by name; unknown authors are a `Person`
+    /// with their name only.
+    pub fn person(&self, author: &str) -> Person {
+        self.people
+            .get(author)
+            .or_else(|| self.people.values().find(|p| p.name == author))
+            .cloned()
+            .unwrap_or_else(|| Person {
+                name: author.to_string(),
+                url: None,
+                same_as: Vec::new(),
+            })
+    }
+
+    /// Resolves the `author` ids of `metadata` to the authors' names, and adds
+    /// their `Person` objects as the JSON-LD `author` to `properties`.
+    pub fn apply(
+        &self,
+        metadata: &mut HashMap<String, String>,
+        properties: &mut Map<String, Value>,
+    ) {
+        let Some(value) = metadata.get(AUTHOR_KEY) else {
+            return;
+        };
+        let people: Vec<Person> = parse_list(value).iter().map(|a| self.person(a)).collect();
+        if people.is_empty() {
+            return;
+        }
+        let names: Vec<&str> = people.iter().map(|p| p.name.as_str()).collect();
+        metadata.insert(AUTHOR_KEY.to_string(), names.join(", "));
+        let author = match people.as_slice() {
+            [person] => person.json_ld(),
+            people => Value::from(people.iter().map(Person::json_ld).collect::<Vec<_>>()),
+        };
+        properties.insert("author".to_string(), author);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_resolves_registered_authors() {
+        let authors = Authors::from_config(
+            &toml::from_str(
+                r#"
+[jane]
+name = "Jane Doe"
+url = "https://jane.dev"
+same-as = ["https://github.com/jane", "https://mastodon.social/@jane"]
+"#,
+            )
+            .unwrap(),
+        );
+        let mut metadata = HashMap::from([(AUTHOR_KEY.to_string(), "jane, Sam".to_string())]);
+        let mut properties = Map::new();
+
+        authors.apply(&mut metadata, &mut properties);
+
+        assert_eq!(metadata[AUTHOR_KEY], "Jane Doe, Sam");
+        assert_eq!(
+            properties["author"],
+            serde_json::json!([
+                {
+                    "@type": "Person",
+                    "name": "Jane Doe",
+                    "url": "https://jane.dev",
+                    "sameAs": ["https://github.com/jane", "https://mastodon.social/@jane"]
+                },
+                { "@type": "Person", "name": "Sam" }
+            ])
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use toml::value::Table;
+
+use crate::metadata::MetadataError;
+use crate::template;
+
+/// Metadata keys handled by the banner instead of the generic emitter.
+pub const BANNER_KEYS: [&str; 2] = ["banner", "banner-alt"];
+
+const DEFAULT_TEMPLATE: &str = "<figure class=\"chapter-banner\"><img src=\"{src}\" alt=\"{alt}\" \
+                                style=\"display: block; width: 100%; height: auto;\" \
+                                fetchpriority=\"high\"></figure>";
+
+/// Renders the `banner` image of a chapter above its content, and uses it as
+/// the chapter's `og:image` (`[preprocessor.metadata.banner]`).
+#[derive(Debug, Clone)]
+pub struct Banner {
+    pub template: String,
+    pub og_image: bool,
+}
+
+impl Banner {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            template: table
+                .get("template")
+                .and_then(|v| v.as_str())
+                .unwrap_or(DEFAULT_TEMPLATE)
+                .to_string(),
+            og_image: table
+                .get("og-image")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true),
+        }
+    }
+
+    /// Renders the banner of a chapter with a `banner` key. The template can
+    /// use the chapter's metadata, `{src}` and `{alt}` (from `banner-alt`),
+    /// all escaped for HTML.
+    pub fn render(
+        &self,
+        metadata: &HashMap<String, String>,
+    ) -> Option<Result<String, MetadataError>> {
+        let src = metadata.get("banner")?.trim();
+        let alt = metadata.get("banner-alt").map_or("", |alt| alt.trim());
+        let mut vars = metadata.clone();
+        vars.insert("src".to_string(), src.to_string());
+        vars.insert("alt".to_string(), alt.to_string());
+        Some(template::render_html(&self.template, &vars))
+    }
+
+    /// Returns the `og:image` and `og:image:alt` values of a chapter's banner,
+    /// unless the chapter sets its own `og:image`. Relative banners are
+    /// resolved against the chapter's URL, so they need a `site-url`.
+    pub fn og_values(
+        &self,
+        metadata: &HashMap<String, String>,
+        url: Option<&str>,
+    ) -> Vec<(&'static str, String)> {
+        if !self.og_image || metadata.contains_key("og:image") {
+            return Vec::new();
+        }
+        let Some(image) = metadata
+            .get("banner")
+            .and_then(|src| absolute_url(src.trim(), url))
+        else {
+            return Vec::new();
+        };
+        let mut values = vec![("og:image", image)];
+        if let Some(alt) = metadata.get("banner-alt").filter(|a| !a.trim().is_empty()) {
+            values.push(("og:image:alt", alt.trim().to_string()));
+        }
+        values
+    }
+}
+
+/// Resolves a link against the URL of the page it is on, dropping `.` and
+/// `..` segments.
+fn absolute_url(link: &str, page: Option<&str>) -> Option<String> {
+    if link.starts_with("https://") || link.starts_with("http://") {
+        return Some(link.to_string());
+    }
+    let page = page?;
+    let (scheme, rest) = page.split_once("://")?;
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    if let Some(link) = link.strip_prefix("//") {
+        return Some(format!("{}://{}", scheme, link));
+    }
+    let mut segments: Vec<&str> = if link.starts_with('/') {
+        Vec::new()
+    } else {
+        let mut dir: Vec<&str> = path.split('/').collect();
+        dir.pop();
+        dir
+    };
+    for segment in link.trim_start_matches('/').split('/') {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    Some(format!("{}://{}/{}", scheme, host, segments.join("/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn banner_renders_and_feeds_og_image() {
+        let banner = Banner::from_config(
+            &toml::from_str(r#"template = "<img src=\"{src}\" alt=\"{alt}\">""#).unwrap(),
+        );
+        let metadata = HashMap::from([
+            ("banner".to_string(), "../img/hero.png".to_string()),
+            ("banner-alt".to_string(), "It's a \"hero\"".to_string()),
+        ]);
+
+        assert_eq!(
+            banner.render(&metadata).unwrap().unwrap(),
+            "<img src=\"../img/hero.png\" alt=\"It&#x27;s a &quot;hero&quot;\">"
+        );
+        assert_eq!(
+            banner.og_values(&metadata, Some("https://example.com/docs/guide/intro.html")),
+            vec![
+                (
+                    "og:image",
+                    "https://example.com/docs/img/hero.png".to_string()
+                ),
+                ("og:image:alt", "It's a \"hero\"".to_string()),
+            ]
+        );
+        assert!(banner.og_values(&metadata, None).is_empty());
+        assert_eq!(
+            absolute_url("/img/hero.png", Some("https://example.com/docs/intro.html")).unwrap(),
+            "https://example.com/img/hero.png"
+        );
+        assert!(banner.render(&HashMap::new()).is_none());
+    }
+}
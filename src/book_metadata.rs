@@ -0,0 +1,220 @@
+use mdbook::book::{Book, BookItem};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+pub use crate::aggregate::ChapterEntry;
+use crate::authors::AUTHOR_KEY;
+use crate::metadata::{parse_list, parse_metadata};
+
+/// Keys whose terms are indexed up front.
+pub const TAXONOMY_KEYS: [&str; 3] = ["tags", "keywords", "categories"];
+
+/// The terms of a taxonomy: chapter indexes keyed by lowercase term, with the
+/// first spelling seen.
+type Terms = BTreeMap<String, (String, Vec<usize>)>;
+
+/// The metadata of a whole book: every chapter's merged metadata in book
+/// order, indexed by path, with its taxonomies and authors. The preprocessor
+/// builds it once per run and every generated chapter and artifact reads it;
+/// other tools can build it from a [`Book`] with [`BookMetadata::from_book`].
+///
+/// It dereferences to the chapters, `&[ChapterEntry]`.
+#[derive(Debug, Clone, Default)]
+pub struct BookMetadata {
+    chapters: Vec<ChapterEntry>,
+    index: HashMap<PathBuf, usize>,
+    taxonomies: BTreeMap<&'static str, Terms>,
+    authors: BTreeMap<String, Vec<usize>>,
+}
+
+impl BookMetadata {
+    /// Indexes chapters given in book order.
+    pub fn new(chapters: Vec<ChapterEntry>) -> Self {
+        let index = chapters
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| (entry.path.clone(), i))
+            .collect();
+        let taxonomies = TAXONOMY_KEYS
+            .iter()
+            .map(|key| (*key, terms(&chapters, key)))
+            .collect();
+        let mut authors: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (i, entry) in chapters.iter().enumerate() {
+            for author in entry
+                .metadata
+                .get(AUTHOR_KEY)
+                .map(|v| parse_list(v))
+                .unwrap_or_default()
+            {
+                authors.entry(author).or_default().push(i);
+            }
+        }
+        Self {
+            chapters,
+            index,
+            taxonomies,
+            authors,
+        }
+    }
+
+    /// Reads the metadata blocks of the chapters of a book as they are,
+    /// without the configured rules, defaults or sidecar files.
+    pub fn from_book(book: &Book) -> Self {
+        let chapters = book
+            .iter()
+            .filter_map(|item| match item {
+                BookItem::Chapter(chapter) => {
+                    let path = chapter.path.as_ref()?;
+                    let metadata = parse_metadata(&chapter.content, true)
+                        .map(|(metadata, _)| metadata)
+                        .unwrap_or_default();
+                    Some(ChapterEntry::new(chapter, path, metadata))
+                }
+                _ => None,
+            })
+            .collect();
+        Self::new(chapters)
+    }
+
+    pub fn chapters(&self) -> &[ChapterEntry] {
+        &self.chapters
+    }
+
+    pub fn into_chapters(self) -> Vec<ChapterEntry> {
+        self.chapters
+    }
+
+    /// Returns the chapter at `path`, relative to the source directory.
+    pub fn get(&self, path: &Path) -> Option<&ChapterEntry> {
+        self.index.get(path).map(|&i| &self.chapters[i])
+    }
+
+    /// Returns the position of a chapter in the book, from 0.
+    pub fn position(&self, path: &Path) -> Option<usize> {
+        self.index.get(path).copied()
+    }
+
+    /// Returns the chapter after the one at `path`.
+    pub fn next(&self, path: &Path) -> Option<&ChapterEntry> {
+        self.chapters.get(self.position(path)? + 1)
+    }
+
+    /// Returns the chapters using each term of the `keys`, keyed by lowercase
+    /// term with its first spelling. Terms differing only in case are the
+    /// same, and chapters are listed once per term, in book order.
+    pub fn terms<K: AsRef<str>>(
+        &self,
+        keys: &[K],
+    ) -> BTreeMap<String, (String, Vec<&ChapterEntry>)> {
+        let mut merged: Terms = BTreeMap::new();
+        for key in keys {
+            let key = key.as_ref();
+            let terms = match self.taxonomies.get(key) {
+                Some(terms) => terms.clone(),
+                None => terms(&self.chapters, key),
+            };
+            for (term, (spelling, chapters)) in terms {
+                let (_, merged_chapters) = merged.entry(term).or_insert((spelling, Vec::new()));
+                merged_chapters.extend(chapters);
+            }
+        }
+        merged
+            .into_iter()
+            .map(|(term, (spelling, mut chapters))| {
+                chapters.sort_unstable();
+                chapters.dedup();
+                let chapters = chapters.into_iter().map(|i| &self.chapters[i]).collect();
+                (term, (spelling, chapters))
+            })
+            .collect()
+    }
+
+    /// Returns the chapters of each author, in book order.
+    pub fn authors(&self) -> BTreeMap<&str, Vec<&ChapterEntry>> {
+        self.authors
+            .iter()
+            .map(|(author, chapters)| {
+                let chapters = chapters.iter().map(|&i| &self.chapters[i]).collect();
+                (author.as_str(), chapters)
+            })
+            .collect()
+    }
+}
+
+impl Deref for BookMetadata {
+    type Target = [ChapterEntry];
+
+    fn deref(&self) -> &[ChapterEntry] {
+        &self.chapters
+    }
+}
+
+impl<'a> IntoIterator for &'a BookMetadata {
+    type Item = &'a ChapterEntry;
+    type IntoIter = std::slice::Iter<'a, ChapterEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chapters.iter()
+    }
+}
+
+fn terms(chapters: &[ChapterEntry], key: &str) -> Terms {
+    let mut terms: Terms = BTreeMap::new();
+    for (i, entry) in chapters.iter().enumerate() {
+        let Some(value) = entry.metadata.get(key) else {
+            continue;
+        };
+        for term in parse_list(value) {
+            let (_, indexes) = terms
+                .entry(term.to_lowercase())
+                .or_insert_with(|| (term.clone(), Vec::new()));
+            if indexes.last() != Some(&i) {
+                indexes.push(i);
+            }
+        }
+    }
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mdbook::book::Chapter;
+
+    #[test]
+    fn book_metadata_indexes_chapters() {
+        let mut book = Book::new();
+        for (name, path, content) in [
+            (
+                "Intro",
+                "intro.md",
+                "---\ntags: [Rust, cli]\nauthor: Jane\n---\n# Intro",
+            ),
+            (
+                "Retries",
+                "guide/retries.md",
+                "---\nkeywords: rust\nauthor: [Jane, Sam]\n---\n",
+            ),
+            ("Plain", "plain.md", "# Plain"),
+        ] {
+            book.push_item(Chapter::new(name, content.to_string(), path, Vec::new()));
+        }
+
+        let metadata = BookMetadata::from_book(&book);
+
+        assert_eq!(metadata.len(), 3);
+        assert_eq!(
+            metadata.next(Path::new("intro.md")).unwrap().name,
+            "Retries"
+        );
+        assert_eq!(metadata.position(Path::new("plain.md")), Some(2));
+        let terms = metadata.terms(&["tags", "keywords"]);
+        let (spelling, chapters) = &terms["rust"];
+        assert_eq!(spelling, "Rust");
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(metadata.authors()["Jane"].len(), 2);
+        assert_eq!(metadata.authors()["Sam"][0].name, "Retries");
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use crate::aggregate::ChapterEntry;
+use crate::metadata::MetadataError;
+use crate::tags::Tag;
+use crate::urls::Urls;
+
+/// The key grouping chapters that duplicate each other's content.
+pub const CANONICAL_GROUP_KEY: &str = "canonical-group";
+/// The key marking the primary chapter of a group.
+pub const CANONICAL_KEY: &str = "canonical";
+
+/// Maps every `canonical-group` to its primary chapter, the one with
+/// `canonical: true`. Groups without exactly one primary are errors, and left
+/// out.
+pub fn primaries(chapters: &[ChapterEntry]) -> (HashMap<String, PathBuf>, Vec<MetadataError>) {
+    let mut groups: BTreeMap<&str, Vec<&ChapterEntry>> = BTreeMap::new();
+    for entry in chapters {
+        if let Some(group) = entry.metadata.get(CANONICAL_GROUP_KEY) {
+            groups.entry(group.trim()).or_default().push(entry);
+        }
+    }
+
+    let mut primaries = HashMap::new();
+    let mut errors = Vec::new();
+    for (group, members) in groups {
+        let candidates: Vec<&&ChapterEntry> = members
+            .iter()
+            .filter(|e| e.metadata.get(CANONICAL_KEY).map(|v| v.trim()) == Some("true"))
+            .collect();
+        match candidates.as_slice() {
+            [primary] => {
+                primaries.insert(group.to_string(), primary.path.clone());
+            }
+            [] => errors.push(MetadataError::InvalidValue {
+                key: CANONICAL_GROUP_KEY.to_string(),
+                message: format!("group '{}' has no chapter with canonical: true", group),
+            }),
+            _ => errors.push(MetadataError::InvalidValue {
+                key: CANONICAL_GROUP_KEY.to_string(),
+                message: format!(
+                    "group '{}' has several chapters with canonical: true ({})",
+                    group,
+                    candidates
+                        .iter()
+                        .map(|e| e.path.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            }),
+        }
+    }
+    (primaries, errors)
+}
+
+/// Returns the canonical link of a chapter in a group, pointing at the
+/// group's primary, and a `robots` `noindex` tag unless it is the primary.
+pub fn canonical_tags(
+    entry: &ChapterEntry,
+    primaries: &HashMap<String, PathBuf>,
+    urls: &Urls,
+) -> Vec<Tag> {
+    let Some(primary) = entry
+        .metadata
+        .get(CANONICAL_GROUP_KEY)
+        .and_then(|group| primaries.get(group.trim()))
+    else {
+        return Vec::new();
+    };
+    let href = urls.url(primary).unwrap_or_else(|| urls.path(primary));
+    let mut tags = vec![Tag::link("canonical", &href)];
+    if *primary != entry.path {
+        tags.push(Tag::meta("robots", "noindex"));
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::entry;
+    use crate::tags::to_html;
+
+    #[test]
+    fn duplicates_point_at_the_primary_chapter() {
+        let chapters = vec![
+            entry(
+                "v2/install.md",
+                &[("canonical-group", "install"), ("canonical", "true")],
+            ),
+            entry("v1/install.md", &[("canonical-group", "install")]),
+            entry("v1/faq.md", &[("canonical-group", "faq")]),
+        ];
+        let urls =
+            Urls::from_config(&toml::from_str("site-url = \"https://example.com\"").unwrap());
+
+        let (primaries, errors) = primaries(&chapters);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            to_html(&canonical_tags(&chapters[1], &primaries, &urls)),
+            "<link rel=\"canonical\" href=\"https://example.com/v2/install.html\">\n\
+             <meta name=\"robots\" content=\"noindex\">\n"
+        );
+        assert_eq!(canonical_tags(&chapters[0], &primaries, &urls).len(), 1);
+        assert!(canonical_tags(&chapters[2], &primaries, &urls).is_empty());
+    }
+}
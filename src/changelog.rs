@@ -0,0 +1,233 @@
+use chrono::NaiveDate;
+use log::warn;
+use mdbook::book::Chapter;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use toml::value::Table;
+
+use crate::aggregate::{generated_chapter, relative_link, ChapterEntry, Position};
+use crate::dates::parse_date;
+use crate::whats_new::Change;
+
+/// Configuration of the generated documentation changelog, listing the
+/// chapters added or updated between two git refs
+/// (`[preprocessor.metadata.changelog]`).
+#[derive(Debug, Clone)]
+pub struct Changelog {
+    pub title: String,
+    pub path: PathBuf,
+    pub position: Position,
+    pub from: Option<String>,
+    pub to: String,
+    pub key: String,
+}
+
+impl Changelog {
+    pub fn from_config(table: &Table) -> Self {
+        let string = |key: &str| table.get(key).and_then(|v| v.as_str()).map(String::from);
+        Self {
+            title: string("title").unwrap_or_else(|| "Documentation changelog".to_string()),
+            path: string("path")
+                .unwrap_or_else(|| "changelog.md".to_string())
+                .into(),
+            position: table
+                .get("position")
+                .and_then(Position::from_toml)
+                .unwrap_or(Position::End),
+            from: string("from"),
+            to: string("to").unwrap_or_else(|| "HEAD".to_string()),
+            key: string("key").unwrap_or_else(|| "changelog".to_string()),
+        }
+    }
+
+    /// Builds the changelog chapter from the chapter files git reports as
+    /// added or modified between `from` (by default the latest tag) and `to`.
+    pub fn chapter(&self, chapters: &[ChapterEntry], src_dir: &Path) -> Chapter {
+        let from = self.from.clone().or_else(|| {
+            git(src_dir, &["describe", "--tags", "--abbrev=0", &self.to])
+                .map(|tag| tag.trim().to_string())
+        });
+        let Some(from) = from else {
+            warn!("The changelog needs a `from` ref or a git tag, leaving it empty");
+            return self.render(&HashMap::new(), chapters, None, |_| None);
+        };
+        let range = format!("{}..{}", from, self.to);
+        let changes = git(
+            src_dir,
+            &[
+                "diff",
+                "--relative",
+                "--name-status",
+                "--diff-filter=AMR",
+                &from,
+                &self.to,
+                "--",
+                ".",
+            ],
+        )
+        .map(|output| parse_name_status(&output))
+        .unwrap_or_default();
+        self.render(&changes, chapters, Some(&from), |path| {
+            git(
+                src_dir,
+                &[
+                    "log",
+                    "-1",
+                    "--format=%cs",
+                    &range,
+                    "--",
+                    &path.to_string_lossy(),
+                ],
+            )
+            .and_then(|date| parse_date(&date))
+        })
+    }
+
+    /// Renders the changed chapters, most recent first. Dates come from the
+    /// `date` (added) or `updated` (updated) metadata, else from `git_date`.
+    /// Chapters with `changelog: skip` are left out, and other values of the
+    /// key are shown as a note.
+    fn render(
+        &self,
+        changes: &HashMap<PathBuf, Change>,
+        chapters: &[ChapterEntry],
+        from: Option<&str>,
+        git_date: impl Fn(&Path) -> Option<NaiveDate>,
+    ) -> Chapter {
+        let mut listed: Vec<(Change, Option<NaiveDate>, &ChapterEntry)> = chapters
+            .iter()
+            .filter(|entry| entry.metadata.get(&self.key).map(|v| v.trim()) != Some("skip"))
+            .filter_map(|entry| {
+                let source = entry.source_path.as_ref()?;
+                let change = *changes.get(source)?;
+                let key = match change {
+                    Change::Added => "date",
+                    Change::Updated => "updated",
+                };
+                let date = entry
+                    .metadata
+                    .get(key)
+                    .and_then(|d| parse_date(d))
+                    .or_else(|| git_date(source));
+                Some((change, date, entry))
+            })
+            .collect();
+        listed.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.2.title().cmp(b.2.title())));
+
+        let mut content = format!("# {}\n", self.title);
+        if let Some(from) = from {
+            let _ = write!(content, "\nChanges from `{}` to `{}`.\n", from, self.to);
+        }
+        for (change, heading) in [(Change::Added, "Added"), (Change::Updated, "Updated")] {
+            let entries: Vec<_> = listed.iter().filter(|(c, _, _)| *c == change).collect();
+            if entries.is_empty() {
+                continue;
+            }
+            let _ = write!(content, "\n## {}\n\n", heading);
+            for (_, date, entry) in entries {
+                let _ = write!(
+                    content,
+                    "- [{}]({})",
+                    entry.title(),
+                    relative_link(&self.path, &entry.path)
+                );
+                if let Some(date) = date {
+                    let _ = write!(content, " — {}", date.format("%Y-%m-%d"));
+                }
+                if let Some(note) = entry
+                    .metadata
+                    .get(&self.key)
+                    .filter(|n| !n.trim().is_empty())
+                {
+                    let _ = write!(content, ": {}", note.trim());
+                }
+                content.push('\n');
+            }
+        }
+        if listed.is_empty() {
+            content.push_str("\nNo documentation changes.\n");
+        }
+
+        generated_chapter(&self.title, content, &self.path)
+    }
+}
+
+/// Maps the files of `git diff --name-status` output to their change. Renamed
+/// files are added under their new name.
+fn parse_name_status(output: &str) -> HashMap<PathBuf, Change> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let status = fields.next()?;
+            let path = fields.next_back()?;
+            let change = match status.chars().next()? {
+                'A' | 'R' => Change::Added,
+                'M' => Change::Updated,
+                _ => return None,
+            };
+            Some((PathBuf::from(path), change))
+        })
+        .collect()
+}
+
+/// Runs git in `dir`, returning its output, or `None` when it fails.
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| warn!("Failed to run git: {}", e))
+        .ok()?;
+    if !output.status.success() {
+        warn!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::named_entry;
+
+    #[test]
+    fn render_lists_added_and_updated_chapters() {
+        let changelog = Changelog::from_config(&Table::new());
+        let changes = parse_name_status(
+            "A\tguide/new.md\nM\tintro.md\nR100\told.md\tmoved.md\nM\ttypo.md\nD\tgone.md\n",
+        );
+        let chapters = vec![
+            named_entry("New", "guide/new.md", &[("date", "2024-06-01")]),
+            named_entry(
+                "Intro",
+                "intro.md",
+                &[("changelog", "Rewrote the overview")],
+            ),
+            named_entry("Moved", "moved.md", &[]),
+            named_entry("Typo", "typo.md", &[("changelog", "skip")]),
+            named_entry("Untouched", "untouched.md", &[]),
+        ];
+
+        let chapter = changelog.render(&changes, &chapters, Some("v1.0"), |path| {
+            (path == Path::new("intro.md")).then(|| NaiveDate::from_ymd_opt(2024, 6, 5).unwrap())
+        });
+
+        assert_eq!(
+            chapter.content,
+            "# Documentation changelog\n\n\
+             Changes from `v1.0` to `HEAD`.\n\n\
+             ## Added\n\n\
+             - [New](guide/new.md) — 2024-06-01\n\
+             - [Moved](moved.md)\n\n\
+             ## Updated\n\n\
+             - [Intro](intro.md) — 2024-06-05: Rewrote the overview\n"
+        );
+    }
+}
@@ -0,0 +1,288 @@
+use chrono::{Datelike, NaiveDate};
+use serde_json::{Map, Value};
+use std::fmt::Write;
+use toml::value::Table;
+
+use crate::aggregate::ChapterEntry;
+use crate::dates::parse_date;
+use crate::identifiers::{normalize_doi, normalize_isbn, normalize_issn};
+use crate::metadata::parse_list;
+use crate::urls::Urls;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// Citations of chapters (`[preprocessor.metadata.citations]`): a "Cite this
+/// page" block, and `citations.bib` and CSL-JSON exports of every chapter.
+#[derive(Debug, Clone)]
+pub struct Citations {
+    pub title: String,
+    pub block: bool,
+    pub bibtex_file: String,
+    pub csl_file: String,
+}
+
+/// The book-level values citations fall back to.
+#[derive(Debug, Clone, Copy)]
+pub struct BookInfo<'a> {
+    pub title: Option<&'a str>,
+    pub authors: &'a [String],
+    pub urls: &'a Urls,
+}
+
+/// The fields of a chapter's citation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Citation {
+    key: String,
+    title: String,
+    authors: Vec<String>,
+    date: Option<NaiveDate>,
+    url: Option<String>,
+    doi: Option<String>,
+    isbn: Option<String>,
+    issn: Option<String>,
+    container: Option<String>,
+}
+
+impl Citations {
+    pub fn from_config(table: &Table) -> Self {
+        let string = |key: &str, default: &str| {
+            table
+                .get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or(default)
+                .to_string()
+        };
+        Self {
+            title: string("title", "Cite this page"),
+            block: table.get("block").and_then(|v| v.as_bool()).unwrap_or(true),
+            bibtex_file: string("bibtex-file", "citations.bib"),
+            csl_file: string("csl-file", "citations.json"),
+        }
+    }
+
+    /// Returns the BibTeX entry of a chapter, or `None` with `cite: false`.
+    pub fn bibtex(&self, entry: &ChapterEntry, info: BookInfo) -> Option<String> {
+        let citation = citation(entry, info)?;
+        let mut fields = vec![("title", citation.title.clone())];
+        if !citation.authors.is_empty() {
+            fields.push(("author", citation.authors.join(" and ")));
+        }
+        if let Some(date) = citation.date {
+            fields.push(("year", date.year().to_string()));
+            fields.push(("month", MONTHS[date.month0() as usize].to_string()));
+        }
+        if let Some(container) = &citation.container {
+            fields.push(("howpublished", container.clone()));
+        }
+        if let Some(url) = &citation.url {
+            fields.push(("url", url.clone()));
+        }
+        if let Some(doi) = &citation.doi {
+            fields.push(("doi", doi.clone()));
+        }
+        if let Some(isbn) = &citation.isbn {
+            fields.push(("isbn", isbn.clone()));
+        }
+        if let Some(issn) = &citation.issn {
+            fields.push(("issn", issn.clone()));
+        }
+
+        let mut bibtex = format!("@misc{{{},\n", citation.key);
+        for (name, value) in fields {
+            // Months are BibTeX macros, left unbraced
+            let value = match name {
+                "month" => value,
+                "url" | "doi" => format!("{{{}}}", value.replace(['{', '}'], "")),
+                _ => format!("{{{}}}", escape(&value)),
+            };
+            let _ = writeln!(bibtex, "  {} = {},", name, value);
+        }
+        bibtex.push_str("}\n");
+        Some(bibtex)
+    }
+
+    /// Returns the CSL-JSON item of a chapter, or `None` with `cite: false`.
+    pub fn csl(&self, entry: &ChapterEntry, info: BookInfo) -> Option<Value> {
+        let citation = citation(entry, info)?;
+        let mut item = Map::new();
+        item.insert("id".to_string(), citation.key.into());
+        item.insert("type".to_string(), "webpage".into());
+        item.insert("title".to_string(), citation.title.into());
+        if !citation.authors.is_empty() {
+            let authors: Vec<Value> = citation
+                .authors
+                .iter()
+                .map(|name| serde_json::json!({ "literal": name }))
+                .collect();
+            item.insert("author".to_string(), authors.into());
+        }
+        if let Some(date) = citation.date {
+            item.insert(
+                "issued".to_string(),
+                serde_json::json!({ "date-parts": [[date.year(), date.month(), date.day()]] }),
+            );
+        }
+        if let Some(container) = citation.container {
+            item.insert("container-title".to_string(), container.into());
+        }
+        if let Some(url) = citation.url {
+            item.insert("URL".to_string(), url.into());
+        }
+        if let Some(doi) = citation.doi {
+            item.insert("DOI".to_string(), doi.into());
+        }
+        if let Some(isbn) = citation.isbn {
+            item.insert("ISBN".to_string(), isbn.into());
+        }
+        if let Some(issn) = citation.issn {
+            item.insert("ISSN".to_string(), issn.into());
+        }
+        Some(Value::Object(item))
+    }
+
+    /// Renders the "Cite this page" block of a chapter: its BibTeX entry in a
+    /// collapsed `<details>` element.
+    pub fn render(&self, entry: &ChapterEntry, info: BookInfo) -> Option<String> {
+        if !self.block {
+            return None;
+        }
+        let bibtex = self.bibtex(entry, info)?;
+        Some(format!(
+            "<details class=\"cite-this-page\">\n<summary>{}</summary>\n\n```bibtex\n{}```\n\n</details>\n",
+            html_escape::encode_text(&self.title),
+            bibtex
+        ))
+    }
+
+    /// Returns the `citations.bib` and CSL-JSON files of every chapter.
+    pub fn artifacts(
+        &self,
+        chapters: &[ChapterEntry],
+        info: BookInfo,
+    ) -> serde_json::Result<Vec<(&str, String)>> {
+        let bibtex: Vec<String> = chapters
+            .iter()
+            .filter_map(|entry| self.bibtex(entry, info))
+            .collect();
+        let csl: Vec<Value> = chapters
+            .iter()
+            .filter_map(|entry| self.csl(entry, info))
+            .collect();
+        Ok(vec![
+            (self.bibtex_file.as_str(), bibtex.join("\n")),
+            (self.csl_file.as_str(), serde_json::to_string_pretty(&csl)?),
+        ])
+    }
+}
+
+/// Collects the citation of a chapter. Authors default to the book's, dates
+/// come from `updated` or `date`, and the key from `slug` or the path. Invalid
+/// identifiers are left out.
+fn citation(entry: &ChapterEntry, info: BookInfo) -> Option<Citation> {
+    let get = |key: &str| {
+        entry
+            .metadata
+            .get(key)
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+    };
+    if get("cite") == Some("false") {
+        return None;
+    }
+    let key_source = get("slug")
+        .map(String::from)
+        .unwrap_or_else(|| entry.path.with_extension("").to_string_lossy().into_owned());
+    let key: String = key_source
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == ':' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let authors = match get("author") {
+        Some(author) => parse_list(author),
+        None => info.authors.to_vec(),
+    };
+    Some(Citation {
+        key,
+        title: entry.title().to_string(),
+        authors,
+        date: get("updated").or(get("date")).and_then(parse_date),
+        url: info.urls.url(&entry.path),
+        doi: get("doi").and_then(normalize_doi),
+        isbn: get("isbn").and_then(normalize_isbn),
+        issn: get("issn").and_then(normalize_issn),
+        container: info.title.map(String::from),
+    })
+}
+
+/// Escapes the characters BibTeX treats specially.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '&' | '%' | '$' | '#' | '_' | '{' | '}') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::named_entry;
+
+    #[test]
+    fn bibtex_and_csl_describe_the_chapter() {
+        let entry = named_entry(
+            "Intro",
+            "guide/intro.md",
+            &[
+                ("title", "Q&A on 100% coverage"),
+                ("author", "Jane Doe, Sam Roe"),
+                ("date", "2024-06-01"),
+                ("doi", "10.5281/zenodo.123"),
+            ],
+        );
+        let urls =
+            Urls::from_config(&toml::from_str("site-url = \"https://example.com\"").unwrap());
+        let info = BookInfo {
+            title: Some("Research Handbook"),
+            authors: &[],
+            urls: &urls,
+        };
+        let citations = Citations::from_config(&Table::new());
+
+        assert_eq!(
+            citations.bibtex(&entry, info).unwrap(),
+            "@misc{guide-intro,\n\
+             \x20 title = {Q\\&A on 100\\% coverage},\n\
+             \x20 author = {Jane Doe and Sam Roe},\n\
+             \x20 year = {2024},\n\
+             \x20 month = jun,\n\
+             \x20 howpublished = {Research Handbook},\n\
+             \x20 url = {https://example.com/guide/intro.html},\n\
+             \x20 doi = {10.5281/zenodo.123},\n\
+             }\n"
+        );
+        assert_eq!(
+            citations.csl(&entry, info).unwrap(),
+            serde_json::json!({
+                "id": "guide-intro",
+                "type": "webpage",
+                "title": "Q&A on 100% coverage",
+                "author": [{ "literal": "Jane Doe" }, { "literal": "Sam Roe" }],
+                "issued": { "date-parts": [[2024, 6, 1]] },
+                "container-title": "Research Handbook",
+                "URL": "https://example.com/guide/intro.html",
+                "DOI": "10.5281/zenodo.123"
+            })
+        );
+    }
+}
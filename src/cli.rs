@@ -1,7 +1,305 @@
-use clap::Command;
+use clap::{Arg, Command};
 
 pub const NAME: &str = "metadata-preprocessor";
 
 pub fn make_app() -> Command {
-    Command::new(NAME).about("An mdbook preprocessor that parses markdown metadata")
+    Command::new(NAME)
+        .about("An mdbook preprocessor that parses markdown metadata")
+        .arg(
+            Arg::new("offline")
+                .long("offline")
+                .action(clap::ArgAction::SetTrue)
+                .help("Use the cached remote metadata instead of fetching it"),
+        )
+        .arg(
+            Arg::new("strict")
+                .long("strict")
+                .action(clap::ArgAction::SetTrue)
+                .help("Fail on every problem, as with strict = true"),
+        )
+        .arg(
+            Arg::new("filter")
+                .long("filter")
+                .value_name("EXPRESSION")
+                .help("Build only the chapters matching a metadata filter, e.g. \"audience=public\""),
+        )
+        .arg(
+            Arg::new("explain")
+                .long("explain")
+                .action(clap::ArgAction::SetTrue)
+                .help("Print where each chapter's metadata and tags came from, as with explain = true"),
+        )
+        .subcommand(
+            Command::new("supports")
+                .arg(Arg::new("renderer").required(true))
+                .arg(
+                    Arg::new("book-dir")
+                        .long("book-dir")
+                        .default_value(".")
+                        .help("The book whose configuration to read"),
+                )
+                .about("Check whether a renderer is supported by this preprocessor"),
+        )
+        .subcommand(
+            Command::new("watch")
+                .arg(
+                    Arg::new("book-dir")
+                        .default_value(".")
+                        .help("Root directory of the book, containing book.toml"),
+                )
+                .arg(
+                    Arg::new("interval")
+                        .long("interval")
+                        .value_parser(clap::value_parser!(u64))
+                        .default_value("500")
+                        .help("Polling interval in milliseconds"),
+                )
+                .arg(
+                    Arg::new("error-format")
+                        .long("error-format")
+                        .value_parser(["human", "github"])
+                        .default_value("human")
+                        .help("Format of the reported problems"),
+                )
+                .about("Validate chapter metadata continuously while editing"),
+        )
+        .subcommand(
+            Command::new("check")
+                .arg(
+                    Arg::new("book-dir")
+                        .default_value(".")
+                        .help("Root directory of the book, containing book.toml"),
+                )
+                .arg(
+                    Arg::new("error-format")
+                        .long("error-format")
+                        .value_parser(["human", "github", "gitlab"])
+                        .default_value("human")
+                        .help("Format of the reported problems: lines, GitHub annotations or a GitLab code quality report"),
+                )
+                .about("Validate chapter metadata once, failing on any problem"),
+        )
+        .subcommand(
+            Command::new("lint")
+                .arg(
+                    Arg::new("book-dir")
+                        .default_value(".")
+                        .help("Root directory of the book, containing book.toml"),
+                )
+                .arg(
+                    Arg::new("error-format")
+                        .long("error-format")
+                        .value_parser(["human", "github", "gitlab"])
+                        .default_value("human")
+                        .help("Format of the reported problems: lines, GitHub annotations or a GitLab code quality report"),
+                )
+                .about("Check the style of metadata blocks: tabs, trailing whitespace and key order"),
+        )
+        .subcommand(
+            Command::new("export")
+                .arg(
+                    Arg::new("book-dir")
+                        .default_value(".")
+                        .help("Root directory of the book, containing book.toml"),
+                )
+                .about("Print the metadata of every chapter as JSON"),
+        )
+        .subcommand(
+            Command::new("diff")
+                .arg(
+                    Arg::new("old")
+                        .required(true)
+                        .help("Export JSON file, book directory or git revision"),
+                )
+                .arg(
+                    Arg::new("new")
+                        .required(true)
+                        .help("Export JSON file, book directory or git revision"),
+                )
+                .arg(
+                    Arg::new("book")
+                        .long("book")
+                        .default_value(".")
+                        .help("Book directory used to read git revisions"),
+                )
+                .about("Report added, removed and changed metadata keys per chapter"),
+        )
+        .subcommand(
+            Command::new("convert")
+                .arg(
+                    Arg::new("book-dir")
+                        .default_value(".")
+                        .help("Root directory of the book, containing book.toml"),
+                )
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .value_parser(["yaml", "toml"])
+                        .help("Only convert metadata in this format"),
+                )
+                .arg(
+                    Arg::new("to")
+                        .long("to")
+                        .required(true)
+                        .value_parser(["yaml", "toml"])
+                        .help("Format to write"),
+                )
+                .arg(
+                    Arg::new("storage")
+                        .long("storage")
+                        .value_parser(["inline", "sidecar"])
+                        .default_value("inline")
+                        .help("Write the metadata inline or to sidecar files"),
+                )
+                .about("Rewrite chapter metadata between formats and storages"),
+        )
+        .subcommand(
+            Command::new("set")
+                .arg(
+                    Arg::new("glob")
+                        .long("glob")
+                        .default_value("**")
+                        .help("Chapters to update, relative to the book directory"),
+                )
+                .arg(
+                    Arg::new("book")
+                        .long("book")
+                        .default_value(".")
+                        .help("Root directory of the book, containing book.toml"),
+                )
+                .arg(
+                    Arg::new("assignments")
+                        .required(true)
+                        .num_args(1..)
+                        .value_name("KEY=VALUE")
+                        .help("Keys to add or update"),
+                )
+                .about("Add or update metadata keys across matching chapters"),
+        )
+        .subcommand(
+            Command::new("import")
+                .arg(
+                    Arg::new("csv")
+                        .long("csv")
+                        .required(true)
+                        .help("CSV file with a header row"),
+                )
+                .arg(
+                    Arg::new("key")
+                        .long("key")
+                        .default_value("path")
+                        .help("Column with the chapter paths, relative to src"),
+                )
+                .arg(
+                    Arg::new("book")
+                        .long("book")
+                        .default_value(".")
+                        .help("Root directory of the book, containing book.toml"),
+                )
+                .arg(
+                    Arg::new("sidecar")
+                        .long("sidecar")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Write the keys to sidecar files instead of the chapters"),
+                )
+                .about("Apply the columns of a spreadsheet to the matching chapters"),
+        )
+        .subcommand(
+            Command::new("migrate-config")
+                .arg(
+                    Arg::new("book-dir")
+                        .default_value(".")
+                        .help("Root directory of the book, containing book.toml"),
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Print the changes without writing book.toml"),
+                )
+                .about("Rewrite renamed [preprocessor.metadata] options in book.toml"),
+        )
+        .subcommand(
+            Command::new("fix")
+                .arg(
+                    Arg::new("book-dir")
+                        .default_value(".")
+                        .help("Root directory of the book, containing book.toml"),
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("List the chapters to fix without writing them"),
+                )
+                .about("Rename legacy keys and fix the style problems reported by lint in chapter files"),
+        )
+        .subcommand(
+            Command::new("enrich-search")
+                .arg(
+                    Arg::new("book-dir")
+                        .default_value(".")
+                        .help("Root directory of the book, containing book.toml"),
+                )
+                .about("Add chapter metadata to the search index of a built book"),
+        )
+        .subcommand(
+            Command::new("report")
+                .arg(
+                    Arg::new("book-dir")
+                        .default_value(".")
+                        .help("Root directory of the book, containing book.toml"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["table", "json", "markdown"])
+                        .default_value("table")
+                        .help("Format of the report"),
+                )
+                .arg(
+                    Arg::new("max-age")
+                        .long("max-age")
+                        .value_parser(clap::value_parser!(i64))
+                        .help("Days after which a review is overdue (default: report.max-age, or 180)"),
+                )
+                .about("List the chapters that are unowned or overdue for review"),
+        )
+        .subcommand(
+            Command::new("owners")
+                .arg(
+                    Arg::new("book-dir")
+                        .default_value(".")
+                        .help("Root directory of the book, containing book.toml"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["codeowners", "json"])
+                        .default_value("codeowners")
+                        .help("Format of the mapping"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .help("File to write instead of printing the mapping"),
+                )
+                .about("Map chapter paths to their owner and team metadata, as CODEOWNERS or JSON"),
+        )
+        .subcommand(
+            Command::new("xgettext")
+                .arg(
+                    Arg::new("book-dir")
+                        .default_value(".")
+                        .help("Root directory of the book, containing book.toml"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .help("POT file to write (default: <po-dir>/metadata.pot)"),
+                )
+                .about("Extract translatable metadata values into a POT catalog"),
+        )
 }
@@ -0,0 +1,321 @@
+use log::{info, warn};
+use mdbook::book::{Book, BookItem, Chapter};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::data_attributes::CSS_CLASS_KEY;
+use crate::metadata::parse_list;
+use crate::tag_synonyms::TAG_KEYS;
+use crate::urls::Urls;
+
+/// Frontmatter conventions of another static site generator or editor, read
+/// as this crate's own (`compat = "jekyll"`, `"hugo"` or `"obsidian"`), for
+/// books migrated from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compat {
+    Jekyll,
+    Hugo,
+    /// Obsidian properties; wiki links in values are resolved separately, once
+    /// every chapter is read.
+    Obsidian,
+}
+
+/// What a chapter's compatibility keys mean for the book, beyond its metadata.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Conventions {
+    /// The chapter is left out of the book.
+    pub draft: bool,
+    /// Chapters are ordered by weight among their siblings, lightest first.
+    pub weight: Option<i64>,
+    /// Former URLs of the chapter, redirected to it.
+    pub aliases: Vec<String>,
+}
+
+impl Compat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "jekyll" => Some(Compat::Jekyll),
+            "hugo" => Some(Compat::Hugo),
+            "obsidian" => Some(Compat::Obsidian),
+            _ => None,
+        }
+    }
+
+    /// Keys renamed to this crate's, `(theirs, ours)`.
+    fn renames(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Compat::Jekyll => &[("excerpt", "description"), ("last_modified_at", "updated")],
+            Compat::Hugo => &[("summary", "description"), ("lastmod", "updated")],
+            Compat::Obsidian => &[("cssclasses", CSS_CLASS_KEY), ("cssclass", CSS_CLASS_KEY)],
+        }
+    }
+
+    /// Keys that only matter to the other generator's templates.
+    fn dropped(self) -> &'static [&'static str] {
+        match self {
+            Compat::Jekyll => &["layout"],
+            Compat::Hugo => &["layout", "type"],
+            Compat::Obsidian => &[],
+        }
+    }
+
+    /// Keys holding former URLs of the chapter.
+    fn alias_keys(self) -> &'static [&'static str] {
+        match self {
+            Compat::Jekyll => &["permalink", "redirect_from"],
+            Compat::Hugo => &["url", "aliases"],
+            // Obsidian aliases are other names of the note, for wiki links
+            Compat::Obsidian => &[],
+        }
+    }
+
+    /// Maps the keys of the other generator onto this crate's: renamed keys,
+    /// `categories` merged into `tags`, and the keys meaning drafts, weights
+    /// and aliases taken out of the metadata. Keys the chapter already sets
+    /// under this crate's name win.
+    pub fn apply(self, metadata: &mut HashMap<String, String>) -> Conventions {
+        for (theirs, ours) in self.renames() {
+            if let Some(value) = metadata.remove(*theirs) {
+                metadata.entry(ours.to_string()).or_insert(value);
+            }
+        }
+        for key in self.dropped() {
+            if metadata.remove(*key).is_some() {
+                info!("Ignoring the {:?} key '{}'", self, key);
+            }
+        }
+        let categories: Vec<String> = ["categories", "category"]
+            .iter()
+            .filter_map(|key| metadata.remove(*key))
+            .flat_map(|value| parse_list(&value))
+            .collect();
+        if !categories.is_empty() {
+            let key = TAG_KEYS[0].to_string();
+            let mut tags = metadata
+                .get(&key)
+                .map(|v| parse_list(v))
+                .unwrap_or_default();
+            for category in categories {
+                if !tags.contains(&category) {
+                    tags.push(category);
+                }
+            }
+            metadata.insert(key, format!("[{}]", tags.join(", ")));
+        }
+
+        let draft = match self {
+            Compat::Jekyll => metadata
+                .get("published")
+                .filter(|v| matches!(v.trim(), "true" | "false"))
+                .cloned()
+                .map(|v| {
+                    metadata.remove("published");
+                    v.trim() == "false"
+                }),
+            Compat::Hugo => metadata.remove("draft").map(|v| v.trim() == "true"),
+            Compat::Obsidian => metadata.remove("publish").map(|v| v.trim() == "false"),
+        };
+        let weight = ["weight", "nav_order"]
+            .iter()
+            .filter_map(|key| Some((*key, metadata.remove(*key)?)))
+            .find_map(|(key, value)| match value.trim().parse() {
+                Ok(weight) => Some(weight),
+                Err(_) => {
+                    warn!("Ignoring {} = '{}': expected an integer", key, value);
+                    None
+                }
+            });
+        let aliases = self
+            .alias_keys()
+            .iter()
+            .filter_map(|key| metadata.remove(*key))
+            .flat_map(|value| parse_list(&value))
+            .collect();
+        Conventions {
+            draft: draft.unwrap_or(false),
+            weight,
+            aliases,
+        }
+    }
+}
+
+/// Orders the chapters of the book by weight among their siblings, renumbering
+/// them. Chapters without a weight come after the others, in their order.
+/// Part titles and separators stay where they are.
+pub fn sort_by_weight(book: &mut Book, weights: &HashMap<PathBuf, i64>) {
+    sort_items(&mut book.sections, weights);
+}
+
+fn sort_items(items: &mut [BookItem], weights: &HashMap<PathBuf, i64>) {
+    for item in items.iter_mut() {
+        if let BookItem::Chapter(chapter) = item {
+            sort_items(&mut chapter.sub_items, weights);
+        }
+    }
+    let weight = |item: &BookItem| match item {
+        BookItem::Chapter(chapter) => chapter.path.as_ref().and_then(|p| weights.get(p)),
+        _ => None,
+    };
+    // Sorts the runs of chapters between part titles and separators, keeping
+    // numbered chapters apart from prefix and suffix ones
+    let mut start = 0;
+    while start < items.len() {
+        let Some(kind) = numbered(&items[start]) else {
+            start += 1;
+            continue;
+        };
+        let end = items[start..]
+            .iter()
+            .position(|item| numbered(item) != Some(kind))
+            .map_or(items.len(), |i| start + i);
+        let run = &mut items[start..end];
+        let numbers: Vec<_> = run.iter().map(chapter_number).collect();
+        run.sort_by_key(|item| (weight(item).is_none(), weight(item).copied()));
+        for (item, number) in run.iter_mut().zip(numbers) {
+            if let BookItem::Chapter(chapter) = item {
+                chapter.number = number;
+                renumber_children(chapter);
+            }
+        }
+        start = end;
+    }
+}
+
+fn numbered(item: &BookItem) -> Option<bool> {
+    match item {
+        BookItem::Chapter(chapter) => Some(chapter.number.is_some()),
+        _ => None,
+    }
+}
+
+fn chapter_number(item: &BookItem) -> Option<mdbook::book::SectionNumber> {
+    match item {
+        BookItem::Chapter(chapter) => chapter.number.clone(),
+        _ => None,
+    }
+}
+
+fn renumber_children(chapter: &mut Chapter) {
+    let Some(parent) = chapter.number.clone() else {
+        return;
+    };
+    for item in &mut chapter.sub_items {
+        if let BookItem::Chapter(child) = item {
+            if let Some(last) = child.number.as_ref().and_then(|n| n.last().copied()) {
+                let mut number = parent.clone();
+                number.push(last);
+                child.number = Some(number);
+                renumber_children(child);
+            }
+        }
+    }
+}
+
+/// Builds the `[output.html.redirect]` table sending the aliases of chapters
+/// to their pages, to paste into `book.toml`. Aliases ending with `/` are
+/// directory indexes, and ones without an extension get `.html`.
+pub fn redirects(aliases: &BTreeMap<PathBuf, Vec<String>>, urls: &Urls) -> String {
+    let mut table = toml::value::Table::new();
+    for (path, chapter_aliases) in aliases {
+        for alias in chapter_aliases {
+            let mut from = format!("/{}", alias.trim().trim_start_matches('/'));
+            if from.ends_with('/') {
+                from.push_str("index.html");
+            } else if Path::new(&from).extension().is_none() {
+                from.push_str(".html");
+            }
+            table.insert(from, toml::Value::String(urls.path(path)));
+        }
+    }
+    let mut output = toml::value::Table::new();
+    let mut html = toml::value::Table::new();
+    html.insert("redirect".to_string(), toml::Value::Table(table));
+    output.insert("html".to_string(), toml::Value::Table(html));
+    let mut root = toml::value::Table::new();
+    root.insert("output".to_string(), toml::Value::Table(output));
+    toml::to_string(&root).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::metadata;
+
+    #[test]
+    fn hugo_keys_map_to_conventions() {
+        let mut hugo = metadata(&[
+            ("title", "Intro"),
+            ("layout", "docs"),
+            ("draft", "true"),
+            ("weight", "20"),
+            ("aliases", "[/old/intro/, /intro.php]"),
+            ("categories", "[guides, rust]"),
+            ("tags", "[rust]"),
+            ("lastmod", "2024-05-01"),
+        ]);
+
+        let conventions = Compat::Hugo.apply(&mut hugo);
+
+        assert_eq!(
+            conventions,
+            Conventions {
+                draft: true,
+                weight: Some(20),
+                aliases: vec!["/old/intro/".to_string(), "/intro.php".to_string()],
+            }
+        );
+        assert_eq!(
+            hugo,
+            metadata(&[
+                ("title", "Intro"),
+                ("tags", "[rust, guides]"),
+                ("updated", "2024-05-01"),
+            ])
+        );
+
+        let mut jekyll = metadata(&[("published", "false"), ("permalink", "/intro")]);
+        let conventions = Compat::Jekyll.apply(&mut jekyll);
+        assert!(conventions.draft);
+        assert!(jekyll.is_empty());
+        assert_eq!(
+            redirects(
+                &BTreeMap::from([(PathBuf::from("guide/intro.md"), conventions.aliases)]),
+                &Urls::default()
+            ),
+            "[output.html.redirect]\n\"/intro.html\" = \"/guide/intro.html\"\n"
+        );
+    }
+
+    #[test]
+    fn chapters_are_sorted_by_weight() {
+        let chapter = |name: &str, number: u32| {
+            let mut chapter = Chapter::new(name, String::new(), format!("{}.md", name), Vec::new());
+            chapter.number = Some(mdbook::book::SectionNumber(vec![number]));
+            BookItem::Chapter(chapter)
+        };
+        let mut book = Book::new();
+        book.sections = vec![chapter("a", 1), chapter("b", 2), chapter("c", 3)];
+        let weights = HashMap::from([(PathBuf::from("c.md"), 1), (PathBuf::from("b.md"), 2)]);
+
+        sort_by_weight(&mut book, &weights);
+
+        let chapters: Vec<(String, String)> = book
+            .iter()
+            .filter_map(|item| match item {
+                BookItem::Chapter(chapter) => Some((
+                    chapter.name.clone(),
+                    chapter.number.as_ref().unwrap().to_string(),
+                )),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            chapters,
+            vec![
+                ("c".to_string(), "1.".to_string()),
+                ("b".to_string(), "2.".to_string()),
+                ("a".to_string(), "3.".to_string()),
+            ]
+        );
+    }
+}
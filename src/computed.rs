@@ -0,0 +1,123 @@
+use chrono::NaiveDate;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashMap;
+use toml::value::Table;
+
+use crate::dates::parse_date;
+use crate::metadata::MetadataError;
+use crate::script;
+use crate::template::{self, is_template};
+
+lazy_static! {
+    static ref DATE_DIFFERENCE_RE: Regex =
+        Regex::new(r"^\s*(now\(\)|[\w.-]+)\s*-\s*(now\(\)|[\w.-]+)\s*$").unwrap();
+}
+
+/// Derived keys defined in `[preprocessor.metadata.computed]`, evaluated per
+/// chapter after its metadata has been merged with the default values.
+#[derive(Debug, Clone, Default)]
+pub struct Computed {
+    pub keys: Vec<(String, String)>,
+}
+
+impl Computed {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            keys: table
+                .iter()
+                .filter(|(key, _)| key.as_str() != "enabled")
+                .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                .collect(),
+        }
+    }
+
+    /// Evaluates every computed key against `vars`, in key order. Each computed
+    /// value is added to `vars`, so later keys can refer to earlier ones.
+    ///
+    /// A definition is a template (`"{title} | {book.title}"`), a date
+    /// difference in days (`"now() - date"`) or an rhai expression
+    /// (`"status == \"draft\""`). Date differences whose operands aren't
+    /// dates, and expressions evaluating to `()`, are skipped.
+    pub fn evaluate(
+        &self,
+        vars: &mut HashMap<String, String>,
+        today: NaiveDate,
+    ) -> Result<Vec<(String, String)>, MetadataError> {
+        let mut computed = Vec::new();
+        for (key, definition) in &self.keys {
+            let value = match DATE_DIFFERENCE_RE.captures(definition) {
+                Some(caps) => {
+                    let operand = |name: &str| match name {
+                        "now()" => Some(today),
+                        key => vars.get(key).and_then(|v| parse_date(v)),
+                    };
+                    match (operand(&caps[1]), operand(&caps[2])) {
+                        (Some(a), Some(b)) => (a - b).num_days().to_string(),
+                        _ => continue,
+                    }
+                }
+                None if is_template(definition) => template::render(definition, vars)?,
+                None => match script::evaluate(definition, vars, today)? {
+                    Some(value) => value,
+                    None => continue,
+                },
+            };
+            vars.insert(key.clone(), value.clone());
+            computed.push((key.clone(), value));
+        }
+        Ok(computed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn computed(definitions: &[(&str, &str)]) -> Computed {
+        let mut table = Table::new();
+        for (key, value) in definitions {
+            table.insert(key.to_string(), value.to_string().into());
+        }
+        Computed::from_config(&table)
+    }
+
+    #[test]
+    fn evaluate_templates_and_date_differences() {
+        let computed = computed(&[
+            ("full-title", "{title} | {book.title}"),
+            ("age-days", "now() - date"),
+            ("shouty-title", "{full-title | upper}"),
+            ("noindex", "status == \"draft\" || date > now()"),
+        ]);
+        let mut vars = HashMap::from([
+            ("title".to_string(), "Intro".to_string()),
+            ("date".to_string(), "2024-06-01".to_string()),
+            ("status".to_string(), "draft".to_string()),
+            ("book.title".to_string(), "Guide".to_string()),
+        ]);
+        let today = NaiveDate::from_ymd_opt(2024, 6, 11).unwrap();
+
+        let values = computed.evaluate(&mut vars, today).unwrap();
+
+        assert_eq!(
+            values,
+            vec![
+                ("age-days".to_string(), "10".to_string()),
+                ("full-title".to_string(), "Intro | Guide".to_string()),
+                ("noindex".to_string(), "true".to_string()),
+                ("shouty-title".to_string(), "INTRO | GUIDE".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn evaluate_skips_date_differences_without_dates() {
+        let computed = computed(&[("age-days", "now() - date")]);
+        let today = NaiveDate::from_ymd_opt(2024, 6, 11).unwrap();
+
+        let values = computed.evaluate(&mut HashMap::new(), today).unwrap();
+
+        assert!(values.is_empty());
+    }
+}
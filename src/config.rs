@@ -0,0 +1,645 @@
+use anyhow::{bail, Context, Result};
+use lazy_static::lazy_static;
+use log::warn;
+use mdbook::Config;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::value::Table;
+use toml::Value;
+
+lazy_static! {
+    static ref HEADER_RE: Regex = Regex::new(r"^(\s*\[\[?\s*)([^\]]+?)(\s*\]\]?.*)$").unwrap();
+    static ref KEY_RE: Regex = Regex::new(r#"^(\s*)("?)([A-Za-z0-9_-]+)("?)(\s*=.*)$"#).unwrap();
+}
+
+/// The TOML path of the preprocessor's configuration.
+const PREFIX: &str = "preprocessor.metadata";
+
+/// The expected type of an option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expected {
+    Bool,
+    Integer,
+    String,
+    Strings,
+    StringOrStrings,
+    StringOrInteger,
+    Table,
+    Tables,
+}
+
+impl Expected {
+    fn matches(self, value: &Value) -> bool {
+        let strings = |v: &Value| {
+            v.as_array()
+                .is_some_and(|items| items.iter().all(Value::is_str))
+        };
+        match self {
+            Expected::Bool => value.is_bool(),
+            Expected::Integer => value.is_integer(),
+            Expected::String => value.is_str(),
+            Expected::Strings => strings(value),
+            Expected::StringOrStrings => value.is_str() || strings(value),
+            Expected::StringOrInteger => value.is_str() || value.is_integer(),
+            Expected::Table => value.is_table(),
+            Expected::Tables => value
+                .as_array()
+                .is_some_and(|items| items.iter().all(Value::is_table)),
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Expected::Bool => "a boolean",
+            Expected::Integer => "an integer",
+            Expected::String => "a string",
+            Expected::Strings => "an array of strings",
+            Expected::StringOrStrings => "a string or an array of strings",
+            Expected::StringOrInteger => "a string or an integer",
+            Expected::Table => "a table",
+            Expected::Tables => "an array of tables",
+        }
+    }
+}
+
+/// Options of `[preprocessor.metadata]`, besides the `default-*` values.
+const OPTIONS: &[(&str, Expected)] = &[
+    // Read by mdBook itself
+    ("command", Expected::String),
+    ("renderers", Expected::Strings),
+    ("before", Expected::Strings),
+    ("after", Expected::Strings),
+    ("optional", Expected::Bool),
+    // Read by the preprocessor
+    ("valid-tags", Expected::Strings),
+    ("required-tags", Expected::Strings),
+    ("vocab", Expected::Strings),
+    ("per-renderer", Expected::Table),
+    ("continue-on-error", Expected::Bool),
+    ("strict", Expected::Bool),
+    ("filter", Expected::String),
+    ("explain", Expected::Bool),
+    ("compat", Expected::String),
+    ("book-defaults", Expected::Bool),
+    ("artifacts-dir", Expected::String),
+    ("tags-manifest", Expected::Bool),
+    ("chapter-position", Expected::Bool),
+    ("rules", Expected::Tables),
+    ("profiles", Expected::Tables),
+    ("include", Expected::StringOrStrings),
+    ("exclude", Expected::StringOrStrings),
+    ("opt-out", Expected::String),
+    ("raw-keys", Expected::Strings),
+    ("sanitize-values", Expected::Bool),
+    ("property-keys", Expected::Strings),
+    ("fediverse-creator", Expected::String),
+    ("meta-version", Expected::Integer),
+    ("hook", Expected::StringOrStrings),
+    ("hook-timeout", Expected::Integer),
+    ("plugins", Expected::Tables),
+    ("site-url", Expected::String),
+    ("path-prefix", Expected::String),
+    ("trailing-slash", Expected::String),
+    ("extends", Expected::StringOrStrings),
+    ("search-export", Expected::String),
+    ("stale-after-days", Expected::Integer),
+    ("stale-template", Expected::String),
+    ("frontmatter-prefixes", Expected::Strings),
+    ("appendix-columns", Expected::Strings),
+    ("appendix-title", Expected::String),
+    ("appendix-path", Expected::String),
+    ("appendix-position", Expected::StringOrInteger),
+];
+
+/// Optional features, configured in their own tables.
+const FEATURES: &[&str] = &[
+    "whats-new",
+    "archive",
+    "glossary",
+    "prerequisites",
+    "see-also",
+    "graph",
+    "learning",
+    "computed",
+    "decorate",
+    "sidebar",
+    "theme-data",
+    "parts",
+    "overlay",
+    "verification",
+    "sitemap",
+    "open-graph",
+    "hints",
+    "consent",
+    "accessibility",
+    "audience",
+    "remote",
+    "algolia",
+    "search-index",
+    "report",
+    "changelog",
+    "authors",
+    "citations",
+    "emit",
+    "nav-groups",
+    "asset-licenses",
+    "gettext",
+    "headings",
+    "events",
+    "reviews",
+    "migrate",
+    "topics",
+    "coverage",
+    "owners",
+    "tag-synonyms",
+    "lint",
+    "banner",
+    "data-attributes",
+    "namespaces",
+    "head-snippet",
+    "short-title",
+    "audit",
+];
+
+/// Options that were renamed, with their current name. Both top-level options
+/// and feature tables are listed.
+const RENAMED: &[(&str, &str)] = &[
+    ("allowed-tags", "valid-tags"),
+    ("valid_tags", "valid-tags"),
+    ("continue_on_error", "continue-on-error"),
+    ("book_defaults", "book-defaults"),
+    ("artifacts_dir", "artifacts-dir"),
+    ("tags_manifest", "tags-manifest"),
+    ("raw_keys", "raw-keys"),
+    ("property_keys", "property-keys"),
+    ("opt_out", "opt-out"),
+    ("whats_new", "whats-new"),
+    ("see_also", "see-also"),
+    ("open_graph", "open-graph"),
+    ("theme_data", "theme-data"),
+];
+
+/// The option replacing `defaults = { author = "..." }` tables.
+const DEFAULTS: &str = "defaults";
+
+/// Returns the current equivalent of a renamed or restructured option.
+fn replacement(key: &str) -> Option<String> {
+    if key == DEFAULTS {
+        return Some("default-<key> options".to_string());
+    }
+    RENAMED
+        .iter()
+        .find(|(old, _)| *old == key)
+        .map(|(_, new)| new.to_string())
+}
+
+/// Checks the types of the `[preprocessor.metadata]` options, returning an
+/// error for every malformed one with its TOML path. Unknown options are
+/// only warned about.
+pub fn validate(table: &Table) -> Vec<String> {
+    let mut errors = Vec::new();
+    let mut check = |path: &str, value: &Value, expected: Expected| {
+        if !expected.matches(value) {
+            errors.push(format!(
+                "{}.{}: expected {}, found {}",
+                PREFIX,
+                path,
+                expected.describe(),
+                describe_value(value)
+            ));
+        }
+    };
+
+    for (key, value) in table {
+        if let Some(&(_, expected)) = OPTIONS.iter().find(|(name, _)| name == key) {
+            check(key, value, expected);
+        } else if key.starts_with("default-") {
+            check(key, value, Expected::String);
+        } else if FEATURES.contains(&key.as_str()) {
+            check(key, value, Expected::Table);
+            if let Some(enabled) = value.get("enabled") {
+                check(&format!("{}.enabled", key), enabled, Expected::Bool);
+            }
+        } else if let Some(new) = replacement(key) {
+            warn!(
+                "Option {0}.{1} was replaced by {0}.{2}; run `mdbook-metadata migrate-config` to update book.toml",
+                PREFIX, key, new
+            );
+        } else {
+            warn!("Unknown option {}.{}", PREFIX, key);
+        }
+    }
+
+    if let Some(rules) = table.get("rules").and_then(|v| v.as_array()) {
+        for (i, rule) in rules.iter().enumerate() {
+            if let Some(glob) = rule.get("glob") {
+                check(&format!("rules[{}].glob", i), glob, Expected::String);
+            }
+            if let Some(when) = rule.get("when") {
+                check(&format!("rules[{}].when", i), when, Expected::String);
+            }
+            if let Some(set) = rule.get("set") {
+                check(&format!("rules[{}].set", i), set, Expected::Table);
+            }
+        }
+    }
+    if let Some(renderers) = table.get("per-renderer").and_then(|v| v.as_table()) {
+        for (renderer, overrides) in renderers {
+            check(
+                &format!("per-renderer.{}", renderer),
+                overrides,
+                Expected::Table,
+            );
+            if let Some(valid_tags) = overrides.get("valid-tags") {
+                let path = format!("per-renderer.{}.valid-tags", renderer);
+                check(&path, valid_tags, Expected::Strings);
+            }
+            if let Some(enabled) = overrides.get("enabled") {
+                let path = format!("per-renderer.{}.enabled", renderer);
+                check(&path, enabled, Expected::Bool);
+            }
+            if let Some(emit) = overrides.get("emit") {
+                check(
+                    &format!("per-renderer.{}.emit", renderer),
+                    emit,
+                    Expected::Table,
+                );
+            }
+        }
+    }
+    if let Some(profiles) = table.get("profiles").and_then(|v| v.as_array()) {
+        for (i, profile) in profiles.iter().enumerate() {
+            if let Some(glob) = profile.get("glob") {
+                check(&format!("profiles[{}].glob", i), glob, Expected::String);
+            }
+            for key in ["required", "allowed"] {
+                if let Some(keys) = profile.get(key) {
+                    check(&format!("profiles[{}].{}", i, key), keys, Expected::Strings);
+                }
+            }
+        }
+    }
+    errors
+}
+
+/// Whether the preprocessor runs for `renderer`: renderers can be turned off
+/// with `enabled = false` under `[preprocessor.metadata.per-renderer.<name>]`.
+/// Books without a `[preprocessor.metadata]` table support every renderer.
+pub fn supports(config: &Config, renderer: &str) -> bool {
+    config
+        .get_preprocessor("metadata")
+        .and_then(|p| p.get("per-renderer"))
+        .and_then(|v| v.get(renderer))
+        .and_then(|v| v.get("enabled"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true)
+}
+
+/// Like [`supports`], for the `book.toml` of a book directory with its
+/// `extends` applied, as `mdbook-metadata supports` answers.
+pub fn book_supports(book_dir: &Path, renderer: &str) -> Result<bool> {
+    let mut config = Config::from_disk(book_dir.join("book.toml"))
+        .with_context(|| format!("Failed to read book.toml in '{}'", book_dir.display()))?;
+    apply_extends(book_dir, &mut config)?;
+    Ok(supports(&config, renderer))
+}
+
+/// Merges the shared files listed in `extends` (relative to the book root)
+/// into the `[preprocessor.metadata]` options of `config`. Local options
+/// override shared ones, tables are merged key by key, and later files override
+/// earlier ones. Shared files can extend other files.
+pub fn apply_extends(root: &Path, config: &mut Config) -> Result<()> {
+    let Some(table) = config.get_preprocessor("metadata") else {
+        return Ok(());
+    };
+    if !table.contains_key("extends") {
+        return Ok(());
+    }
+    let merged = extend(root, table.clone(), &mut Vec::new())?;
+    config.set(PREFIX, merged)?;
+    Ok(())
+}
+
+/// Resolves the `extends` of `table`, whose relative paths start from `dir`.
+/// `chain` holds the files being resolved, to detect cycles.
+fn extend(dir: &Path, mut table: Table, chain: &mut Vec<PathBuf>) -> Result<Table> {
+    let files: Vec<String> = match table.remove("extends") {
+        Some(Value::String(file)) => vec![file],
+        Some(Value::Array(files)) => files
+            .iter()
+            .filter_map(|f| f.as_str().map(String::from))
+            .collect(),
+        _ => return Ok(table),
+    };
+
+    let mut merged = Table::new();
+    for file in files {
+        let path = dir.join(&file);
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if chain.contains(&canonical) {
+            bail!("{}.extends: '{}' extends itself", PREFIX, path.display());
+        }
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("{}.extends: failed to read '{}'", PREFIX, path.display()))?;
+        let mut shared: Table = toml::from_str(&contents)
+            .with_context(|| format!("{}.extends: invalid TOML in '{}'", PREFIX, path.display()))?;
+        // A shared book.toml fragment, or the options themselves
+        let shared = match shared
+            .remove("preprocessor")
+            .and_then(|p| p.get("metadata").cloned())
+        {
+            Some(Value::Table(options)) => options,
+            _ => shared,
+        };
+
+        chain.push(canonical);
+        let shared = extend(path.parent().unwrap_or(dir), shared, chain)?;
+        chain.pop();
+        merge(&mut merged, shared);
+    }
+    merge(&mut merged, table);
+    Ok(merged)
+}
+
+/// Merges `overrides` into `base`, key by key for tables.
+fn merge(base: &mut Table, overrides: Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(value)) => merge(base, value),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Rewrites the `[preprocessor.metadata]` options of a `book.toml` that were
+/// renamed or restructured, keeping everything else (comments, order and
+/// formatting) as it is. Returns the new contents and a note per change.
+pub fn migrate(book_toml: &str) -> (String, Vec<String>) {
+    let mut migrated = String::with_capacity(book_toml.len());
+    let mut notes = Vec::new();
+    let mut in_options = false;
+
+    for line in book_toml.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\r', '\n']);
+        let ending = &line[text.len()..];
+        if let Some(caps) = HEADER_RE.captures(text) {
+            let path = caps[2].to_string();
+            in_options = path == PREFIX;
+            let feature = path
+                .strip_prefix(PREFIX)
+                .and_then(|rest| rest.strip_prefix('.'))
+                .map(|rest| rest.split('.').next().unwrap_or(rest));
+            if let Some((old, new)) =
+                feature.and_then(|f| RENAMED.iter().find(|(old, _)| *old == f))
+            {
+                let new_path = path.replacen(old, new, 1);
+                notes.push(format!("[{}] -> [{}]", path, new_path));
+                migrated.push_str(&format!("{}{}{}{}", &caps[1], new_path, &caps[3], ending));
+                continue;
+            }
+        } else if in_options {
+            if let Some(caps) = KEY_RE.captures(text) {
+                let key = &caps[3];
+                if let Some((_, new)) = RENAMED.iter().find(|(old, _)| *old == key) {
+                    notes.push(format!("{}.{} -> {}.{}", PREFIX, key, PREFIX, new));
+                    migrated.push_str(&format!("{}{}{}{}", &caps[1], new, &caps[5], ending));
+                    continue;
+                }
+                if key == DEFAULTS {
+                    if let Some(defaults) = inline_table(text) {
+                        for (name, value) in &defaults {
+                            migrated.push_str(&format!(
+                                "{}\"default-{}\" = {}{}",
+                                &caps[1],
+                                name,
+                                value,
+                                if ending.is_empty() { "\n" } else { ending }
+                            ));
+                        }
+                        notes.push(format!(
+                            "{}.{} -> {} default-<key> options",
+                            PREFIX,
+                            DEFAULTS,
+                            defaults.len()
+                        ));
+                        continue;
+                    }
+                    notes.push(format!(
+                        "{}.{} isn't an inline table, migrate it by hand",
+                        PREFIX, DEFAULTS
+                    ));
+                }
+            }
+        }
+        migrated.push_str(line);
+    }
+    (migrated, notes)
+}
+
+/// Parses a `key = { ... }` line, returning the entries of the inline table.
+fn inline_table(line: &str) -> Option<Table> {
+    let parsed: Table = toml::from_str(line).ok()?;
+    parsed.into_iter().next()?.1.as_table().cloned()
+}
+
+fn describe_value(value: &Value) -> String {
+    match value {
+        Value::Array(items) => match items.iter().find(|v| !v.is_str()) {
+            Some(item) => format!("an array containing {} {}", article(item), item.type_str()),
+            None => "an array".to_string(),
+        },
+        value => format!("{} {}", article(value), value.type_str()),
+    }
+}
+
+fn article(value: &Value) -> &'static str {
+    match value.type_str() {
+        "integer" | "array" => "an",
+        _ => "a",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_rewrites_renamed_options() {
+        let book_toml = r#"[book]
+title = "Book"
+
+[preprocessor.metadata]
+valid_tags = ["title"] # kept comment
+defaults = { author = "Jane", year = "2024" }
+continue-on-error = false
+
+[preprocessor.metadata.whats_new]
+limit = 5
+
+[output.html]
+valid_tags = "untouched"
+"#;
+
+        let (migrated, notes) = migrate(book_toml);
+
+        assert_eq!(
+            migrated,
+            r#"[book]
+title = "Book"
+
+[preprocessor.metadata]
+valid-tags = ["title"] # kept comment
+"default-author" = "Jane"
+"default-year" = "2024"
+continue-on-error = false
+
+[preprocessor.metadata.whats-new]
+limit = 5
+
+[output.html]
+valid_tags = "untouched"
+"#
+        );
+        assert_eq!(notes.len(), 3);
+        assert!(toml::from_str::<Table>(&migrated).is_ok());
+    }
+
+    #[test]
+    fn validate_reports_every_malformed_option() {
+        let table: Table = toml::from_str(
+            r#"
+command = "mdbook-metadata"
+valid-tags = ["title", 3]
+continue-on-error = "yes"
+default-author = "Jane"
+default-year = 2024
+include = "src/**"
+unknown = true
+
+[[rules]]
+glob = ["src/**"]
+
+[graph]
+enabled = 1
+"#,
+        )
+        .unwrap();
+
+        let mut errors = validate(&table);
+        errors.sort();
+
+        assert_eq!(
+            errors,
+            vec![
+                "preprocessor.metadata.continue-on-error: expected a boolean, found a string",
+                "preprocessor.metadata.default-year: expected a string, found an integer",
+                "preprocessor.metadata.graph.enabled: expected a boolean, found an integer",
+                "preprocessor.metadata.rules[0].glob: expected a string, found an array",
+                "preprocessor.metadata.valid-tags: expected an array of strings, found an array containing an integer",
+            ]
+        );
+    }
+
+    #[test]
+    fn supports_skips_disabled_renderers() {
+        let config: Config = toml::from_str(
+            r#"
+[preprocessor.metadata.per-renderer.markdown]
+enabled = false
+
+[preprocessor.metadata.per-renderer.epub]
+valid-tags = ["title"]
+"#,
+        )
+        .unwrap();
+
+        assert!(!supports(&config, "markdown"));
+        assert!(supports(&config, "epub"));
+        assert!(supports(&config, "html"));
+        assert!(supports(&Config::default(), "markdown"));
+    }
+
+    #[test]
+    fn book_supports_reads_extended_files() {
+        let dir =
+            std::env::temp_dir().join(format!("mdbook-metadata-supports-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("book.toml"),
+            "[preprocessor.metadata]\nextends = \"shared.toml\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("shared.toml"),
+            "[per-renderer.markdown]\nenabled = false\n",
+        )
+        .unwrap();
+
+        let markdown = book_supports(&dir, "markdown").unwrap();
+        let html = book_supports(&dir, "html").unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!markdown);
+        assert!(html);
+    }
+
+    #[test]
+    fn apply_extends_merges_shared_options() {
+        let dir =
+            std::env::temp_dir().join(format!("mdbook-metadata-extends-{}", std::process::id()));
+        fs::create_dir_all(dir.join("shared")).unwrap();
+        fs::create_dir_all(dir.join("book")).unwrap();
+        fs::write(
+            dir.join("shared/base.toml"),
+            "valid-tags = [\"title\"]\ndefault-author = \"Docs team\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("shared/metadata.toml"),
+            r#"
+[preprocessor.metadata]
+extends = "base.toml"
+valid-tags = ["title", "author"]
+sitemap = { site-url = "https://example.com", enabled = true }
+"#,
+        )
+        .unwrap();
+        let mut config: Config = r#"
+[preprocessor.metadata]
+extends = "../shared/metadata.toml"
+default-author = "Jane"
+sitemap = { enabled = false }
+"#
+        .parse()
+        .unwrap();
+
+        apply_extends(&dir.join("book"), &mut config).unwrap();
+
+        let table = config.get_preprocessor("metadata").unwrap();
+        assert_eq!(
+            Value::Table(table.clone()),
+            toml::from_str::<Value>(
+                r#"
+valid-tags = ["title", "author"]
+default-author = "Jane"
+sitemap = { site-url = "https://example.com", enabled = false }
+"#
+            )
+            .unwrap()
+        );
+
+        fs::write(
+            dir.join("shared/base.toml"),
+            "extends = \"metadata.toml\"\n",
+        )
+        .unwrap();
+        let mut cyclic: Config = "[preprocessor.metadata]\nextends = \"../shared/metadata.toml\"\n"
+            .parse()
+            .unwrap();
+        assert!(apply_extends(&dir.join("book"), &mut cyclic).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
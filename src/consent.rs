@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use toml::value::Table;
+
+use crate::metadata::{parse_list, MetadataError};
+use crate::tags::Tag;
+
+/// Consent categories a chapter requires, for consent-management scripts
+/// (`[preprocessor.metadata.consent]`).
+#[derive(Debug, Clone)]
+pub struct Consent {
+    pub key: String,
+    pub categories: BTreeMap<String, String>,
+}
+
+impl Consent {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            key: table
+                .get("key")
+                .and_then(|v| v.as_str())
+                .unwrap_or("requires-consent")
+                .to_string(),
+            categories: table
+                .get("categories")
+                .and_then(|v| v.as_table())
+                .map(|categories| {
+                    categories
+                        .iter()
+                        .filter_map(|(name, value)| {
+                            Some((name.clone(), value.as_str()?.to_string()))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Returns the tags of the categories listed in `value`: a `<meta>` tag
+    /// with the categories, and a `page-consent` JSON script with the
+    /// categories and their configured values, also set as its `data-consent`
+    /// attribute. When categories are configured, other ones are errors.
+    pub fn tags(&self, value: &str) -> Result<Vec<Tag>, MetadataError> {
+        let categories = parse_list(&value.to_lowercase());
+        if categories.is_empty() {
+            return Ok(Vec::new());
+        }
+        let values = categories
+            .iter()
+            .map(|category| match self.categories.get(category) {
+                Some(value) => Ok(value.clone()),
+                None if self.categories.is_empty() => Ok(category.clone()),
+                None => Err(MetadataError::InvalidValue {
+                    key: self.key.clone(),
+                    message: format!(
+                        "unknown consent category '{}', expected one of: {}",
+                        category,
+                        self.categories
+                            .keys()
+                            .cloned()
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                }),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut script = Tag::script(
+            "application/json",
+            &serde_json::json!({ "categories": categories, "values": values }),
+        );
+        script
+            .attributes
+            .insert("id".to_string(), "page-consent".to_string());
+        script
+            .attributes
+            .insert("data-consent".to_string(), values.join(" "));
+        Ok(vec![Tag::meta(&self.key, &categories.join(", ")), script])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::to_html;
+
+    #[test]
+    fn tags_map_categories_to_values() {
+        let table: Table = toml::from_str(
+            r#"
+[categories]
+analytics = "C0002"
+marketing = "C0004"
+"#,
+        )
+        .unwrap();
+        let consent = Consent::from_config(&table);
+
+        assert_eq!(
+            to_html(&consent.tags("[Analytics, marketing]").unwrap()),
+            "<meta name=\"requires-consent\" content=\"analytics, marketing\">\n\
+             <script type=\"application/json\" data-consent=\"C0002 C0004\" id=\"page-consent\">\
+             {\"categories\":[\"analytics\",\"marketing\"],\"values\":[\"C0002\",\"C0004\"]}</script>\n"
+        );
+        assert!(consent.tags("video").is_err());
+        assert_eq!(
+            Consent::from_config(&Table::new()).tags("video").unwrap()[1].attributes
+                ["data-consent"],
+            "video"
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::frontmatter::{self, Format};
+use crate::metadata::parse_entries;
+
+/// Where a chapter's metadata is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Storage {
+    /// A block opening the chapter.
+    Inline,
+    /// A `<chapter>.meta.yml` or `<chapter>.meta.toml` file next to the chapter.
+    Sidecar,
+}
+
+impl Storage {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "inline" => Some(Storage::Inline),
+            "sidecar" => Some(Storage::Sidecar),
+            _ => None,
+        }
+    }
+}
+
+/// Rewrites chapter metadata between formats and storages, for the `convert`
+/// subcommand. Content outside the metadata block is kept byte for byte.
+#[derive(Debug, Clone, Copy)]
+pub struct Conversion {
+    /// Only convert metadata in this format; any format when `None`.
+    pub from: Option<Format>,
+    pub to: Format,
+    pub storage: Storage,
+}
+
+impl Conversion {
+    /// Converts the metadata of a single chapter file. Returns whether the
+    /// chapter was rewritten.
+    pub fn convert_file(&self, path: &Path) -> Result<bool> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read '{}'", path.display()))?;
+
+        if let Some(block) = frontmatter::find(&content) {
+            if self.from.is_some_and(|f| f != block.format)
+                || (block.format == self.to && self.storage == Storage::Inline)
+            {
+                return Ok(false);
+            }
+            let entries = parse_entries(block.format, block.body, false)
+                .with_context(|| format!("Invalid metadata in '{}'", path.display()))?;
+
+            let converted = match self.storage {
+                Storage::Inline => format!(
+                    "{}{}{}",
+                    &content[..block.start],
+                    frontmatter::render(self.to, &entries),
+                    &content[block.end..]
+                ),
+                Storage::Sidecar => {
+                    write(
+                        &frontmatter::sidecar_path(path, self.to),
+                        &frontmatter::render_body(self.to, &entries),
+                    )?;
+                    let rest = &content[block.end..];
+                    let rest = rest
+                        .strip_prefix("\r\n")
+                        .or_else(|| rest.strip_prefix('\n'))
+                        .unwrap_or(rest);
+                    format!("{}{}", &content[..block.start], rest)
+                }
+            };
+            write(path, &converted)?;
+            return Ok(true);
+        }
+
+        let Some((sidecar, format)) = frontmatter::find_sidecar(path) else {
+            return Ok(false);
+        };
+        if self.from.is_some_and(|f| f != format)
+            || (format == self.to && self.storage == Storage::Sidecar)
+        {
+            return Ok(false);
+        }
+        let body = fs::read_to_string(&sidecar)
+            .with_context(|| format!("Failed to read '{}'", sidecar.display()))?;
+        let entries = parse_entries(format, &body, false)
+            .with_context(|| format!("Invalid metadata in '{}'", sidecar.display()))?;
+
+        match self.storage {
+            Storage::Inline => write(
+                path,
+                &format!("{}\n{}", frontmatter::render(self.to, &entries), content),
+            )?,
+            Storage::Sidecar => write(
+                &frontmatter::sidecar_path(path, self.to),
+                &frontmatter::render_body(self.to, &entries),
+            )?,
+        }
+        fs::remove_file(&sidecar)
+            .with_context(|| format!("Failed to remove '{}'", sidecar.display()))?;
+        Ok(true)
+    }
+}
+
+fn write(path: &Path, contents: &str) -> Result<()> {
+    fs::write(path, contents).with_context(|| format!("Failed to write '{}'", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_chapter(name: &str, content: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("mdbook-metadata-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("chapter.md");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn convert_file_between_formats_keeps_content() {
+        let path = temp_chapter(
+            "convert-format",
+            "---\ntitle: Intro\ntags: [a, b]\n---\n\n# Intro\r\nBody ---\n",
+        );
+        let to_toml = Conversion {
+            from: Some(Format::Yaml),
+            to: Format::Toml,
+            storage: Storage::Inline,
+        };
+
+        assert!(to_toml.convert_file(&path).unwrap());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "+++\ntitle = \"Intro\"\ntags = \"[a, b]\"\n+++\n\n# Intro\r\nBody ---\n"
+        );
+        assert!(!to_toml.convert_file(&path).unwrap());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn convert_file_between_inline_and_sidecar() {
+        let content = "---\ntitle: Intro\n---\n# Intro\n";
+        let path = temp_chapter("convert-sidecar", content);
+        let sidecar = path.with_extension("meta.toml");
+
+        let to_sidecar = Conversion {
+            from: None,
+            to: Format::Toml,
+            storage: Storage::Sidecar,
+        };
+        assert!(to_sidecar.convert_file(&path).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "# Intro\n");
+        assert_eq!(fs::read_to_string(&sidecar).unwrap(), "title = \"Intro\"\n");
+
+        let to_inline = Conversion {
+            from: None,
+            to: Format::Yaml,
+            storage: Storage::Inline,
+        };
+        assert!(to_inline.convert_file(&path).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+        assert!(!sidecar.exists());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,131 @@
+use serde::Serialize;
+use std::path::Path;
+use toml::value::Table;
+
+use crate::aggregate::ChapterEntry;
+use crate::profiles::{self, Profile};
+
+/// Writes a shields.io endpoint file with the share of chapters meeting the
+/// metadata policy, for a "docs metadata" badge
+/// (`[preprocessor.metadata.coverage]`).
+#[derive(Debug, Clone)]
+pub struct Coverage {
+    pub required: Vec<String>,
+    pub label: String,
+    pub file: String,
+}
+
+/// A shields.io endpoint badge.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Badge {
+    pub schema_version: u32,
+    pub label: String,
+    pub message: String,
+    pub color: String,
+}
+
+impl Coverage {
+    pub fn from_config(table: &Table) -> Self {
+        let string = |key: &str, default: &str| {
+            table
+                .get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or(default)
+                .to_string()
+        };
+        Self {
+            required: table
+                .get("required")
+                .and_then(|v| v.as_array())
+                .map(|keys| {
+                    keys.iter()
+                        .filter_map(|k| k.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_else(|| vec!["title".into(), "description".into()]),
+            label: string("label", "docs metadata"),
+            file: string("file", "meta-coverage.json"),
+        }
+    }
+
+    /// Returns whether a chapter has every `required` key and follows the
+    /// profiles matching it. `book_src` is the `src` directory, relative to
+    /// the book root.
+    pub fn covers(&self, entry: &ChapterEntry, profiles: &[Profile], book_src: &Path) -> bool {
+        self.required.iter().all(|key| {
+            entry
+                .metadata
+                .get(key)
+                .is_some_and(|v| !v.trim().is_empty())
+        }) && profiles::check(profiles, &book_src.join(&entry.path), &entry.metadata).is_empty()
+    }
+
+    /// Returns the badge of the chapters: the rounded-down percentage of
+    /// chapters covered, red below 50% to bright green from 90%.
+    pub fn badge(&self, chapters: &[ChapterEntry], profiles: &[Profile], book_src: &Path) -> Badge {
+        let covered = chapters
+            .iter()
+            .filter(|entry| self.covers(entry, profiles, book_src))
+            .count();
+        let percent = (covered * 100).checked_div(chapters.len()).unwrap_or(100);
+        let color = match percent {
+            90.. => "brightgreen",
+            75.. => "green",
+            50.. => "yellow",
+            25.. => "orange",
+            _ => "red",
+        };
+        Badge {
+            schema_version: 1,
+            label: self.label.clone(),
+            message: format!("{}%", percent),
+            color: color.to_string(),
+        }
+    }
+
+    /// Returns the endpoint file of the badge.
+    pub fn artifact(
+        &self,
+        chapters: &[ChapterEntry],
+        profiles: &[Profile],
+        book_src: &Path,
+    ) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.badge(chapters, profiles, book_src))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::entry;
+
+    #[test]
+    fn badge_reports_the_covered_chapters() {
+        let coverage = Coverage::from_config(&toml::from_str("required = [\"title\"]").unwrap());
+        let profiles = profiles::profiles_from_config(
+            &toml::from_str::<Table>("[[profiles]]\nglob = \"src/blog/**\"\nrequired = [\"date\"]")
+                .unwrap()["profiles"],
+        );
+        let chapters = vec![
+            entry("intro.md", &[("title", "Intro")]),
+            entry("setup.md", &[("title", " ")]),
+            entry("blog/launch.md", &[("title", "Launch")]),
+            entry("blog/v2.md", &[("title", "v2"), ("date", "2024-06-01")]),
+        ];
+
+        assert_eq!(
+            coverage.badge(&chapters, &profiles, Path::new("src")),
+            Badge {
+                schema_version: 1,
+                label: "docs metadata".to_string(),
+                message: "50%".to_string(),
+                color: "yellow".to_string(),
+            }
+        );
+        assert_eq!(
+            coverage.badge(&[], &profiles, Path::new("src")).message,
+            "100%"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use html_escape::encode_double_quoted_attribute;
+use std::collections::HashMap;
+use std::fmt::Write;
+use toml::value::Table;
+
+use crate::metadata::parse_list;
+use crate::tags::is_attribute_name;
+
+/// The key listing extra classes of a chapter's wrapper element.
+pub const CSS_CLASS_KEY: &str = "css-class";
+
+/// Wraps chapters in an element with `data-meta-*` attributes of allowlisted
+/// keys, for themes to style with attribute selectors
+/// (`[preprocessor.metadata.data-attributes]`).
+#[derive(Debug, Clone)]
+pub struct DataAttributes {
+    pub keys: Vec<String>,
+    pub element: String,
+    pub class: String,
+}
+
+impl DataAttributes {
+    pub fn from_config(table: &Table) -> Self {
+        let string = |key: &str, default: &str| {
+            table
+                .get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or(default)
+                .to_string()
+        };
+        Self {
+            keys: table
+                .get("keys")
+                .and_then(|v| v.as_array())
+                .map(|keys| {
+                    keys.iter()
+                        .filter_map(|k| k.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_else(|| vec!["status".into(), "level".into()]),
+            element: string("element", "div"),
+            class: string("class", "chapter-meta"),
+        }
+    }
+
+    /// Wraps the content of a chapter with any of the `keys` or a `css-class`,
+    /// leaving others as they are. Keys become `data-meta-<key>`, lowercase
+    /// with other characters than letters, digits and `-` replaced by `-`, and
+    /// `css-class` items are added to the `class`.
+    pub fn wrap(&self, metadata: &HashMap<String, String>, content: &str) -> Option<String> {
+        let mut attributes = String::new();
+        for key in &self.keys {
+            let Some(value) = metadata.get(key) else {
+                continue;
+            };
+            let name: String = key
+                .to_lowercase()
+                .chars()
+                .map(|c| match c {
+                    'a'..='z' | '0'..='9' | '-' => c,
+                    _ => '-',
+                })
+                .collect();
+            let name = format!("data-meta-{}", name.trim_matches('-'));
+            if is_attribute_name(&name) {
+                let value = encode_double_quoted_attribute(value.trim());
+                let _ = write!(attributes, " {}=\"{}\"", name, value);
+            }
+        }
+        let classes: Vec<String> = metadata
+            .get(CSS_CLASS_KEY)
+            .map(|v| parse_list(v))
+            .unwrap_or_default();
+        if attributes.is_empty() && classes.is_empty() {
+            return None;
+        }
+        let element = if is_attribute_name(&self.element) {
+            self.element.as_str()
+        } else {
+            "div"
+        };
+        let class = std::iter::once(self.class.trim())
+            .chain(classes.iter().map(|c| c.trim()))
+            .filter(|c| !c.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let class = match class.as_str() {
+            "" => String::new(),
+            class => format!(" class=\"{}\"", encode_double_quoted_attribute(class)),
+        };
+        // Blank lines around the content keep it markdown
+        Some(format!(
+            "<{}{}{}>\n\n{}\n\n</{}>\n",
+            element,
+            class,
+            attributes,
+            content.trim_end(),
+            element
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_adds_the_allowlisted_keys() {
+        let data = DataAttributes::from_config(
+            &toml::from_str("keys = [\"status\", \"Review Tier\"]").unwrap(),
+        );
+        let metadata = HashMap::from([
+            ("status".to_string(), "draft \"wip\"".to_string()),
+            ("Review Tier".to_string(), "1".to_string()),
+            ("author".to_string(), "Jane".to_string()),
+        ]);
+
+        assert_eq!(
+            data.wrap(&metadata, "# Intro\n").unwrap(),
+            "<div class=\"chapter-meta\" data-meta-status=\"draft &quot;wip&quot;\" \
+             data-meta-review-tier=\"1\">\n\n# Intro\n\n</div>\n"
+        );
+        assert_eq!(
+            data.wrap(
+                &HashMap::from([("author".to_string(), "Jane".to_string())]),
+                "# Intro\n"
+            ),
+            None
+        );
+        assert_eq!(
+            data.wrap(
+                &HashMap::from([("css-class".to_string(), "[wide, dark]".to_string())]),
+                "# Intro\n"
+            )
+            .unwrap(),
+            "<div class=\"chapter-meta wide dark\">\n\n# Intro\n\n</div>\n"
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use log::warn;
+use std::path::Path;
+use std::process::Command;
+
+/// Parses a metadata date value. Accepts `YYYY-MM-DD`, RFC 3339 timestamps and
+/// `YYYY-MM-DD HH:MM:SS`; only the calendar date is kept.
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .or_else(|| {
+            DateTime::parse_from_rfc3339(value)
+                .ok()
+                .map(|d| d.date_naive())
+        })
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .map(|d| d.date())
+        })
+}
+
+/// Dates recorded by git for a single file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GitDates {
+    pub created: NaiveDate,
+    pub modified: NaiveDate,
+}
+
+/// Looks up the first and last commit dates of `file` (relative to `dir`).
+/// Returns `None` when git is unavailable or the file is untracked.
+pub fn git_dates(dir: &Path, file: &Path) -> Option<GitDates> {
+    let output = Command::new("git")
+        .arg("log")
+        .arg("--format=%cs")
+        .arg("--")
+        .arg(file)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| warn!("Failed to run git for '{}': {}", file.display(), e))
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let dates: Vec<NaiveDate> = stdout.lines().filter_map(parse_date).collect();
+
+    Some(GitDates {
+        created: *dates.last()?,
+        modified: *dates.first()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_date_accepts_common_formats() {
+        let expected = NaiveDate::from_ymd_opt(2024, 6, 1);
+
+        assert_eq!(parse_date("2024-06-01"), expected);
+        assert_eq!(parse_date("\"2024-06-01\""), expected);
+        assert_eq!(parse_date("2024-06-01T10:30:00+02:00"), expected);
+        assert_eq!(parse_date("2024-06-01 10:30:00"), expected);
+    }
+
+    #[test]
+    fn parse_date_rejects_invalid_values() {
+        assert_eq!(parse_date("June 1st"), None);
+        assert_eq!(parse_date("2024-13-01"), None);
+        assert_eq!(parse_date(""), None);
+    }
+}
@@ -0,0 +1,210 @@
+use mdbook::book::{Book, BookItem};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use toml::value::Table;
+
+use crate::aggregate::ChapterEntry;
+use crate::metadata::parse_list;
+
+/// Text added around the name of chapters with a given status.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Marker {
+    pub prefix: String,
+    pub suffix: String,
+}
+
+/// Rewrites chapter names in the navigation from their status
+/// (`[preprocessor.metadata.decorate]`).
+#[derive(Debug, Clone)]
+pub struct Decorate {
+    pub key: String,
+    pub markers: BTreeMap<String, Marker>,
+}
+
+impl Decorate {
+    pub fn from_config(table: &Table) -> Self {
+        let markers = match table.get("markers").and_then(|v| v.as_table()) {
+            Some(markers) => markers
+                .iter()
+                .filter_map(|(status, marker)| {
+                    let marker = marker.as_table()?;
+                    let text = |key: &str| {
+                        marker
+                            .get(key)
+                            .and_then(|v| v.as_str())
+                            .unwrap_or_default()
+                            .to_string()
+                    };
+                    Some((
+                        status.clone(),
+                        Marker {
+                            prefix: text("prefix"),
+                            suffix: text("suffix"),
+                        },
+                    ))
+                })
+                .collect(),
+            None => BTreeMap::from([
+                (
+                    "draft".to_string(),
+                    Marker {
+                        prefix: "🚧 ".to_string(),
+                        suffix: String::new(),
+                    },
+                ),
+                (
+                    "deprecated".to_string(),
+                    Marker {
+                        prefix: String::new(),
+                        suffix: " (deprecated)".to_string(),
+                    },
+                ),
+            ]),
+        };
+
+        Self {
+            key: table
+                .get("key")
+                .and_then(|v| v.as_str())
+                .unwrap_or("status")
+                .to_string(),
+            markers,
+        }
+    }
+
+    /// Returns the statuses of a chapter: the values of the status key, plus
+    /// every marker name set to `true` (e.g. `draft: true`).
+    pub fn statuses(&self, metadata: &HashMap<String, String>) -> Vec<String> {
+        let mut statuses: Vec<String> = metadata
+            .get(&self.key)
+            .map(|v| parse_list(&v.to_lowercase()))
+            .unwrap_or_default();
+        for status in self.markers.keys() {
+            if metadata.get(status).map(|v| v.trim()) == Some("true") && !statuses.contains(status)
+            {
+                statuses.push(status.clone());
+            }
+        }
+        statuses
+    }
+
+    pub fn decorate(&self, name: &str, metadata: &HashMap<String, String>) -> String {
+        let mut prefix = String::new();
+        let mut suffix = String::new();
+        for status in self.statuses(metadata) {
+            if let Some(marker) = self.markers.get(&status) {
+                prefix.push_str(&marker.prefix);
+                suffix.push_str(&marker.suffix);
+            }
+        }
+        format!("{}{}{}", prefix, name, suffix)
+    }
+
+    /// Decorates every chapter of the book, keeping the `parent_names` of nested
+    /// chapters in sync with their decorated ancestors.
+    pub fn apply(&self, book: &mut Book, chapters: &[ChapterEntry]) {
+        let metadata_by_path: HashMap<&PathBuf, &HashMap<String, String>> = chapters
+            .iter()
+            .map(|entry| (&entry.path, &entry.metadata))
+            .collect();
+        self.apply_items(&mut book.sections, &[], &metadata_by_path);
+    }
+
+    fn apply_items(
+        &self,
+        items: &mut [BookItem],
+        parent_names: &[String],
+        metadata_by_path: &HashMap<&PathBuf, &HashMap<String, String>>,
+    ) {
+        for item in items {
+            if let BookItem::Chapter(chap) = item {
+                if let Some(metadata) = chap.path.as_ref().and_then(|p| metadata_by_path.get(p)) {
+                    chap.name = self.decorate(&chap.name, metadata);
+                }
+                chap.parent_names = parent_names.to_vec();
+
+                let mut names = parent_names.to_vec();
+                names.push(chap.name.clone());
+                self.apply_items(&mut chap.sub_items, &names, metadata_by_path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::metadata;
+    use mdbook::book::Chapter;
+    use std::path::Path;
+
+    #[test]
+    fn decorate_uses_default_markers() {
+        let decorate = Decorate::from_config(&Table::new());
+
+        assert_eq!(
+            decorate.decorate("Intro", &metadata(&[("status", "Draft")])),
+            "🚧 Intro"
+        );
+        assert_eq!(
+            decorate.decorate("Old API", &metadata(&[("deprecated", "true")])),
+            "Old API (deprecated)"
+        );
+        assert_eq!(
+            decorate.decorate("Stable", &metadata(&[("status", "stable")])),
+            "Stable"
+        );
+    }
+
+    #[test]
+    fn decorate_uses_configured_markers() {
+        let table: Table = toml::from_str(
+            r#"
+key = "state"
+markers = { beta = { suffix = " β" } }
+"#,
+        )
+        .unwrap();
+        let decorate = Decorate::from_config(&table);
+
+        assert_eq!(
+            decorate.decorate("Feature", &metadata(&[("state", "beta")])),
+            "Feature β"
+        );
+        assert_eq!(
+            decorate.decorate("Feature", &metadata(&[("status", "draft")])),
+            "Feature"
+        );
+    }
+
+    #[test]
+    fn apply_updates_parent_names() {
+        let decorate = Decorate::from_config(&Table::new());
+        let mut parent = Chapter::new("Parent", String::new(), "parent.md", Vec::new());
+        parent.sub_items.push(BookItem::Chapter(Chapter::new(
+            "Child",
+            String::new(),
+            "child.md",
+            vec!["Parent".to_string()],
+        )));
+        let mut book = Book::new();
+        book.push_item(parent.clone());
+        let chapters = vec![ChapterEntry::new(
+            &parent,
+            Path::new("parent.md"),
+            metadata(&[("status", "draft")]),
+        )];
+
+        decorate.apply(&mut book, &chapters);
+
+        let BookItem::Chapter(parent) = &book.sections[0] else {
+            panic!("Expected a chapter");
+        };
+        let BookItem::Chapter(child) = &parent.sub_items[0] else {
+            panic!("Expected a chapter");
+        };
+        assert_eq!(parent.name, "🚧 Parent");
+        assert_eq!(child.name, "Child");
+        assert_eq!(child.parent_names, vec!["🚧 Parent"]);
+    }
+}
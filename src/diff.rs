@@ -0,0 +1,193 @@
+use anyhow::{bail, Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::metadata::parse_metadata;
+use crate::sources::{chapter_files, chapter_name, load_config};
+
+/// The metadata of every chapter with metadata, keyed by its path relative to
+/// `src`, as read from disk or git. This is the format of `export`.
+pub type Snapshot = BTreeMap<String, BTreeMap<String, String>>;
+
+/// A change to a single key of a chapter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyChange {
+    Added(String, String),
+    Removed(String, String),
+    Changed(String, String, String),
+}
+
+/// The key changes of a chapter between two revisions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChapterDiff {
+    pub chapter: String,
+    pub changes: Vec<KeyChange>,
+}
+
+fn chapter_metadata(content: &str) -> BTreeMap<String, String> {
+    parse_metadata(content, true)
+        .map(|(metadata, _)| metadata.into_iter().collect())
+        .unwrap_or_default()
+}
+
+/// Reads the metadata of the chapters of a book on disk.
+pub fn from_dir(book_dir: &Path) -> Result<Snapshot> {
+    let (_, src_dir) = load_config(book_dir)?;
+    let mut book = Snapshot::new();
+    for path in chapter_files(&src_dir) {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read '{}'", path.display()))?;
+        let metadata = chapter_metadata(&content);
+        if !metadata.is_empty() {
+            book.insert(chapter_name(&src_dir, &path), metadata);
+        }
+    }
+    Ok(book)
+}
+
+/// Reads the metadata of the chapters of a book at a git revision.
+pub fn from_git(book_dir: &Path, rev: &str) -> Result<Snapshot> {
+    let (config, _) = load_config(book_dir)?;
+    let src = config.book.src.to_string_lossy().replace('\\', "/");
+    let files = git(book_dir, &["ls-tree", "-r", "--name-only", rev, "--", &src])?;
+
+    let mut book = Snapshot::new();
+    for file in files.lines().filter(|f| f.ends_with(".md")) {
+        let content = git(book_dir, &["show", &format!("{}:./{}", rev, file)])?;
+        let metadata = chapter_metadata(&content);
+        let name = file.strip_prefix(&format!("{}/", src)).unwrap_or(file);
+        if !metadata.is_empty() && name != "SUMMARY.md" {
+            book.insert(name.to_string(), metadata);
+        }
+    }
+    Ok(book)
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Loads one side of a diff: an `export` JSON file, a book directory, or else
+/// a git revision of the book in `book_dir`.
+pub fn load(source: &str, book_dir: &Path) -> Result<Snapshot> {
+    let path = Path::new(source);
+    if path.is_file() {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read '{}'", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("Invalid export '{}'", source))
+    } else if path.is_dir() {
+        from_dir(path)
+    } else {
+        from_git(book_dir, source)
+    }
+}
+
+/// Compares the metadata of two revisions, chapter by chapter.
+pub fn diff(old: &Snapshot, new: &Snapshot) -> Vec<ChapterDiff> {
+    let empty = BTreeMap::new();
+    let chapters: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+
+    chapters
+        .into_iter()
+        .filter_map(|chapter| {
+            let before = old.get(chapter).unwrap_or(&empty);
+            let after = new.get(chapter).unwrap_or(&empty);
+            let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+            let changes: Vec<KeyChange> = keys
+                .into_iter()
+                .filter_map(|key| match (before.get(key), after.get(key)) {
+                    (None, Some(v)) => Some(KeyChange::Added(key.clone(), v.clone())),
+                    (Some(v), None) => Some(KeyChange::Removed(key.clone(), v.clone())),
+                    (Some(a), Some(b)) if a != b => {
+                        Some(KeyChange::Changed(key.clone(), a.clone(), b.clone()))
+                    }
+                    _ => None,
+                })
+                .collect();
+            (!changes.is_empty()).then(|| ChapterDiff {
+                chapter: chapter.clone(),
+                changes,
+            })
+        })
+        .collect()
+}
+
+/// Formats a diff for the terminal: `+` for added keys, `-` for removed keys
+/// and `~` for changed values.
+pub fn render(diffs: &[ChapterDiff]) -> String {
+    let mut out = String::new();
+    for chapter in diffs {
+        let _ = writeln!(out, "{}", chapter.chapter);
+        for change in &chapter.changes {
+            let _ = match change {
+                KeyChange::Added(key, value) => writeln!(out, "  + {}: {}", key, value),
+                KeyChange::Removed(key, value) => writeln!(out, "  - {}: {}", key, value),
+                KeyChange::Changed(key, old, new) => {
+                    writeln!(out, "  ~ {}: {} -> {}", key, old, new)
+                }
+            };
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(chapters: &[(&str, &[(&str, &str)])]) -> Snapshot {
+        chapters
+            .iter()
+            .map(|(chapter, metadata)| {
+                (
+                    chapter.to_string(),
+                    metadata
+                        .iter()
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn diff_reports_key_changes_per_chapter() {
+        let old = book(&[
+            ("intro.md", &[("title", "Intro"), ("status", "draft")]),
+            ("removed.md", &[("title", "Gone")]),
+            ("same.md", &[("title", "Same")]),
+        ]);
+        let new = book(&[
+            ("intro.md", &[("title", "Introduction"), ("owner", "docs")]),
+            ("added.md", &[("title", "New")]),
+            ("same.md", &[("title", "Same")]),
+        ]);
+
+        assert_eq!(
+            render(&diff(&old, &new)),
+            "added.md\n\
+             \x20 + title: New\n\
+             intro.md\n\
+             \x20 + owner: docs\n\
+             \x20 - status: draft\n\
+             \x20 ~ title: Intro -> Introduction\n\
+             removed.md\n\
+             \x20 - title: Gone\n"
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+
+use crate::frontmatter::{self, Format};
+
+/// Sets keys in the body of a metadata block, replacing the line of each key
+/// that is already set and appending the others. Every other line, comments
+/// included, is kept as is.
+pub fn set_in_body(format: Format, body: &str, updates: &[(String, String)]) -> String {
+    let mut lines: Vec<String> = body.split_inclusive('\n').map(String::from).collect();
+
+    for (key, value) in updates {
+        let entry = frontmatter::render_entry(format, key, value);
+        let separator = match format {
+            Format::Yaml => ":",
+            Format::Toml => "=",
+        };
+        let key_re = Regex::new(&format!(
+            r#"^\s*"?{}"?\s*{}"#,
+            regex::escape(key),
+            separator
+        ))
+        .unwrap();
+        // Keys of TOML tables belong to the table, not to the chapter
+        let top_level = match format {
+            Format::Yaml => lines.len(),
+            Format::Toml => lines
+                .iter()
+                .position(|l| l.trim_start().starts_with('['))
+                .unwrap_or(lines.len()),
+        };
+
+        if let Some(line) = lines[..top_level].iter_mut().find(|l| key_re.is_match(l)) {
+            let ending = &line[line.trim_end_matches(['\r', '\n']).len()..];
+            *line = format!("{}{}", entry, ending);
+            continue;
+        }
+        if let Some(previous) = lines[..top_level].last_mut() {
+            if !previous.ends_with('\n') {
+                previous.push('\n');
+            }
+        }
+        lines.insert(top_level, format!("{}\n", entry));
+    }
+    lines.concat()
+}
+
+/// Sets keys in a chapter's metadata block, adding a `---` block when the
+/// chapter has none. Content outside the block is kept byte for byte.
+pub fn set_in_content(content: &str, updates: &[(String, String)]) -> String {
+    match frontmatter::find(content) {
+        Some(block) => {
+            let body = set_in_body(block.format, block.body, updates);
+            let body = if body.starts_with('\n') {
+                body
+            } else {
+                format!("\n{}", body)
+            };
+            let fence = &content[block.start..block.start + 3];
+            format!(
+                "{}{}{}{}{}",
+                &content[..block.start],
+                fence,
+                body,
+                fence,
+                &content[block.end..]
+            )
+        }
+        None => format!(
+            "{}\n{}",
+            frontmatter::render(Format::Yaml, updates),
+            content
+        ),
+    }
+}
+
+/// Sets keys in the metadata of a chapter file: in its metadata block, else in
+/// its sidecar file, else in a new block. Returns whether the file changed.
+pub fn set_in_file(path: &Path, updates: &[(String, String)]) -> Result<bool> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read '{}'", path.display()))?;
+
+    let (target, original, updated) = match frontmatter::find_sidecar(path) {
+        Some((sidecar, format)) if frontmatter::find(&content).is_none() => {
+            let body = fs::read_to_string(&sidecar)
+                .with_context(|| format!("Failed to read '{}'", sidecar.display()))?;
+            let updated = set_in_body(format, &body, updates);
+            (sidecar, body, updated)
+        }
+        _ => {
+            let updated = set_in_content(&content, updates);
+            (path.to_path_buf(), content, updated)
+        }
+    };
+
+    if updated == original {
+        return Ok(false);
+    }
+    fs::write(&target, updated)
+        .with_context(|| format!("Failed to write '{}'", target.display()))?;
+    Ok(true)
+}
+
+/// Sets keys in the sidecar file of a chapter, creating a `.meta.yml` sidecar
+/// when it has none. Returns whether the sidecar changed.
+pub fn set_in_sidecar(path: &Path, updates: &[(String, String)]) -> Result<bool> {
+    let (sidecar, format) = frontmatter::find_sidecar(path)
+        .unwrap_or_else(|| (frontmatter::sidecar_path(path, Format::Yaml), Format::Yaml));
+    let body = if sidecar.is_file() {
+        fs::read_to_string(&sidecar)
+            .with_context(|| format!("Failed to read '{}'", sidecar.display()))?
+    } else {
+        String::new()
+    };
+
+    let updated = set_in_body(format, &body, updates);
+    if updated == body {
+        return Ok(false);
+    }
+    fs::write(&sidecar, updated)
+        .with_context(|| format!("Failed to write '{}'", sidecar.display()))?;
+    Ok(true)
+}
+
+/// Parses `key=value` arguments.
+pub fn parse_assignment(assignment: &str) -> Option<(String, String)> {
+    let (key, value) = assignment.split_once('=')?;
+    let key = key.trim();
+    (!key.is_empty()).then(|| (key.to_string(), value.trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn updates(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn set_in_content_updates_and_appends_keys() {
+        let content = "---\ntitle: Intro\r\nowner: docs\r\n---\n# Intro\n";
+
+        assert_eq!(
+            set_in_content(
+                content,
+                &updates(&[("owner", "platform"), ("reviewed", "2024-06-01")])
+            ),
+            "---\ntitle: Intro\r\nowner: platform\r\nreviewed: 2024-06-01\n---\n# Intro\n"
+        );
+    }
+
+    #[test]
+    fn set_in_content_keeps_toml_tables() {
+        let content = "+++\ntitle = \"Intro\"\n\n[taxonomies]\nowner = [\"x\"]\n+++\n# Intro";
+
+        assert_eq!(
+            set_in_content(content, &updates(&[("owner", "platform")])),
+            "+++\ntitle = \"Intro\"\n\nowner = \"platform\"\n[taxonomies]\nowner = [\"x\"]\n+++\n# Intro"
+        );
+    }
+
+    #[test]
+    fn set_in_content_adds_missing_block() {
+        assert_eq!(
+            set_in_content("# Intro\n", &updates(&[("owner", "docs")])),
+            "---\nowner: docs\n---\n# Intro\n"
+        );
+    }
+
+    #[test]
+    fn parse_assignment_splits_on_first_equals() {
+        assert_eq!(
+            parse_assignment("query=a=b"),
+            Some(("query".to_string(), "a=b".to_string()))
+        );
+        assert_eq!(parse_assignment("=x"), None);
+        assert_eq!(parse_assignment("flag"), None);
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use toml::value::Table;
+
+use crate::metadata::{parse_list, MetadataError};
+use crate::tags::{Tag, TagKind};
+
+/// The key overriding which groups of tags a chapter emits.
+pub const EMIT_KEY: &str = "emit";
+
+/// A group of emitted tags that can be turned off for a book or a chapter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Group {
+    Meta,   // <title> and <meta name> tags
+    Og,     // <meta property> tags, such as Open Graph
+    JsonLd, // JSON-LD scripts
+    Links,  // <link> elements
+    Raw,    // Raw HTML, from raw-keys and plugins
+}
+
+impl Group {
+    pub const ALL: [Group; 5] = [
+        Group::Meta,
+        Group::Og,
+        Group::JsonLd,
+        Group::Links,
+        Group::Raw,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Group::Meta => "meta",
+            Group::Og => "og",
+            Group::JsonLd => "jsonld",
+            Group::Links => "links",
+            Group::Raw => "raw",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|group| group.name() == name)
+    }
+
+    /// Returns the group of a tag.
+    pub fn of(tag: &Tag) -> Self {
+        match tag.kind {
+            TagKind::Title | TagKind::Meta => Group::Meta,
+            TagKind::Property => Group::Og,
+            TagKind::Script if tag.name == "application/ld+json" => Group::JsonLd,
+            TagKind::Script => Group::Meta,
+            TagKind::Link => Group::Links,
+            TagKind::Raw => Group::Raw,
+        }
+    }
+}
+
+/// The groups of tags emitted (`[preprocessor.metadata.emit]`). Every group
+/// is on unless turned off, and chapters can override them with `emit`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Emit {
+    groups: Vec<Group>,
+}
+
+impl Default for Emit {
+    fn default() -> Self {
+        Self {
+            groups: Group::ALL.to_vec(),
+        }
+    }
+}
+
+impl Emit {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            groups: Group::ALL
+                .into_iter()
+                .filter(|group| {
+                    table
+                        .get(group.name())
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(true)
+                })
+                .collect(),
+        }
+    }
+
+    /// Takes the `emit` key out of a chapter's metadata, returning the groups
+    /// the chapter emits: the listed ones, none with `emit: none`, or the
+    /// book's when it has no `emit` key.
+    pub fn for_chapter(
+        &self,
+        metadata: &mut HashMap<String, String>,
+    ) -> Result<Self, MetadataError> {
+        let Some(value) = metadata.remove(EMIT_KEY) else {
+            return Ok(self.clone());
+        };
+        match value.trim() {
+            "none" => return Ok(Self { groups: Vec::new() }),
+            "all" => return Ok(Self::default()),
+            _ => {}
+        }
+        let groups = parse_list(&value)
+            .iter()
+            .map(|name| {
+                Group::from_name(name).ok_or_else(|| MetadataError::InvalidValue {
+                    key: EMIT_KEY.to_string(),
+                    message: format!(
+                        "unknown group '{}', expected none, all or some of {}",
+                        name,
+                        Group::ALL.map(Group::name).join(", ")
+                    ),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { groups })
+    }
+
+    /// Returns whether a tag is emitted.
+    pub fn allows(&self, tag: &Tag) -> bool {
+        self.groups.contains(&Group::of(tag))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chapters_override_the_book_groups() {
+        let emit = Emit::from_config(&toml::from_str("raw = false").unwrap());
+        let tags = [
+            Tag::meta("description", "Intro"),
+            Tag::property("og:title", "Intro"),
+            Tag::script("application/ld+json", &serde_json::json!({})),
+            Tag::raw("banner", "<style></style>"),
+        ];
+        let emitted = |emit: &Emit| -> Vec<bool> { tags.iter().map(|t| emit.allows(t)).collect() };
+        assert_eq!(emitted(&emit), [true, true, true, false]);
+
+        let mut metadata = HashMap::from([(EMIT_KEY.to_string(), "[og, jsonld]".to_string())]);
+        let chapter = emit.for_chapter(&mut metadata).unwrap();
+        assert_eq!(emitted(&chapter), [false, true, true, false]);
+        assert!(metadata.is_empty());
+
+        let mut metadata = HashMap::from([(EMIT_KEY.to_string(), "none".to_string())]);
+        assert_eq!(
+            emitted(&emit.for_chapter(&mut metadata).unwrap()),
+            [false; 4]
+        );
+        let mut metadata = HashMap::from([(EMIT_KEY.to_string(), "og, css".to_string())]);
+        assert!(emit.for_chapter(&mut metadata).is_err());
+    }
+}
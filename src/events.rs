@@ -0,0 +1,345 @@
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, Utc};
+use serde_json::Value;
+use std::fmt::Write;
+use std::path::PathBuf;
+use toml::value::Table;
+
+use crate::aggregate::ChapterEntry;
+use crate::cli::NAME;
+use crate::metadata::MetadataError;
+use crate::tags::Tag;
+
+/// Metadata keys handled by the events emitter instead of the generic one.
+pub const EVENT_KEYS: [&str; 3] = ["event-start", "event-end", "location"];
+
+/// Emits the workshops and talks described by chapters as schema.org `Event`
+/// JSON-LD and `event:*` meta tags, with optional iCalendar files
+/// (`[preprocessor.metadata.events]`).
+#[derive(Debug, Clone)]
+pub struct Events {
+    pub ics: bool,
+    pub dir: PathBuf,
+}
+
+/// The start or end of an event: a day, a local time, or a time with an
+/// offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventTime {
+    Date(NaiveDate),
+    Local(NaiveDateTime),
+    Zoned(DateTime<FixedOffset>),
+}
+
+/// The event described by a chapter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub name: String,
+    pub description: Option<String>,
+    pub start: EventTime,
+    pub end: Option<EventTime>,
+    pub location: Option<String>,
+}
+
+impl EventTime {
+    /// Parses `YYYY-MM-DD`, `YYYY-MM-DD HH:MM[:SS]` (also with a `T`) and RFC
+    /// 3339 timestamps.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+        if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            return Some(Self::Date(date));
+        }
+        if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+            return Some(Self::Zoned(time));
+        }
+        [
+            "%Y-%m-%d %H:%M:%S",
+            "%Y-%m-%d %H:%M",
+            "%Y-%m-%dT%H:%M:%S",
+            "%Y-%m-%dT%H:%M",
+        ]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(Self::Local)
+    }
+
+    /// Returns the ISO 8601 form used in meta tags and JSON-LD.
+    pub fn iso(&self) -> String {
+        match self {
+            Self::Date(date) => date.format("%Y-%m-%d").to_string(),
+            Self::Local(time) => time.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            Self::Zoned(time) => time.to_rfc3339(),
+        }
+    }
+
+    /// Returns the iCalendar property `name` with this time. The end of an
+    /// all-day event is exclusive, so `end` moves dates to the next day.
+    fn ics(&self, name: &str, end: bool) -> String {
+        match self {
+            Self::Date(date) => {
+                let date = if end {
+                    *date + Duration::days(1)
+                } else {
+                    *date
+                };
+                format!("{};VALUE=DATE:{}", name, date.format("%Y%m%d"))
+            }
+            Self::Local(time) => format!("{}:{}", name, time.format("%Y%m%dT%H%M%S")),
+            Self::Zoned(time) => format!(
+                "{}:{}",
+                name,
+                time.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ")
+            ),
+        }
+    }
+
+    /// Returns the time for ordering, reading local times and days as UTC.
+    fn naive_utc(&self) -> NaiveDateTime {
+        match self {
+            Self::Date(date) => date.and_hms_opt(0, 0, 0).unwrap_or_default(),
+            Self::Local(time) => *time,
+            Self::Zoned(time) => time.naive_utc(),
+        }
+    }
+}
+
+impl Events {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            ics: table.get("ics").and_then(|v| v.as_bool()).unwrap_or(false),
+            dir: table
+                .get("path")
+                .and_then(|v| v.as_str())
+                .unwrap_or("events")
+                .into(),
+        }
+    }
+
+    /// Reads the event of a chapter that declares any event key. Events need a
+    /// valid `event-start`, and an `event-end` that isn't before it.
+    pub fn event(&self, entry: &ChapterEntry) -> Option<Result<Event, MetadataError>> {
+        if !EVENT_KEYS.iter().any(|k| entry.metadata.contains_key(*k)) {
+            return None;
+        }
+        Some(read_event(entry))
+    }
+
+    /// Where the iCalendar file of `entry` is written, relative to the
+    /// artifacts directory.
+    pub fn ics_path(&self, entry: &ChapterEntry) -> PathBuf {
+        self.dir.join(entry.path.with_extension("ics"))
+    }
+
+    /// Returns the `event:*` meta tags and the `Event` JSON-LD of an event.
+    /// Locations that are URLs are online events.
+    pub fn tags(&self, event: &Event, url: Option<&str>) -> Vec<Tag> {
+        let mut tags = vec![Tag::meta("event:start_time", &event.start.iso())];
+        let mut data = serde_json::json!({
+            "@context": "https://schema.org",
+            "@type": "Event",
+            "name": event.name,
+            "startDate": event.start.iso(),
+        });
+        if let Some(end) = &event.end {
+            tags.push(Tag::meta("event:end_time", &end.iso()));
+            data["endDate"] = end.iso().into();
+        }
+        if let Some(location) = &event.location {
+            tags.push(Tag::meta("event:location", location));
+            let online = location.starts_with("https://") || location.starts_with("http://");
+            data["location"] = if online {
+                serde_json::json!({ "@type": "VirtualLocation", "url": location })
+            } else {
+                serde_json::json!({ "@type": "Place", "name": location })
+            };
+            data["eventAttendanceMode"] = Value::from(if online {
+                "https://schema.org/OnlineEventAttendanceMode"
+            } else {
+                "https://schema.org/OfflineEventAttendanceMode"
+            });
+        }
+        if let Some(description) = &event.description {
+            data["description"] = description.as_str().into();
+        }
+        if let Some(url) = url {
+            data["url"] = url.into();
+        }
+        tags.push(Tag::script("application/ld+json", &data));
+        tags
+    }
+
+    /// Returns the iCalendar file of an event. `uid` identifies it across
+    /// builds; `now` is its timestamp.
+    pub fn ics(&self, event: &Event, uid: &str, url: Option<&str>, now: DateTime<Utc>) -> String {
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            format!("PRODID:-//{}//EN", NAME),
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}", escape(uid)),
+            format!("DTSTAMP:{}", now.format("%Y%m%dT%H%M%SZ")),
+            event.start.ics("DTSTART", false),
+        ];
+        if let Some(end) = &event.end {
+            lines.push(end.ics("DTEND", true));
+        }
+        lines.push(format!("SUMMARY:{}", escape(&event.name)));
+        if let Some(description) = &event.description {
+            lines.push(format!("DESCRIPTION:{}", escape(description)));
+        }
+        if let Some(location) = &event.location {
+            lines.push(format!("LOCATION:{}", escape(location)));
+        }
+        if let Some(url) = url {
+            lines.push(format!("URL:{}", url));
+        }
+        lines.push("END:VEVENT".to_string());
+        lines.push("END:VCALENDAR".to_string());
+
+        let mut ics = String::new();
+        for line in lines {
+            let _ = write!(ics, "{}\r\n", fold(&line));
+        }
+        ics
+    }
+}
+
+/// Reads the event keys of a chapter.
+fn read_event(entry: &ChapterEntry) -> Result<Event, MetadataError> {
+    let time = |key: &str| -> Result<Option<EventTime>, MetadataError> {
+        let Some(value) = entry.metadata.get(key) else {
+            return Ok(None);
+        };
+        EventTime::parse(value)
+            .map(Some)
+            .ok_or_else(|| MetadataError::InvalidValue {
+                key: key.to_string(),
+                message: format!("'{}' isn't a date or a date and time", value.trim()),
+            })
+    };
+    let start = time("event-start")?.ok_or_else(|| MetadataError::InvalidValue {
+        key: "event-start".to_string(),
+        message: format!("events need a start ('{}')", entry.path.display()),
+    })?;
+    let end = time("event-end")?;
+    if end.is_some_and(|end| end.naive_utc() < start.naive_utc()) {
+        return Err(MetadataError::InvalidValue {
+            key: "event-end".to_string(),
+            message: format!(
+                "the event ends before it starts ('{}')",
+                entry.path.display()
+            ),
+        });
+    }
+    Ok(Event {
+        name: entry.title().to_string(),
+        description: entry.metadata.get("description").cloned(),
+        start,
+        end,
+        location: entry
+            .metadata
+            .get("location")
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty()),
+    })
+}
+
+/// Escapes an iCalendar text value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Folds an iCalendar line into lines of at most 75 bytes.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::named_entry;
+    use crate::tags::to_html;
+
+    fn entry(metadata: &[(&str, &str)]) -> ChapterEntry {
+        named_entry("Rust workshop", "talks/rust.md", metadata)
+    }
+
+    #[test]
+    fn events_have_json_ld_and_ics() {
+        let events = Events::from_config(&toml::from_str("ics = true").unwrap());
+        let entry = entry(&[
+            ("title", "Rust workshop"),
+            ("event-start", "2024-06-01T10:00:00+02:00"),
+            ("event-end", "2024-06-01T12:30:00+02:00"),
+            ("location", "Room 4, Main Hall"),
+        ]);
+
+        let event = events.event(&entry).unwrap().unwrap();
+
+        assert_eq!(
+            to_html(&events.tags(&event, None)),
+            "<meta name=\"event:start_time\" content=\"2024-06-01T10:00:00+02:00\">\n\
+             <meta name=\"event:end_time\" content=\"2024-06-01T12:30:00+02:00\">\n\
+             <meta name=\"event:location\" content=\"Room 4, Main Hall\">\n\
+             <script type=\"application/ld+json\">{\"@context\":\"https://schema.org\",\
+             \"@type\":\"Event\",\"endDate\":\"2024-06-01T12:30:00+02:00\",\
+             \"eventAttendanceMode\":\"https://schema.org/OfflineEventAttendanceMode\",\
+             \"location\":{\"@type\":\"Place\",\"name\":\"Room 4, Main Hall\"},\
+             \"name\":\"Rust workshop\",\"startDate\":\"2024-06-01T10:00:00+02:00\"}</script>\n"
+        );
+        let now = DateTime::parse_from_rfc3339("2024-05-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            events.ics(&event, "talks/rust.md", None, now),
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//metadata-preprocessor//EN\r\nBEGIN:VEVENT\r\n\
+             UID:talks/rust.md\r\nDTSTAMP:20240501T000000Z\r\nDTSTART:20240601T080000Z\r\n\
+             DTEND:20240601T103000Z\r\nSUMMARY:Rust workshop\r\n\
+             LOCATION:Room 4\\, Main Hall\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"
+        );
+        assert_eq!(
+            events.ics_path(&entry),
+            PathBuf::from("events/talks/rust.ics")
+        );
+    }
+
+    #[test]
+    fn event_times_are_checked() {
+        let events = Events::from_config(&Table::new());
+        assert!(events.event(&entry(&[("title", "Intro")])).is_none());
+        assert!(events
+            .event(&entry(&[("location", "Online")]))
+            .unwrap()
+            .is_err());
+        assert!(events
+            .event(&entry(&[
+                ("event-start", "2024-06-02"),
+                ("event-end", "2024-06-01")
+            ]))
+            .unwrap()
+            .is_err());
+        assert_eq!(
+            EventTime::parse("2024-06-01 09:30")
+                .unwrap()
+                .ics("DTSTART", false),
+            "DTSTART:20240601T093000"
+        );
+        assert_eq!(
+            EventTime::parse("2024-06-01").unwrap().ics("DTEND", true),
+            "DTEND;VALUE=DATE:20240602"
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use crate::emit::Group;
+use crate::tags::Tag;
+
+/// How a chapter's metadata came to be, for `explain`: the steps that set,
+/// changed or removed each key, the defaults used, the keys left out by
+/// `valid-tags` and the plugins that emitted tags.
+#[derive(Debug, Clone, Default)]
+pub struct Explanation {
+    snapshot: HashMap<String, String>,
+    sources: BTreeMap<String, Vec<&'static str>>,
+    removed: BTreeMap<String, &'static str>,
+    defaults: Vec<String>,
+    filtered: Vec<String>,
+    emitters: Vec<String>,
+}
+
+impl Explanation {
+    /// Records the keys a step set, changed or removed, comparing the metadata
+    /// with what it was after the previous step.
+    pub fn step(&mut self, step: &'static str, metadata: &HashMap<String, String>) {
+        for (key, value) in metadata {
+            if self.snapshot.get(key) != Some(value) {
+                self.sources.entry(key.clone()).or_default().push(step);
+                self.removed.remove(key);
+            }
+        }
+        for key in self.snapshot.keys() {
+            if !metadata.contains_key(key) {
+                self.removed.insert(key.clone(), step);
+            }
+        }
+        self.snapshot = metadata.clone();
+    }
+
+    /// Records the default values used for keys the chapter doesn't set.
+    pub fn defaults(
+        &mut self,
+        default_values: &HashMap<String, String>,
+        metadata: &HashMap<String, String>,
+    ) {
+        self.defaults = default_values
+            .keys()
+            .filter(|key| !metadata.contains_key(*key))
+            .cloned()
+            .collect();
+        self.defaults.sort();
+    }
+
+    /// Records a key left out because it isn't in `valid-tags`.
+    pub fn filtered(&mut self, key: &str) {
+        self.filtered.push(key.to_string());
+    }
+
+    /// Records a plugin that emitted tags for the chapter.
+    pub fn emitter(&mut self, name: &str) {
+        self.emitters.push(name.to_string());
+    }
+
+    /// Renders the explanation of a chapter, with the tags it emits and the
+    /// ones left out by `emit`.
+    pub fn render(&self, chapter: &str, emitted: &[Tag], dropped: &[Tag]) -> String {
+        let mut out = format!("explain: {}\n", chapter);
+        for (key, steps) in &self.sources {
+            let _ = match self.removed.get(key) {
+                Some(step) => writeln!(out, "  {}: {}, removed by {}", key, steps.join(", "), step),
+                None => writeln!(out, "  {}: {}", key, steps.join(", ")),
+            };
+        }
+        let mut filtered = self.filtered.clone();
+        filtered.sort();
+        for (label, keys) in [
+            ("defaults", &self.defaults),
+            ("not in valid-tags", &filtered),
+            ("plugins", &self.emitters),
+        ] {
+            if !keys.is_empty() {
+                let _ = writeln!(out, "  {}: {}", label, keys.join(", "));
+            }
+        }
+        for (label, tags) in [("emitted", emitted), ("left out by emit", dropped)] {
+            if !tags.is_empty() {
+                let _ = writeln!(out, "  {}: {}", label, by_group(tags));
+            }
+        }
+        out
+    }
+}
+
+/// Lists tag names by group, `meta(title, author), og(og:title)`.
+fn by_group(tags: &[Tag]) -> String {
+    Group::ALL
+        .into_iter()
+        .filter_map(|group| {
+            let names: Vec<&str> = tags
+                .iter()
+                .filter(|tag| Group::of(tag) == group)
+                .map(|tag| tag.name.as_str())
+                .collect();
+            (!names.is_empty()).then(|| format!("{}({})", group.name(), names.join(", ")))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::metadata;
+
+    #[test]
+    fn explanation_traces_every_step() {
+        let mut explanation = Explanation::default();
+        explanation.step(
+            "frontmatter",
+            &metadata(&[("title", "Intro"), ("tags", "Rust")]),
+        );
+        explanation.step(
+            "rules",
+            &metadata(&[("title", "Intro"), ("tags", "Rust"), ("owner", "docs")]),
+        );
+        explanation.step(
+            "tag-synonyms",
+            &metadata(&[("title", "Intro"), ("tags", "rust")]),
+        );
+        explanation.defaults(
+            &metadata(&[("author", "Jane"), ("title", "Book")]),
+            &metadata(&[("title", "Intro")]),
+        );
+        explanation.filtered("tags");
+
+        let rendered = explanation.render(
+            "intro.md",
+            &[Tag::title("Intro"), Tag::meta("author", "Jane")],
+            &[Tag::property("og:title", "Intro")],
+        );
+
+        assert_eq!(
+            rendered,
+            "explain: intro.md\n\
+             \x20 owner: rules, removed by tag-synonyms\n\
+             \x20 tags: frontmatter, tag-synonyms\n\
+             \x20 title: frontmatter\n\
+             \x20 defaults: author\n\
+             \x20 not in valid-tags: tags\n\
+             \x20 emitted: meta(title, author)\n\
+             \x20 left out by emit: og(og:title)\n"
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use crate::metadata::{parse_list, MetadataError};
+use crate::tags::Tag;
+
+/// The key holding the fediverse handles of a chapter's authors.
+pub const FEDIVERSE_KEY: &str = "fediverse";
+
+/// Returns a `fediverse:creator` tag per handle of `value` (a handle or a
+/// list of them), so link previews on Mastodon credit the authors. Handles are
+/// `@user@instance`; the leading `@` is optional.
+pub fn creator_tags(value: &str) -> Result<Vec<Tag>, MetadataError> {
+    parse_list(value)
+        .iter()
+        .map(|handle| {
+            let handle = handle.trim_start_matches('@');
+            match handle.split_once('@') {
+                Some((user, instance))
+                    if !user.is_empty()
+                        && instance.contains('.')
+                        && !handle.contains(char::is_whitespace) =>
+                {
+                    Ok(Tag::meta("fediverse:creator", &format!("@{}", handle)))
+                }
+                _ => Err(MetadataError::InvalidValue {
+                    key: FEDIVERSE_KEY.to_string(),
+                    message: format!("'{}' isn't a @user@instance handle", handle),
+                }),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::to_html;
+
+    #[test]
+    fn creator_tags_normalize_handles() {
+        assert_eq!(
+            to_html(&creator_tags("[@jane@mastodon.social, john@hachyderm.io]").unwrap()),
+            "<meta name=\"fediverse:creator\" content=\"@jane@mastodon.social\">\n\
+             <meta name=\"fediverse:creator\" content=\"@john@hachyderm.io\">\n"
+        );
+        assert!(creator_tags("@jane").is_err());
+        assert!(creator_tags("jane@localhost").is_err());
+    }
+}
@@ -0,0 +1,308 @@
+use mdbook::book::{Book, BookItem};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::metadata::{parse_list, MetadataError};
+
+/// A metadata filter expression selecting the chapters of a partial build,
+/// `audience=public && status=stable` (`filter`, `--filter`).
+///
+/// `key=value` matches chapters whose value, or one of whose list items, is
+/// `value`; `key!=value` is its negation and a bare `key` matches chapters
+/// setting it. Terms combine with `!`, `&&`, `||` and parentheses; values with
+/// spaces or operators can be quoted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    Has(String),
+    Equals(String, String),
+    Not(Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Equals,
+    NotEquals,
+    Not,
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+impl Filter {
+    pub fn parse(expression: &str) -> Result<Self, MetadataError> {
+        let error = |message: &str| MetadataError::Expression {
+            expression: expression.to_string(),
+            message: message.to_string(),
+        };
+        let tokens = tokenize(expression).map_err(|m| error(&m))?;
+        let mut parser = Parser { tokens, next: 0 };
+        let filter = parser.or().map_err(|m| error(&m))?;
+        match parser.tokens.get(parser.next) {
+            None => Ok(filter),
+            Some(token) => Err(error(&format!("unexpected {}", describe(token)))),
+        }
+    }
+
+    /// Returns whether a chapter's metadata matches the filter.
+    pub fn matches(&self, metadata: &HashMap<String, String>) -> bool {
+        match self {
+            Filter::Has(key) => metadata.contains_key(key),
+            Filter::Equals(key, expected) => metadata.get(key).is_some_and(|value| {
+                value.trim() == expected || parse_list(value).iter().any(|item| item == expected)
+            }),
+            Filter::Not(filter) => !filter.matches(metadata),
+            Filter::And(a, b) => a.matches(metadata) && b.matches(metadata),
+            Filter::Or(a, b) => a.matches(metadata) || b.matches(metadata),
+        }
+    }
+
+    /// Removes the chapters not matching the filter from the book, looking up
+    /// their metadata by path.
+    pub fn retain(&self, book: &mut Book, metadata: &HashMap<PathBuf, HashMap<String, String>>) {
+        let empty = HashMap::new();
+        retain_chapters(book, &|path| {
+            self.matches(metadata.get(path).unwrap_or(&empty))
+        });
+    }
+}
+
+/// Removes the chapters whose path isn't kept from the book. Chapters are kept
+/// when one of their sub-chapters is, and part titles when one of their
+/// chapters is.
+pub fn retain_chapters(book: &mut Book, keep: &dyn Fn(&Path) -> bool) {
+    book.sections = retain_items(std::mem::take(&mut book.sections), keep);
+
+    // Drop the part titles left without chapters
+    let mut items: Vec<BookItem> = Vec::new();
+    for item in std::mem::take(&mut book.sections) {
+        if matches!(item, BookItem::PartTitle(_))
+            && matches!(items.last(), Some(BookItem::PartTitle(_)))
+        {
+            items.pop();
+        }
+        items.push(item);
+    }
+    while matches!(
+        items.last(),
+        Some(BookItem::PartTitle(_) | BookItem::Separator)
+    ) {
+        items.pop();
+    }
+    book.sections = items;
+}
+
+fn retain_items(items: Vec<BookItem>, keep: &dyn Fn(&Path) -> bool) -> Vec<BookItem> {
+    items
+        .into_iter()
+        .filter_map(|item| match item {
+            BookItem::Chapter(mut chapter) => {
+                chapter.sub_items = retain_items(chapter.sub_items, keep);
+                let kept = chapter.path.as_deref().is_some_and(keep);
+                (kept || !chapter.sub_items.is_empty()).then_some(BookItem::Chapter(chapter))
+            }
+            item => Some(item),
+        })
+        .collect()
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '=' => {
+                chars.next_if_eq(&'=');
+                Token::Equals
+            }
+            '!' if chars.next_if_eq(&'=').is_some() => Token::NotEquals,
+            '!' => Token::Not,
+            '&' if chars.next_if_eq(&'&').is_some() => Token::And,
+            '|' if chars.next_if_eq(&'|').is_some() => Token::Or,
+            '&' | '|' => return Err(format!("expected '{}{}'", c, c)),
+            '"' | '\'' => {
+                let mut word = String::new();
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => break,
+                        Some(other) => word.push(other),
+                        None => return Err("unterminated quote".to_string()),
+                    }
+                }
+                Token::Word(word)
+            }
+            c => {
+                let mut word = c.to_string();
+                while let Some(next) =
+                    chars.next_if(|c| !c.is_whitespace() && !"()=!&|\"'".contains(*c))
+                {
+                    word.push(next);
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Word(word) => format!("'{}'", word),
+        Token::Equals => "'='".to_string(),
+        Token::NotEquals => "'!='".to_string(),
+        Token::Not => "'!'".to_string(),
+        Token::And => "'&&'".to_string(),
+        Token::Or => "'||'".to_string(),
+        Token::Open => "'('".to_string(),
+        Token::Close => "')'".to_string(),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.next).cloned();
+        self.next += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Filter, String> {
+        let mut filter = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next += 1;
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<Filter, String> {
+        let mut filter = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.next += 1;
+            filter = Filter::And(Box::new(filter), Box::new(self.unary()?));
+        }
+        Ok(filter)
+    }
+
+    fn unary(&mut self) -> Result<Filter, String> {
+        match self.advance() {
+            Some(Token::Not) => Ok(Filter::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let filter = self.or()?;
+                match self.advance() {
+                    Some(Token::Close) => Ok(filter),
+                    _ => Err("expected ')'".to_string()),
+                }
+            }
+            Some(Token::Word(key)) => {
+                let negated = match self.peek() {
+                    Some(Token::Equals) => false,
+                    Some(Token::NotEquals) => true,
+                    _ => return Ok(Filter::Has(key)),
+                };
+                self.next += 1;
+                let value = match self.advance() {
+                    Some(Token::Word(value)) => value,
+                    _ => return Err(format!("expected a value for '{}'", key)),
+                };
+                let filter = Filter::Equals(key, value);
+                Ok(if negated {
+                    Filter::Not(Box::new(filter))
+                } else {
+                    filter
+                })
+            }
+            Some(token) => Err(format!("unexpected {}", describe(&token))),
+            None => Err("unexpected end of the expression".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::metadata;
+    use mdbook::book::Chapter;
+
+    #[test]
+    fn filter_matches_metadata() {
+        let filter = Filter::parse(
+            "audience=public && (status == stable || !status) && tags!='in progress'",
+        )
+        .unwrap();
+
+        assert!(filter.matches(&metadata(&[
+            ("audience", "[internal, public]"),
+            ("status", "stable"),
+        ])));
+        assert!(filter.matches(&metadata(&[("audience", "public"), ("tags", "[cli]")])));
+        assert!(!filter.matches(&metadata(&[
+            ("audience", "public"),
+            ("tags", "cli, in progress"),
+        ])));
+        assert!(!filter.matches(&metadata(&[("audience", "public"), ("status", "draft")])));
+        assert!(Filter::parse("audience=").is_err());
+        assert!(Filter::parse("(status=stable").is_err());
+        assert!(Filter::parse("status=stable &").is_err());
+    }
+
+    #[test]
+    fn retain_keeps_matching_chapters_and_their_parents() {
+        let chapter = |name: &str, path: &str, sub_items: Vec<BookItem>| {
+            let mut chapter = Chapter::new(name, String::new(), path, Vec::new());
+            chapter.sub_items = sub_items;
+            BookItem::Chapter(chapter)
+        };
+        let mut book = Book::new();
+        book.sections = vec![
+            chapter("Intro", "intro.md", Vec::new()),
+            BookItem::PartTitle("Guide".to_string()),
+            chapter(
+                "Guide",
+                "guide/index.md",
+                vec![chapter("Retries", "guide/retries.md", Vec::new())],
+            ),
+            BookItem::PartTitle("Internals".to_string()),
+            chapter("Internals", "internals.md", Vec::new()),
+        ];
+        let chapters = HashMap::from([
+            (
+                PathBuf::from("intro.md"),
+                metadata(&[("audience", "public")]),
+            ),
+            (
+                PathBuf::from("guide/retries.md"),
+                metadata(&[("audience", "public")]),
+            ),
+        ]);
+
+        Filter::parse("audience=public")
+            .unwrap()
+            .retain(&mut book, &chapters);
+
+        let names: Vec<String> = book
+            .iter()
+            .map(|item| match item {
+                BookItem::Chapter(chapter) => chapter.name.clone(),
+                BookItem::PartTitle(title) => format!("# {}", title),
+                BookItem::Separator => "---".to_string(),
+            })
+            .collect();
+        assert_eq!(names, vec!["Intro", "# Guide", "Guide", "Retries"]);
+    }
+}
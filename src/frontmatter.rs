@@ -0,0 +1,361 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+/// The syntax of a metadata block: `---` fenced `key: value` lines, or `+++`
+/// fenced TOML as written by Hugo and Zola.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "yaml" | "yml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            _ => None,
+        }
+    }
+
+    fn fence(self) -> &'static str {
+        match self {
+            Format::Yaml => "---",
+            Format::Toml => "+++",
+        }
+    }
+
+    fn sidecar_extension(self) -> &'static str {
+        match self {
+            Format::Yaml => "meta.yml",
+            Format::Toml => "meta.toml",
+        }
+    }
+}
+
+/// What may come before the metadata block of a chapter, besides whitespace:
+/// a byte order mark, or an HTML comment such as a license header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prefix {
+    Bom,
+    HtmlComment,
+}
+
+impl Prefix {
+    pub const ALL: [Prefix; 2] = [Prefix::Bom, Prefix::HtmlComment];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bom" => Some(Prefix::Bom),
+            "html-comment" => Some(Prefix::HtmlComment),
+            _ => None,
+        }
+    }
+
+    /// Returns `content` after this prefix, if it starts with it.
+    fn strip(self, content: &str) -> Option<&str> {
+        match self {
+            Prefix::Bom => content.strip_prefix('\u{feff}'),
+            Prefix::HtmlComment => {
+                let comment = content.strip_prefix("<!--")?;
+                Some(&comment[comment.find("-->")? + 3..])
+            }
+        }
+    }
+}
+
+/// The metadata block opening a chapter. `start..end` spans the block from its
+/// opening fence to the end of its closing fence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Block<'a> {
+    pub format: Format,
+    pub body: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Block<'_> {
+    /// Returns the 1-based line number, in `content`, of the line of index `i`
+    /// in `body.lines()`. The first of these is the rest of the opening fence.
+    pub fn line_number(&self, content: &str, i: usize) -> usize {
+        content[..self.start].matches('\n').count() + 1 + i
+    }
+}
+
+/// Finds the metadata block of a chapter. The block must open the chapter
+/// (after optional whitespace, a byte order mark and HTML comments) and ends
+/// at the next fence of the same kind.
+pub fn find(content: &str) -> Option<Block<'_>> {
+    find_after(content, &Prefix::ALL)
+}
+
+/// Like [`find`], only skipping the given prefixes before the block.
+pub fn find_after<'a>(content: &'a str, prefixes: &[Prefix]) -> Option<Block<'a>> {
+    let mut rest = content.trim_start();
+    while let Some(stripped) = prefixes.iter().find_map(|p| p.strip(rest)) {
+        rest = stripped.trim_start();
+    }
+    let start = content.len() - rest.len();
+    let mut lines = lines_from(content, start);
+    let (_, opener) = lines.next()?;
+    let format = [Format::Yaml, Format::Toml]
+        .into_iter()
+        .find(|f| is_fence(opener, *f))?;
+    let body_start = start + 3;
+    let (close, _) = lines.find(|(_, line)| is_fence(line, format))?;
+    Some(Block {
+        format,
+        body: &content[body_start..close],
+        start,
+        end: close + 3,
+    })
+}
+
+/// Returns the lines of `content` from `start` with their offsets, without
+/// their line breaks.
+fn lines_from(content: &str, start: usize) -> impl Iterator<Item = (usize, &str)> {
+    content[start..]
+        .split_inclusive('\n')
+        .scan(start, |offset, line| {
+            let line_start = *offset;
+            *offset += line.len();
+            Some((line_start, line.trim_end_matches('\n')))
+        })
+}
+
+/// Whether a line is a fence of the format on its own, `---` or `+++` with
+/// only trailing whitespace, so `----` rules and `---` inside values aren't.
+fn is_fence(line: &str, format: Format) -> bool {
+    line.trim_end_matches([' ', '\t', '\r']) == format.fence()
+}
+
+/// Parses a TOML block into `(key, value)` pairs. Arrays become `[a, b]` list
+/// values, and keys of nested tables are joined with dots.
+pub fn parse_toml(body: &str) -> Result<Vec<(String, String)>, String> {
+    let table: toml::value::Table = toml::from_str(body).map_err(|e| e.to_string())?;
+    Ok(flatten_table(&table))
+}
+
+/// Flattens a TOML table into `(key, value)` pairs, the way TOML blocks are read.
+pub fn flatten_table(table: &toml::value::Table) -> Vec<(String, String)> {
+    let mut entries = Vec::new();
+    flatten("", table, &mut entries);
+    entries
+}
+
+fn flatten(prefix: &str, table: &toml::value::Table, entries: &mut Vec<(String, String)>) {
+    for (key, value) in table {
+        let key = format!("{}{}", prefix, key);
+        match value {
+            Value::Table(nested) => flatten(&format!("{}.", key), nested, entries),
+            value => entries.push((key, scalar(value))),
+        }
+    }
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(items) => format!(
+            "[{}]",
+            items.iter().map(scalar).collect::<Vec<_>>().join(", ")
+        ),
+        // Inline tables, such as the entries of an array of tables
+        Value::Table(table) => format!(
+            "{{{}}}",
+            table
+                .iter()
+                .map(|(key, value)| format!("{} = {}", toml_key(key), Value::String(scalar(value))))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        value => value.to_string(),
+    }
+}
+
+/// Renders a single `(key, value)` pair as a line of a block, without the
+/// line break.
+pub fn render_entry(format: Format, key: &str, value: &str) -> String {
+    match format {
+        Format::Yaml => format!("{}: {}", key, value.replace(['\r', '\n'], " ")),
+        Format::Toml => format!("{} = {}", toml_key(key), Value::String(value.to_string())),
+    }
+}
+
+/// Renders `(key, value)` pairs as the body of a block, without fences.
+pub fn render_body(format: Format, entries: &[(String, String)]) -> String {
+    entries
+        .iter()
+        .map(|(key, value)| render_entry(format, key, value) + "\n")
+        .collect()
+}
+
+/// Renders `(key, value)` pairs as a fenced block.
+pub fn render(format: Format, entries: &[(String, String)]) -> String {
+    format!(
+        "{fence}\n{}{fence}",
+        render_body(format, entries),
+        fence = format.fence()
+    )
+}
+
+/// Returns a TOML key, quoted unless it is a bare key.
+pub fn toml_key(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if bare {
+        key.to_string()
+    } else {
+        Value::String(key.to_string()).to_string()
+    }
+}
+
+/// The sidecar metadata file of a chapter in the given format, e.g.
+/// `intro.meta.yml` next to `intro.md`.
+pub fn sidecar_path(chapter: &Path, format: Format) -> PathBuf {
+    chapter.with_extension(format.sidecar_extension())
+}
+
+/// Finds the sidecar metadata file of a chapter, if any.
+pub fn find_sidecar(chapter: &Path) -> Option<(PathBuf, Format)> {
+    [Format::Yaml, Format::Toml]
+        .into_iter()
+        .map(|format| (sidecar_path(chapter, format), format))
+        .find(|(path, _)| path.is_file())
+}
+
+/// Rewrites the body of the metadata block of a chapter file, or else of its
+/// sidecar file, keeping the rest of the file. Returns whether it changed.
+pub fn rewrite_file(
+    path: &Path,
+    dry_run: bool,
+    rewrite: impl Fn(Format, &str) -> String,
+) -> Result<bool> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read '{}'", path.display()))?;
+
+    let (target, original, updated) = match find(&content) {
+        Some(block) => {
+            let body = rewrite(block.format, block.body);
+            let fence = &content[block.start..block.start + 3];
+            let updated = format!(
+                "{}{}{}{}{}",
+                &content[..block.start],
+                fence,
+                body,
+                fence,
+                &content[block.end..]
+            );
+            (path.to_path_buf(), content, updated)
+        }
+        None => match find_sidecar(path) {
+            Some((sidecar, format)) => {
+                let body = fs::read_to_string(&sidecar)
+                    .with_context(|| format!("Failed to read '{}'", sidecar.display()))?;
+                let updated = rewrite(format, &body);
+                (sidecar, body, updated)
+            }
+            None => return Ok(false),
+        },
+    };
+
+    if updated == original {
+        return Ok(false);
+    }
+    if !dry_run {
+        fs::write(&target, updated)
+            .with_context(|| format!("Failed to write '{}'", target.display()))?;
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_locates_both_fences() {
+        let content = "\n---\ntitle: A\n---\n# A";
+        let block = find(content).unwrap();
+        assert_eq!(block.format, Format::Yaml);
+        assert_eq!(block.body, "\ntitle: A\n");
+        assert_eq!(&content[block.end..], "\n# A");
+
+        let block = find("+++\ntitle = \"A\"\n+++\n# A").unwrap();
+        assert_eq!(block.format, Format::Toml);
+        assert_eq!(block.body, "\ntitle = \"A\"\n");
+
+        assert_eq!(find("# A\n---\nnot: metadata\n---"), None);
+    }
+
+    #[test]
+    fn find_matches_fences_as_whole_lines() {
+        let content = "---\ntitle: A --- B\nsummary: ---\n---  \n# A";
+        let block = find(content).unwrap();
+        assert_eq!(block.body, "\ntitle: A --- B\nsummary: ---\n");
+        assert_eq!(&content[block.end..], "  \n# A");
+
+        assert_eq!(find("----\n# A\n\nText\n----\n"), None);
+        assert_eq!(find("---x\ntitle: A\n---"), None);
+        assert_eq!(find("+++\ntitle = \"A +++ B\"\n+++++\n"), None);
+    }
+
+    #[test]
+    fn find_skips_allowed_prefixes() {
+        let content =
+            "\u{feff}<!-- SPDX-License-Identifier: MIT -->\r\n---\r\ntitle: A\r\n---\r\n# A";
+        let block = find(content).unwrap();
+        assert_eq!(block.body, "\r\ntitle: A\r\n");
+        assert_eq!(&content[block.end..], "\r\n# A");
+
+        assert_eq!(find_after(content, &[Prefix::Bom]), None);
+        assert!(find_after("\u{feff}---\ntitle: A\n---", &[Prefix::Bom]).is_some());
+        assert_eq!(find("<!-- unclosed\n---\ntitle: A\n---"), None);
+    }
+
+    #[test]
+    fn parse_toml_flattens_arrays_and_tables() {
+        let entries = parse_toml(
+            "title = \"Intro\"\nweight = 3\ndraft = false\n\n[taxonomies]\ntags = [\"rust\", \"mdbook\"]\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                ("draft".to_string(), "false".to_string()),
+                ("taxonomies.tags".to_string(), "[rust, mdbook]".to_string()),
+                ("title".to_string(), "Intro".to_string()),
+                ("weight".to_string(), "3".to_string()),
+            ]
+        );
+        assert!(parse_toml("title = ").is_err());
+    }
+
+    #[test]
+    fn render_round_trips_through_parse() {
+        let entries = vec![
+            ("title".to_string(), "Say \"hi\"".to_string()),
+            ("taxonomies.tags".to_string(), "[rust]".to_string()),
+        ];
+
+        assert_eq!(
+            render(Format::Yaml, &entries),
+            "---\ntitle: Say \"hi\"\ntaxonomies.tags: [rust]\n---"
+        );
+        let toml = render(Format::Toml, &entries);
+        assert_eq!(
+            toml,
+            "+++\ntitle = \"Say \\\"hi\\\"\"\n\"taxonomies.tags\" = \"[rust]\"\n+++"
+        );
+        let mut parsed = parse_toml(find(&toml).unwrap().body).unwrap();
+        parsed.sort();
+        let mut expected = entries;
+        expected.sort();
+        assert_eq!(parsed, expected);
+    }
+}
@@ -0,0 +1,139 @@
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::links::parse_maps;
+use crate::metadata::MetadataError;
+use crate::tags::Tag;
+
+/// The key holding the location a chapter is about.
+pub const GEO_KEY: &str = "geo";
+
+/// Takes the `geo` key out of `metadata`, a map with a `lat`, a `long` and an
+/// optional `placename` (or a `[geo]` table in TOML blocks), returning its `geo.position`, `ICBM` and
+/// `geo.placename` meta tags and adding a `Place` as the JSON-LD
+/// `contentLocation`.
+pub fn tags(
+    metadata: &mut HashMap<String, String>,
+    properties: &mut Map<String, Value>,
+) -> Result<Vec<Tag>, MetadataError> {
+    let mut map = match metadata.remove(GEO_KEY) {
+        Some(value) => {
+            let mut maps = parse_maps(GEO_KEY, &value)?;
+            let (Some(map), true) = (maps.pop(), maps.is_empty()) else {
+                return Err(invalid("expected a single map with a lat and a long"));
+            };
+            map
+        }
+        // A `[geo]` table in TOML blocks, flattened to `geo.lat` and so on
+        None if metadata.contains_key("geo.lat") || metadata.contains_key("geo.long") => {
+            ["lat", "long", "placename"]
+                .into_iter()
+                .filter_map(|name| {
+                    let value = metadata.remove(&format!("{}.{}", GEO_KEY, name))?;
+                    Some((name.to_string(), value))
+                })
+                .collect()
+        }
+        None => return Ok(Vec::new()),
+    };
+    let latitude = coordinate(&mut map, "lat", 90.0)?;
+    let longitude = coordinate(&mut map, "long", 180.0)?;
+    let placename = map
+        .remove("placename")
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
+
+    let mut tags = vec![
+        Tag::meta("geo.position", &format!("{};{}", latitude, longitude)),
+        Tag::meta("ICBM", &format!("{}, {}", latitude, longitude)),
+    ];
+    let mut place = serde_json::json!({
+        "@type": "Place",
+        "geo": {
+            "@type": "GeoCoordinates",
+            "latitude": latitude,
+            "longitude": longitude,
+        },
+    });
+    if let Some(placename) = placename {
+        tags.push(Tag::meta("geo.placename", &placename));
+        place["name"] = placename.into();
+    }
+    properties.insert("contentLocation".to_string(), place);
+    Ok(tags)
+}
+
+/// Takes a coordinate out of the map, in decimal degrees up to `max`.
+fn coordinate(
+    map: &mut BTreeMap<String, String>,
+    name: &str,
+    max: f64,
+) -> Result<f64, MetadataError> {
+    let value = map
+        .remove(name)
+        .ok_or_else(|| invalid(&format!("missing '{}'", name)))?;
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|degrees| degrees.abs() <= max)
+        .ok_or_else(|| {
+            invalid(&format!(
+                "'{}' isn't a {} between -{} and {}",
+                value.trim(),
+                name,
+                max,
+                max
+            ))
+        })
+}
+
+fn invalid(message: &str) -> MetadataError {
+    MetadataError::InvalidValue {
+        key: GEO_KEY.to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::to_html;
+
+    #[test]
+    fn geo_emits_meta_tags_and_a_place() {
+        let mut metadata = HashMap::from([(
+            GEO_KEY.to_string(),
+            "{lat: 48.8584, long: 2.2945, placename: Eiffel Tower}".to_string(),
+        )]);
+        let mut properties = Map::new();
+
+        let geo_tags = tags(&mut metadata, &mut properties).unwrap();
+
+        assert!(metadata.is_empty());
+        assert_eq!(
+            to_html(&geo_tags),
+            "<meta name=\"geo.position\" content=\"48.8584;2.2945\">\n\
+             <meta name=\"ICBM\" content=\"48.8584, 2.2945\">\n\
+             <meta name=\"geo.placename\" content=\"Eiffel Tower\">\n"
+        );
+        assert_eq!(
+            properties["contentLocation"],
+            serde_json::json!({
+                "@type": "Place",
+                "name": "Eiffel Tower",
+                "geo": {"@type": "GeoCoordinates", "latitude": 48.8584, "longitude": 2.2945}
+            })
+        );
+
+        let mut metadata = HashMap::from([
+            ("geo.lat".to_string(), "-33.8568".to_string()),
+            ("geo.long".to_string(), "151.2153".to_string()),
+        ]);
+        assert_eq!(tags(&mut metadata, &mut properties).unwrap().len(), 2);
+        assert!(metadata.is_empty());
+
+        let mut metadata = HashMap::from([(GEO_KEY.to_string(), "{lat: 91, long: 0}".to_string())]);
+        assert!(tags(&mut metadata, &mut properties).is_err());
+    }
+}
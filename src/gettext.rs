@@ -0,0 +1,292 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::value::Table;
+
+use crate::frontmatter;
+use crate::metadata::parse_metadata;
+use crate::sources::{chapter_files, chapter_name, load_config};
+
+/// Translation of metadata values with gettext catalogs, alongside
+/// mdbook-i18n-helpers (`[preprocessor.metadata.gettext]`).
+#[derive(Debug, Clone)]
+pub struct Gettext {
+    pub keys: Vec<String>,
+    pub po_dir: PathBuf,
+}
+
+impl Gettext {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            keys: table
+                .get("keys")
+                .and_then(|v| v.as_array())
+                .map(|keys| {
+                    keys.iter()
+                        .filter_map(|k| k.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_else(|| vec!["title".into(), "description".into()]),
+            po_dir: table
+                .get("po-dir")
+                .and_then(|v| v.as_str())
+                .unwrap_or("po")
+                .into(),
+        }
+    }
+
+    /// Reads the gettext settings of a book.
+    pub fn load(book_dir: &Path) -> Result<Self> {
+        let (config, _) = load_config(book_dir)?;
+        let table = config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("gettext"))
+            .and_then(|v| v.as_table())
+            .cloned()
+            .unwrap_or_default();
+        Ok(Self::from_config(&table))
+    }
+
+    /// Extracts the values of the translated keys of every chapter into a POT
+    /// catalog, with a `#: path:line` reference per use.
+    pub fn extract(&self, book_dir: &Path) -> Result<String> {
+        let (_, src_dir) = load_config(book_dir)?;
+        // Keyed by message, in order of first use
+        let mut messages: Vec<(String, Vec<String>)> = Vec::new();
+        for path in chapter_files(&src_dir) {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read '{}'", path.display()))?;
+            let Ok((metadata, _)) = parse_metadata(&content, true) else {
+                continue;
+            };
+            let name = chapter_name(&src_dir, &path);
+            for key in &self.keys {
+                let Some(value) = metadata.get(key).filter(|v| !v.trim().is_empty()) else {
+                    continue;
+                };
+                let reference = match key_line(&content, key) {
+                    Some(line) => format!("{}:{}", name, line),
+                    None => name.clone(),
+                };
+                match messages.iter_mut().find(|(msgid, _)| msgid == value) {
+                    Some((_, references)) => references.push(reference),
+                    None => messages.push((value.clone(), vec![reference])),
+                }
+            }
+        }
+
+        let mut pot = String::from(
+            "msgid \"\"\nmsgstr \"\"\n\"Content-Type: text/plain; charset=UTF-8\\n\"\n",
+        );
+        for (msgid, references) in messages {
+            let _ = write!(
+                pot,
+                "\n#: {}\nmsgid {}\nmsgstr \"\"\n",
+                references.join(" "),
+                quote(&msgid)
+            );
+        }
+        Ok(pot)
+    }
+
+    /// Reads the catalog of a language, `<po-dir>/<language>.po`, or `None`
+    /// when there is none.
+    pub fn catalog(&self, root: &Path, language: &str) -> Result<Option<HashMap<String, String>>> {
+        let path = root.join(&self.po_dir).join(format!("{}.po", language));
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read '{}'", path.display()))?;
+        Ok(Some(parse_po(&content)))
+    }
+
+    /// Replaces the values of the translated keys with their translations.
+    pub fn translate(
+        &self,
+        metadata: &mut HashMap<String, String>,
+        catalog: &HashMap<String, String>,
+    ) {
+        for key in &self.keys {
+            if let Some(value) = metadata.get_mut(key) {
+                if let Some(translation) = catalog.get(value.as_str()) {
+                    *value = translation.clone();
+                }
+            }
+        }
+    }
+}
+
+/// Parses the translated messages of a PO file. Fuzzy, untranslated and
+/// plural messages, and the header, are left out.
+pub fn parse_po(content: &str) -> HashMap<String, String> {
+    #[derive(PartialEq)]
+    enum Field {
+        None,
+        Id,
+        Str,
+        Other,
+    }
+    let mut catalog = HashMap::new();
+    let (mut msgid, mut msgstr) = (String::new(), String::new());
+    let (mut field, mut fuzzy, mut plural) = (Field::None, false, false);
+    let mut flush = |msgid: &mut String, msgstr: &mut String, fuzzy: bool, plural: bool| {
+        if !msgid.is_empty() && !msgstr.is_empty() && !fuzzy && !plural {
+            catalog.insert(std::mem::take(msgid), std::mem::take(msgstr));
+        }
+        msgid.clear();
+        msgstr.clear();
+    };
+
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            if field == Field::Str {
+                flush(&mut msgid, &mut msgstr, fuzzy, plural);
+                (field, fuzzy, plural) = (Field::None, false, false);
+            }
+            if line.starts_with("#,") && line.contains("fuzzy") {
+                fuzzy = true;
+            }
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("msgid ") {
+            if field == Field::Str {
+                flush(&mut msgid, &mut msgstr, fuzzy, plural);
+                (fuzzy, plural) = (false, false);
+            }
+            field = Field::Id;
+            msgid = unquote(rest);
+        } else if let Some(rest) = line.strip_prefix("msgstr ") {
+            field = Field::Str;
+            msgstr = unquote(rest);
+        } else if line.starts_with("msgid_plural") {
+            plural = true;
+            field = Field::Other;
+        } else if line.starts_with("msgstr[") {
+            field = Field::Str;
+        } else if line.starts_with('"') {
+            match field {
+                Field::Id => msgid.push_str(&unquote(line)),
+                Field::Str if !plural => msgstr.push_str(&unquote(line)),
+                _ => {}
+            }
+        } else {
+            // msgctxt and unknown keywords
+            if field == Field::Str {
+                flush(&mut msgid, &mut msgstr, fuzzy, plural);
+                (fuzzy, plural) = (false, false);
+            }
+            field = Field::Other;
+        }
+    }
+    flush(&mut msgid, &mut msgstr, fuzzy, plural);
+    catalog
+}
+
+/// Returns the 1-based line of `key` in the metadata block of `content`.
+fn key_line(content: &str, key: &str) -> Option<usize> {
+    let block = frontmatter::find(content)?;
+    let i = block.body.lines().position(|line| {
+        let line = line.trim_start().trim_start_matches('"');
+        line.strip_prefix(key).is_some_and(|rest| {
+            rest.trim_start_matches('"')
+                .trim_start()
+                .starts_with([':', '='])
+        })
+    })?;
+    Some(block.line_number(content, i))
+}
+
+/// Quotes a PO string.
+fn quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t");
+    format!("\"{}\"", escaped)
+}
+
+/// Unquotes a PO string.
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value);
+    let mut unquoted = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unquoted.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unquoted.push('\n'),
+            Some('t') => unquoted.push('\t'),
+            Some(c) => unquoted.push(c),
+            None => {}
+        }
+    }
+    unquoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_po_reads_translated_messages() {
+        let po = r#"msgid ""
+msgstr ""
+"Language: de\n"
+
+#: intro.md:2
+msgid "Getting "
+"started"
+msgstr "Erste Schritte"
+
+#, fuzzy
+msgid "Overview"
+msgstr "Überblick"
+
+msgid "Say \"hi\""
+msgstr "Sag \"hallo\""
+
+msgid "Untranslated"
+msgstr ""
+"#;
+        let catalog = parse_po(po);
+        assert_eq!(
+            catalog,
+            HashMap::from([
+                ("Getting started".to_string(), "Erste Schritte".to_string()),
+                ("Say \"hi\"".to_string(), "Sag \"hallo\"".to_string()),
+            ])
+        );
+
+        let gettext = Gettext::from_config(&Table::new());
+        let mut metadata = HashMap::from([
+            ("title".to_string(), "Getting started".to_string()),
+            ("author".to_string(), "Say \"hi\"".to_string()),
+        ]);
+        gettext.translate(&mut metadata, &catalog);
+        assert_eq!(metadata["title"], "Erste Schritte");
+        assert_eq!(metadata["author"], "Say \"hi\"");
+    }
+
+    #[test]
+    fn key_line_finds_yaml_and_toml_keys() {
+        assert_eq!(
+            key_line("---\ntitle: A\ndescription: B\n---\n", "description"),
+            Some(3)
+        );
+        assert_eq!(
+            key_line("\n+++\n\"title\" = \"A\"\n+++\n", "title"),
+            Some(3)
+        );
+        assert_eq!(quote("a \"b\"\n"), "\"a \\\"b\\\"\\n\"");
+    }
+}
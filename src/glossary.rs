@@ -0,0 +1,145 @@
+use mdbook::book::Chapter;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+use toml::value::Table;
+
+use crate::aggregate::{generated_chapter, relative_link, ChapterEntry, Position};
+use crate::metadata::parse_list;
+
+/// Configuration of the generated glossary chapter
+/// (`[preprocessor.metadata.glossary]`).
+#[derive(Debug, Clone)]
+pub struct Glossary {
+    pub title: String,
+    pub path: PathBuf,
+    pub position: Position,
+}
+
+impl Glossary {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            title: table
+                .get("title")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Glossary")
+                .to_string(),
+            path: table
+                .get("path")
+                .and_then(|v| v.as_str())
+                .unwrap_or("glossary.md")
+                .into(),
+            position: table
+                .get("position")
+                .and_then(Position::from_toml)
+                .unwrap_or(Position::End),
+        }
+    }
+
+    /// Builds the glossary chapter from every chapter's `defines` key. Each term
+    /// links back to the chapter(s) defining it; terms defined in several
+    /// chapters are also returned as warnings.
+    pub fn chapter(&self, chapters: &[ChapterEntry]) -> (Chapter, Vec<String>) {
+        // Keyed by lowercase term so that "API" and "api" are the same entry
+        let mut terms: BTreeMap<String, (String, Vec<&ChapterEntry>)> = BTreeMap::new();
+        for entry in chapters {
+            let Some(defines) = entry.metadata.get("defines") else {
+                continue;
+            };
+            for term in parse_list(defines) {
+                terms
+                    .entry(term.to_lowercase())
+                    .or_insert_with(|| (term.clone(), Vec::new()))
+                    .1
+                    .push(entry);
+            }
+        }
+
+        let mut content = format!("# {}\n", self.title);
+        let mut current_letter = None;
+        let mut warnings = Vec::new();
+        for (term, definitions) in terms.values() {
+            if definitions.len() > 1 {
+                warnings.push(format!(
+                    "Glossary term '{}' is defined in multiple chapters: {}",
+                    term,
+                    definitions
+                        .iter()
+                        .map(|e| e.path.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+
+            let letter = term.chars().next().map(|c| c.to_uppercase().to_string());
+            if letter != current_letter {
+                let _ = write!(content, "\n## {}\n\n", letter.as_deref().unwrap_or(""));
+                current_letter = letter;
+            }
+
+            let links: Vec<String> = definitions
+                .iter()
+                .map(|e| format!("[{}]({})", e.title(), relative_link(&self.path, &e.path)))
+                .collect();
+            let _ = writeln!(content, "- **{}** — {}", term, links.join(", "));
+        }
+
+        (
+            generated_chapter(&self.title, content, &self.path),
+            warnings,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::named_entry;
+
+    fn entry(name: &str, path: &str, defines: &str) -> ChapterEntry {
+        named_entry(name, path, &[("defines", defines)])
+    }
+
+    #[test]
+    fn glossary_lists_terms_alphabetically() {
+        let glossary = Glossary::from_config(&Table::new());
+        let chapters = vec![
+            entry("Retries", "guide/retries.md", "[idempotency, backpressure]"),
+            entry("Queues", "guide/queues.md", "[Backpressure]"),
+            entry("APIs", "api.md", "api"),
+        ];
+
+        let (chapter, warnings) = glossary.chapter(&chapters);
+
+        assert_eq!(chapter.path, Some(PathBuf::from("glossary.md")));
+        assert_eq!(
+            chapter.content,
+            "# Glossary\n\
+             \n## A\n\n\
+             - **api** — [APIs](api.md)\n\
+             \n## B\n\n\
+             - **backpressure** — [Retries](guide/retries.md), [Queues](guide/queues.md)\n\
+             \n## I\n\n\
+             - **idempotency** — [Retries](guide/retries.md)\n"
+        );
+        assert_eq!(
+            warnings,
+            vec![
+                "Glossary term 'backpressure' is defined in multiple chapters: \
+                 guide/retries.md, guide/queues.md"
+            ]
+        );
+    }
+
+    #[test]
+    fn glossary_page_snapshot() {
+        let glossary = Glossary::from_config(&Table::new());
+        let chapters = vec![
+            entry("Queues", "guide/queues.md", "[Backpressure, Dead letter]"),
+            entry("Retries", "guide/retries.md", "backpressure, jitter"),
+            entry("Überblick", "de/überblick.md", "Überblick"),
+        ];
+
+        insta::assert_snapshot!(glossary.chapter(&chapters).0.content);
+    }
+}
@@ -0,0 +1,305 @@
+use mdbook::book::Chapter;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+use toml::value::Table;
+
+use crate::aggregate::{generated_chapter, resolve_reference, ChapterEntry, Position};
+use crate::metadata::parse_list;
+
+/// The relationship an edge of the chapter graph represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    Prerequisite,
+    SeeAlso,
+    Series,
+}
+
+/// A directed edge between two chapters, as indices into the chapter list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+/// Configuration of the chapter dependency graph export
+/// (`[preprocessor.metadata.graph]`).
+#[derive(Debug, Clone)]
+pub struct Graph {
+    pub dot: bool,
+    pub mermaid: bool,
+    pub map_page: Option<MapPage>,
+}
+
+/// The optional "Map of the book" chapter embedding the mermaid diagram.
+#[derive(Debug, Clone)]
+pub struct MapPage {
+    pub title: String,
+    pub path: PathBuf,
+    pub position: Position,
+}
+
+impl Graph {
+    pub fn from_config(table: &Table) -> Self {
+        let formats: Vec<&str> = table
+            .get("formats")
+            .and_then(|v| v.as_array())
+            .map(|f| f.iter().filter_map(|v| v.as_str()).collect())
+            .unwrap_or_else(|| vec!["dot", "mermaid"]);
+
+        let map_page = table
+            .get("map-page")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+            .then(|| MapPage {
+                title: table
+                    .get("map-title")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Map of the book")
+                    .to_string(),
+                path: table
+                    .get("map-path")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("map.md")
+                    .into(),
+                position: table
+                    .get("map-position")
+                    .and_then(Position::from_toml)
+                    .unwrap_or(Position::End),
+            });
+
+        Self {
+            dot: formats.contains(&"dot"),
+            mermaid: formats.contains(&"mermaid"),
+            map_page,
+        }
+    }
+
+    /// Returns the files to write into the artifacts directory.
+    pub fn artifacts(&self, chapters: &[ChapterEntry]) -> Vec<(&'static str, String)> {
+        let edges = edges(chapters);
+        let mut artifacts = Vec::new();
+        if self.dot {
+            artifacts.push(("graph.dot", to_dot(chapters, &edges)));
+        }
+        if self.mermaid {
+            artifacts.push(("graph.mmd", to_mermaid(chapters, &edges)));
+        }
+        artifacts
+    }
+
+    /// Builds the "Map of the book" chapter, when enabled.
+    pub fn map_chapter(&self, chapters: &[ChapterEntry]) -> Option<(Chapter, Position)> {
+        let map_page = self.map_page.as_ref()?;
+        let content = format!(
+            "# {}\n\n```mermaid\n{}```\n",
+            map_page.title,
+            to_mermaid(chapters, &edges(chapters))
+        );
+        Some((
+            generated_chapter(&map_page.title, content, &map_page.path),
+            map_page.position,
+        ))
+    }
+}
+
+/// Collects the edges described by the `prerequisites`, `see-also` and `series`
+/// keys. References that don't resolve are left out.
+pub fn edges(chapters: &[ChapterEntry]) -> Vec<Edge> {
+    let index_of: HashMap<&PathBuf, usize> = chapters
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (&e.path, i))
+        .collect();
+    let mut edges = Vec::new();
+
+    for (i, entry) in chapters.iter().enumerate() {
+        for (key, kind) in [
+            ("prerequisites", EdgeKind::Prerequisite),
+            ("see-also", EdgeKind::SeeAlso),
+        ] {
+            let references = entry.metadata.get(key).map(|v| parse_list(v));
+            for reference in references.unwrap_or_default() {
+                if let Some(target) = resolve_reference(chapters, &reference) {
+                    let j = index_of[&target.path];
+                    // Prerequisites point from the required chapter to the one requiring it
+                    let (from, to) = match kind {
+                        EdgeKind::Prerequisite => (j, i),
+                        _ => (i, j),
+                    };
+                    edges.push(Edge { from, to, kind });
+                }
+            }
+        }
+    }
+
+    // Chapters of a series are chained in book order
+    let mut last_in_series: HashMap<&str, usize> = HashMap::new();
+    for (i, entry) in chapters.iter().enumerate() {
+        if let Some(series) = entry.metadata.get("series") {
+            if let Some(previous) = last_in_series.insert(series.as_str(), i) {
+                edges.push(Edge {
+                    from: previous,
+                    to: i,
+                    kind: EdgeKind::Series,
+                });
+            }
+        }
+    }
+
+    edges
+}
+
+fn connected_nodes(chapters: &[ChapterEntry], edges: &[Edge]) -> Vec<usize> {
+    let mut nodes: Vec<usize> = edges.iter().flat_map(|e| [e.from, e.to]).collect();
+    nodes.sort_unstable();
+    nodes.dedup();
+    nodes.retain(|&i| i < chapters.len());
+    nodes
+}
+
+/// Renders the graph in Graphviz DOT format.
+pub fn to_dot(chapters: &[ChapterEntry], edges: &[Edge]) -> String {
+    let mut dot = String::from("digraph book {\n    rankdir=LR;\n");
+    for i in connected_nodes(chapters, edges) {
+        let _ = writeln!(
+            dot,
+            "    n{} [label=\"{}\", URL=\"{}\"];",
+            i,
+            chapters[i]
+                .title()
+                .replace('\\', "\\\\")
+                .replace('"', "\\\""),
+            chapters[i].path.with_extension("html").display()
+        );
+    }
+    for edge in edges {
+        let style = match edge.kind {
+            EdgeKind::Prerequisite => "solid",
+            EdgeKind::SeeAlso => "dashed",
+            EdgeKind::Series => "dotted",
+        };
+        let _ = writeln!(dot, "    n{} -> n{} [style={}];", edge.from, edge.to, style);
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Renders the graph as a mermaid flowchart.
+pub fn to_mermaid(chapters: &[ChapterEntry], edges: &[Edge]) -> String {
+    let mut mermaid = String::from("graph LR\n");
+    for i in connected_nodes(chapters, edges) {
+        let _ = writeln!(
+            mermaid,
+            "    n{}[\"{}\"]",
+            i,
+            chapters[i].title().replace('"', "#quot;")
+        );
+    }
+    for edge in edges {
+        let arrow = match edge.kind {
+            EdgeKind::Prerequisite => "-->",
+            EdgeKind::SeeAlso => "-.->",
+            EdgeKind::Series => "==>",
+        };
+        let _ = writeln!(mermaid, "    n{} {} n{}", edge.from, arrow, edge.to);
+    }
+    mermaid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::entry;
+
+    fn chapters() -> Vec<ChapterEntry> {
+        vec![
+            entry("install.md", &[("title", "Install"), ("series", "basics")]),
+            entry(
+                "usage.md",
+                &[
+                    ("title", "Usage \"101\""),
+                    ("series", "basics"),
+                    ("prerequisites", "install"),
+                    ("see-also", "[faq, missing]"),
+                ],
+            ),
+            entry("faq.md", &[("title", "FAQ")]),
+            entry("unrelated.md", &[]),
+        ]
+    }
+
+    #[test]
+    fn edges_follow_metadata() {
+        assert_eq!(
+            edges(&chapters()),
+            vec![
+                Edge {
+                    from: 0,
+                    to: 1,
+                    kind: EdgeKind::Prerequisite
+                },
+                Edge {
+                    from: 1,
+                    to: 2,
+                    kind: EdgeKind::SeeAlso
+                },
+                Edge {
+                    from: 0,
+                    to: 1,
+                    kind: EdgeKind::Series
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn to_dot_renders_connected_chapters() {
+        let chapters = chapters();
+
+        assert_eq!(
+            to_dot(&chapters, &edges(&chapters)),
+            "digraph book {\n    rankdir=LR;\n\
+             \x20   n0 [label=\"Install\", URL=\"install.html\"];\n\
+             \x20   n1 [label=\"Usage \\\"101\\\"\", URL=\"usage.html\"];\n\
+             \x20   n2 [label=\"FAQ\", URL=\"faq.html\"];\n\
+             \x20   n0 -> n1 [style=solid];\n\
+             \x20   n1 -> n2 [style=dashed];\n\
+             \x20   n0 -> n1 [style=dotted];\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn map_chapter_embeds_mermaid_diagram() {
+        let mut table = Table::new();
+        table.insert("map-page".to_string(), true.into());
+        let graph = Graph::from_config(&table);
+        let chapters = chapters();
+
+        let (chapter, position) = graph.map_chapter(&chapters).unwrap();
+
+        assert_eq!(position, Position::End);
+        assert_eq!(
+            chapter.content,
+            "# Map of the book\n\n```mermaid\ngraph LR\n\
+             \x20   n0[\"Install\"]\n\
+             \x20   n1[\"Usage #quot;101#quot;\"]\n\
+             \x20   n2[\"FAQ\"]\n\
+             \x20   n0 --> n1\n\
+             \x20   n1 -.-> n2\n\
+             \x20   n0 ==> n1\n\
+             ```\n"
+        );
+    }
+
+    #[test]
+    fn artifacts_snapshot() {
+        let graph = Graph::from_config(&Table::new());
+
+        for (name, contents) in graph.artifacts(&chapters()) {
+            insta::assert_snapshot!(name.replace(".", "_"), contents);
+        }
+    }
+}
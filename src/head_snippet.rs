@@ -0,0 +1,104 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use toml::value::Table;
+
+use crate::metadata::{parse_list, MetadataError};
+use crate::tags::Tag;
+
+/// The key naming the head snippets of a chapter.
+pub const HEAD_SNIPPET_KEY: &str = "head-snippet";
+
+/// HTML files a chapter adds to its head with `head-snippet`, for one-off tags
+/// without theme edits (`[preprocessor.metadata.head-snippet]`). Snippets are
+/// read from `dir`, relative to the book root.
+#[derive(Debug, Clone)]
+pub struct HeadSnippet {
+    pub dir: PathBuf,
+}
+
+impl HeadSnippet {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            dir: table
+                .get("dir")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .into(),
+        }
+    }
+
+    /// Reads the snippets listed in a chapter's `head-snippet`, as raw tags.
+    /// Paths are relative to the book root and must stay inside `dir`:
+    /// absolute paths, `..` and symbolic links leading out of it are errors.
+    pub fn tags(&self, root: &Path, value: &str) -> (Vec<Tag>, Vec<MetadataError>) {
+        let mut tags = Vec::new();
+        let mut errors = Vec::new();
+        for snippet in parse_list(value) {
+            match self.read(root, &snippet) {
+                Ok(html) => tags.push(Tag::raw(HEAD_SNIPPET_KEY, &html)),
+                Err(message) => errors.push(MetadataError::InvalidValue {
+                    key: HEAD_SNIPPET_KEY.to_string(),
+                    message: format!("'{}' {}", snippet, message),
+                }),
+            }
+        }
+        (tags, errors)
+    }
+
+    fn read(&self, root: &Path, snippet: &str) -> Result<String, String> {
+        let relative = Path::new(snippet);
+        if !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err("must be a path inside the book, without '..'".to_string());
+        }
+        let dir = root
+            .join(&self.dir)
+            .canonicalize()
+            .map_err(|e| format!("can't be read: {}", e))?;
+        let path = root
+            .join(relative)
+            .canonicalize()
+            .map_err(|e| format!("can't be read: {}", e))?;
+        if !path.starts_with(&dir) {
+            return Err(format!("isn't in '{}'", self.dir.display()));
+        }
+        fs::read_to_string(&path).map_err(|e| format!("can't be read: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippets_are_read_from_inside_the_book() {
+        let root =
+            std::env::temp_dir().join(format!("mdbook-metadata-snippets-{}", std::process::id()));
+        fs::create_dir_all(root.join("snippets")).unwrap();
+        fs::write(
+            root.join("snippets/matomo.html"),
+            "<script>matomo()</script>\n",
+        )
+        .unwrap();
+        fs::write(root.join("book.toml"), "").unwrap();
+        let snippets = HeadSnippet::from_config(&toml::from_str("dir = \"snippets\"").unwrap());
+
+        let (tags, errors) = snippets.tags(
+            &root,
+            "[snippets/matomo.html, ../secret.html, /etc/passwd, book.toml]",
+        );
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            tags,
+            vec![Tag::raw("head-snippet", "<script>matomo()</script>\n")]
+        );
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            errors[2].to_string(),
+            "Invalid value of 'head-snippet': 'book.toml' isn't in 'snippets'"
+        );
+    }
+}
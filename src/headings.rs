@@ -0,0 +1,203 @@
+use mdbook::book::{Book, BookItem};
+use mdbook::utils::unique_id_from_content;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use toml::value::Table;
+
+use crate::aggregate::ChapterEntry;
+use crate::tags::Tag;
+
+/// Exports the headings of every chapter, with the anchors mdBook gives them
+/// (`[preprocessor.metadata.headings]`).
+#[derive(Debug, Clone)]
+pub struct Headings {
+    pub min_level: usize,
+    pub max_level: usize,
+    pub script: bool,
+    pub file: String,
+}
+
+/// A heading of a chapter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Heading {
+    pub text: String,
+    pub level: usize,
+    pub anchor: String,
+}
+
+impl Headings {
+    pub fn from_config(table: &Table) -> Self {
+        let level = |key: &str, default: usize| {
+            table
+                .get(key)
+                .and_then(|v| v.as_integer())
+                .map_or(default, |l| l.clamp(1, 6) as usize)
+        };
+        Self {
+            min_level: level("min-level", 1),
+            max_level: level("max-level", 6),
+            script: table
+                .get("script")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true),
+            file: table
+                .get("file")
+                .and_then(|v| v.as_str())
+                .unwrap_or("headings.json")
+                .to_string(),
+        }
+    }
+
+    /// Returns the ATX headings of a chapter between `min-level` and
+    /// `max-level`, outside code blocks. Anchors are `{#id}` attributes, or
+    /// else derived from the text as mdBook does, numbered when repeated.
+    pub fn headings(&self, markdown: &str) -> Vec<Heading> {
+        let mut ids = HashMap::new();
+        let mut fence: Option<&str> = None;
+        let mut headings = Vec::new();
+        for line in markdown.lines() {
+            let trimmed = line.trim_start();
+            if let Some(open) = fence {
+                if trimmed.starts_with(open) {
+                    fence = None;
+                }
+                continue;
+            }
+            if let Some(marker) = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m)) {
+                fence = Some(marker);
+                continue;
+            }
+            if line.len() - trimmed.len() > 3 {
+                continue; // Indented code
+            }
+            let level = trimmed.chars().take_while(|&c| c == '#').count();
+            let rest = &trimmed[level..];
+            if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+                continue;
+            }
+            let mut text = rest.trim().trim_end_matches('#').trim_end();
+            let mut custom_id = None;
+            if let Some(start) = text.rfind("{#").filter(|_| text.ends_with('}')) {
+                custom_id = text[start + 2..text.len() - 1]
+                    .split_whitespace()
+                    .next()
+                    .map(String::from);
+                text = text[..start].trim_end();
+            }
+            let text = plain_text(text);
+            let anchor = match custom_id {
+                Some(id) => id,
+                None => unique_id_from_content(&text, &mut ids),
+            };
+            if (self.min_level..=self.max_level).contains(&level) {
+                headings.push(Heading {
+                    text,
+                    level,
+                    anchor,
+                });
+            }
+        }
+        headings
+    }
+
+    /// Returns the `<script type="application/json" id="chapter-headings">` tag
+    /// of a chapter, for in-page widgets.
+    pub fn tag(&self, headings: &[Heading]) -> Option<Tag> {
+        if !self.script || headings.is_empty() {
+            return None;
+        }
+        let data = serde_json::to_value(headings).ok()?;
+        let mut tag = Tag::script("application/json", &data);
+        tag.attributes
+            .insert("id".to_string(), "chapter-headings".to_string());
+        Some(tag)
+    }
+
+    /// Returns the headings file, mapping the path of every chapter with
+    /// headings to them.
+    pub fn artifact(&self, book: &Book, chapters: &[ChapterEntry]) -> serde_json::Result<String> {
+        let data: BTreeMap<String, Vec<Heading>> = book
+            .iter()
+            .filter_map(|item| match item {
+                BookItem::Chapter(chap) => {
+                    let path = chap.path.as_ref()?;
+                    chapters.iter().find(|entry| &entry.path == path)?;
+                    let headings = self.headings(&chap.content);
+                    (!headings.is_empty()).then(|| (path.display().to_string(), headings))
+                }
+                _ => None,
+            })
+            .collect();
+        serde_json::to_string_pretty(&data)
+    }
+}
+
+/// Strips inline markdown from heading text: code spans, emphasis markers,
+/// images and link targets.
+fn plain_text(markdown: &str) -> String {
+    let chars: Vec<char> = markdown.chars().collect();
+    let mut text = String::with_capacity(markdown.len());
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '`' | '*' | '~' => {}
+            '_' => {
+                let word = |c: Option<&char>| c.is_some_and(|c| c.is_alphanumeric());
+                if word(i.checked_sub(1).and_then(|j| chars.get(j))) && word(chars.get(i + 1)) {
+                    text.push('_');
+                }
+            }
+            '!' if chars.get(i + 1) == Some(&'[') => {
+                // Images have no text in the heading
+                let end = chars[i..].iter().position(|&c| c == ')');
+                i += end.unwrap_or(0);
+            }
+            ']' if chars.get(i + 1) == Some(&'(') => {
+                let end = chars[i..].iter().position(|&c| c == ')');
+                i += end.unwrap_or(0);
+            }
+            '[' => {}
+            c => text.push(c),
+        }
+        i += 1;
+    }
+    text.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headings_have_mdbook_anchors() {
+        let headings = Headings::from_config(&toml::from_str("max-level = 3").unwrap());
+        let markdown = "# Getting *started*\n\nText\n\n## Install `cargo` [tools](tools.md)\n\
+                        ```sh\n# not a heading\n```\n\n## Install `cargo` [tools](tools.md)\n\
+                        ### Custom {#my-id}\n#### Too deep\n#hashtag\n    ## indented code\n";
+
+        let all = headings.headings(markdown);
+        let found: Vec<(usize, &str, &str)> = all
+            .iter()
+            .map(|h| (h.level, h.text.as_str(), h.anchor.as_str()))
+            .collect();
+
+        assert_eq!(
+            found,
+            [
+                (1, "Getting started", "getting-started"),
+                (2, "Install cargo tools", "install-cargo-tools"),
+                (2, "Install cargo tools", "install-cargo-tools-1"),
+                (3, "Custom", "my-id"),
+            ]
+        );
+        assert_eq!(
+            headings.tag(&all[3..]).unwrap().to_html(),
+            "<script type=\"application/json\" id=\"chapter-headings\">\
+             [{\"anchor\":\"my-id\",\"level\":3,\"text\":\"Custom\"}]</script>\n"
+        );
+        assert_eq!(
+            plain_text("snake_case and _emphasis_"),
+            "snake_case and emphasis"
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use std::path::Path;
+use toml::value::Table;
+
+use crate::aggregate::{relative_href, ChapterEntry};
+use crate::metadata::parse_list;
+use crate::tags::Tag;
+
+/// The key listing the assets a chapter preloads.
+pub const PRELOAD_KEY: &str = "preload";
+
+/// Resource hints (`[preprocessor.metadata.hints]`): `<link rel="preload">`
+/// tags for the assets of the `preload` key and a `<link rel="prefetch">` of
+/// the next chapter.
+#[derive(Debug, Clone)]
+pub struct Hints {
+    pub prefetch_next: bool,
+}
+
+impl Hints {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            prefetch_next: table
+                .get("prefetch-next")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true),
+        }
+    }
+
+    /// Returns the prefetch tag of the chapter following `entry` in book order.
+    pub fn prefetch_tag(&self, entry: &ChapterEntry, next: Option<&ChapterEntry>) -> Option<Tag> {
+        let next = next.filter(|_| self.prefetch_next)?;
+        Some(Tag::link(
+            "prefetch",
+            &relative_href(&entry.path, &next.path),
+        ))
+    }
+}
+
+/// Returns a preload tag per asset of the `preload` value, with the `as`
+/// attribute inferred from the extension. Fonts are fetched with CORS.
+pub fn preload_tags(value: &str) -> Vec<Tag> {
+    parse_list(value)
+        .iter()
+        .map(|href| {
+            let mut tag = Tag::link("preload", href);
+            let extension = Path::new(href.split(['?', '#']).next().unwrap_or_default())
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(str::to_lowercase)
+                .unwrap_or_default();
+            let destination = match extension.as_str() {
+                "css" => Some("style"),
+                "js" | "mjs" => Some("script"),
+                "woff" | "woff2" | "ttf" | "otf" => Some("font"),
+                "png" | "jpg" | "jpeg" | "gif" | "svg" | "webp" | "avif" => Some("image"),
+                "json" => Some("fetch"),
+                _ => None,
+            };
+            if let Some(destination) = destination {
+                tag.attributes
+                    .insert("as".to_string(), destination.to_string());
+                if destination == "font" || destination == "fetch" {
+                    tag.attributes
+                        .insert("crossorigin".to_string(), "anonymous".to_string());
+                }
+            }
+            tag
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::to_html;
+
+    #[test]
+    fn preload_tags_infer_destinations() {
+        assert_eq!(
+            to_html(&preload_tags("[theme/big.css, fonts/Inter.woff2, diagram.svg?v=2, data.bin]")),
+            "<link rel=\"preload\" href=\"theme/big.css\" as=\"style\">\n\
+             <link rel=\"preload\" href=\"fonts/Inter.woff2\" as=\"font\" crossorigin=\"anonymous\">\n\
+             <link rel=\"preload\" href=\"diagram.svg?v=2\" as=\"image\">\n\
+             <link rel=\"preload\" href=\"data.bin\">\n"
+        );
+    }
+}
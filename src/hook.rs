@@ -0,0 +1,173 @@
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use toml::Value as TomlValue;
+
+/// An external command transforming each chapter's metadata (`hook`). It
+/// receives the metadata as a JSON object on stdin and the chapter path as its
+/// argument, and prints the transformed metadata as a JSON object.
+#[derive(Debug, Clone)]
+pub struct Hook {
+    pub program: String,
+    pub args: Vec<String>,
+    pub timeout: Duration,
+}
+
+impl Hook {
+    /// Reads the hook, a command line as a string (`"scripts/enrich.sh"`) or
+    /// an array (`["python3", "enrich.py"]`).
+    pub fn from_config(value: &TomlValue, timeout: Option<&TomlValue>) -> Option<Self> {
+        let mut command: Vec<String> = match value {
+            TomlValue::String(command) => command.split_whitespace().map(String::from).collect(),
+            value => value
+                .as_array()?
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect(),
+        };
+        if command.is_empty() {
+            return None;
+        }
+        let program = command.remove(0);
+        let timeout = timeout
+            .and_then(|v| v.as_integer())
+            .and_then(|v| u64::try_from(v).ok())
+            .unwrap_or(10);
+        Some(Self {
+            program,
+            args: command,
+            timeout: Duration::from_secs(timeout),
+        })
+    }
+
+    /// Runs the hook for a chapter from the book root. Relative program paths
+    /// with a directory (`scripts/enrich.sh`) are relative to the root too.
+    pub fn run(
+        &self,
+        root: &Path,
+        chapter: &Path,
+        metadata: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>> {
+        let program = if self.program.contains('/') {
+            root.join(&self.program)
+        } else {
+            PathBuf::from(&self.program)
+        };
+        let mut child = Command::new(&program)
+            .args(&self.args)
+            .arg(chapter)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("Failed to run hook '{}'", program.display()))?;
+
+        // Write and read on separate threads, so large payloads can't deadlock
+        let input = serde_json::to_vec(metadata)?;
+        let mut stdin = child.stdin.take().context("Failed to open hook stdin")?;
+        let writer = thread::spawn(move || stdin.write_all(&input));
+        let mut stdout = child.stdout.take().context("Failed to open hook stdout")?;
+        let reader = thread::spawn(move || {
+            let mut output = Vec::new();
+            stdout.read_to_end(&mut output).map(|_| output)
+        });
+
+        let started = Instant::now();
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if started.elapsed() > self.timeout {
+                let _ = child.kill();
+                let _ = child.wait();
+                bail!(
+                    "Hook timed out after {}s for '{}'",
+                    self.timeout.as_secs(),
+                    chapter.display()
+                );
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+        // A hook may exit without reading its input
+        let _ = writer.join();
+        let output = reader
+            .join()
+            .map_err(|_| anyhow::anyhow!("Failed to read hook output"))??;
+        if !status.success() {
+            bail!("Hook failed for '{}': {}", chapter.display(), status);
+        }
+
+        parse_output(&output)
+            .with_context(|| format!("Invalid hook output for '{}'", chapter.display()))
+    }
+}
+
+/// Parses the JSON object printed by a hook. Non-string values are kept as
+/// their JSON text.
+fn parse_output(output: &[u8]) -> Result<HashMap<String, String>> {
+    let Value::Object(object) = serde_json::from_slice(output)? else {
+        bail!("expected a JSON object");
+    };
+    Ok(object
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(key, value)| match value {
+            Value::String(s) => (key, s),
+            value => (key, value.to_string()),
+        })
+        .collect())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn script(dir: &Path, name: &str, body: &str) {
+        let path = dir.join(name);
+        fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn run_transforms_metadata_and_enforces_timeouts() {
+        let dir = std::env::temp_dir().join(format!("mdbook-metadata-hook-{}", std::process::id()));
+        fs::create_dir_all(dir.join("scripts")).unwrap();
+        script(
+            &dir,
+            "scripts/enrich.sh",
+            r#"cat > /dev/null; printf '{"owner": "%s", "tier": 1, "gone": null}' "$1""#,
+        );
+        script(&dir, "scripts/slow.sh", "sleep 5");
+        let metadata = HashMap::from([("title".to_string(), "Intro".to_string())]);
+
+        let hook = Hook::from_config(&"scripts/enrich.sh".into(), None).unwrap();
+        let enriched = hook
+            .run(&dir, Path::new("guide/intro.md"), &metadata)
+            .unwrap();
+        assert_eq!(
+            enriched,
+            HashMap::from([
+                ("owner".to_string(), "guide/intro.md".to_string()),
+                ("tier".to_string(), "1".to_string()),
+            ])
+        );
+
+        let slow = Hook {
+            timeout: Duration::from_millis(100),
+            ..Hook::from_config(&"scripts/slow.sh".into(), None).unwrap()
+        };
+        let error = slow
+            .run(&dir, Path::new("intro.md"), &metadata)
+            .unwrap_err();
+        assert!(error.to_string().starts_with("Hook timed out"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,163 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::metadata::MetadataError;
+use crate::tags::Tag;
+
+/// Keys holding the identifiers of a chapter.
+pub const IDENTIFIER_KEYS: [&str; 3] = ["doi", "isbn", "issn"];
+
+lazy_static! {
+    static ref DOI_RE: Regex = Regex::new(r"^10\.\d{4,9}/\S+$").unwrap();
+}
+
+/// Normalizes a DOI, dropping `doi:` and `https://doi.org/` prefixes, or
+/// returns `None` when it isn't one.
+pub fn normalize_doi(value: &str) -> Option<String> {
+    let value = value.trim();
+    let doi = [
+        "https://doi.org/",
+        "http://doi.org/",
+        "https://dx.doi.org/",
+        "doi:",
+    ]
+    .iter()
+    .find_map(|prefix| value.strip_prefix(prefix))
+    .unwrap_or(value);
+    DOI_RE.is_match(doi).then(|| doi.to_string())
+}
+
+/// Normalizes an ISBN-10 or ISBN-13 to its digits, checking its check digit.
+pub fn normalize_isbn(value: &str) -> Option<String> {
+    let isbn: String = value
+        .trim()
+        .trim_start_matches("ISBN")
+        .trim_start_matches(':')
+        .chars()
+        .filter(|c| !matches!(c, '-' | ' '))
+        .collect::<String>()
+        .to_uppercase();
+    let digits = |s: &str| -> Option<Vec<u32>> {
+        s.chars()
+            .map(|c| if c == 'X' { Some(10) } else { c.to_digit(10) })
+            .collect()
+    };
+    let valid = match isbn.len() {
+        10 => digits(&isbn).is_some_and(|d| {
+            !d[..9].contains(&10)
+                && d.iter()
+                    .enumerate()
+                    .map(|(i, d)| (10 - i as u32) * d)
+                    .sum::<u32>()
+                    % 11
+                    == 0
+        }),
+        13 => digits(&isbn).is_some_and(|d| {
+            !d.contains(&10)
+                && d.iter()
+                    .enumerate()
+                    .map(|(i, d)| if i % 2 == 0 { *d } else { 3 * d })
+                    .sum::<u32>()
+                    % 10
+                    == 0
+        }),
+        _ => false,
+    };
+    valid.then_some(isbn)
+}
+
+/// Normalizes an ISSN to the `1234-5679` form, checking its check digit.
+pub fn normalize_issn(value: &str) -> Option<String> {
+    let issn: String = value
+        .trim()
+        .trim_start_matches("ISSN")
+        .trim_start_matches(':')
+        .chars()
+        .filter(|c| !matches!(c, '-' | ' '))
+        .collect::<String>()
+        .to_uppercase();
+    if issn.len() != 8 {
+        return None;
+    }
+    let mut sum = 0;
+    for (i, c) in issn.chars().enumerate() {
+        let digit = match c {
+            'X' if i == 7 => 10,
+            c => c.to_digit(10)?,
+        };
+        sum += (8 - i as u32) * digit;
+    }
+    (sum % 11 == 0).then(|| format!("{}-{}", &issn[..4], &issn[4..]))
+}
+
+/// Takes the `doi`, `isbn` and `issn` keys out of `metadata`, returning
+/// their `citation_*` and `DC.identifier` meta tags. Invalid identifiers are
+/// errors, and their keys are left out.
+pub fn identifier_tags(metadata: &mut HashMap<String, String>) -> (Vec<Tag>, Vec<MetadataError>) {
+    let mut tags = Vec::new();
+    let mut errors = Vec::new();
+    for key in IDENTIFIER_KEYS {
+        let Some(value) = metadata.remove(key) else {
+            continue;
+        };
+        let normalized = match key {
+            "doi" => normalize_doi(&value).map(|doi| (doi.clone(), format!("doi:{}", doi))),
+            "isbn" => {
+                normalize_isbn(&value).map(|isbn| (isbn.clone(), format!("urn:isbn:{}", isbn)))
+            }
+            _ => normalize_issn(&value).map(|issn| (issn.clone(), format!("urn:issn:{}", issn))),
+        };
+        match normalized {
+            Some((identifier, urn)) => {
+                tags.push(Tag::meta(&format!("citation_{}", key), &identifier));
+                tags.push(Tag::meta("DC.identifier", &urn));
+            }
+            None => errors.push(MetadataError::InvalidValue {
+                key: key.to_string(),
+                message: format!("'{}' isn't a valid {}", value.trim(), key.to_uppercase()),
+            }),
+        }
+    }
+    (tags, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::to_html;
+
+    #[test]
+    fn identifiers_are_validated_and_normalized() {
+        assert_eq!(
+            normalize_doi("https://doi.org/10.5281/zenodo.123").as_deref(),
+            Some("10.5281/zenodo.123")
+        );
+        assert_eq!(normalize_doi("10.12/x"), None);
+        assert_eq!(
+            normalize_isbn("978-0-306-40615-7").as_deref(),
+            Some("9780306406157")
+        );
+        assert_eq!(
+            normalize_isbn("0-306-40615-2").as_deref(),
+            Some("0306406152")
+        );
+        assert_eq!(normalize_isbn("978-0-306-40615-8"), None);
+        assert_eq!(normalize_issn("2434-561x").as_deref(), Some("2434-561X"));
+        assert_eq!(normalize_issn("0378-5954"), None);
+
+        let mut metadata = HashMap::from([
+            ("doi".to_string(), "doi:10.5281/zenodo.123".to_string()),
+            ("issn".to_string(), "1234".to_string()),
+            ("title".to_string(), "Intro".to_string()),
+        ]);
+        let (tags, errors) = identifier_tags(&mut metadata);
+        assert_eq!(
+            to_html(&tags),
+            "<meta name=\"citation_doi\" content=\"10.5281/zenodo.123\">\n\
+             <meta name=\"DC.identifier\" content=\"doi:10.5281/zenodo.123\">\n"
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(metadata.len(), 1);
+    }
+}
@@ -0,0 +1,96 @@
+use anyhow::{bail, Context, Result};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// The keys to set on one chapter, from a row of the imported spreadsheet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub chapter: String,
+    pub updates: Vec<(String, String)>,
+}
+
+/// Reads a CSV file with a header row. `key_column` identifies the chapter of
+/// each row; the other columns are the keys to set. Empty cells are skipped.
+pub fn read_csv<R: Read>(reader: R, key_column: &str) -> Result<Vec<Row>> {
+    let mut reader = csv::Reader::from_reader(reader);
+    let headers = reader
+        .headers()
+        .context("Failed to read the CSV header")?
+        .clone();
+    let Some(key_index) = headers.iter().position(|h| h.trim() == key_column) else {
+        bail!("The CSV has no '{}' column", key_column);
+    };
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.context("Failed to read a CSV row")?;
+        let chapter = record.get(key_index).unwrap_or_default().trim();
+        if chapter.is_empty() {
+            continue;
+        }
+        let updates = headers
+            .iter()
+            .zip(record.iter())
+            .enumerate()
+            .filter(|(i, (_, value))| *i != key_index && !value.trim().is_empty())
+            .map(|(_, (key, value))| (key.trim().to_string(), value.trim().to_string()))
+            .collect();
+        rows.push(Row {
+            chapter: chapter.to_string(),
+            updates,
+        });
+    }
+    Ok(rows)
+}
+
+/// Finds the chapter file of a row: a path relative to `src` (or to the book,
+/// starting with `src/`), with an optional `.md` extension.
+pub fn chapter_file(src_dir: &Path, reference: &str) -> Option<PathBuf> {
+    let reference = reference.trim().trim_start_matches("./");
+    let src_name = src_dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("src");
+    let reference = reference
+        .strip_prefix(&format!("{}/", src_name))
+        .unwrap_or(reference);
+
+    let path = src_dir.join(reference);
+    [path.clone(), path.with_extension("md")]
+        .into_iter()
+        .find(|p| p.is_file() && p.extension() == Some("md".as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_csv_maps_columns_to_keys() {
+        let csv = "path,owner,tier, review date\n\
+                   guide/intro.md,docs,1,2024-06-01\n\
+                   api,platform,,\n\
+                   ,orphan,2,\n";
+
+        let rows = read_csv(csv.as_bytes(), "path").unwrap();
+
+        assert_eq!(
+            rows,
+            vec![
+                Row {
+                    chapter: "guide/intro.md".to_string(),
+                    updates: vec![
+                        ("owner".to_string(), "docs".to_string()),
+                        ("tier".to_string(), "1".to_string()),
+                        ("review date".to_string(), "2024-06-01".to_string()),
+                    ],
+                },
+                Row {
+                    chapter: "api".to_string(),
+                    updates: vec![("owner".to_string(), "platform".to_string())],
+                },
+            ]
+        );
+        assert!(read_csv(csv.as_bytes(), "slug").is_err());
+    }
+}
@@ -0,0 +1,124 @@
+use lazy_static::lazy_static;
+use log::debug;
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+
+use crate::frontmatter;
+
+lazy_static! {
+    // mdBook's `{{#include file}}`, `{{#include file:anchor}}` and
+    // `{{#include file:start:end}}` links; `\{{#include}}` is escaped
+    static ref INCLUDE_RE: Regex =
+        Regex::new(r"(\\)?\{\{\s*#include\s+([^}\s]+)\s*\}\}").unwrap();
+}
+
+/// Removes the metadata blocks of the fragments a chapter includes, so they
+/// are neither read as the chapter's metadata nor rendered. `source` is the
+/// chapter file, whose directory include paths are relative to.
+///
+/// Includes mdBook hasn't resolved yet are resolved here when they include a
+/// whole fragment with a block; once resolved, the fragments' blocks are found
+/// through the includes of the chapter file on disk. Returns `None` when the
+/// chapter is unchanged.
+pub fn strip_included_blocks(content: &str, source: &Path) -> Option<String> {
+    let dir = source.parent()?;
+    let mut stripped = content.to_string();
+
+    // Includes the preprocessor sees, before mdBook's links preprocessor
+    for caps in INCLUDE_RE.captures_iter(content) {
+        if caps.get(1).is_some() {
+            continue;
+        }
+        let target = &caps[2];
+        let Some((fragment, start, end)) = fragment_block(dir, target) else {
+            continue;
+        };
+        if target.contains(':') {
+            debug!("Not resolving '{}': it includes part of a fragment", target);
+            continue;
+        }
+        let body = format!("{}{}", &fragment[..start], fragment[end..].trim_start());
+        stripped = stripped.replacen(&caps[0], &body, 1);
+    }
+
+    // Includes mdBook already resolved
+    if let Ok(original) = fs::read_to_string(source) {
+        for caps in INCLUDE_RE.captures_iter(&original) {
+            if caps.get(1).is_some() || content.contains(&caps[0]) {
+                continue;
+            }
+            let Some((fragment, start, end)) = fragment_block(dir, &caps[2]) else {
+                continue;
+            };
+            let block = &fragment[start..end];
+            // Skip the chapter's own block, in case it reads the same
+            let from = frontmatter::find(&original)
+                .and_then(|_| frontmatter::find(&stripped))
+                .map_or(0, |b| b.end);
+            if let Some(i) = stripped[from..].find(block) {
+                let i = from + i;
+                let rest = stripped[i + block.len()..].trim_start_matches(['\r', '\n']);
+                stripped = format!("{}{}", &stripped[..i], rest);
+            }
+        }
+    }
+
+    (stripped != content).then_some(stripped)
+}
+
+/// Reads the fragment an include points at, returning it with the bounds of
+/// its metadata block, if it has one.
+fn fragment_block(dir: &Path, target: &str) -> Option<(String, usize, usize)> {
+    let path = target.split(':').next()?;
+    let fragment = fs::read_to_string(dir.join(path)).ok()?;
+    let block = frontmatter::find(&fragment)?;
+    let (start, end) = (block.start, block.end);
+    Some((fragment, start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn included_blocks_are_stripped() {
+        let dir =
+            std::env::temp_dir().join(format!("mdbook-metadata-includes-{}", std::process::id()));
+        fs::create_dir_all(dir.join("fragments")).unwrap();
+        fs::write(
+            dir.join("fragments/note.md"),
+            "---\nowner: docs\n---\n> A note\n",
+        )
+        .unwrap();
+        let chapter = "---\ntitle: Intro\n---\n# Intro\n\n{{#include fragments/note.md}}\n";
+        fs::write(dir.join("intro.md"), chapter).unwrap();
+
+        // Before mdBook resolves the include
+        assert_eq!(
+            strip_included_blocks(chapter, &dir.join("intro.md")).unwrap(),
+            "---\ntitle: Intro\n---\n# Intro\n\n> A note\n\n"
+        );
+        // After
+        assert_eq!(
+            strip_included_blocks(
+                "---\ntitle: Intro\n---\n# Intro\n\n---\nowner: docs\n---\n> A note\n\n",
+                &dir.join("intro.md")
+            )
+            .unwrap(),
+            "---\ntitle: Intro\n---\n# Intro\n\n> A note\n\n"
+        );
+        // A chapter opening with a fragment has no metadata of its own
+        fs::write(dir.join("setup.md"), "{{#include fragments/note.md}}\n").unwrap();
+        assert_eq!(
+            strip_included_blocks("---\nowner: docs\n---\n> A note\n\n", &dir.join("setup.md"))
+                .unwrap(),
+            "> A note\n\n"
+        );
+        assert_eq!(
+            strip_included_blocks("\\{{#include fragments/note.md}}", &dir.join("intro.md")),
+            None
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
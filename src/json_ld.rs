@@ -0,0 +1,21 @@
+use serde_json::{Map, Value};
+
+use crate::tags::Tag;
+
+/// Returns the JSON-LD block of a chapter: a schema.org `WebPage` named after
+/// the chapter, or an `Article` with the chapter as its headline, with the
+/// `properties` contributed by the emitters.
+pub fn web_page(name: Option<&str>, article: bool, properties: Map<String, Value>) -> Tag {
+    let mut data = Map::new();
+    data.insert("@context".to_string(), "https://schema.org".into());
+    let page_type = if article { "Article" } else { "WebPage" };
+    data.insert("@type".to_string(), page_type.into());
+    if let Some(name) = name {
+        data.insert("name".to_string(), name.into());
+        if article {
+            data.insert("headline".to_string(), name.into());
+        }
+    }
+    data.extend(properties);
+    Tag::script("application/ld+json", &Value::Object(data))
+}
@@ -0,0 +1,60 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::metadata::MetadataError;
+use crate::tags::Tag;
+
+/// Key declaring the language of a chapter, for books mixing languages.
+pub const LANG_KEY: &str = "lang";
+
+lazy_static! {
+    // BCP 47 language tags, e.g. `fr`, `fr-CA` or `zh-Hant-TW`
+    static ref LANG_RE: Regex = Regex::new(r"^[A-Za-z]{2,3}(-[A-Za-z0-9]{1,8})*$").unwrap();
+}
+
+/// Returns the tags of a chapter's `lang`: a `content-language` meta tag,
+/// `og:locale` when `og_locale` is set, and a script correcting the page's
+/// `<html lang>` and adding a `lang-<tag>` class, as mdBook renders every page
+/// with the book language.
+pub fn lang_tags(value: &str, og_locale: bool) -> Result<Vec<Tag>, MetadataError> {
+    let lang = value.trim();
+    if !LANG_RE.is_match(lang) {
+        return Err(MetadataError::InvalidValue {
+            key: LANG_KEY.to_string(),
+            message: format!("'{}' isn't a language tag like 'en' or 'fr-CA'", lang),
+        });
+    }
+    let mut tags = vec![Tag::meta("content-language", lang)];
+    if og_locale {
+        tags.push(Tag::property("og:locale", &lang.replace('-', "_")));
+    }
+    tags.push(Tag::raw(
+        LANG_KEY,
+        &format!(
+            "<script>document.documentElement.lang = \"{}\"; \
+             document.documentElement.classList.add(\"lang-{}\");</script>",
+            lang,
+            lang.to_lowercase()
+        ),
+    ));
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::to_html;
+
+    #[test]
+    fn lang_tags_correct_the_page_language() {
+        assert_eq!(
+            to_html(&lang_tags("fr-CA", true).unwrap()),
+            "<meta name=\"content-language\" content=\"fr-CA\">\n\
+             <meta property=\"og:locale\" content=\"fr_CA\">\n\
+             <script>document.documentElement.lang = \"fr-CA\"; \
+             document.documentElement.classList.add(\"lang-fr-ca\");</script>\n"
+        );
+        assert_eq!(lang_tags("de", false).unwrap().len(), 2);
+        assert!(lang_tags("fr\"><script>", true).is_err());
+    }
+}
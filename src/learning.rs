@@ -0,0 +1,216 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use std::path::PathBuf;
+use toml::value::Table;
+
+use crate::aggregate::ChapterEntry;
+use crate::metadata::parse_list;
+use crate::tags::Tag;
+
+lazy_static! {
+    static ref DURATION_PART_RE: Regex =
+        Regex::new(r"(?i)(\d+)\s*(hours?|hrs?|h|minutes?|mins?|m)\b").unwrap();
+    static ref ISO_DURATION_RE: Regex = Regex::new(r"^PT(?:(\d+)H)?(?:(\d+)M)?$").unwrap();
+}
+
+/// Metadata keys handled by the learning emitter instead of the generic one.
+pub const LEARNING_KEYS: [&str; 4] = ["objective", "objectives", "duration", "level"];
+
+/// Emits learning metadata as `learning:*` meta tags and per-chapter JSON
+/// records for LMS integrations (`[preprocessor.metadata.learning]`).
+#[derive(Debug, Clone)]
+pub struct Learning {
+    pub dir: PathBuf,
+}
+
+/// The learning context of a single chapter, as written to its JSON record.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LearningRecord {
+    pub chapter: String,
+    pub title: String,
+    pub objectives: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_minutes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+}
+
+impl Learning {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            dir: table
+                .get("path")
+                .and_then(|v| v.as_str())
+                .unwrap_or("learning")
+                .into(),
+        }
+    }
+
+    /// Builds the record of a chapter that declares any learning key.
+    pub fn record(&self, entry: &ChapterEntry) -> Option<LearningRecord> {
+        if !LEARNING_KEYS
+            .iter()
+            .any(|k| entry.metadata.contains_key(*k))
+        {
+            return None;
+        }
+
+        let objectives = ["objective", "objectives"]
+            .iter()
+            .filter_map(|k| entry.metadata.get(*k))
+            .flat_map(|v| parse_list(v))
+            .collect();
+        let duration_minutes = entry
+            .metadata
+            .get("duration")
+            .and_then(|d| parse_duration_minutes(d));
+
+        Some(LearningRecord {
+            chapter: entry.path.with_extension("html").display().to_string(),
+            title: entry.title().to_string(),
+            objectives,
+            duration: duration_minutes.map(iso_duration),
+            duration_minutes,
+            level: entry.metadata.get("level").cloned(),
+        })
+    }
+
+    /// Where the record of `entry` is written, relative to the artifacts directory.
+    pub fn record_path(&self, entry: &ChapterEntry) -> PathBuf {
+        self.dir.join(entry.path.with_extension("json"))
+    }
+
+    /// Returns the `learning:*` meta tags of a record.
+    pub fn tags(&self, record: &LearningRecord) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = record
+            .objectives
+            .iter()
+            .map(|objective| Tag::meta("learning:objective", objective))
+            .collect();
+        if let Some(duration) = &record.duration {
+            tags.push(Tag::meta("learning:duration", duration));
+        }
+        if let Some(level) = &record.level {
+            tags.push(Tag::meta("learning:level", level));
+        }
+        tags
+    }
+}
+
+/// Parses durations such as `45m`, `1h 30m`, `90 minutes`, `PT1H30M` or a bare
+/// number of minutes.
+pub fn parse_duration_minutes(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(minutes) = value.parse::<u64>() {
+        return Some(minutes);
+    }
+    if let Some(caps) = ISO_DURATION_RE.captures(value) {
+        let hours: u64 = caps.get(1).map_or(Ok(0), |h| h.as_str().parse()).ok()?;
+        let minutes: u64 = caps.get(2).map_or(Ok(0), |m| m.as_str().parse()).ok()?;
+        return hours.checked_mul(60)?.checked_add(minutes);
+    }
+
+    let mut total = None;
+    for caps in DURATION_PART_RE.captures_iter(value) {
+        let amount: u64 = caps[1].parse().ok()?;
+        let factor = if caps[2].to_lowercase().starts_with('h') {
+            60
+        } else {
+            1
+        };
+        total = Some(
+            amount
+                .checked_mul(factor)?
+                .checked_add(total.unwrap_or(0))?,
+        );
+    }
+    total
+}
+
+/// Formats minutes as an ISO 8601 duration, e.g. `PT1H30M`.
+pub fn iso_duration(minutes: u64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("PT{}M", m),
+        (h, 0) => format!("PT{}H", h),
+        (h, m) => format!("PT{}H{}M", h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::entry;
+
+    #[test]
+    fn parse_duration_minutes_formats() {
+        assert_eq!(parse_duration_minutes("45"), Some(45));
+        assert_eq!(parse_duration_minutes("45m"), Some(45));
+        assert_eq!(parse_duration_minutes("1h 30m"), Some(90));
+        assert_eq!(parse_duration_minutes("2 hours"), Some(120));
+        assert_eq!(parse_duration_minutes("90 minutes"), Some(90));
+        assert_eq!(parse_duration_minutes("PT1H30M"), Some(90));
+        assert_eq!(parse_duration_minutes("a while"), None);
+        assert_eq!(parse_duration_minutes("PT999999999999999999H"), None);
+        assert_eq!(parse_duration_minutes("9999999999999999999 hours"), None);
+    }
+
+    #[test]
+    fn record_and_tags_for_learning_keys() {
+        let learning = Learning::from_config(&Table::new());
+        let entry = entry(
+            "course/intro.md",
+            &[
+                ("title", "Intro"),
+                ("objectives", "[Install the CLI, Build a book]"),
+                ("duration", "1h 30m"),
+                ("level", "beginner"),
+            ],
+        );
+
+        let record = learning.record(&entry).unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&record).unwrap(),
+            r#"{"chapter":"course/intro.html","title":"Intro","objectives":["Install the CLI","Build a book"],"duration":"PT1H30M","durationMinutes":90,"level":"beginner"}"#
+        );
+        assert_eq!(
+            learning.record_path(&entry),
+            PathBuf::from("learning/course/intro.json")
+        );
+        assert_eq!(
+            crate::tags::to_html(&learning.tags(&record)),
+            "<meta name=\"learning:objective\" content=\"Install the CLI\">\n\
+             <meta name=\"learning:objective\" content=\"Build a book\">\n\
+             <meta name=\"learning:duration\" content=\"PT1H30M\">\n\
+             <meta name=\"learning:level\" content=\"beginner\">\n"
+        );
+    }
+
+    #[test]
+    fn record_is_skipped_without_learning_keys() {
+        let learning = Learning::from_config(&Table::new());
+
+        assert_eq!(learning.record(&entry("a.md", &[("title", "A")])), None);
+    }
+
+    #[test]
+    fn html_tags_snapshot() {
+        let learning = Learning::from_config(&Table::new());
+        let entry = entry(
+            "course/advanced.md",
+            &[
+                ("objective", "Write a <preprocessor>"),
+                ("duration", "PT2H"),
+                ("level", "advanced"),
+            ],
+        );
+
+        let record = learning.record(&entry).unwrap();
+
+        insta::assert_snapshot!(crate::tags::to_html(&learning.tags(&record)));
+    }
+}
@@ -0,0 +1,88 @@
+mod accessibility;
+mod aggregate;
+mod appendix;
+mod archive;
+mod artifacts;
+mod asset_licenses;
+mod audience;
+mod audit;
+mod authors;
+mod banner;
+pub mod book_metadata;
+mod canonical;
+mod changelog;
+mod citations;
+pub mod cli;
+mod compat;
+mod computed;
+pub mod config;
+mod consent;
+pub mod convert;
+mod coverage;
+mod data_attributes;
+mod dates;
+mod decorate;
+pub mod diff;
+pub mod edit;
+mod emit;
+mod events;
+mod explain;
+mod fediverse;
+mod filter;
+pub mod frontmatter;
+mod geo;
+pub mod gettext;
+mod glossary;
+mod graph;
+mod head_snippet;
+mod headings;
+mod hints;
+mod hook;
+mod identifiers;
+pub mod import;
+pub mod includes;
+mod json_ld;
+mod lang;
+mod learning;
+mod links;
+pub mod lint;
+pub mod metadata;
+pub mod migrate;
+mod namespaces;
+mod nav_groups;
+mod obsidian;
+mod open_graph;
+mod overlay;
+pub mod owners;
+mod parts;
+mod plugins;
+mod position;
+mod prerequisites;
+mod profiles;
+mod remote;
+pub mod report;
+mod reviews;
+mod rules;
+mod scope;
+mod script;
+mod search;
+pub mod search_index;
+mod see_also;
+mod short_title;
+mod sidebar;
+mod sitemap;
+pub mod sources;
+mod stale;
+mod tag_synonyms;
+pub mod tags;
+mod template;
+mod theme_data;
+mod topics;
+mod urls;
+mod verification;
+mod versions;
+mod vocab;
+pub mod watch;
+mod whats_new;
+
+pub use book_metadata::BookMetadata;
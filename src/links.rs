@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::metadata::MetadataError;
+use crate::tags::{is_attribute_name, Tag};
+
+/// The key holding the `<link>` elements of a chapter.
+pub const LINKS_KEY: &str = "links";
+
+/// Parses the `links` key, a list of maps with `rel`, `href` and optional
+/// attributes such as `type`:
+///
+/// ```yaml
+/// links: [{rel: license, href: LICENSE.html}, {rel: alternate, href: feed.xml, type: application/rss+xml}]
+/// ```
+///
+/// TOML blocks can use an array of inline tables, with `=` instead of `:`.
+pub fn parse_links(value: &str) -> Result<Vec<Tag>, MetadataError> {
+    parse_maps(LINKS_KEY, value)?
+        .into_iter()
+        .map(link)
+        .collect()
+}
+
+/// Parses the value of `key` as a list of maps, `[{a: 1, b: 2}, {a: 3}]`, or
+/// an array of inline TOML tables.
+pub fn parse_maps(key: &str, value: &str) -> Result<Vec<BTreeMap<String, String>>, MetadataError> {
+    let value = value.trim();
+    let inner = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(value);
+
+    let mut chars = inner.chars().peekable();
+    let mut maps = Vec::new();
+    loop {
+        skip(&mut chars, |c| c.is_whitespace() || c == ',');
+        match chars.next() {
+            None => break,
+            Some('{') => maps.push(parse_map(&mut chars, key)?),
+            Some(c) => return Err(invalid(key, &format!("expected '{{', found '{}'", c))),
+        }
+    }
+    Ok(maps)
+}
+
+fn link(mut attributes: BTreeMap<String, String>) -> Result<Tag, MetadataError> {
+    let (Some(rel), Some(href)) = (attributes.remove("rel"), attributes.remove("href")) else {
+        return Err(invalid(LINKS_KEY, "every link needs a rel and an href"));
+    };
+    if let Some(name) = attributes.keys().find(|name| !is_attribute_name(name)) {
+        return Err(invalid(
+            LINKS_KEY,
+            &format!("invalid attribute name '{}'", name),
+        ));
+    }
+    Ok(Tag {
+        attributes,
+        ..Tag::link(&rel, &href)
+    })
+}
+
+/// Parses the `key: value` pairs of a map, after its opening brace.
+fn parse_map(
+    chars: &mut Peekable<Chars>,
+    key: &str,
+) -> Result<BTreeMap<String, String>, MetadataError> {
+    let mut map = BTreeMap::new();
+    loop {
+        skip(chars, |c| c.is_whitespace() || c == ',');
+        if chars.peek() == Some(&'}') {
+            chars.next();
+            return Ok(map);
+        }
+
+        let name = parse_scalar(chars, &[':', '='], key)?;
+        match chars.next() {
+            Some(':' | '=') => {}
+            _ => return Err(invalid(key, &format!("missing value for '{}'", name))),
+        }
+        let value = parse_scalar(chars, &[',', '}'], key)?;
+        if name.is_empty() {
+            return Err(invalid(key, "empty attribute name"));
+        }
+        map.insert(name, value);
+    }
+}
+
+/// Parses a quoted string, or unquoted text up to one of `terminators`.
+fn parse_scalar(
+    chars: &mut Peekable<Chars>,
+    terminators: &[char],
+    key: &str,
+) -> Result<String, MetadataError> {
+    skip(chars, char::is_whitespace);
+    let mut scalar = String::new();
+    match chars.peek().copied() {
+        Some(quote @ ('"' | '\'')) => {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('\\') if quote == '"' => scalar.extend(chars.next()),
+                    Some(c) if c == quote => break,
+                    Some(c) => scalar.push(c),
+                    None => return Err(invalid(key, "unclosed quote")),
+                }
+            }
+            skip(chars, char::is_whitespace);
+        }
+        _ => {
+            while let Some(&c) = chars.peek() {
+                if terminators.contains(&c) {
+                    break;
+                }
+                scalar.push(c);
+                chars.next();
+            }
+            if chars.peek().is_none() {
+                return Err(invalid(key, "unclosed '{'"));
+            }
+            scalar = scalar.trim().to_string();
+        }
+    }
+    Ok(scalar)
+}
+
+fn skip(chars: &mut Peekable<Chars>, predicate: impl Fn(char) -> bool) {
+    while chars.next_if(|&c| predicate(c)).is_some() {}
+}
+
+fn invalid(key: &str, message: &str) -> MetadataError {
+    MetadataError::InvalidValue {
+        key: key.to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::to_html;
+
+    #[test]
+    fn parse_links_reads_yaml_and_toml_maps() {
+        let yaml = r#"[{rel: license, href: "https://example.com/LICENSE"}, {rel: alternate, href: feed.xml, type: application/rss+xml}]"#;
+        let toml = r#"[{ href = "https://example.com/webmention", rel = "webmention" }]"#;
+
+        assert_eq!(
+            to_html(&parse_links(yaml).unwrap()),
+            "<link rel=\"license\" href=\"https://example.com/LICENSE\">\n\
+             <link rel=\"alternate\" href=\"feed.xml\" type=\"application/rss+xml\">\n"
+        );
+        assert_eq!(
+            to_html(&parse_links(toml).unwrap()),
+            "<link rel=\"webmention\" href=\"https://example.com/webmention\">\n"
+        );
+    }
+
+    #[test]
+    fn parse_links_rejects_malformed_lists() {
+        assert!(parse_links("[{rel: license}]").is_err());
+        assert!(parse_links("[{rel: license, href: x").is_err());
+        assert!(parse_links("[license]").is_err());
+        assert!(parse_links("[{rel: a, href: b, \"on load\": c}]").is_err());
+    }
+}
@@ -0,0 +1,290 @@
+use anyhow::{Context, Result};
+use log::warn;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::value::Table;
+
+use crate::frontmatter::{self, Format};
+use crate::sources::load_config;
+use crate::watch::Diagnostic;
+
+/// The order keys of a metadata block must follow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyOrder {
+    Alphabetical,
+    /// The listed keys first, in this order, then the others as they are.
+    Listed(Vec<String>),
+}
+
+/// Style checks of metadata blocks, for the `lint` and `fix` subcommands
+/// (`[preprocessor.metadata.lint]`).
+#[derive(Debug, Clone)]
+pub struct Lint {
+    pub tabs: bool,
+    pub trailing_whitespace: bool,
+    pub key_order: Option<KeyOrder>,
+}
+
+/// A top-level key of a block, with its value lines and the comments above it.
+struct Entry<'a> {
+    key: String,
+    line: usize,
+    lines: Vec<&'a str>,
+}
+
+impl Lint {
+    pub fn from_config(table: &Table) -> Self {
+        let check = |key: &str| table.get(key).and_then(|v| v.as_bool()).unwrap_or(true);
+        let key_order = match table.get("key-order") {
+            None => None,
+            Some(v) if v.as_str() == Some("alphabetical") => Some(KeyOrder::Alphabetical),
+            Some(v) if v.is_array() => Some(KeyOrder::Listed(
+                v.as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|k| k.as_str().map(String::from))
+                    .collect(),
+            )),
+            Some(v) => {
+                warn!(
+                    "Ignoring lint.key-order = {}: expected \"alphabetical\" or a list of keys",
+                    v
+                );
+                None
+            }
+        };
+        Self {
+            tabs: check("tabs"),
+            trailing_whitespace: check("trailing-whitespace"),
+            key_order,
+        }
+    }
+
+    /// Reads the lint settings of a book.
+    pub fn load(book_dir: &Path) -> Result<Self> {
+        let (config, _) = load_config(book_dir)?;
+        let table = config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("lint"))
+            .and_then(|v| v.as_table())
+            .cloned()
+            .unwrap_or_default();
+        Ok(Self::from_config(&table))
+    }
+
+    /// Returns the style problems of the body of a metadata block, with the
+    /// index of their line in the body.
+    pub fn check(&self, format: Format, body: &str) -> Vec<(usize, String)> {
+        let mut problems = Vec::new();
+        for (i, line) in body.split_inclusive('\n').enumerate() {
+            let text = line.trim_end_matches(['\r', '\n']);
+            let indent = &text[..text.len() - text.trim_start().len()];
+            if self.tabs && indent.contains('\t') {
+                problems.push((i, "Indented with a tab".to_string()));
+            }
+            if self.trailing_whitespace && text.trim_end() != text {
+                problems.push((i, "Trailing whitespace".to_string()));
+            }
+        }
+        if let Some(order) = &self.key_order {
+            let (_, entries, _) = entries(format, body);
+            let mut last: Option<&Entry> = None;
+            for entry in &entries {
+                match last {
+                    Some(previous) if rank(order, entry) < rank(order, previous) => {
+                        problems.push((
+                            entry.line,
+                            format!("Key '{}' should come before '{}'", entry.key, previous.key),
+                        ));
+                    }
+                    _ => last = Some(entry),
+                }
+            }
+        }
+        problems.sort_by_key(|(i, _)| *i);
+        problems
+    }
+
+    /// Fixes the style problems of the body of a metadata block: leading tabs
+    /// become two spaces, trailing whitespace is removed and keys are sorted.
+    pub fn fix(&self, format: Format, body: &str) -> String {
+        let mut fixed = String::with_capacity(body.len());
+        for line in body.split_inclusive('\n') {
+            let text = line.trim_end_matches(['\r', '\n']);
+            let ending = &line[text.len()..];
+            let rest = text.trim_start();
+            let indent = &text[..text.len() - rest.len()];
+            if self.tabs {
+                fixed.push_str(&indent.replace('\t', "  "));
+            } else {
+                fixed.push_str(indent);
+            }
+            if self.trailing_whitespace {
+                fixed.push_str(rest.trim_end());
+            } else {
+                fixed.push_str(rest);
+            }
+            fixed.push_str(ending);
+        }
+
+        let Some(order) = &self.key_order else {
+            return fixed;
+        };
+        let (head, mut entries, tail) = entries(format, &fixed);
+        entries.sort_by_key(|entry| rank(order, entry));
+        let mut sorted = head.concat();
+        for entry in &entries {
+            for line in &entry.lines {
+                sorted.push_str(line);
+            }
+            if !sorted.ends_with('\n') {
+                sorted.push('\n');
+            }
+        }
+        sorted.push_str(&tail.concat());
+        if !fixed.ends_with('\n') && sorted.ends_with('\n') {
+            sorted.pop();
+        }
+        sorted
+    }
+
+    /// Checks the metadata block of a chapter file, or else its sidecar file.
+    /// Returns the file checked, with its problems.
+    pub fn check_file(&self, path: &Path) -> Result<(PathBuf, Vec<Diagnostic>)> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read '{}'", path.display()))?;
+        let diagnostics = |problems: Vec<(usize, String)>, line: &dyn Fn(usize) -> usize| {
+            problems
+                .into_iter()
+                .map(|(i, message)| Diagnostic {
+                    line: Some(line(i)),
+                    message,
+                })
+                .collect()
+        };
+        if let Some(block) = frontmatter::find(&content) {
+            let problems = self.check(block.format, block.body);
+            let line = |i| block.line_number(&content, i);
+            return Ok((path.to_path_buf(), diagnostics(problems, &line)));
+        }
+        match frontmatter::find_sidecar(path) {
+            Some((sidecar, format)) => {
+                let body = fs::read_to_string(&sidecar)
+                    .with_context(|| format!("Failed to read '{}'", sidecar.display()))?;
+                let problems = self.check(format, &body);
+                Ok((sidecar, diagnostics(problems, &|i| i + 1)))
+            }
+            None => Ok((path.to_path_buf(), Vec::new())),
+        }
+    }
+}
+
+/// Splits a block body into the lines before the first key, the top-level
+/// keys, and the lines after the last one, such as TOML tables.
+fn entries(format: Format, body: &str) -> (Vec<&str>, Vec<Entry<'_>>, Vec<&str>) {
+    let lines: Vec<&str> = body.split_inclusive('\n').collect();
+    let end = match format {
+        Format::Yaml => lines.len(),
+        Format::Toml => lines
+            .iter()
+            .position(|l| l.starts_with('['))
+            .unwrap_or(lines.len()),
+    };
+
+    let mut head = Vec::new();
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut comments = Vec::new();
+    for (i, line) in lines[..end].iter().enumerate() {
+        if let Some(key) = top_level_key(format, line) {
+            let mut entry_lines = std::mem::take(&mut comments);
+            entry_lines.push(*line);
+            entries.push(Entry {
+                key,
+                line: i,
+                lines: entry_lines,
+            });
+        } else if line.starts_with('#') {
+            comments.push(*line);
+        } else {
+            let pending = std::mem::take(&mut comments);
+            match entries.last_mut() {
+                Some(entry) => entry.lines.extend(pending.into_iter().chain([*line])),
+                None => head.extend(pending.into_iter().chain([*line])),
+            }
+        }
+    }
+    let mut tail = comments;
+    tail.extend(&lines[end..]);
+    (head, entries, tail)
+}
+
+fn top_level_key(format: Format, line: &str) -> Option<String> {
+    if line.starts_with(|c: char| c.is_whitespace() || c == '#' || c == '-' || c == '[') {
+        return None;
+    }
+    let separator = match format {
+        Format::Yaml => ':',
+        Format::Toml => '=',
+    };
+    let (key, _) = line.split_once(separator)?;
+    let key = key.trim().trim_matches(|c| c == '"' || c == '\'');
+    (!key.is_empty()).then(|| key.to_string())
+}
+
+fn rank(order: &KeyOrder, entry: &Entry) -> (usize, String) {
+    match order {
+        KeyOrder::Alphabetical => (0, entry.key.to_lowercase()),
+        KeyOrder::Listed(keys) => (
+            keys.iter()
+                .position(|k| *k == entry.key)
+                .unwrap_or(keys.len()),
+            String::new(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(key_order: &str) -> Lint {
+        Lint::from_config(&toml::from_str(&format!("key-order = {}", key_order)).unwrap())
+    }
+
+    #[test]
+    fn check_reports_style_problems() {
+        let body = "\ntitle: Intro \ntags:\n\t- rust\n# Who\nauthor: Jane\n";
+
+        assert_eq!(
+            lint("[\"title\", \"author\"]").check(Format::Yaml, body),
+            vec![
+                (1, "Trailing whitespace".to_string()),
+                (3, "Indented with a tab".to_string()),
+                (5, "Key 'author' should come before 'tags'".to_string()),
+            ]
+        );
+        assert_eq!(
+            lint("\"alphabetical\"").check(Format::Yaml, "\nb: 1\na: 2\nc: 3\n"),
+            vec![(2, "Key 'a' should come before 'b'".to_string())]
+        );
+    }
+
+    #[test]
+    fn fix_rewrites_the_block() {
+        let lint = lint("[\"title\", \"author\"]");
+        assert_eq!(
+            lint.fix(
+                Format::Yaml,
+                "\ntitle: Intro \r\ntags:\n\t- rust\n# Who\nauthor: Jane\n"
+            ),
+            "\ntitle: Intro\r\n# Who\nauthor: Jane\ntags:\n  - rust\n"
+        );
+        assert_eq!(
+            lint.fix(
+                Format::Toml,
+                "\nauthor = \"Jane\"\ntitle = \"Intro\"\n[extra]\ntitle = \"x\"\n"
+            ),
+            "\ntitle = \"Intro\"\nauthor = \"Jane\"\n[extra]\ntitle = \"x\"\n"
+        );
+    }
+}
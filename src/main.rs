@@ -1,28 +1,333 @@
-mod cli;
-mod metadata;
-
-use env_logger;
+use anyhow::{anyhow, Context, Result};
+use clap::ArgMatches;
+use globset::Glob;
 use mdbook::preprocess::{CmdPreprocessor, Preprocessor};
-use metadata::Metadata;
-use serde_json;
-use std::{io, process};
+use mdbook_metadata::convert::{Conversion, Storage};
+use mdbook_metadata::frontmatter::{self, Format};
+use mdbook_metadata::gettext::Gettext;
+use mdbook_metadata::lint::Lint;
+use mdbook_metadata::metadata::Metadata;
+use mdbook_metadata::migrate::Migrate;
+use mdbook_metadata::owners::{self, Owners, OwnersFormat};
+use mdbook_metadata::report::{self, Report, ReportFormat};
+use mdbook_metadata::watch::{ErrorFormat, Validator};
+use mdbook_metadata::{cli, config, diff, edit, import, search_index, sources};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use std::process;
+use std::time::Duration;
 
 fn main() {
     env_logger::init();
 
-    if std::env::args().nth(1).as_deref() == Some("supports") {
-        process::exit(0);
+    let matches = cli::make_app().get_matches();
+
+    match run(&matches) {
+        Ok(code) => process::exit(code),
+        Err(e) => {
+            report_error(&e);
+            process::exit(1);
+        }
     }
+}
 
-    let _app = cli::make_app();
+/// Writes an error to stderr as a single JSON line, `{"error": ..., "causes":
+/// [...]}`, for mdBook's output and the tools wrapping it to show as is.
+fn report_error(error: &anyhow::Error) {
+    let report = serde_json::json!({
+        "error": error.to_string(),
+        "causes": error.chain().skip(1).map(|cause| cause.to_string()).collect::<Vec<_>>(),
+    });
+    eprintln!("{}", report);
+}
+
+/// Runs a subcommand, or the preprocessor when there is none, returning the
+/// exit code. Nothing is written to stdout when the preprocessor fails, so
+/// mdBook never reads a partial book.
+fn run(matches: &ArgMatches) -> Result<i32> {
+    match matches.subcommand() {
+        Some(("supports", sub_args)) => {
+            let renderer = sub_args.get_one::<String>("renderer").unwrap();
+            let book_dir = Path::new(sub_args.get_one::<String>("book-dir").unwrap());
+            // Without a readable book.toml, the run reports the problem itself
+            let supported = config::book_supports(book_dir, renderer).unwrap_or(true);
+            return Ok(if supported { 0 } else { 1 });
+        }
+        Some(("watch", sub_args)) => {
+            let book_dir = sub_args.get_one::<String>("book-dir").unwrap();
+            let interval = *sub_args.get_one::<u64>("interval").unwrap();
+            let format =
+                ErrorFormat::from_name(sub_args.get_one::<String>("error-format").unwrap())
+                    .unwrap();
+            let validator = Validator::load(Path::new(book_dir)).context("Failed to load book")?;
+            validator.watch(Duration::from_millis(interval), format);
+        }
+        Some(("check", sub_args)) => {
+            let book_dir = sub_args.get_one::<String>("book-dir").unwrap();
+            let format =
+                ErrorFormat::from_name(sub_args.get_one::<String>("error-format").unwrap())
+                    .unwrap();
+            let validator = Validator::load(Path::new(book_dir)).context("Failed to load book")?;
+            let results = validator.check();
+            if format == ErrorFormat::Gitlab {
+                let report = validator.gitlab_report(&results);
+                serde_json::to_writer_pretty(io::stdout(), &report)
+                    .context("Failed to emit report")?;
+                println!();
+            } else {
+                for (path, diagnostics) in &results {
+                    print!("{}", validator.render(path, diagnostics, format));
+                }
+            }
+            return Ok(if results.is_empty() { 0 } else { 1 });
+        }
+        Some(("lint", sub_args)) => {
+            let book_dir = Path::new(sub_args.get_one::<String>("book-dir").unwrap());
+            let format =
+                ErrorFormat::from_name(sub_args.get_one::<String>("error-format").unwrap())
+                    .unwrap();
+            let lint = Lint::load(book_dir).context("Failed to load book")?;
+            let validator = Validator::load(book_dir).context("Failed to load book")?;
+            let mut results = Vec::new();
+            for path in sources::chapter_files(&validator.src_dir) {
+                let (path, diagnostics) = lint
+                    .check_file(&path)
+                    .with_context(|| format!("Failed to lint '{}'", path.display()))?;
+                if !diagnostics.is_empty() {
+                    results.push((path, diagnostics));
+                }
+            }
+            if format == ErrorFormat::Gitlab {
+                let report = validator.gitlab_report(&results);
+                serde_json::to_writer_pretty(io::stdout(), &report)
+                    .context("Failed to emit report")?;
+                println!();
+            } else {
+                for (path, diagnostics) in &results {
+                    print!("{}", validator.render(path, diagnostics, format));
+                }
+            }
+            return Ok(if results.is_empty() { 0 } else { 1 });
+        }
+        Some(("export", sub_args)) => {
+            let book_dir = sub_args.get_one::<String>("book-dir").unwrap();
+            let metadata = diff::from_dir(Path::new(book_dir)).context("Failed to read book")?;
+            serde_json::to_writer_pretty(io::stdout(), &metadata)
+                .context("Failed to emit metadata")?;
+            println!();
+            return Ok(0);
+        }
+        Some(("diff", sub_args)) => {
+            let book_dir = Path::new(sub_args.get_one::<String>("book").unwrap());
+            let load = |arg: &str| {
+                diff::load(sub_args.get_one::<String>(arg).unwrap(), book_dir)
+                    .context("Failed to load metadata")
+            };
+            print!(
+                "{}",
+                diff::render(&diff::diff(&load("old")?, &load("new")?))
+            );
+            return Ok(0);
+        }
+        Some(("convert", sub_args)) => {
+            let format = |arg: &str| {
+                sub_args
+                    .get_one::<String>(arg)
+                    .and_then(|name| Format::from_name(name))
+            };
+            let conversion = Conversion {
+                from: format("from"),
+                to: format("to").unwrap(),
+                storage: Storage::from_name(sub_args.get_one::<String>("storage").unwrap())
+                    .unwrap(),
+            };
+            let book_dir = Path::new(sub_args.get_one::<String>("book-dir").unwrap());
+            let (_, src_dir) = sources::load_config(book_dir).context("Failed to load book")?;
+            let mut converted = 0;
+            for path in sources::chapter_files(&src_dir) {
+                if conversion
+                    .convert_file(&path)
+                    .with_context(|| format!("Failed to convert '{}'", path.display()))?
+                {
+                    println!("Converted {}", sources::chapter_name(&src_dir, &path));
+                    converted += 1;
+                }
+            }
+            println!("Converted {} chapters", converted);
+            return Ok(0);
+        }
+        Some(("set", sub_args)) => {
+            let updates: Vec<(String, String)> = sub_args
+                .get_many::<String>("assignments")
+                .unwrap()
+                .map(|a| {
+                    edit::parse_assignment(a)
+                        .ok_or_else(|| anyhow!("Expected KEY=VALUE, got '{}'", a))
+                })
+                .collect::<Result<_>>()?;
+            let glob = Glob::new(sub_args.get_one::<String>("glob").unwrap())
+                .context("Invalid glob")?
+                .compile_matcher();
+            let book_dir = Path::new(sub_args.get_one::<String>("book").unwrap());
+            let (_, src_dir) = sources::load_config(book_dir).context("Failed to load book")?;
+            let mut updated = 0;
+            for path in sources::chapter_files(&src_dir) {
+                if !glob.is_match(sources::chapter_name(book_dir, &path)) {
+                    continue;
+                }
+                if edit::set_in_file(&path, &updates)
+                    .with_context(|| format!("Failed to update '{}'", path.display()))?
+                {
+                    println!("Updated {}", sources::chapter_name(&src_dir, &path));
+                    updated += 1;
+                }
+            }
+            println!("Updated {} chapters", updated);
+            return Ok(0);
+        }
+        Some(("import", sub_args)) => {
+            let csv_path = sub_args.get_one::<String>("csv").unwrap();
+            let file = File::open(csv_path).context("Failed to open CSV file")?;
+            let rows = import::read_csv(file, sub_args.get_one::<String>("key").unwrap())
+                .context("Failed to read CSV file")?;
+            let book_dir = Path::new(sub_args.get_one::<String>("book").unwrap());
+            let (_, src_dir) = sources::load_config(book_dir).context("Failed to load book")?;
+            let mut updated = 0;
+            for row in rows {
+                let Some(path) = import::chapter_file(&src_dir, &row.chapter) else {
+                    eprintln!("No chapter found for '{}'", row.chapter);
+                    continue;
+                };
+                let changed = if sub_args.get_flag("sidecar") {
+                    edit::set_in_sidecar(&path, &row.updates)
+                } else {
+                    edit::set_in_file(&path, &row.updates)
+                };
+                if changed.with_context(|| format!("Failed to update '{}'", path.display()))? {
+                    println!("Updated {}", sources::chapter_name(&src_dir, &path));
+                    updated += 1;
+                }
+            }
+            println!("Updated {} chapters", updated);
+            return Ok(0);
+        }
+        Some(("migrate-config", sub_args)) => {
+            let book_dir = Path::new(sub_args.get_one::<String>("book-dir").unwrap());
+            let path = book_dir.join("book.toml");
+            let book_toml = fs::read_to_string(&path).context("Failed to read book.toml")?;
+            let (migrated, notes) = config::migrate(&book_toml);
+            for note in &notes {
+                println!("{}", note);
+            }
+            if notes.is_empty() {
+                println!("Nothing to migrate");
+            } else if !sub_args.get_flag("dry-run") {
+                fs::write(&path, migrated).context("Failed to write book.toml")?;
+                println!("Updated {}", path.display());
+            }
+            return Ok(0);
+        }
+        Some(("fix", sub_args)) => {
+            let book_dir = Path::new(sub_args.get_one::<String>("book-dir").unwrap());
+            let dry_run = sub_args.get_flag("dry-run");
+            let migrate = Migrate::load(book_dir).context("Failed to load book")?;
+            let lint = Lint::load(book_dir).context("Failed to load book")?;
+            let (_, src_dir) = sources::load_config(book_dir).context("Failed to load book")?;
+            let mut fixed = 0;
+            for path in sources::chapter_files(&src_dir) {
+                if frontmatter::rewrite_file(&path, dry_run, |format, body| {
+                    lint.fix(format, &migrate.rename_in_body(format, body))
+                })
+                .with_context(|| format!("Failed to fix '{}'", path.display()))?
+                {
+                    println!("Fixed {}", sources::chapter_name(&src_dir, &path));
+                    fixed += 1;
+                }
+            }
+            println!("Fixed {} chapters", fixed);
+            return Ok(0);
+        }
+        Some(("enrich-search", sub_args)) => {
+            let book_dir = Path::new(sub_args.get_one::<String>("book-dir").unwrap());
+            let enriched = search_index::enrich_build(book_dir)
+                .context("Failed to enrich the search index")?;
+            println!("Enriched {} search documents", enriched);
+            return Ok(0);
+        }
+        Some(("report", sub_args)) => {
+            let book_dir = Path::new(sub_args.get_one::<String>("book-dir").unwrap());
+            let format =
+                ReportFormat::from_name(sub_args.get_one::<String>("format").unwrap()).unwrap();
+            let mut settings = Report::load(book_dir).context("Failed to load book")?;
+            if let Some(max_age) = sub_args.get_one::<i64>("max-age") {
+                settings.max_age = *max_age;
+            }
+            let rows = settings
+                .rows(book_dir, chrono::Local::now().date_naive())
+                .context("Failed to read chapters")?;
+            print!(
+                "{}",
+                report::render(&rows, format).context("Failed to render report")?
+            );
+            return Ok(0);
+        }
+        Some(("owners", sub_args)) => {
+            let book_dir = Path::new(sub_args.get_one::<String>("book-dir").unwrap());
+            let format =
+                OwnersFormat::from_name(sub_args.get_one::<String>("format").unwrap()).unwrap();
+            let settings = Owners::load(book_dir).context("Failed to load book")?;
+            let mapping = settings.rows(book_dir).context("Failed to read chapters")?;
+            let out = owners::render(&mapping, format).context("Failed to render owners")?;
+            match sub_args.get_one::<String>("output") {
+                Some(path) => {
+                    if let Some(dir) = Path::new(path).parent() {
+                        fs::create_dir_all(dir).context("Failed to create the output directory")?;
+                    }
+                    fs::write(path, out).context("Failed to write owners")?;
+                    println!("Wrote {}", path);
+                }
+                None => print!("{}", out),
+            }
+            return Ok(0);
+        }
+        Some(("xgettext", sub_args)) => {
+            let book_dir = Path::new(sub_args.get_one::<String>("book-dir").unwrap());
+            let gettext = Gettext::load(book_dir).context("Failed to load book")?;
+            let pot = gettext
+                .extract(book_dir)
+                .context("Failed to extract metadata")?;
+            let path = match sub_args.get_one::<String>("output") {
+                Some(output) => output.into(),
+                None => book_dir.join(&gettext.po_dir).join("metadata.pot"),
+            };
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).context("Failed to create the catalog directory")?;
+            }
+            fs::write(&path, pot).context("Failed to write the catalog")?;
+            println!("Wrote {}", path.display());
+            return Ok(0);
+        }
+        _ => {}
+    }
 
-    let (ctx, book) = CmdPreprocessor::parse_input(io::stdin()).expect("Failed to parse input");
+    let (mut ctx, book) =
+        CmdPreprocessor::parse_input(io::stdin()).context("Failed to parse input")?;
+    config::apply_extends(&ctx.root, &mut ctx.config).context("Failed to apply extends")?;
 
-    let preprocessor = Metadata::new(&ctx);
+    let preprocessor = Metadata::new(&ctx)
+        .with_offline(matches.get_flag("offline"))
+        .with_strict(matches.get_flag("strict"))
+        .with_filter(matches.get_one::<String>("filter").cloned())
+        .with_explain(matches.get_flag("explain"));
 
     let processed_book = preprocessor
         .run(&ctx, book)
-        .expect("Failed to process book");
+        .context("Failed to process book")?;
 
-    serde_json::to_writer(io::stdout(), &processed_book).expect("Failed to emit processed book");
+    let output = serde_json::to_vec(&processed_book).context("Failed to emit processed book")?;
+    io::stdout()
+        .write_all(&output)
+        .context("Failed to emit processed book")?;
+    Ok(0)
 }
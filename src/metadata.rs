@@ -1,129 +1,523 @@
-use html_escape::encode_safe;
+use chrono::{Local, Utc};
 use lazy_static::lazy_static;
 use log::{error, info, warn};
 use mdbook::{
-    book::{Book, BookItem},
+    book::{Book, BookItem, Chapter},
+    config::BookConfig,
     errors::Error as MdBookError,
     preprocess::{Preprocessor, PreprocessorContext},
 };
 use regex::Regex;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
-
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::value::Table;
+
+use crate::accessibility::Accessibility;
+use crate::aggregate::{insert_chapter, ChapterEntry};
+use crate::appendix::Appendix;
+use crate::archive::Archive;
+use crate::artifacts::write_artifact;
+use crate::asset_licenses::{AssetLicenses, ASSET_LICENSES_KEY};
+use crate::audience::Audience;
+use crate::audit::Audit;
+use crate::authors::Authors;
+use crate::banner::{Banner, BANNER_KEYS};
+use crate::book_metadata::BookMetadata;
+use crate::canonical::{self, CANONICAL_GROUP_KEY, CANONICAL_KEY};
+use crate::changelog::Changelog;
+use crate::citations::{BookInfo, Citations};
 use crate::cli::NAME;
+use crate::compat::{self, Compat, Conventions};
+use crate::computed::Computed;
+use crate::config;
+use crate::consent::Consent;
+use crate::coverage::Coverage;
+use crate::data_attributes::{DataAttributes, CSS_CLASS_KEY};
+use crate::decorate::Decorate;
+use crate::emit::Emit;
+use crate::events::{Events, EVENT_KEYS};
+use crate::explain::Explanation;
+use crate::fediverse::{creator_tags, FEDIVERSE_KEY};
+use crate::filter::{self, Filter};
+use crate::frontmatter::{self, Format, Prefix};
+use crate::geo;
+use crate::gettext::Gettext;
+use crate::glossary::Glossary;
+use crate::graph::Graph;
+use crate::head_snippet::{HeadSnippet, HEAD_SNIPPET_KEY};
+use crate::headings::Headings;
+use crate::hints::{preload_tags, Hints, PRELOAD_KEY};
+use crate::hook::Hook;
+use crate::identifiers::identifier_tags;
+use crate::includes;
+use crate::json_ld;
+use crate::lang::{lang_tags, LANG_KEY};
+use crate::learning::{Learning, LEARNING_KEYS};
+use crate::links::{parse_links, LINKS_KEY};
+use crate::migrate::Migrate;
+use crate::namespaces::{self, Namespace, Namespaces};
+use crate::nav_groups::NavGroups;
+use crate::obsidian;
+use crate::open_graph::OpenGraph;
+use crate::overlay::Overlay;
+use crate::parts::Parts;
+use crate::plugins::{self, Plugin};
+use crate::position::add_position_keys;
+use crate::prerequisites::{insert_after_title, Prerequisites};
+use crate::profiles::{self, Profile};
+use crate::remote::{self, Remote, RemoteData};
+use crate::reviews::{Reviews, REVIEW_KEYS};
+use crate::rules::{self, Rule};
+use crate::scope::Scope;
+use crate::search::{Algolia, SearchExport};
+use crate::search_index::SearchIndex;
+use crate::see_also::SeeAlso;
+use crate::short_title::ShortTitle;
+use crate::sidebar::Sidebar;
+use crate::sitemap::{Sitemap, SITEMAP_KEYS};
+use crate::stale::{Stale, STALE_CHECK_KEY};
+use crate::tag_synonyms::TagSynonyms;
+use crate::tags::{self, Tag, TagKind};
+use crate::template::{self, is_template};
+use crate::theme_data::ThemeData;
+use crate::topics::Topics;
+use crate::urls::Urls;
+use crate::verification::Verification;
+use crate::versions::{self, LATEST_META_VERSION, META_VERSION_KEY};
+use crate::vocab::Vocabularies;
+use crate::whats_new::WhatsNew;
 
 lazy_static! {
-    static ref METADATA_BLOCK_RE: Regex = Regex::new(r"(?s)---(.*?)---").unwrap();
-    static ref METADATA_LINE_RE: Regex = Regex::new(r"^(.+?):\s*(.+)$").unwrap();
+    // `key: value` lines split on the first `: `, so keys can contain colons
+    // (`og:title: Intro`); lines without one split on the first `:`
+    static ref METADATA_LINE_RE: Regex = Regex::new(r"^(.+?):\s+(.+)$").unwrap();
+    static ref COMPACT_METADATA_LINE_RE: Regex = Regex::new(r"^(.+?):(.+)$").unwrap();
+    static ref EMPTY_TABLE: Table = Table::new();
 }
 
+/// Environment variable turning on `strict`, like `--strict`.
+pub const STRICT_VAR: &str = "MDBOOK_METADATA_STRICT";
+
+/// Environment variable setting `filter`, like `--filter`.
+pub const FILTER_VAR: &str = "MDBOOK_METADATA_FILTER";
+
+/// Environment variable turning on `explain`, like `--explain`.
+pub const EXPLAIN_VAR: &str = "MDBOOK_METADATA_EXPLAIN";
+
 #[derive(Debug)]
-enum MetadataError {
+pub enum MetadataError {
     ImproperlyFormattedLine(String),
+    InvalidToml(String),
+    UnresolvedReference {
+        chapter: String,
+        key: String,
+        reference: String,
+    },
+    Template {
+        template: String,
+        message: String,
+    },
+    InvalidValue {
+        key: String,
+        message: String,
+    },
+    Expression {
+        expression: String,
+        message: String,
+    },
+    Profile {
+        profile: String,
+        message: String,
+    },
 }
 
-fn parse_metadata(
+pub fn parse_metadata(
     content: &str,
     continue_on_error: bool,
-) -> Result<(HashMap<String, String>, String), MetadataError> {
-    let mut metadata = HashMap::new();
-    let content_without_metadata = METADATA_BLOCK_RE
-        .replace(content, "")
-        .to_string()
-        .trim_start()
-        .to_string(); // Remove the metadata block and trim leading whitespaces/newlines
-
-    if let Some(caps) = METADATA_BLOCK_RE.captures(content) {
-        let metadata_block = caps.get(1).unwrap().as_str();
-
-        for line in metadata_block.lines() {
-            if line.trim().is_empty() {
-                continue; // Skip empty lines
-            }
-            match METADATA_LINE_RE.captures(line) {
-                Some(caps) => {
-                    let key = caps.get(1).unwrap().as_str().trim().to_string();
-                    let value = caps.get(2).unwrap().as_str().trim().to_string();
-                    info!("Parsed metadata: {}: {}", key, value);
-                    metadata.insert(key, value);
-                }
-                None => {
-                    if continue_on_error {
-                        // Warn and continue to the next line
-                        warn!("Improperly formatted metadata line skipped: '{}'", line);
-                        continue;
-                    } else {
-                        // Return an error and halt processing
-                        return Err(MetadataError::ImproperlyFormattedLine(line.to_string()));
-                    }
+) -> Result<(HashMap<String, String>, Cow<'_, str>), MetadataError> {
+    parse_metadata_after(content, continue_on_error, &Prefix::ALL)
+}
+
+/// Like [`parse_metadata`], only skipping the given prefixes before the block.
+/// Skipped HTML comments are kept, above the rest of the content.
+pub fn parse_metadata_after<'a>(
+    content: &'a str,
+    continue_on_error: bool,
+    prefixes: &[Prefix],
+) -> Result<(HashMap<String, String>, Cow<'a, str>), MetadataError> {
+    let Some(block) = frontmatter::find_after(content, prefixes) else {
+        return Ok((HashMap::new(), Cow::Borrowed(content)));
+    };
+
+    let metadata: HashMap<String, String> =
+        parse_entries(block.format, block.body, continue_on_error)?
+            .into_iter()
+            .collect();
+    info!("Parsed metadata: {:?}", metadata);
+    let rest = content[block.end..].trim_start();
+    let skipped = content[..block.start].trim_start_matches('\u{feff}').trim();
+    if skipped.is_empty() {
+        Ok((metadata, Cow::Borrowed(rest)))
+    } else {
+        Ok((metadata, Cow::Owned(format!("{}\n\n{}", skipped, rest))))
+    }
+}
+
+/// Parses the body of a metadata block into `(key, value)` pairs, in order.
+pub fn parse_entries(
+    format: Format,
+    body: &str,
+    continue_on_error: bool,
+) -> Result<Vec<(String, String)>, MetadataError> {
+    if format == Format::Toml {
+        return match frontmatter::parse_toml(body) {
+            Ok(entries) => Ok(entries),
+            Err(e) if continue_on_error => {
+                warn!("Invalid TOML metadata skipped: {}", e);
+                Ok(Vec::new())
+            }
+            Err(e) => Err(MetadataError::InvalidToml(e)),
+        };
+    }
+
+    let mut entries = Vec::new();
+    for line in body.lines() {
+        if line.trim().is_empty() {
+            continue; // Skip empty lines
+        }
+        match split_line(line) {
+            Some((key, value)) => {
+                info!("Parsed metadata: {}: {}", key, value);
+                entries.push((key, value));
+            }
+            None => {
+                if continue_on_error {
+                    // Warn and continue to the next line
+                    warn!("Improperly formatted metadata line skipped: '{}'", line);
+                    continue;
+                } else {
+                    // Return an error and halt processing
+                    return Err(MetadataError::ImproperlyFormattedLine(line.to_string()));
                 }
             }
         }
     }
-    info!("Parsed metadata: {:?}", metadata);
-    Ok((metadata, content_without_metadata.to_string()))
+    Ok(entries)
 }
 
-fn metadata_to_html(
-    metadata: &HashMap<String, String>,
-    default_values: &HashMap<String, String>,
-) -> String {
-    let mut tags = HashMap::new();
+/// Splits a `key: value` line into its trimmed key and value.
+fn split_line(line: &str) -> Option<(String, String)> {
+    let caps = METADATA_LINE_RE
+        .captures(line)
+        .or_else(|| COMPACT_METADATA_LINE_RE.captures(line))?;
+    Some((caps[1].trim().to_string(), caps[2].trim().to_string()))
+}
 
-    for (key, value) in default_values {
-        let escaped_value = encode_safe(value);
-        tags.insert(key.clone(), escaped_value);
-    }
+/// What happens to chapters opting out with `metadata: false` or
+/// `mdbook-metadata: skip` (`opt-out`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OptOut {
+    Strip,     // The metadata block is removed, but nothing is emitted
+    Untouched, // The chapter is left byte-identical
+}
 
-    for (key, value) in metadata {
-        let escaped_value = encode_safe(value);
-        tags.insert(key.clone(), escaped_value);
-    }
+/// Returns whether a chapter's own metadata opts it out of the preprocessor.
+fn opts_out(metadata: &HashMap<String, String>) -> bool {
+    metadata.get("metadata").map(|v| v.trim()) == Some("false")
+        || metadata.get("mdbook-metadata").map(|v| v.trim()) == Some("skip")
+}
 
-    let mut html_tags = String::new();
-    for (key, escaped_value) in tags {
-        match key.as_str() {
-            "title" => html_tags.push_str(&format!("<title>{}</title>\n", escaped_value)),
-            _ => html_tags.push_str(&format!(
-                "<meta name=\"{}\" content=\"{}\">\n",
-                key, escaped_value
-            )),
+/// Reads the sidecar metadata file of a chapter (`intro.meta.yml` or
+/// `intro.meta.toml` next to `intro.md`), if it has one.
+fn sidecar_metadata(
+    chapter: &Path,
+    continue_on_error: bool,
+) -> Result<HashMap<String, String>, MetadataError> {
+    let Some((path, format)) = frontmatter::find_sidecar(chapter) else {
+        return Ok(HashMap::new());
+    };
+    match fs::read_to_string(&path) {
+        Ok(body) => Ok(parse_entries(format, &body, continue_on_error)?
+            .into_iter()
+            .collect()),
+        Err(e) => {
+            warn!("Failed to read '{}': {}", path.display(), e);
+            Ok(HashMap::new())
         }
     }
+}
+
+/// Returns the problems of a chapter's metadata block: lines that aren't
+/// `key: value` pairs, or invalid TOML.
+pub fn metadata_errors(content: &str) -> Vec<MetadataError> {
+    located_metadata_errors(content)
+        .into_iter()
+        .map(|(_, e)| e)
+        .collect()
+}
+
+/// Like [`metadata_errors`], along with the 1-based line of each error.
+pub fn located_metadata_errors(content: &str) -> Vec<(usize, MetadataError)> {
+    let Some(block) = frontmatter::find(content) else {
+        return Vec::new();
+    };
+    match block.format {
+        Format::Yaml => block
+            .body
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && split_line(line).is_none())
+            .map(|(i, line)| {
+                (
+                    block.line_number(content, i),
+                    MetadataError::ImproperlyFormattedLine(line.to_string()),
+                )
+            })
+            .collect(),
+        Format::Toml => match toml::from_str::<Table>(block.body) {
+            Ok(_) => Vec::new(),
+            Err(e) => {
+                let line = e.line_col().map(|(line, _)| line).unwrap_or_default();
+                vec![(
+                    block.line_number(content, line),
+                    MetadataError::InvalidToml(e.to_string()),
+                )]
+            }
+        },
+    }
+}
+
+/// Returns the 1-based line defining `key` in the metadata block of `content`.
+/// Keys of nested TOML tables aren't located.
+pub fn key_line(content: &str, key: &str) -> Option<usize> {
+    let block = frontmatter::find(content)?;
+    let i = block.body.lines().position(|line| match block.format {
+        Format::Yaml => split_line(line).is_some_and(|(k, _)| k == key),
+        Format::Toml => line
+            .split_once('=')
+            .is_some_and(|(k, _)| k.trim().trim_matches('"') == key),
+    })?;
+    Some(block.line_number(content, i))
+}
+
+/// Splits a list value such as `[a, b]` or `a, b` into its trimmed, unquoted items.
+pub fn parse_list(value: &str) -> Vec<String> {
+    let value = value.trim();
+    let value = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(value);
+
+    value
+        .split(',')
+        .map(|item| item.trim().trim_matches(|c| c == '"' || c == '\'').trim())
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Builds the tags of a chapter from its metadata merged over the default
+/// values, in key order. The `title` key becomes the `<title>` element, and
+/// prefixed keys are dispatched to their namespace (`og:`, `dc:`, ...).
+pub fn metadata_tags(
+    metadata: &HashMap<String, String>,
+    default_values: &HashMap<String, String>,
+) -> Vec<Tag> {
+    let mut values = BTreeMap::new();
+    values.extend(default_values);
+    values.extend(metadata);
+
+    values
+        .into_iter()
+        .map(|(key, value)| namespaces::tag(key, value))
+        .collect()
+}
+
+pub fn metadata_to_html(
+    metadata: &HashMap<String, String>,
+    default_values: &HashMap<String, String>,
+) -> String {
+    let html_tags = tags::to_html(&metadata_tags(metadata, default_values));
 
     info!("Generated HTML tags: {}", html_tags);
     html_tags
 }
 
-
 pub struct Metadata {
     valid_tags: Option<Vec<String>>, // Optional list of valid tags specified in the configuration
+    required_tags: Vec<String>,      // Keys every chapter must set
+    vocab: Vocabularies,             // Bundled key sets, valid along with valid-tags
+    namespaces: Option<Namespaces>,  // Templates of custom: keys and the dc: schema link
     default_tag_values: HashMap<String, String>, // Optional map of default tag values
+    rules: Vec<Rule>,                // Metadata set on chapters matching a glob
+    profiles: Vec<Profile>,          // Keys required and allowed on chapters matching a glob
+    coverage: Option<Coverage>,      // Optional metadata coverage badge
+    scope: Scope,                    // Chapters matching the include/exclude globs
+    opt_out: OptOut,                 // Handling of chapters opting out in their metadata
+    raw_keys: Vec<String>,           // Trusted keys whose values are injected unescaped
+    sanitize_values: bool,           // Whether HTML is stripped from the other values
+    audit: Option<Audit>,            // Optional rejection of suspicious values
+    property_keys: Vec<String>,      // Keys emitted as <meta property>, e.g. og:*
+    fediverse_creator: Option<String>, // Default fediverse handle of chapter authors
+    meta_version: u32,               // Metadata conventions of chapters declaring none
+    hook: Option<Hook>,              // Optional external command transforming metadata
+    plugins: Vec<Plugin>,            // WebAssembly modules emitting custom tags
+    remote: Option<Remote>,          // Optional metadata from an HTTP endpoint or JSON dump
+    migrate: Option<Migrate>,        // Optional renames of legacy keys
+    compat: Option<Compat>,          // Optional Jekyll or Hugo frontmatter conventions
+    tag_synonyms: Option<TagSynonyms>, // Optional canonical spellings of tags
     continue_on_error: bool,         // Optional flag to continue processing after an error occurs
+    strict: bool,                    // Turns warnings about chapters into errors, for CI
+    config_warnings: Vec<String>,    // Problems with the options, errors when strict
+    filter: Option<String>,          // Optional expression selecting the chapters to build
+    explain: bool,                   // Whether the origin of chapters' metadata is printed
+    artifacts_dir: PathBuf,          // Directory of generated files, relative to the book root
+    urls: Urls,                      // Builds the URLs of chapters
+    tags_manifest: bool,             // Optional meta-tags.json listing the emitted tags
+    chapter_position: bool,          // Whether chapters get their number, depth and position
+    stale: Option<Stale>,            // Optional banner on chapters not updated for long
+    banner: Option<Banner>,          // Renders the banner image of chapters
+    head_snippet: Option<HeadSnippet>, // Adds the head-snippet files of chapters
+    data_attributes: Option<DataAttributes>, // Optional data-meta-* wrapper of chapters
+    frontmatter_prefixes: Vec<Prefix>, // What may come before metadata blocks
+    emit: Emit,                      // Groups of tags emitted unless chapters override them
+    whats_new: Option<WhatsNew>,     // Optional generated "What's new" chapter
+    changelog: Option<Changelog>,    // Optional generated documentation changelog chapter
+    archive: Option<Archive>,        // Optional generated archive chapters
+    glossary: Option<Glossary>,      // Optional generated glossary chapter
+    topics: Option<Topics>,          // Optional generated keyword cloud chapter
+    appendix: Option<Appendix>,      // Optional generated table of chapter metadata
+    asset_licenses: Option<AssetLicenses>, // Optional third-party content chapter
+    prerequisites: Option<Prerequisites>, // Renders the "Before you read this" box
+    see_also: Option<SeeAlso>,       // Renders the "See also" section and related links
+    graph: Option<Graph>,            // Optional chapter dependency graph export
+    learning: Option<Learning>,      // Optional learning metadata emitter
+    events: Option<Events>,          // Optional event emitter
+    reviews: Option<Reviews>,        // Optional review emitter
+    computed: Option<Computed>,      // Optional keys derived from the merged metadata
+    short_title: Option<ShortTitle>, // Optional shorter chapter names in the navigation
+    decorate: Option<Decorate>,      // Optional status markers in chapter names
+    sidebar: Option<Sidebar>,        // Optional sidebar badge data for themes
+    nav_groups: Option<NavGroups>,   // Optional nav-groups.json for theme menus
+    gettext: Option<Gettext>,        // Optional translation of values from PO files
+    headings: Option<Headings>,      // Optional export of chapter headings and anchors
+    theme_data: Option<ThemeData>,   // Optional theme-data.json for handlebars partials
+    parts: Option<Parts>,            // Optional metadata inherited from part intro chapters
+    overlay: Option<Overlay>,        // Optional diagnostics overlay while serving
+    verification: Option<Verification>, // Optional site verification tags
+    sitemap: Option<Sitemap>,        // Optional sitemap.xml with per-chapter hints
+    algolia: Option<Algolia>,        // Optional Algolia DocSearch records export
+    search_export: Option<SearchExport>, // Optional Meilisearch or Typesense documents
+    search_index: Option<SearchIndex>, // Optional metadata for mdBook's search index
+    open_graph: Option<OpenGraph>,   // Optional og:locale and article:* tags
+    hints: Option<Hints>,            // Optional preload and prefetch hints
+    consent: Option<Consent>,        // Optional consent categories for consent managers
+    accessibility: Option<Accessibility>, // Optional accessibility declarations
+    audience: Option<Audience>,      // Optional audience and reading-level declarations
+    authors: Option<Authors>,        // Optional author registry for JSON-LD
+    citations: Option<Citations>,    // Optional "Cite this page" blocks and exports
 }
 
 impl Metadata {
     pub fn new(ctx: &PreprocessorContext) -> Self {
-        let valid_tags: Option<Vec<String>> = ctx
-            .config
-            .get_preprocessor("metadata")
-            .and_then(|p| p.get("valid-tags").cloned())
+        let valid_tags: Option<Vec<String>> = renderer_table(ctx)
+            .and_then(|t| t.get("valid-tags"))
+            .or_else(|| {
+                ctx.config
+                    .get_preprocessor("metadata")
+                    .and_then(|p| p.get("valid-tags"))
+            })
             .map(|tags| {
                 tags.as_array()
-                    .unwrap()
-                    .iter()
-                    .map(|t| t.as_str().unwrap().to_string())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|t| t.as_str().map(String::from))
+                    .collect()
+            });
+
+        let required_tags: Vec<String> = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("required-tags"))
+            .and_then(|v| v.as_array())
+            .map(|keys| {
+                keys.iter()
+                    .filter_map(|k| k.as_str().map(String::from))
                     .collect()
+            })
+            .unwrap_or_default();
+
+        let vocab = Vocabularies::from_names(
+            &ctx.config
+                .get_preprocessor("metadata")
+                .and_then(|p| p.get("vocab"))
+                .and_then(|v| v.as_array())
+                .map(|names| {
+                    names
+                        .iter()
+                        .filter_map(|n| n.as_str().map(String::from))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default(),
+        );
+        // Vocabularies compose with the custom keys of valid-tags
+        let valid_tags = if vocab.is_empty() {
+            valid_tags
+        } else {
+            let mut valid_tags = valid_tags.unwrap_or_default();
+            valid_tags.extend(vocab.keys().map(String::from));
+            Some(valid_tags)
+        };
+
+        let strict: bool = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("strict"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+            || std::env::var_os(STRICT_VAR).is_some_and(|v| !v.is_empty() && v != "0");
+
+        let filter: Option<String> = std::env::var(FILTER_VAR)
+            .ok()
+            .filter(|f| !f.trim().is_empty())
+            .or_else(|| {
+                ctx.config
+                    .get_preprocessor("metadata")
+                    .and_then(|p| p.get("filter"))
+                    .and_then(|v| v.as_str())
+                    .map(String::from)
             });
 
-        let continue_on_error: bool = ctx
+        let explain: bool = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("explain"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+            || std::env::var_os(EXPLAIN_VAR).is_some_and(|v| !v.is_empty() && v != "0");
+
+        let continue_on_error: bool = !strict
+            && ctx
+                .config
+                .get_preprocessor("metadata")
+                .and_then(|p| p.get("continue-on-error").cloned())
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true);
+
+        let book_defaults: bool = ctx
             .config
             .get_preprocessor("metadata")
-            .and_then(|p| p.get("continue-on-error").cloned())
+            .and_then(|p| p.get("book-defaults"))
             .and_then(|v| v.as_bool())
-            .unwrap_or(true);
+            .unwrap_or(false);
 
         let mut default_tag_values = HashMap::new();
+        if book_defaults {
+            default_tag_values = book_default_values(&ctx.config.book);
+        }
         if let Some(preprocessor_config) = ctx.config.get_preprocessor("metadata") {
             for (key, value) in preprocessor_config.iter() {
                 if key.starts_with("default-") {
@@ -136,191 +530,1757 @@ impl Metadata {
             }
         }
 
-        Self {
-            valid_tags,
-            default_tag_values,
-            continue_on_error,
-        }
-    }
-}
+        let coverage: Option<Coverage> = feature_table(ctx, "coverage").map(Coverage::from_config);
 
-impl fmt::Display for MetadataError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            MetadataError::ImproperlyFormattedLine(ref line) => {
-                write!(f, "Improperly formatted metadata line: '{}'", line)
-            }
-        }
-    }
-}
+        let profiles: Vec<Profile> = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("profiles"))
+            .map(profiles::profiles_from_config)
+            .unwrap_or_default();
 
-impl Preprocessor for Metadata {
-    fn name(&self) -> &str {
-        NAME
-    }
+        let rules: Vec<Rule> = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("rules"))
+            .map(rules::rules_from_config)
+            .unwrap_or_default();
 
-    fn run(&self, _ctx: &PreprocessorContext, mut book: Book) -> Result<Book, MdBookError> {
-        let mut errors: Vec<String> = Vec::new();
+        let scope: Scope = ctx
+            .config
+            .get_preprocessor("metadata")
+            .map(|p| Scope::from_config(p.get("include"), p.get("exclude")))
+            .unwrap_or_default();
 
-        book.for_each_mut(|item: &mut BookItem| {
-            if let BookItem::Chapter(ref mut chap) = item {
-                if let Ok((parsed_metadata, modified_content)) = parse_metadata(&chap.content, self.continue_on_error) {
-                    let mut metadata = parsed_metadata;
-                    let content = modified_content;
-        
-                    if !metadata.is_empty() && self.valid_tags.is_some() {
-                        metadata = metadata.into_iter()
-                            .filter(|(k, _)| self.valid_tags.as_ref().unwrap().contains(k))
-                            .collect();
-                    }
-        
-                    let html_tags = metadata_to_html(&metadata, &self.default_tag_values);
-        
-                    chap.content = if !html_tags.is_empty() {
-                        format!("{}\n{}", html_tags, content)
-                    } else {
-                        content
-                    };
-                } else if self.continue_on_error {
-                    warn!("Failed to parse metadata for chapter '{}', continuing with original content.", chap.name);
-                } else {
-                    let error_msg = format!("Failed to parse metadata for chapter '{}'.", chap.name);
-                    errors.push(error_msg);
-                }
+        let mut config_warnings = Vec::new();
+        let opt_out = match ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("opt-out"))
+            .and_then(|v| v.as_str())
+        {
+            Some("untouched") => OptOut::Untouched,
+            Some("strip") | None => OptOut::Strip,
+            Some(other) => {
+                config_warnings.push(format!(
+                    "Unknown opt-out '{}', stripping the metadata block",
+                    other
+                ));
+                OptOut::Strip
             }
+        };
+
+        let raw_keys: Vec<String> = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("raw-keys"))
+            .and_then(|v| v.as_array())
+            .map(|keys| {
+                keys.iter()
+                    .filter_map(|k| k.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let property_keys: Vec<String> = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("property-keys"))
+            .and_then(|v| v.as_array())
+            .map(|keys| {
+                keys.iter()
+                    .filter_map(|k| k.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_else(|| {
+                ["og:*", "article:*", "book:*", "profile:*", "fb:*"]
+                    .map(String::from)
+                    .to_vec()
+            });
+
+        let fediverse_creator: Option<String> = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("fediverse-creator"))
+            .and_then(|v| v.as_str())
+            .map(String::from);
+
+        let meta_version: u32 = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("meta-version"))
+            .and_then(|v| v.as_integer())
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| (1..=LATEST_META_VERSION).contains(v))
+            .unwrap_or(LATEST_META_VERSION);
+
+        let hook: Option<Hook> = ctx.config.get_preprocessor("metadata").and_then(|p| {
+            p.get("hook")
+                .and_then(|hook| Hook::from_config(hook, p.get("hook-timeout")))
         });
-        
 
-        if errors.is_empty() {
-            Ok(book)
-        } else {
-            error!(
-                "Errors occurred during preprocessing: \n{}",
-                errors.join("\n")
-            );
-            Err(MdBookError::from(anyhow::Error::msg(errors.join("\n"))))
-        }
-    }
-}
+        let plugins: Vec<Plugin> = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("plugins"))
+            .map(plugins::plugins_from_config)
+            .unwrap_or_default();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::collections::HashSet;
+        let frontmatter_prefixes: Vec<Prefix> = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("frontmatter-prefixes"))
+            .and_then(|v| v.as_array())
+            .map(|names| {
+                names
+                    .iter()
+                    .filter_map(|v| v.as_str())
+                    .filter_map(|name| {
+                        let prefix = Prefix::from_name(name);
+                        if prefix.is_none() {
+                            warn!("Unknown frontmatter prefix '{}' ignored", name);
+                        }
+                        prefix
+                    })
+                    .collect()
+            })
+            .unwrap_or_else(|| Prefix::ALL.to_vec());
 
-    #[test]
-    fn test_parse_metadata_without_metadata_block() {
-        let content = "This is a test chapter content without metadata.";
+        let emit: Emit = renderer_table(ctx)
+            .and_then(|t| t.get("emit"))
+            .and_then(|v| v.as_table())
+            .or_else(|| feature_table(ctx, "emit"))
+            .map(Emit::from_config)
+            .unwrap_or_default();
 
-        let (metadata, content_without_metadata) = parse_metadata(content, false).unwrap();
+        let stale: Option<Stale> = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(Stale::from_config);
 
-        assert!(metadata.is_empty(), "Expected metadata to be empty");
-        assert_eq!(
-            content_without_metadata, content,
-            "Content should remain unchanged"
-        );
-    }
+        let remote: Option<Remote> = feature_table(ctx, "remote").map(Remote::from_config);
+        let migrate: Option<Migrate> = feature_table(ctx, "migrate").map(Migrate::from_config);
+        let compat: Option<Compat> = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("compat"))
+            .and_then(|v| v.as_str())
+            .and_then(|name| {
+                let compat = Compat::from_name(name);
+                if compat.is_none() {
+                    warn!(
+                        "Ignoring compat = \"{}\", expected \"jekyll\" or \"hugo\"",
+                        name
+                    );
+                }
+                compat
+            });
 
-    #[test]
-    fn test_parse_metadata_with_valid_metadata_block() {
-        let content_with_metadata = r#"---
-title: Test Chapter
-keywords: rust, testing, mdbook
-released: true
----
+        let tag_synonyms: Option<TagSynonyms> =
+            feature_table(ctx, "tag-synonyms").map(TagSynonyms::from_config);
 
-This is the chapter content."#;
+        let artifacts_dir: PathBuf = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("artifacts-dir"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("book-metadata")
+            .into();
 
-        let (metadata, content_without_metadata) =
-            parse_metadata(content_with_metadata, false).unwrap();
+        let urls: Urls = ctx
+            .config
+            .get_preprocessor("metadata")
+            .map(Urls::from_config)
+            .unwrap_or_default();
 
-        assert_eq!(
-            metadata.get("title"),
-            Some(&"Test Chapter".to_string()),
-            "Title should be 'Test Chapter'"
-        );
-        assert_eq!(
-            metadata.get("keywords"),
-            Some(&"rust, testing, mdbook".to_string()),
-            "Keywords should be 'rust, testing, mdbook'"
-        );
-        assert_eq!(
-            metadata.get("released"),
-            Some(&"true".to_string()),
-            "Released should be 'true'"
-        );
+        let tags_manifest: bool = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("tags-manifest"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
 
-        let expected_content = "This is the chapter content.";
-        assert_eq!(
-            content_without_metadata, expected_content,
-            "Content should not include metadata block"
-        );
-    }
+        let sanitize_values: bool = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("sanitize-values"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
 
-    #[test]
-    fn test_parse_metadata_with_bad_indentation() {
-        let content_with_badly_indented_metadata = r#"---
-    title: Test Chapter
-   keywords:   rust, testing, mdbook
-    released :true
----
+        let audit: Option<Audit> = feature_table(ctx, "audit").map(Audit::from_config);
 
-This is the chapter content."#;
+        let chapter_position: bool = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("chapter-position"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
 
-        let (metadata, content_without_metadata) =
-            parse_metadata(content_with_badly_indented_metadata, false).unwrap();
+        let whats_new: Option<WhatsNew> =
+            feature_table(ctx, "whats-new").map(WhatsNew::from_config);
 
-        assert_eq!(
-            metadata.get("title"),
-            Some(&"Test Chapter".to_string()),
-            "Title should be 'Test Chapter'"
-        );
-        assert_eq!(
-            metadata.get("keywords"),
-            Some(&"rust, testing, mdbook".to_string()),
-            "Keywords should be 'rust, testing, mdbook'"
-        );
-        assert_eq!(
-            metadata.get("released"),
-            Some(&"true".to_string()),
-            "Released should be 'true'"
-        );
+        let changelog: Option<Changelog> =
+            feature_table(ctx, "changelog").map(Changelog::from_config);
 
-        let expected_content = "This is the chapter content.";
-        assert_eq!(
-            content_without_metadata, expected_content,
-            "Content should not include metadata block"
-        );
-    }
+        let archive: Option<Archive> = feature_table(ctx, "archive").map(Archive::from_config);
 
-    #[test]
-    fn test_parse_metadata_with_bad_metadata_block() {
-        let content_with_bad_metadata = r#"---
-title = Incorrect Format
-keywords = rust, testing, mdbook
-released = false
----
+        let glossary: Option<Glossary> = feature_table(ctx, "glossary").map(Glossary::from_config);
+        let topics: Option<Topics> = feature_table(ctx, "topics").map(Topics::from_config);
+        let appendix: Option<Appendix> = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(Appendix::from_config);
+
+        let asset_licenses: Option<AssetLicenses> =
+            feature_table(ctx, "asset-licenses").map(AssetLicenses::from_config);
+
+        let prerequisites: Option<Prerequisites> =
+            default_feature_table(ctx, "prerequisites").map(Prerequisites::from_config);
+        let banner: Option<Banner> = default_feature_table(ctx, "banner").map(Banner::from_config);
+        let head_snippet: Option<HeadSnippet> =
+            default_feature_table(ctx, "head-snippet").map(HeadSnippet::from_config);
+        let namespaces: Option<Namespaces> =
+            default_feature_table(ctx, "namespaces").map(Namespaces::from_config);
+        // Obsidian's cssclasses need the wrapper, even without data-attributes
+        let data_attributes: Option<DataAttributes> = feature_table(ctx, "data-attributes")
+            .map(DataAttributes::from_config)
+            .or_else(|| {
+                (compat == Some(Compat::Obsidian)).then(|| DataAttributes {
+                    keys: Vec::new(),
+                    ..DataAttributes::from_config(&Table::new())
+                })
+            });
 
-This is the chapter content."#;
+        let see_also: Option<SeeAlso> =
+            default_feature_table(ctx, "see-also").map(SeeAlso::from_config);
 
-        let result = parse_metadata(content_with_bad_metadata, false);
+        let graph: Option<Graph> = feature_table(ctx, "graph").map(Graph::from_config);
 
-        assert!(
-            result.is_err(),
-            "Expected an error due to bad metadata block format"
-        );
+        let learning: Option<Learning> = feature_table(ctx, "learning").map(Learning::from_config);
+        let events: Option<Events> = feature_table(ctx, "events").map(Events::from_config);
+        let reviews: Option<Reviews> = feature_table(ctx, "reviews").map(Reviews::from_config);
 
-        if let Err(MetadataError::ImproperlyFormattedLine(line)) = result {
-            assert_eq!(
-                line, "title = Incorrect Format",
-                "Expected error for the improperly formatted line"
-            );
-        } else {
-            panic!("Expected an ImproperlyFormattedLine error");
-        }
-    }
+        let computed: Option<Computed> = feature_table(ctx, "computed").map(Computed::from_config);
 
-    #[test]
+        let short_title: Option<ShortTitle> =
+            feature_table(ctx, "short-title").map(ShortTitle::from_config);
+
+        let decorate: Option<Decorate> = feature_table(ctx, "decorate").map(Decorate::from_config);
+
+        let sidebar: Option<Sidebar> = feature_table(ctx, "sidebar").map(Sidebar::from_config);
+
+        let headings: Option<Headings> = feature_table(ctx, "headings").map(Headings::from_config);
+
+        let gettext: Option<Gettext> = feature_table(ctx, "gettext").map(Gettext::from_config);
+
+        let nav_groups: Option<NavGroups> =
+            feature_table(ctx, "nav-groups").map(NavGroups::from_config);
+
+        let theme_data: Option<ThemeData> =
+            feature_table(ctx, "theme-data").map(ThemeData::from_config);
+
+        let parts: Option<Parts> = feature_table(ctx, "parts").map(Parts::from_config);
+
+        let overlay: Option<Overlay> = feature_table(ctx, "overlay").map(Overlay::from_config);
+
+        let verification: Option<Verification> =
+            feature_table(ctx, "verification").map(Verification::from_config);
+
+        let sitemap: Option<Sitemap> = feature_table(ctx, "sitemap").map(Sitemap::from_config);
+
+        let algolia: Option<Algolia> = feature_table(ctx, "algolia").map(Algolia::from_config);
+
+        let search_export: Option<SearchExport> = ctx
+            .config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("search-export"))
+            .and_then(|v| v.as_str())
+            .and_then(SearchExport::from_name);
+
+        let search_index: Option<SearchIndex> =
+            feature_table(ctx, "search-index").map(SearchIndex::from_config);
+
+        let open_graph: Option<OpenGraph> =
+            feature_table(ctx, "open-graph").map(OpenGraph::from_config);
+
+        let hints: Option<Hints> = feature_table(ctx, "hints").map(Hints::from_config);
+
+        let consent: Option<Consent> = feature_table(ctx, "consent").map(Consent::from_config);
+
+        let accessibility: Option<Accessibility> =
+            feature_table(ctx, "accessibility").map(Accessibility::from_config);
+
+        let audience: Option<Audience> = feature_table(ctx, "audience").map(Audience::from_config);
+
+        let authors: Option<Authors> = feature_table(ctx, "authors").map(Authors::from_config);
+
+        let citations: Option<Citations> =
+            feature_table(ctx, "citations").map(Citations::from_config);
+
+        Self {
+            valid_tags,
+            required_tags,
+            vocab,
+            namespaces,
+            default_tag_values,
+            rules,
+            profiles,
+            coverage,
+            scope,
+            opt_out,
+            raw_keys,
+            sanitize_values,
+            audit,
+            property_keys,
+            fediverse_creator,
+            meta_version,
+            hook,
+            plugins,
+            remote,
+            migrate,
+            compat,
+            tag_synonyms,
+            continue_on_error,
+            strict,
+            config_warnings,
+            filter,
+            explain,
+            artifacts_dir,
+            urls,
+            tags_manifest,
+            chapter_position,
+            stale,
+            banner,
+            head_snippet,
+            data_attributes,
+            frontmatter_prefixes,
+            emit,
+            whats_new,
+            changelog,
+            archive,
+            glossary,
+            topics,
+            appendix,
+            asset_licenses,
+            prerequisites,
+            see_also,
+            graph,
+            learning,
+            events,
+            reviews,
+            computed,
+            short_title,
+            decorate,
+            sidebar,
+            nav_groups,
+            gettext,
+            headings,
+            theme_data,
+            parts,
+            overlay,
+            verification,
+            sitemap,
+            algolia,
+            search_export,
+            search_index,
+            open_graph,
+            hints,
+            consent,
+            accessibility,
+            audience,
+            authors,
+            citations,
+        }
+    }
+
+    /// Skips fetching the remote metadata source, using its cache (`--offline`).
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        if let Some(remote) = &mut self.remote {
+            remote.offline |= offline;
+        }
+        self
+    }
+
+    /// Turns the warnings about chapters into errors and disables
+    /// `continue-on-error`, for CI builds.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        if strict {
+            self.strict = true;
+            self.continue_on_error = false;
+        }
+        self
+    }
+
+    /// Reports a problem that doesn't stop local builds: an error when
+    /// `strict`, a warning otherwise.
+    fn warning(&self, message: String, errors: &mut Vec<String>) {
+        if self.strict {
+            errors.push(message);
+        } else {
+            warn!("{}", message);
+        }
+    }
+
+    /// First pass over a chapter: strips its metadata block and runs the
+    /// parsed metadata through every configured step.
+    fn collect_chapter(&self, chap: &mut Chapter, pass: &mut FirstPass) {
+        let FirstPass {
+            ctx,
+            src_dir,
+            overlay,
+            remote_data,
+            catalog,
+            position,
+            errors,
+            chapters,
+            warnings,
+            conventions,
+            explanations,
+            skipped,
+        } = pass;
+        let Some(path) = chap.path.clone() else {
+            return; // Draft chapters have no content
+        };
+        *position += 1;
+        if !self.scope.contains(&ctx.config.book.src.join(&path)) {
+            // Chapters out of scope are left byte-identical
+            chapters.push(ChapterEntry::new(chap, &path, HashMap::new()));
+            skipped.insert(path);
+            return;
+        }
+        if let Some(source) = &chap.source_path {
+            if let Some(stripped) =
+                includes::strip_included_blocks(&chap.content, &src_dir.join(source))
+            {
+                chap.content = stripped;
+            }
+        }
+        if overlay.is_some() {
+            let block_errors = metadata_errors(&chap.content);
+            if !block_errors.is_empty() {
+                warnings.insert(
+                    path.clone(),
+                    block_errors.iter().map(|e| e.to_string()).collect(),
+                );
+            }
+        }
+        let block_format =
+            frontmatter::find_after(&chap.content, &self.frontmatter_prefixes).map(|b| b.format);
+        match parse_metadata_after(
+            &chap.content,
+            self.continue_on_error,
+            &self.frontmatter_prefixes,
+        ) {
+            Ok((mut metadata, content)) => {
+                let opted_out = opts_out(&metadata);
+                // Only reallocate chapters whose content actually changed
+                if content.len() != chap.content.len()
+                    && !(opted_out && self.opt_out == OptOut::Untouched)
+                {
+                    chap.content = content.into_owned();
+                }
+                if opted_out {
+                    // The chapter's head is managed elsewhere
+                    chapters.push(ChapterEntry::new(chap, &path, HashMap::new()));
+                    skipped.insert(path);
+                    return;
+                }
+                let mut explanation = self.explain.then(Explanation::default);
+                let mut trace = |step, metadata: &HashMap<String, String>| {
+                    if let Some(explanation) = &mut explanation {
+                        explanation.step(step, metadata);
+                    }
+                };
+                trace("frontmatter", &metadata);
+                match versions::chapter_version(&metadata, self.meta_version) {
+                    Ok(version) => {
+                        if version < self.meta_version {
+                            let message = format!(
+                                "Chapter follows meta-version {}, the book is on {}: it needs migrating",
+                                version, self.meta_version
+                            );
+                            self.warning(format!("{} ('{}')", message, chap.name), errors);
+                            if overlay.is_some() {
+                                warnings.entry(path.clone()).or_default().push(message);
+                            }
+                        }
+                        if version == 1 && block_format == Some(Format::Yaml) {
+                            metadata = versions::legacy_keys(metadata);
+                        }
+                    }
+                    Err(e) if self.continue_on_error => warn!("{}", e),
+                    Err(e) => errors.push(e.to_string()),
+                }
+                trace("meta-version", &metadata);
+                let sidecar = chap
+                    .source_path
+                    .as_ref()
+                    .map(|source| sidecar_metadata(&src_dir.join(source), self.continue_on_error));
+                match sidecar {
+                    Some(Ok(sidecar)) => {
+                        for (key, value) in sidecar {
+                            metadata.entry(key).or_insert(value);
+                        }
+                    }
+                    Some(Err(e)) => errors.push(format!(
+                        "Failed to parse the metadata sidecar of chapter '{}': {}",
+                        chap.name, e
+                    )),
+                    None => {}
+                }
+                trace("sidecar", &metadata);
+                if let Some(migrate) = &self.migrate {
+                    migrate.apply(&mut metadata);
+                    trace("migrate", &metadata);
+                }
+                if let Some(compat) = self.compat {
+                    conventions.insert(path.clone(), compat.apply(&mut metadata));
+                    trace("compat", &metadata);
+                }
+                if let (Some(remote), Some(data)) = (&self.remote, &*remote_data) {
+                    let values = data.lookup(&path, metadata.get("slug").map(String::as_str));
+                    remote::merge(&mut metadata, values, remote.precedence);
+                    trace("remote", &metadata);
+                }
+                let rule_errors = rules::apply(
+                    &self.rules,
+                    &ctx.config.book.src.join(&path),
+                    &mut metadata,
+                    Local::now().date_naive(),
+                );
+                for e in rule_errors {
+                    if self.continue_on_error {
+                        warn!("{} ('{}')", e, chap.name);
+                    } else {
+                        errors.push(format!("{} ('{}')", e, chap.name));
+                    }
+                }
+                trace("rules", &metadata);
+                if let (Some(gettext), Some(catalog)) = (&self.gettext, &*catalog) {
+                    gettext.translate(&mut metadata, catalog);
+                    trace("gettext", &metadata);
+                }
+                if let Some(hook) = &self.hook {
+                    match hook.run(&ctx.root, &path, &metadata) {
+                        Ok(transformed) => metadata = transformed,
+                        Err(e) if self.continue_on_error => {
+                            warn!("{:#}, keeping the metadata as it is", e)
+                        }
+                        Err(e) => errors.push(format!("{:#}", e)),
+                    }
+                    trace("hook", &metadata);
+                }
+                if self.sanitize_values {
+                    for (key, value) in metadata.iter_mut() {
+                        if !self.raw_keys.contains(key) {
+                            *value = tags::strip_html(value);
+                        }
+                    }
+                    trace("sanitize-values", &metadata);
+                }
+                if let Some(audit) = &self.audit {
+                    for e in audit.apply(&mut metadata) {
+                        if overlay.is_some() {
+                            warnings
+                                .entry(path.clone())
+                                .or_default()
+                                .push(e.to_string());
+                        }
+                        if self.continue_on_error {
+                            warn!("{} ('{}')", e, chap.name);
+                        } else {
+                            errors.push(format!("{} ('{}')", e, chap.name));
+                        }
+                    }
+                    trace("audit", &metadata);
+                }
+                if let Some(tag_synonyms) = &self.tag_synonyms {
+                    for (synonym, tag) in tag_synonyms.apply(&mut metadata) {
+                        let message = format!("Tag '{}' is a synonym of '{}'", synonym, tag);
+                        self.warning(format!("{} ('{}')", message, chap.name), errors);
+                        if overlay.is_some() {
+                            warnings.entry(path.clone()).or_default().push(message);
+                        }
+                    }
+                    trace("tag-synonyms", &metadata);
+                }
+                let profile_errors =
+                    profiles::check(&self.profiles, &ctx.config.book.src.join(&path), &metadata);
+                for e in profile_errors {
+                    if overlay.is_some() {
+                        warnings
+                            .entry(path.clone())
+                            .or_default()
+                            .push(e.to_string());
+                    }
+                    if self.continue_on_error {
+                        warn!("{} ('{}')", e, chap.name);
+                    } else {
+                        errors.push(format!("{} ('{}')", e, chap.name));
+                    }
+                }
+                for key in &self.required_tags {
+                    if metadata.get(key).is_some_and(|v| !v.trim().is_empty()) {
+                        continue;
+                    }
+                    let message = format!("Missing required tag '{}'", key);
+                    self.warning(format!("{} ('{}')", message, chap.name), errors);
+                    if overlay.is_some() {
+                        warnings.entry(path.clone()).or_default().push(message);
+                    }
+                }
+                if self.chapter_position && !metadata.is_empty() {
+                    add_position_keys(&mut metadata, chap, *position);
+                    trace("chapter-position", &metadata);
+                }
+                if let Some(explanation) = explanation {
+                    explanations.insert(path.clone(), explanation);
+                }
+                chapters.push(ChapterEntry::new(chap, &path, metadata));
+            }
+            Err(_) if self.continue_on_error => {
+                warn!(
+                    "Failed to parse metadata for chapter '{}', continuing with original content.",
+                    chap.name
+                );
+            }
+            Err(_) => {
+                errors.push(format!(
+                    "Failed to parse metadata for chapter '{}'.",
+                    chap.name
+                ));
+            }
+        }
+    }
+
+    /// Builds only the chapters matching a filter expression (`--filter`),
+    /// overriding the `filter` option.
+    pub fn with_filter(mut self, filter: Option<String>) -> Self {
+        if filter.is_some() {
+            self.filter = filter;
+        }
+        self
+    }
+
+    /// Prints where every chapter's metadata came from to stderr (`--explain`).
+    pub fn with_explain(mut self, explain: bool) -> Self {
+        self.explain |= explain;
+        self
+    }
+
+    /// Renders the `default-*` values that are templates against the chapter's metadata.
+    fn render_default_values(
+        &self,
+        entry: &ChapterEntry,
+        ctx: &PreprocessorContext,
+    ) -> Result<HashMap<String, String>, MetadataError> {
+        if !self.default_tag_values.values().any(|v| is_template(v)) {
+            return Ok(self.default_tag_values.clone());
+        }
+
+        let vars = template::variables(&entry.metadata, &ctx.config.book);
+        self.default_tag_values
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), template::render(value, &vars)?)))
+            .collect()
+    }
+}
+
+/// Maps the `[book]` settings to default tag values: `title` to `application-name`,
+/// `authors` (joined) to `author`, `description` and `language`.
+fn book_default_values(book: &BookConfig) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    if let Some(title) = &book.title {
+        values.insert("application-name".to_string(), title.clone());
+    }
+    if !book.authors.is_empty() {
+        values.insert("author".to_string(), book.authors.join(", "));
+    }
+    if let Some(description) = &book.description {
+        values.insert("description".to_string(), description.clone());
+    }
+    if let Some(language) = &book.language {
+        values.insert("language".to_string(), language.clone());
+    }
+    values
+}
+
+/// Returns the `[preprocessor.metadata.<name>]` table of an optional feature,
+/// unless it is explicitly disabled with `enabled = false`.
+fn feature_table<'a>(ctx: &'a PreprocessorContext, name: &str) -> Option<&'a Table> {
+    ctx.config
+        .get_preprocessor("metadata")
+        .and_then(|p| p.get(name))
+        .and_then(|v| v.as_table())
+        .filter(|t| t.get("enabled").and_then(|v| v.as_bool()).unwrap_or(true))
+}
+
+/// What the first pass reads and collects while going through the chapters.
+struct FirstPass<'a> {
+    ctx: &'a PreprocessorContext,
+    src_dir: &'a Path,
+    overlay: Option<&'a Overlay>,
+    remote_data: Option<&'a RemoteData>,
+    catalog: Option<&'a HashMap<String, String>>,
+    position: usize,
+    errors: &'a mut Vec<String>,
+    chapters: &'a mut Vec<ChapterEntry>,
+    warnings: &'a mut HashMap<PathBuf, Vec<String>>,
+    conventions: &'a mut HashMap<PathBuf, Conventions>,
+    explanations: &'a mut HashMap<PathBuf, Explanation>,
+    skipped: &'a mut HashSet<PathBuf>,
+}
+
+/// Records the changes a step after the first pass made to every chapter, for `explain`.
+fn trace_chapters(
+    explanations: &mut HashMap<PathBuf, Explanation>,
+    chapters: &[ChapterEntry],
+    step: &'static str,
+) {
+    for entry in chapters {
+        if let Some(explanation) = explanations.get_mut(&entry.path) {
+            explanation.step(step, &entry.metadata);
+        }
+    }
+}
+
+/// Returns the overrides of the renderer the book is built for
+/// (`[preprocessor.metadata.per-renderer.<renderer>]`).
+fn renderer_table(ctx: &PreprocessorContext) -> Option<&Table> {
+    ctx.config
+        .get_preprocessor("metadata")
+        .and_then(|p| p.get("per-renderer"))
+        .and_then(|v| v.get(&ctx.renderer))
+        .and_then(|v| v.as_table())
+}
+
+/// Like [`feature_table`], for features that are on unless disabled. Returns an
+/// empty table when the feature isn't configured.
+fn default_feature_table<'a>(ctx: &'a PreprocessorContext, name: &str) -> Option<&'a Table> {
+    match ctx
+        .config
+        .get_preprocessor("metadata")
+        .and_then(|p| p.get(name))
+    {
+        Some(_) => feature_table(ctx, name),
+        None => Some(&EMPTY_TABLE),
+    }
+}
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MetadataError::ImproperlyFormattedLine(ref line) => {
+                write!(f, "Improperly formatted metadata line: '{}'", line)
+            }
+            MetadataError::InvalidToml(ref message) => {
+                write!(f, "Invalid TOML metadata: {}", message)
+            }
+            MetadataError::UnresolvedReference {
+                ref chapter,
+                ref key,
+                ref reference,
+            } => write!(
+                f,
+                "Chapter '{}' references unknown chapter '{}' in '{}'",
+                chapter, reference, key
+            ),
+            MetadataError::Template {
+                ref template,
+                ref message,
+            } => write!(f, "Invalid template '{}': {}", template, message),
+            MetadataError::InvalidValue {
+                ref key,
+                ref message,
+            } => write!(f, "Invalid value of '{}': {}", key, message),
+            MetadataError::Expression {
+                ref expression,
+                ref message,
+            } => write!(f, "Invalid expression '{}': {}", expression, message),
+            MetadataError::Profile {
+                ref profile,
+                ref message,
+            } => write!(
+                f,
+                "Chapter doesn't follow profile '{}': {}",
+                profile, message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MetadataError {}
+
+impl Preprocessor for Metadata {
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn run(&self, ctx: &PreprocessorContext, mut book: Book) -> Result<Book, MdBookError> {
+        if let Some(table) = ctx.config.get_preprocessor("metadata") {
+            let config_errors = config::validate(table);
+            if !config_errors.is_empty() {
+                error!("Invalid configuration: \n{}", config_errors.join("\n"));
+                return Err(anyhow::Error::msg(config_errors.join("\n")));
+            }
+        }
+
+        let mut errors: Vec<String> = Vec::new();
+        for message in &self.config_warnings {
+            self.warning(message.clone(), &mut errors);
+        }
+        let mut emitters = Vec::new();
+        for plugin in &self.plugins {
+            match plugin.load(&ctx.root) {
+                Ok(emitter) => emitters.push((plugin, emitter)),
+                Err(e) if self.continue_on_error => warn!("{:#}, skipping it", e),
+                Err(e) => errors.push(format!("{:#}", e)),
+            }
+        }
+        let remote_data = match self.remote.as_ref().map(|r| r.load(&ctx.root)) {
+            Some(Ok(data)) => Some(data),
+            Some(Err(e)) if self.continue_on_error => {
+                warn!("{:#}, skipping the remote metadata", e);
+                None
+            }
+            Some(Err(e)) => {
+                errors.push(format!("{:#}", e));
+                None
+            }
+            None => None,
+        };
+        let catalog = match (&self.gettext, &ctx.config.book.language) {
+            (Some(gettext), Some(language)) => match gettext.catalog(&ctx.root, language) {
+                Ok(catalog) => catalog,
+                Err(e) if self.continue_on_error => {
+                    warn!("{:#}, leaving the metadata untranslated", e);
+                    None
+                }
+                Err(e) => {
+                    errors.push(format!("{:#}", e));
+                    None
+                }
+            },
+            _ => None,
+        };
+        let mut chapters: Vec<ChapterEntry> = Vec::new();
+        let src_dir = ctx.root.join(&ctx.config.book.src);
+        let overlay = self.overlay.as_ref().filter(|o| o.enabled(&ctx.config));
+        let mut warnings: HashMap<PathBuf, Vec<String>> = HashMap::new();
+        let mut conventions: HashMap<PathBuf, Conventions> = HashMap::new();
+        let mut explanations: HashMap<PathBuf, Explanation> = HashMap::new();
+        // Chapters out of scope or opted out: left as they are, but still
+        // collected with empty metadata so references to them resolve
+        let mut skipped: HashSet<PathBuf> = HashSet::new();
+
+        // First pass: strip the metadata blocks and collect the parsed metadata
+        let mut pass = FirstPass {
+            ctx,
+            src_dir: &src_dir,
+            overlay,
+            remote_data: remote_data.as_ref(),
+            catalog: catalog.as_ref(),
+            position: 0,
+            errors: &mut errors,
+            chapters: &mut chapters,
+            warnings: &mut warnings,
+            conventions: &mut conventions,
+            explanations: &mut explanations,
+            skipped: &mut skipped,
+        };
+        book.for_each_mut(|item: &mut BookItem| {
+            if let BookItem::Chapter(ref mut chap) = item {
+                self.collect_chapter(chap, &mut pass);
+            }
+        });
+
+        if !errors.is_empty() {
+            error!(
+                "Errors occurred during preprocessing: \n{}",
+                errors.join("\n")
+            );
+            return Err(anyhow::Error::msg(errors.join("\n")));
+        }
+
+        let artifacts_dir = ctx.root.join(&self.artifacts_dir);
+        if self.compat.is_some() {
+            let drafts: HashSet<&Path> = conventions
+                .iter()
+                .filter(|(_, c)| c.draft)
+                .map(|(path, _)| path.as_path())
+                .collect();
+            if !drafts.is_empty() {
+                filter::retain_chapters(&mut book, &|path| !drafts.contains(path));
+                chapters.retain(|c| !drafts.contains(c.path.as_path()));
+            }
+            let weights: HashMap<PathBuf, i64> = conventions
+                .iter()
+                .filter_map(|(path, c)| Some((path.clone(), c.weight?)))
+                .collect();
+            if !weights.is_empty() {
+                compat::sort_by_weight(&mut book, &weights);
+            }
+            let aliases: BTreeMap<PathBuf, Vec<String>> = conventions
+                .iter()
+                .filter(|(path, c)| !c.aliases.is_empty() && !drafts.contains(path.as_path()))
+                .map(|(path, c)| (path.clone(), c.aliases.clone()))
+                .collect();
+            if !aliases.is_empty() {
+                let redirects = compat::redirects(&aliases, &self.urls);
+                write_artifact(&artifacts_dir, "redirects.toml", &redirects)?;
+            }
+            if self.compat == Some(Compat::Obsidian) {
+                for e in obsidian::resolve_wiki_links(&mut chapters) {
+                    if self.continue_on_error {
+                        warn!("{}", e);
+                    } else {
+                        errors.push(e.to_string());
+                    }
+                }
+                trace_chapters(&mut explanations, &chapters, "wiki-links");
+            }
+        }
+
+        if let Some(expression) = &self.filter {
+            let filter = Filter::parse(expression)?;
+            let metadata = chapters
+                .iter()
+                .map(|c| (c.path.clone(), c.metadata.clone()))
+                .collect();
+            filter.retain(&mut book, &metadata);
+            chapters.retain(|c| filter.matches(&c.metadata));
+        }
+
+        if let Some(parts) = &self.parts {
+            parts.apply(&book, &mut chapters);
+            for entry in chapters.iter_mut().filter(|c| skipped.contains(&c.path)) {
+                entry.metadata.clear();
+            }
+            trace_chapters(&mut explanations, &chapters, "parts");
+        }
+        let chapters = BookMetadata::new(chapters);
+
+        if let Some(whats_new) = &self.whats_new {
+            let chapter = whats_new.chapter(&chapters, &src_dir);
+            insert_chapter(&mut book, chapter, whats_new.position);
+        }
+        if let Some(changelog) = &self.changelog {
+            let chapter = changelog.chapter(&chapters, &src_dir);
+            insert_chapter(&mut book, chapter, changelog.position);
+        }
+        if let Some(archive) = &self.archive {
+            insert_chapter(&mut book, archive.chapter(&chapters), archive.position);
+        }
+        if let Some(glossary) = &self.glossary {
+            let (chapter, glossary_warnings) = glossary.chapter(&chapters);
+            for message in glossary_warnings {
+                self.warning(message, &mut errors);
+            }
+            insert_chapter(&mut book, chapter, glossary.position);
+        }
+        if let Some(topics) = &self.topics {
+            match topics.chapter(&chapters) {
+                Ok(chapter) => insert_chapter(&mut book, chapter, topics.position),
+                Err(e) if self.continue_on_error => warn!("{}", e),
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+        if let Some(appendix) = &self.appendix {
+            insert_chapter(&mut book, appendix.chapter(&chapters), appendix.position);
+        }
+        if let Some(asset_licenses) = &self.asset_licenses {
+            let (assets, asset_errors) = asset_licenses.collect(&chapters);
+            for e in asset_errors {
+                if self.continue_on_error {
+                    warn!("{}", e);
+                } else {
+                    errors.push(e.to_string());
+                }
+            }
+            write_artifact(
+                &artifacts_dir,
+                &asset_licenses.file,
+                &asset_licenses.artifact(&assets)?,
+            )?;
+            let chapter = asset_licenses.chapter(&assets);
+            insert_chapter(&mut book, chapter, asset_licenses.position);
+        }
+        if let Some(graph) = &self.graph {
+            for (name, contents) in graph.artifacts(&chapters) {
+                write_artifact(&artifacts_dir, name, &contents)?;
+            }
+            if let Some((chapter, position)) = graph.map_chapter(&chapters) {
+                insert_chapter(&mut book, chapter, position);
+            }
+        }
+
+        if let Some(sidebar) = &self.sidebar {
+            for (name, contents) in sidebar.artifacts(&chapters)? {
+                write_artifact(&artifacts_dir, name, &contents)?;
+            }
+        }
+        if let Some(nav_groups) = &self.nav_groups {
+            let json = nav_groups.artifact(&chapters, &self.urls)?;
+            write_artifact(&artifacts_dir, &nav_groups.file, &json)?;
+        }
+        if let Some(coverage) = &self.coverage {
+            let covered: Vec<ChapterEntry> = chapters
+                .iter()
+                .filter(|c| !skipped.contains(&c.path))
+                .cloned()
+                .collect();
+            let json = coverage.artifact(&covered, &self.profiles, &ctx.config.book.src)?;
+            write_artifact(&artifacts_dir, &coverage.file, &json)?;
+        }
+        if let Some(headings) = &self.headings {
+            let json = headings.artifact(&book, &chapters)?;
+            write_artifact(&artifacts_dir, &headings.file, &json)?;
+        }
+        if let Some(theme_data) = &self.theme_data {
+            let json = theme_data.artifact(&chapters)?;
+            write_artifact(&artifacts_dir, &theme_data.file, &json)?;
+        }
+        if let Some(sitemap) = &self.sitemap {
+            if let Some((xml, sitemap_errors)) = sitemap.artifact(&book, &chapters, &self.urls) {
+                for e in sitemap_errors {
+                    if self.continue_on_error {
+                        warn!("{}", e);
+                    } else {
+                        errors.push(e.to_string());
+                    }
+                }
+                write_artifact(&artifacts_dir, "sitemap.xml", &xml)?;
+            }
+        }
+        if let Some(algolia) = &self.algolia {
+            let title = ctx.config.book.title.as_deref();
+            let json = algolia.artifact(&book, title, &chapters, &self.urls)?;
+            write_artifact(&artifacts_dir, &algolia.file, &json)?;
+        }
+        if let Some(search_export) = &self.search_export {
+            let (name, contents) = search_export.artifact(&book, &chapters, &self.urls)?;
+            write_artifact(&artifacts_dir, name, &contents)?;
+        }
+        let book_info = BookInfo {
+            title: ctx.config.book.title.as_deref(),
+            authors: &ctx.config.book.authors,
+            urls: &self.urls,
+        };
+        if let Some(citations) = &self.citations {
+            for (name, contents) in citations.artifacts(&chapters, book_info)? {
+                write_artifact(&artifacts_dir, name, &contents)?;
+            }
+        }
+        if let Some(search_index) = &self.search_index {
+            for (name, contents) in search_index.artifacts(&book, &chapters)? {
+                write_artifact(&artifacts_dir, name, &contents)?;
+            }
+        }
+        if let Some(short_title) = &self.short_title {
+            short_title.apply(&mut book, &chapters);
+        }
+        if let Some(decorate) = &self.decorate {
+            decorate.apply(&mut book, &chapters);
+        }
+
+        if let Some(learning) = &self.learning {
+            for entry in &chapters {
+                if let Some(record) = learning.record(entry) {
+                    let json = serde_json::to_string_pretty(&record)?;
+                    write_artifact(
+                        &artifacts_dir,
+                        &learning.record_path(entry).to_string_lossy(),
+                        &json,
+                    )?;
+                }
+            }
+        }
+
+        if let Some(events) = self.events.as_ref().filter(|events| events.ics) {
+            let now = Utc::now();
+            for entry in &chapters {
+                // Invalid events are reported with the chapter's tags
+                if let Some(Ok(event)) = events.event(entry) {
+                    let url = self.urls.url(&entry.path);
+                    let uid = url.clone().unwrap_or_else(|| self.urls.path(&entry.path));
+                    write_artifact(
+                        &artifacts_dir,
+                        &events.ics_path(entry).to_string_lossy(),
+                        &events.ics(&event, &uid, url.as_deref(), now),
+                    )?;
+                }
+            }
+        }
+
+        let ratings = self
+            .reviews
+            .as_ref()
+            .map(|reviews| reviews.ratings(&chapters))
+            .unwrap_or_default();
+
+        let (canonical_primaries, canonical_errors) = canonical::primaries(&chapters);
+        for e in canonical_errors {
+            if self.continue_on_error {
+                warn!("{}", e);
+            } else {
+                errors.push(e.to_string());
+            }
+        }
+
+        // Second pass: emit the HTML tags for every chapter that was parsed
+        let mut manifest: BTreeMap<String, Vec<Tag>> = BTreeMap::new();
+        // The first chapter of the book, rendered as `index.html`
+        let index_path = book.iter().find_map(|item| match item {
+            BookItem::Chapter(chap) => chap.path.clone(),
+            _ => None,
+        });
+
+        book.for_each_mut(|item: &mut BookItem| {
+            if let BookItem::Chapter(ref mut chap) = item {
+                let Some(entry) = chap.path.as_ref().and_then(|p| chapters.get(p)) else {
+                    return;
+                };
+                if skipped.contains(&entry.path) {
+                    return;
+                }
+                let mut chapter_warnings = warnings.remove(&entry.path).unwrap_or_default();
+                let mut explanation = explanations.remove(&entry.path);
+                // Tags added to chapters regardless of their metadata
+                let mut site_tags: Vec<Tag> = self
+                    .verification
+                    .as_ref()
+                    .map(|v| v.tags(index_path.as_ref() == Some(&entry.path)).to_vec())
+                    .unwrap_or_default();
+                if let Some(hints) = &self.hints {
+                    let next = chapters.next(&entry.path);
+                    site_tags.extend(hints.prefetch_tag(entry, next));
+                }
+                if let Some(accessibility) = &self.accessibility {
+                    site_tags.extend(accessibility.statement_tag(&entry.path));
+                }
+                if entry.metadata.is_empty() && chapter_warnings.is_empty() && site_tags.is_empty()
+                {
+                    return; // Chapters without metadata are left byte-identical
+                }
+
+                let mut report = |reference_errors: Vec<MetadataError>| {
+                    for e in reference_errors {
+                        if overlay.is_some() {
+                            chapter_warnings.push(e.to_string());
+                        }
+                        // Unresolved references always fail, like broken links
+                        let unresolved = matches!(e, MetadataError::UnresolvedReference { .. });
+                        if self.continue_on_error && !unresolved {
+                            warn!("{}", e);
+                        } else {
+                            errors.push(e.to_string());
+                        }
+                    }
+                };
+
+                // Taken before blocks are added, as in the headings file
+                let headings_tag = self
+                    .headings
+                    .as_ref()
+                    .and_then(|h| h.tag(&h.headings(&chap.content)));
+
+                if let Some(prerequisites) = &self.prerequisites {
+                    match prerequisites.render(entry, &chapters) {
+                        Ok(Some(block)) => chap.content = insert_after_title(&chap.content, &block),
+                        Ok(None) => {}
+                        Err(e) => report(e),
+                    }
+                }
+
+                let mut extra_tags: Vec<Tag> = headings_tag.into_iter().collect();
+                if let Some(stale) = &self.stale {
+                    let today = Local::now().date_naive();
+                    if let Some(updated) = stale.check(entry, &src_dir, today) {
+                        match stale.banner(&entry.metadata, updated, today) {
+                            Ok(banner) => chap.content = insert_after_title(&chap.content, &banner),
+                            Err(e) => report(vec![e]),
+                        }
+                        extra_tags.push(Tag::meta("stale", "true"));
+                    }
+                }
+                if let Some(see_also) = &self.see_also {
+                    match see_also.render(entry, &chapters) {
+                        Ok(Some(rendered)) => {
+                            chap.content =
+                                format!("{}\n\n{}", chap.content.trim_end(), rendered.section);
+                            extra_tags.extend(rendered.link_tags);
+                        }
+                        Ok(None) => {}
+                        Err(e) => report(e),
+                    }
+                }
+
+                if let Some(citations) = &self.citations {
+                    if let Some(block) = citations.render(entry, book_info) {
+                        chap.content = format!("{}\n\n{}", chap.content.trim_end(), block);
+                    }
+                }
+
+                let default_values = match self.render_default_values(entry, ctx) {
+                    Ok(default_values) => default_values,
+                    Err(e) => {
+                        report(vec![e]);
+                        self.default_tag_values.clone()
+                    }
+                };
+
+                let mut metadata = entry.metadata.clone();
+                metadata.remove(META_VERSION_KEY);
+                if self.citations.is_some() {
+                    metadata.remove("cite");
+                }
+                metadata.remove(STALE_CHECK_KEY);
+                if let Some(banner) = &self.banner {
+                    match banner.render(&entry.metadata) {
+                        Some(Ok(html)) => chap.content = format!("{}\n\n{}", html, chap.content),
+                        Some(Err(e)) => report(vec![e]),
+                        None => {}
+                    }
+                    let url = self.urls.url(&entry.path);
+                    for (key, value) in banner.og_values(&entry.metadata, url.as_deref()) {
+                        metadata.entry(key.to_string()).or_insert(value);
+                    }
+                    metadata.retain(|k, _| !BANNER_KEYS.contains(&k.as_str()));
+                }
+                if let Some(head_snippet) = &self.head_snippet {
+                    if let Some(value) = entry.metadata.get(HEAD_SNIPPET_KEY) {
+                        let (snippet_tags, snippet_errors) = head_snippet.tags(&ctx.root, value);
+                        extra_tags.extend(snippet_tags);
+                        report(snippet_errors);
+                    }
+                    metadata.remove(HEAD_SNIPPET_KEY);
+                }
+                if let Some(short_title) = &self.short_title {
+                    metadata.remove(&short_title.key);
+                }
+                if self.data_attributes.is_some() {
+                    metadata.remove(CSS_CLASS_KEY);
+                }
+                if self.compat == Some(Compat::Obsidian) {
+                    metadata.remove(obsidian::ALIASES_KEY);
+                }
+                if self.asset_licenses.is_some() {
+                    metadata.remove(ASSET_LICENSES_KEY);
+                }
+                if let Some(nav_groups) = &self.nav_groups {
+                    metadata.remove(&nav_groups.key);
+                    metadata.remove(&nav_groups.order_key);
+                }
+                let emit = self.emit.for_chapter(&mut metadata).unwrap_or_else(|e| {
+                    report(vec![e]);
+                    self.emit.clone()
+                });
+                let version = versions::chapter_version(&entry.metadata, self.meta_version)
+                    .unwrap_or(self.meta_version);
+                if let Some(computed) = &self.computed {
+                    let mut merged = default_values.clone();
+                    merged.extend(metadata.clone());
+                    let mut vars = template::variables(&merged, &ctx.config.book);
+                    match computed.evaluate(&mut vars, Local::now().date_naive()) {
+                        Ok(values) => metadata.extend(values),
+                        Err(e) => report(vec![e]),
+                    }
+                }
+
+                let lang = metadata.remove(LANG_KEY).filter(|lang| {
+                    let og_locale = !metadata.contains_key("og:locale")
+                        && !self.open_graph.as_ref().is_some_and(|og| og.locale);
+                    match lang_tags(lang, og_locale) {
+                        Ok(lang_tags) => {
+                            extra_tags.extend(lang_tags);
+                            true
+                        }
+                        Err(e) => {
+                            report(vec![e]);
+                            false
+                        }
+                    }
+                });
+
+                extra_tags.extend(canonical::canonical_tags(
+                    entry,
+                    &canonical_primaries,
+                    &self.urls,
+                ));
+                if metadata.remove(CANONICAL_GROUP_KEY).is_some() {
+                    metadata.remove(CANONICAL_KEY);
+                }
+
+                let (tags, identifier_errors) = identifier_tags(&mut metadata);
+                extra_tags.extend(tags);
+                report(identifier_errors);
+
+                if let Some(open_graph) = &self.open_graph {
+                    let is_root = index_path.as_ref() == Some(&entry.path);
+                    if let Some(og_type) = open_graph.infer_type(&entry.path, is_root, &metadata) {
+                        metadata.insert("og:type".to_string(), og_type.to_string());
+                    }
+                    let mut merged = default_values.clone();
+                    merged.extend(metadata.clone());
+                    let language = lang.as_deref().map(str::trim);
+                    extra_tags.extend(open_graph.tags(
+                        &merged,
+                        language.or(ctx.config.book.language.as_deref()),
+                        self.urls.url(&entry.path).as_deref(),
+                    ));
+                }
+
+                let fediverse = metadata
+                    .remove(FEDIVERSE_KEY)
+                    .or_else(|| self.fediverse_creator.clone())
+                    .filter(|_| !metadata.contains_key("fediverse:creator"));
+                if let Some(handles) = fediverse {
+                    match creator_tags(&handles) {
+                        Ok(creator_tags) => extra_tags.extend(creator_tags),
+                        Err(e) => report(vec![e]),
+                    }
+                }
+
+                let mut json_ld = serde_json::Map::new();
+                if let Some(authors) = &self.authors {
+                    authors.apply(&mut metadata, &mut json_ld);
+                }
+                if let Some(accessibility) = &self.accessibility {
+                    match accessibility.tags(&mut metadata, &mut json_ld) {
+                        Ok(accessibility_tags) => extra_tags.extend(accessibility_tags),
+                        Err(e) => report(vec![e]),
+                    }
+                }
+                if let Some(audience) = &self.audience {
+                    match audience.tags(&mut metadata, &mut json_ld) {
+                        Ok(audience_tags) => extra_tags.extend(audience_tags),
+                        Err(e) => report(vec![e]),
+                    }
+                }
+
+                match geo::tags(&mut metadata, &mut json_ld) {
+                    Ok(geo_tags) => extra_tags.extend(geo_tags),
+                    Err(e) => report(vec![e]),
+                }
+
+                if let Some(consent) = &self.consent {
+                    if let Some(value) = metadata.remove(&consent.key) {
+                        match consent.tags(&value) {
+                            Ok(consent_tags) => extra_tags.extend(consent_tags),
+                            Err(e) => report(vec![e]),
+                        }
+                    }
+                }
+
+                if self.hints.is_some() {
+                    if let Some(preload) = metadata.remove(PRELOAD_KEY) {
+                        extra_tags.extend(preload_tags(&preload));
+                    }
+                }
+
+                if let Some(links) = metadata.remove(LINKS_KEY) {
+                    match parse_links(&links) {
+                        Ok(link_tags) => extra_tags.extend(link_tags),
+                        Err(e) => report(vec![e]),
+                    }
+                }
+
+                if let Some(events) = &self.events {
+                    match events.event(entry) {
+                        Some(Ok(event)) => extra_tags
+                            .extend(events.tags(&event, self.urls.url(&entry.path).as_deref())),
+                        Some(Err(e)) => report(vec![e]),
+                        None => {}
+                    }
+                    metadata.retain(|k, _| !EVENT_KEYS.contains(&k.as_str()));
+                }
+
+                if let Some(reviews) = &self.reviews {
+                    match reviews.review(&entry.metadata) {
+                        Some(Ok(review)) => {
+                            let author = entry
+                                .metadata
+                                .get("author")
+                                .or(default_values.get("author"));
+                            extra_tags.push(reviews.tag(
+                                entry.title(),
+                                &review,
+                                author.map(String::as_str),
+                                &ratings,
+                            ));
+                        }
+                        Some(Err(e)) => report(vec![e]),
+                        None => {}
+                    }
+                    metadata.retain(|k, _| !REVIEW_KEYS.contains(&k.as_str()));
+                }
+
+                if !self.vocab.is_empty() {
+                    let (vocab_tags, vocab_errors) =
+                        self.vocab.apply(&mut metadata, &self.property_keys);
+                    extra_tags.extend(vocab_tags);
+                    report(vocab_errors);
+                }
+
+                if let Some(namespaces) = &self.namespaces {
+                    let (namespace_tags, namespace_errors) = namespaces.apply(&mut metadata);
+                    extra_tags.extend(namespace_tags);
+                    report(namespace_errors);
+                }
+
+                for (plugin, emitter) in &emitters {
+                    let input = plugin.take_input(&mut metadata);
+                    if input.is_empty() {
+                        continue;
+                    }
+                    match emitter.emit(&input) {
+                        Ok(html) if html.trim().is_empty() => {}
+                        Ok(html) => {
+                            if let Some(explanation) = &mut explanation {
+                                explanation.emitter(&emitter.name);
+                            }
+                            extra_tags.push(Tag::raw(&emitter.name, &html));
+                        }
+                        Err(e) if self.continue_on_error => {
+                            warn!("{:#} ('{}')", e, entry.path.display())
+                        }
+                        Err(e) => errors.push(format!("{:#} ('{}')", e, entry.path.display())),
+                    }
+                }
+
+                if let Some(valid_tags) = &self.valid_tags {
+                    metadata.retain(|k, _| {
+                        let valid =
+                            valid_tags.contains(k) || Namespace::of(k) == Namespace::Extension;
+                        let learning_key =
+                            self.learning.is_some() && LEARNING_KEYS.contains(&k.as_str());
+                        let sitemap_key =
+                            self.sitemap.is_some() && SITEMAP_KEYS.contains(&k.as_str());
+                        if !valid && !learning_key && !sitemap_key {
+                            if let Some(explanation) = &mut explanation {
+                                explanation.filtered(k);
+                            }
+                        }
+                        if !valid && !learning_key && !sitemap_key && entry.metadata.contains_key(k)
+                        {
+                            let message = format!("Key '{}' isn't in valid-tags", k);
+                            if self.strict {
+                                errors.push(format!("{} ('{}')", message, entry.path.display()));
+                            } else if overlay.is_some() {
+                                chapter_warnings.push(message);
+                            }
+                        }
+                        valid
+                    });
+                }
+
+                if self.sitemap.is_some() {
+                    metadata.retain(|k, _| !SITEMAP_KEYS.contains(&k.as_str()));
+                }
+
+                if let Some(learning) = &self.learning {
+                    if let Some(record) = learning.record(entry) {
+                        extra_tags.extend(learning.tags(&record));
+                    }
+                    metadata.retain(|k, _| !LEARNING_KEYS.contains(&k.as_str()));
+                }
+
+                if !json_ld.is_empty() {
+                    let og_type = metadata.get("og:type").or(default_values.get("og:type"));
+                    let article = og_type.is_some_and(|t| t.trim() == "article");
+                    extra_tags.push(json_ld::web_page(Some(entry.title()), article, json_ld));
+                }
+
+                if let Some(explanation) = &mut explanation {
+                    explanation.defaults(&default_values, &metadata);
+                }
+                let mut chapter_tags = metadata_tags(&metadata, &default_values);
+                for tag in &mut chapter_tags {
+                    if tag.kind != TagKind::Meta {
+                        continue;
+                    }
+                    if self.raw_keys.contains(&tag.name) {
+                        tag.kind = TagKind::Raw;
+                    } else if version >= 2 && tags::matches_key(&self.property_keys, &tag.name) {
+                        tag.kind = TagKind::Property;
+                    }
+                }
+                chapter_tags.splice(0..0, site_tags);
+                chapter_tags.extend(extra_tags);
+                let (chapter_tags, dropped_tags): (Vec<Tag>, Vec<Tag>) =
+                    chapter_tags.into_iter().partition(|tag| emit.allows(tag));
+                if let Some(explanation) = &explanation {
+                    let rendered = explanation.render(
+                        &entry.path.display().to_string(),
+                        &chapter_tags,
+                        &dropped_tags,
+                    );
+                    eprint!("{}", rendered);
+                }
+                let html_tags = tags::to_html(&chapter_tags);
+                info!("Generated HTML tags: {}", html_tags);
+
+                if let Some(data_attributes) = &self.data_attributes {
+                    if let Some(wrapped) = data_attributes.wrap(&entry.metadata, &chap.content) {
+                        chap.content = wrapped;
+                    }
+                }
+
+                if let Some(overlay) = overlay {
+                    chap.content = format!(
+                        "{}\n\n{}",
+                        chap.content.trim_end(),
+                        overlay.render(&entry.metadata, &chapter_warnings)
+                    );
+                }
+
+                if !html_tags.is_empty() {
+                    chap.content = format!("{}\n{}", html_tags, chap.content);
+                    if self.tags_manifest {
+                        manifest.insert(entry.path.display().to_string(), chapter_tags);
+                    }
+                }
+            }
+        });
+
+        if self.tags_manifest {
+            let json = serde_json::to_string_pretty(&manifest)?;
+            write_artifact(&artifacts_dir, "meta-tags.json", &json)?;
+        }
+
+        if !errors.is_empty() {
+            error!(
+                "Errors occurred during preprocessing: \n{}",
+                errors.join("\n")
+            );
+            return Err(anyhow::Error::msg(errors.join("\n")));
+        }
+
+        Ok(book)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mdbook::book::Chapter;
+    use mdbook::Config;
+    use std::collections::HashSet;
+    use std::str::FromStr;
+
+    fn context(book_toml: &str) -> PreprocessorContext {
+        let config = Config::from_str(book_toml).unwrap();
+        serde_json::from_value(serde_json::json!({
+            "root": ".",
+            "config": config,
+            "renderer": "html",
+            "mdbook_version": mdbook::MDBOOK_VERSION,
+        }))
+        .unwrap()
+    }
+
+    fn book(chapters: &[(&str, &str, &str)]) -> Book {
+        let mut book = Book::new();
+        for (name, path, content) in chapters {
+            book.push_item(Chapter::new(name, content.to_string(), *path, Vec::new()));
+        }
+        book
+    }
+
+    fn chapter_contents(book: &Book) -> Vec<(String, String)> {
+        book.iter()
+            .filter_map(|item| match item {
+                BookItem::Chapter(chap) => Some((chap.name.clone(), chap.content.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_metadata_without_metadata_block() {
+        let content = "This is a test chapter content without metadata.";
+
+        let (metadata, content_without_metadata) = parse_metadata(content, false).unwrap();
+
+        assert!(metadata.is_empty(), "Expected metadata to be empty");
+        assert_eq!(
+            content_without_metadata, content,
+            "Content should remain unchanged"
+        );
+    }
+
+    #[test]
+    fn test_parse_metadata_borrows_untouched_content() {
+        let content = "# Chapter\n\nNo metadata here.";
+
+        let (metadata, content_without_metadata) = parse_metadata(content, false).unwrap();
+
+        assert!(metadata.is_empty());
+        assert!(
+            matches!(content_without_metadata, Cow::Borrowed(c) if c == content),
+            "Content without metadata should be borrowed, not copied"
+        );
+    }
+
+    #[test]
+    fn test_parse_metadata_keeps_leading_whitespace_without_metadata() {
+        let content = "\n\n  Indented first line.";
+
+        let (_, content_without_metadata) = parse_metadata(content, false).unwrap();
+
+        assert_eq!(content_without_metadata, content);
+    }
+
+    #[test]
+    fn test_parse_metadata_ignores_thematic_breaks_after_content() {
+        let content = "# Chapter\n\n---\n\nnot: metadata\n\n---\n";
+
+        let (metadata, content_without_metadata) = parse_metadata(content, false).unwrap();
+
+        assert!(
+            metadata.is_empty(),
+            "Only a block opening the chapter is metadata"
+        );
+        assert_eq!(content_without_metadata, content);
+    }
+
+    #[test]
+    fn test_parse_metadata_with_fence_inside_value() {
+        let content = "---\ntitle: A --- B\ndescription: Fast\n---\n# A --- B\n";
+
+        let (metadata, content_without_metadata) = parse_metadata(content, false).unwrap();
+
+        assert_eq!(metadata["title"], "A --- B");
+        assert_eq!(metadata["description"], "Fast");
+        assert_eq!(content_without_metadata, "# A --- B\n");
+    }
+
+    #[test]
+    fn test_parse_metadata_ignores_horizontal_rule_opening_the_chapter() {
+        let content = "----\n\n# Chapter\n\n----\n";
+
+        let (metadata, content_without_metadata) = parse_metadata(content, false).unwrap();
+
+        assert!(metadata.is_empty());
+        assert_eq!(content_without_metadata, content);
+    }
+
+    #[test]
+    fn test_parse_metadata_keeps_comments_before_the_block() {
+        let content =
+            "\u{feff}<!-- Licensed under MIT -->\r\n---\r\ntitle: Intro\r\n---\r\n# Intro";
+
+        let (metadata, content_without_metadata) = parse_metadata(content, false).unwrap();
+
+        assert_eq!(metadata.get("title").map(String::as_str), Some("Intro"));
+        assert_eq!(
+            content_without_metadata,
+            "<!-- Licensed under MIT -->\n\n# Intro"
+        );
+        let (metadata, _) = parse_metadata_after(content, false, &[Prefix::Bom]).unwrap();
+        assert!(metadata.is_empty());
+    }
+
+    #[test]
+    fn test_parse_metadata_with_valid_metadata_block() {
+        let content_with_metadata = r#"---
+title: Test Chapter
+keywords: rust, testing, mdbook
+released: true
+---
+
+This is the chapter content."#;
+
+        let (metadata, content_without_metadata) =
+            parse_metadata(content_with_metadata, false).unwrap();
+
+        assert_eq!(
+            metadata.get("title"),
+            Some(&"Test Chapter".to_string()),
+            "Title should be 'Test Chapter'"
+        );
+        assert_eq!(
+            metadata.get("keywords"),
+            Some(&"rust, testing, mdbook".to_string()),
+            "Keywords should be 'rust, testing, mdbook'"
+        );
+        assert_eq!(
+            metadata.get("released"),
+            Some(&"true".to_string()),
+            "Released should be 'true'"
+        );
+
+        let expected_content = "This is the chapter content.";
+        assert_eq!(
+            content_without_metadata, expected_content,
+            "Content should not include metadata block"
+        );
+    }
+
+    #[test]
+    fn test_parse_metadata_with_bad_indentation() {
+        let content_with_badly_indented_metadata = r#"---
+    title: Test Chapter
+   keywords:   rust, testing, mdbook
+    released :true
+---
+
+This is the chapter content."#;
+
+        let (metadata, content_without_metadata) =
+            parse_metadata(content_with_badly_indented_metadata, false).unwrap();
+
+        assert_eq!(
+            metadata.get("title"),
+            Some(&"Test Chapter".to_string()),
+            "Title should be 'Test Chapter'"
+        );
+        assert_eq!(
+            metadata.get("keywords"),
+            Some(&"rust, testing, mdbook".to_string()),
+            "Keywords should be 'rust, testing, mdbook'"
+        );
+        assert_eq!(
+            metadata.get("released"),
+            Some(&"true".to_string()),
+            "Released should be 'true'"
+        );
+
+        let expected_content = "This is the chapter content.";
+        assert_eq!(
+            content_without_metadata, expected_content,
+            "Content should not include metadata block"
+        );
+    }
+
+    #[test]
+    fn test_parse_metadata_with_bad_metadata_block() {
+        let content_with_bad_metadata = r#"---
+title = Incorrect Format
+keywords = rust, testing, mdbook
+released = false
+---
+
+This is the chapter content."#;
+
+        let result = parse_metadata(content_with_bad_metadata, false);
+
+        assert!(
+            result.is_err(),
+            "Expected an error due to bad metadata block format"
+        );
+
+        if let Err(MetadataError::ImproperlyFormattedLine(line)) = result {
+            assert_eq!(
+                line, "title = Incorrect Format",
+                "Expected error for the improperly formatted line"
+            );
+        } else {
+            panic!("Expected an ImproperlyFormattedLine error");
+        }
+    }
+
+    #[test]
     fn test_parse_metadata_with_duplicate_keys() {
         let content_with_duplicate_keys = r#"---
 title: First Title
@@ -329,294 +2289,1239 @@ title: Second Title
 keywords: second, set
 ---
 
-Chapter content."#;
+Chapter content."#;
+
+        let (metadata, content_without_metadata) =
+            parse_metadata(content_with_duplicate_keys, true).unwrap();
+
+        assert_eq!(
+            content_without_metadata, "Chapter content.",
+            "The content should exclude the metadata block."
+        );
+
+        assert_eq!(
+            metadata.get("title"),
+            Some(&"Second Title".to_string()),
+            "The 'title' key should reflect the last occurrence."
+        );
+        assert_eq!(
+            metadata.get("keywords"),
+            Some(&"second, set".to_string()),
+            "The 'keywords' key should reflect the last occurrence."
+        );
+
+        assert_eq!(
+            metadata.len(),
+            2,
+            "The metadata HashMap should only contain two entries, one for each unique key."
+        );
+    }
+
+    #[test]
+    fn test_metadata_to_html_basic() {
+        let mut metadata = HashMap::new();
+        metadata.insert("title".to_string(), "Example Title".to_string());
+        metadata.insert("keywords".to_string(), "rust, mdbook, testing".to_string());
+        metadata.insert("author".to_string(), "John Doe".to_string());
+
+        let html_output = metadata_to_html(&metadata, &HashMap::new());
+
+        assert!(
+            html_output.contains("<title>Example Title</title>"),
+            "Output should contain title tag."
+        );
+        assert!(
+            html_output.contains("<meta name=\"keywords\" content=\"rust, mdbook, testing\">"),
+            "Output should contain keywords meta tag."
+        );
+        assert!(
+            html_output.contains("<meta name=\"author\" content=\"John Doe\">"),
+            "Output should contain author meta tag."
+        );
+    }
+
+    #[test]
+    fn test_metadata_to_html_empty() {
+        let metadata = HashMap::new();
+
+        let html_output = metadata_to_html(&metadata, &HashMap::new());
+
+        assert!(
+            html_output.is_empty(),
+            "HTML output should be empty for empty metadata."
+        );
+    }
+
+    #[test]
+    fn test_metadata_to_html_complex() {
+        let metadata = HashMap::from([
+            (
+                "title".to_string(),
+                "Complex & <Special> 'Characters'".to_string(),
+            ),
+            (
+                "description".to_string(),
+                r#"Testing "quotes" and other <html> elements"#.to_string(),
+            ),
+            (
+                "keywords".to_string(),
+                r#"rust,mdbook,"special, characters",<html>"#.to_string(),
+            ),
+        ]);
+
+        let html_output = metadata_to_html(&metadata, &HashMap::new());
+
+        let expected_outputs = [
+            r#"<title>Complex &amp; &lt;Special&gt; 'Characters'</title>"#,
+            r#"<meta name="description" content="Testing &quot;quotes&quot; and other &lt;html&gt; elements">"#,
+            r#"<meta name="keywords" content="rust,mdbook,&quot;special, characters&quot;,&lt;html&gt;">"#,
+        ];
+
+        // Split the output by newline and collect into a set for order-independent comparison
+        let html_output_set: HashSet<_> = html_output.lines().collect();
+        let expected_output_set: HashSet<_> = expected_outputs.iter().cloned().collect();
+
+        assert_eq!(
+        html_output_set, expected_output_set,
+        "The HTML output should correctly handle special characters and HTML content, order-independent."
+    );
+    }
+
+    #[test]
+    fn test_metadata_to_html_xss_prevention() {
+        let metadata = HashMap::from([
+            ("title".to_string(), "Safe Title".to_string()),
+            (
+                "script_injection".to_string(),
+                "<script>alert('XSS');</script>".to_string(),
+            ),
+        ]);
+
+        let html_output = metadata_to_html(&metadata, &HashMap::new());
+
+        // Expected outputs should escape the <, >, and other special HTML characters
+        let expected_outputs = [
+            r#"<title>Safe Title</title>"#,
+            // Include the encoded forward slash in the expected output
+            r#"<meta name="script_injection" content="&lt;script&gt;alert('XSS');&lt;/script&gt;">"#,
+        ];
+
+        let html_output_set: HashSet<_> = html_output.lines().collect();
+        let expected_output_set: HashSet<_> = expected_outputs.iter().cloned().collect();
+
+        assert_eq!(
+            html_output_set, expected_output_set,
+            "The HTML output should escape potential XSS injection attempts, ensuring code safety."
+        );
+    }
+
+    #[test]
+    fn test_metadata_to_html_malicious_code() {
+        let metadata = HashMap::from([
+            ("title".to_string(), "Normal Title".to_string()),
+            // Attempted JavaScript injection
+            (
+                "description".to_string(),
+                r#"<script>alert("malicious code");</script>"#.to_string(),
+            ),
+        ]);
+
+        let html_output = metadata_to_html(&metadata, &HashMap::new());
+
+        let expected_outputs = [
+            r#"<title>Normal Title</title>"#,
+            r#"<meta name="description" content="&lt;script&gt;alert(&quot;malicious code&quot;);&lt;/script&gt;">"#,
+        ];
+
+        let html_output_set: HashSet<_> = html_output.lines().collect();
+        let expected_output_set: HashSet<_> = expected_outputs.iter().cloned().collect();
+
+        assert_eq!(
+            html_output_set, expected_output_set,
+            "The HTML output should escape potentially malicious code."
+        );
+    }
+
+    #[test]
+    fn test_metadata_to_html_escapes_keys() {
+        let metadata = HashMap::from([(r#"x" onload="alert(1)"#.to_string(), "value".to_string())]);
+
+        let html_output = metadata_to_html(&metadata, &HashMap::new());
+
+        assert_eq!(html_output, "");
+    }
+
+    #[test]
+    fn test_metadata_to_html_complex_structures() {
+        use std::collections::BTreeMap;
+
+        let mut nested_map = BTreeMap::new();
+        nested_map.insert("nested_key", vec!["value1", "value2"]);
+
+        let metadata = HashMap::from([
+            ("title".to_string(), "Complex Structures".to_string()),
+            ("complex".to_string(), format!("{:?}", nested_map)),
+        ]);
+
+        let html_output = metadata_to_html(&metadata, &HashMap::new());
+
+        let expected_html_tags = vec![
+        "<title>Complex Structures</title>",
+        r#"<meta name="complex" content="{&quot;nested_key&quot;: [&quot;value1&quot;, &quot;value2&quot;]}">"#,
+    ].into_iter().map(String::from).collect::<HashSet<_>>();
+
+        let output_html_tags = html_output
+            .lines()
+            .map(String::from)
+            .collect::<HashSet<_>>();
+
+        assert_eq!(
+            output_html_tags, expected_html_tags,
+            "The HTML output should correctly handle and escape complex structures."
+        );
+    }
+
+    #[test]
+    fn test_metadata_to_html_large_volume() {
+        let mut metadata = HashMap::new();
+        for i in 0..1000 {
+            metadata.insert(format!("key_{}", i), format!("value_{}", i));
+        }
+
+        let html_output = metadata_to_html(&metadata, &HashMap::new());
+
+        for i in 0..1000 {
+            let expected_key = format!("key_{}", i);
+            let expected_value = format!("value_{}", i);
+            let expected_tag = format!(
+                "<meta name=\"{}\" content=\"{}\">",
+                expected_key, expected_value
+            );
+
+            assert!(
+                html_output.contains(&expected_tag),
+                "The HTML output should contain the metadata entry for key {}: {}",
+                expected_key,
+                expected_value
+            );
+        }
+
+        assert!(
+            !html_output.contains("<title>"),
+            "The HTML output should not contain a title tag when not specified in the metadata."
+        );
+    }
+
+    #[test]
+    fn metadata_empty_default_values_empty() {
+        let metadata = HashMap::new();
+        let default_values = HashMap::new();
+
+        let html_output = metadata_to_html(&metadata, &default_values);
+
+        assert!(html_output.is_empty(), "HTML output should be empty when both metadata and default values are empty.");
+    }
+
+    #[test]
+    fn metadata_empty_default_values_not_empty() {
+        let metadata = HashMap::new();
+        let mut default_values = HashMap::new();
+        default_values.insert("author".to_string(), "Jane Doe".to_string());
+
+        let html_output = metadata_to_html(&metadata, &default_values);
+
+        assert!(html_output.contains("<meta name=\"author\" content=\"Jane Doe\">"), "HTML output should contain default values when metadata is empty.");
+    }
+
+    #[test]
+    fn metadata_not_empty_default_values_not_empty_conflicting_values() {
+        let mut metadata = HashMap::new();
+        metadata.insert("author".to_string(), "John Doe".to_string());
+
+        let mut default_values = HashMap::new();
+        default_values.insert("author".to_string(), "Jane Doe".to_string());
+
+        let html_output = metadata_to_html(&metadata, &default_values);
+
+        assert!(html_output.contains("<meta name=\"author\" content=\"John Doe\">"), "Metadata values should override default values when both are present.");
+    }
+
+    #[test]
+    fn metadata_not_empty_default_values_not_empty_metadata_has_extra_tags() {
+        let mut metadata = HashMap::new();
+        metadata.insert("author".to_string(), "John Doe".to_string());
+        metadata.insert("keywords".to_string(), "rust, mdbook".to_string());
+
+        let mut default_values = HashMap::new();
+        default_values.insert("description".to_string(), "Sample book".to_string());
+
+        let html_output = metadata_to_html(&metadata, &default_values);
+
+        assert!(html_output.contains("<meta name=\"author\" content=\"John Doe\">") && html_output.contains("<meta name=\"keywords\" content=\"rust, mdbook\">") && html_output.contains("<meta name=\"description\" content=\"Sample book\">"), "HTML output should include all tags from both metadata and default values, with metadata taking precedence.");
+    }
+
+    #[test]
+    fn default_values_has_tags_not_in_metadata() {
+        let mut metadata = HashMap::new();
+        metadata.insert("author".to_string(), "John Doe".to_string());
+
+        let mut default_values = HashMap::new();
+        default_values.insert("author".to_string(), "Jane Doe".to_string());
+        default_values.insert("keywords".to_string(), "rust, mdbook".to_string());
+
+        let html_output = metadata_to_html(&metadata, &default_values);
+
+        assert!(html_output.contains("<meta name=\"author\" content=\"John Doe\">") && html_output.contains("<meta name=\"keywords\" content=\"rust, mdbook\">"), "HTML output should include tags from metadata and default values not present in metadata, with metadata values taking precedence.");
+    }
+
+    #[test]
+    fn special_characters_in_metadata() {
+        let mut metadata = HashMap::new();
+        metadata.insert("description".to_string(), "This & That <test>".to_string());
+
+        let default_values = HashMap::new(); // Empty default values for simplicity
+
+        let html_output = metadata_to_html(&metadata, &default_values);
+
+        assert!(
+            html_output.contains("<meta name=\"description\" content=\"This &amp; That &lt;test&gt;\">"),
+            "HTML output should correctly escape special characters in metadata values."
+        );
+    }
+
+    #[test]
+    fn run_inserts_whats_new_chapter() {
+        let ctx = context(
+            r#"
+[preprocessor.metadata.whats-new]
+position = "start"
+"#,
+        );
+        let book = book(&[
+            ("Intro", "intro.md", "---\ndate: 2024-05-03\n---\n# Intro"),
+            ("Undated", "undated.md", "# Undated"),
+        ]);
+
+        let book = Metadata::new(&ctx).run(&ctx, book).unwrap();
+        let contents = chapter_contents(&book);
+
+        assert_eq!(contents[0].0, "What's new");
+        assert!(contents[0].1.contains("[Intro](intro.md)"));
+        assert!(
+            !contents[0].1.contains("<meta"),
+            "Generated chapters should not receive metadata tags."
+        );
+        assert!(contents[1]
+            .1
+            .contains("<meta name=\"date\" content=\"2024-05-03\">"));
+        assert_eq!(contents[2].1, "# Undated");
+    }
+
+    #[test]
+    fn parse_list_handles_brackets_and_quotes() {
+        assert_eq!(
+            parse_list("[idempotency, \"back pressure\"]"),
+            vec!["idempotency", "back pressure"]
+        );
+        assert_eq!(parse_list("rust, mdbook"), vec!["rust", "mdbook"]);
+        assert_eq!(parse_list("single"), vec!["single"]);
+        assert!(parse_list("[]").is_empty());
+    }
+
+    #[test]
+    fn run_fails_on_unresolved_prerequisites() {
+        let ctx = context("");
+        let book = book(&[(
+            "Tuning",
+            "tuning.md",
+            "---\nprerequisites: [install]\n---\n# Tuning",
+        )]);
+
+        let result = Metadata::new(&ctx).run(&ctx, book);
+
+        assert!(
+            result.is_err(),
+            "Missing prerequisites should fail the build even with continue-on-error."
+        );
+    }
+
+    #[test]
+    fn run_fails_on_unresolved_see_also() {
+        let ctx = context("");
+        let book = book(&[(
+            "Tuning",
+            "tuning.md",
+            "---\nsee-also: [renamed.md]\n---\n# Tuning",
+        )]);
+
+        let error = Metadata::new(&ctx).run(&ctx, book).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Chapter 'Tuning' references unknown chapter 'renamed.md' in 'see-also'"
+        );
+    }
+
+    #[test]
+    fn run_renders_templated_default_values() {
+        let ctx = context(
+            r#"
+[book]
+title = "My Book"
+
+[preprocessor.metadata]
+default-description = "{title | upper} in {book.title}"
+"#,
+        );
+        let book = book(&[("Intro", "intro.md", "---\ntitle: Intro\n---\n# Intro")]);
+
+        let book = Metadata::new(&ctx).run(&ctx, book).unwrap();
+
+        assert!(chapter_contents(&book)[0]
+            .1
+            .contains("<meta name=\"description\" content=\"INTRO in My Book\">"));
+    }
+
+    #[test]
+    fn run_emits_computed_keys() {
+        let ctx = context(
+            r#"
+[book]
+title = "My Book"
+
+[preprocessor.metadata]
+default-author = "Jane Doe"
+
+[preprocessor.metadata.computed]
+byline = "{title} by {author}"
+"#,
+        );
+        let book = book(&[("Intro", "intro.md", "---\ntitle: Intro\n---\n# Intro")]);
+
+        let book = Metadata::new(&ctx).run(&ctx, book).unwrap();
+
+        assert!(chapter_contents(&book)[0]
+            .1
+            .contains("<meta name=\"byline\" content=\"Intro by Jane Doe\">"));
+    }
+
+    #[test]
+    fn run_emits_book_defaults() {
+        let ctx = context(
+            r#"
+[book]
+title = "My Book"
+authors = ["Jane Doe", "John Doe"]
+description = "A book"
+language = "en"
+
+[preprocessor.metadata]
+book-defaults = true
+default-language = "en-GB"
+"#,
+        );
+        let book = book(&[(
+            "Intro",
+            "intro.md",
+            "---\ndescription: The intro\n---\n# Intro",
+        )]);
+
+        let book = Metadata::new(&ctx).run(&ctx, book).unwrap();
+        let content = &chapter_contents(&book)[0].1;
+
+        for tag in [
+            "<meta name=\"application-name\" content=\"My Book\">",
+            "<meta name=\"author\" content=\"Jane Doe, John Doe\">",
+            "<meta name=\"description\" content=\"The intro\">",
+            "<meta name=\"language\" content=\"en-GB\">",
+        ] {
+            assert!(content.contains(tag), "Missing {} in {}", tag, content);
+        }
+    }
+
+    #[test]
+    fn run_leaves_chapters_without_metadata_untouched() {
+        let ctx = context(
+            r#"
+[preprocessor.metadata]
+default-author = "Jane Doe"
+"#,
+        );
+        let content = "\n  # Untouched\n\nBody\n";
+        let book = book(&[("Untouched", "untouched.md", content)]);
+
+        let book = Metadata::new(&ctx).run(&ctx, book).unwrap();
+
+        assert_eq!(chapter_contents(&book)[0].1, content);
+    }
+
+    #[test]
+    fn run_writes_tags_manifest() {
+        let dir = std::env::temp_dir().join(format!("mdbook-metadata-tags-{}", std::process::id()));
+        let ctx = context(&format!(
+            r#"
+[preprocessor.metadata]
+tags-manifest = true
+artifacts-dir = {:?}
+"#,
+            dir
+        ));
+        let book = book(&[
+            (
+                "Intro",
+                "intro.md",
+                "---\ntitle: Intro\nsee-also: plain\n---\n# Intro",
+            ),
+            ("Plain", "plain.md", "# Plain"),
+        ]);
+
+        Metadata::new(&ctx).run(&ctx, book).unwrap();
+
+        let manifest: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.join("meta-tags.json")).unwrap())
+                .unwrap();
+        assert_eq!(
+            manifest,
+            serde_json::json!({
+                "intro.md": [
+                    { "name": "see-also", "content": "plain", "kind": "meta" },
+                    { "name": "title", "content": "Intro", "kind": "title" },
+                    { "name": "related", "content": "plain.html", "kind": "link" },
+                ]
+            })
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn run_adds_overlay_when_serving() {
+        let book_toml = r#"
+[preprocessor.metadata]
+valid-tags = ["title"]
+
+[preprocessor.metadata.overlay]
+"#;
+        let chapters = [(
+            "Intro",
+            "intro.md",
+            "---\ntitle: Intro\nnot a pair\nstatus: draft\n---\n# Intro",
+        )];
+
+        let ctx = context(book_toml);
+        let built = Metadata::new(&ctx).run(&ctx, book(&chapters)).unwrap();
+        assert!(!chapter_contents(&built)[0].1.contains("metadata-overlay"));
+
+        let ctx = context(&format!(
+            "{}\n[output.html]\nlive-reload-endpoint = \"__livereload\"\n",
+            book_toml
+        ));
+        let served = Metadata::new(&ctx).run(&ctx, book(&chapters)).unwrap();
+        let content = &chapter_contents(&served)[0].1;
+
+        assert!(content
+            .starts_with("<title>Intro</title>\n\n# Intro\n\n<details class=\"metadata-overlay\""));
+        assert!(content.contains("<summary>Metadata: 2 keys, 2 warnings</summary>"));
+        assert!(content
+            .contains("<li>⚠️ Improperly formatted metadata line: &#x27;not a pair&#x27;</li>"));
+        assert!(content.contains("<li>⚠️ Key &#x27;status&#x27; isn&#x27;t in valid-tags</li>"));
+    }
+
+    #[test]
+    fn run_reads_toml_blocks_and_sidecars() {
+        let dir =
+            std::env::temp_dir().join(format!("mdbook-metadata-sidecar-{}", std::process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(
+            dir.join("src/intro.meta.yml"),
+            "title: Sidecar\nowner: docs\n",
+        )
+        .unwrap();
+        let mut ctx = context("");
+        ctx.root = dir.clone();
+        let book = book(&[
+            ("Intro", "intro.md", "---\ntitle: Intro\n---\n# Intro"),
+            (
+                "Zola",
+                "zola.md",
+                "+++\ntitle = \"Zola\"\n[taxonomies]\ntags = [\"a\"]\n+++\n# Zola",
+            ),
+        ]);
+
+        let book = Metadata::new(&ctx).run(&ctx, book).unwrap();
+        let contents = chapter_contents(&book);
+
+        assert_eq!(
+            contents[0].1,
+            "<meta name=\"owner\" content=\"docs\">\n<title>Intro</title>\n\n# Intro"
+        );
+        assert_eq!(
+            contents[1].1,
+            "<meta name=\"taxonomies.tags\" content=\"[a]\">\n<title>Zola</title>\n\n# Zola"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn run_applies_glob_rules_before_frontmatter() {
+        let ctx = context(
+            r#"
+[[preprocessor.metadata.rules]]
+glob = "src/reference/**"
+set = { layout = "api", noindex = true }
+"#,
+        );
+        let book = book(&[
+            (
+                "API",
+                "reference/api.md",
+                "---
+layout: overview
+---
+# API",
+            ),
+            ("Intro", "intro.md", "# Intro"),
+        ]);
+
+        let book = Metadata::new(&ctx).run(&ctx, book).unwrap();
+        let contents = chapter_contents(&book);
 
-        let (metadata, content_without_metadata) =
-            parse_metadata(content_with_duplicate_keys, true).unwrap();
+        assert_eq!(
+            contents[0].1,
+            "<meta name=\"layout\" content=\"overview\">\n<meta name=\"noindex\" content=\"true\">\n\n# API"
+        );
+        assert_eq!(contents[1].1, "# Intro");
+    }
+
+    #[test]
+    fn run_skips_chapters_out_of_scope() {
+        let ctx = context(
+            r#"
+[preprocessor.metadata]
+continue-on-error = false
+include = ["src/blog/**"]
+exclude = ["src/blog/drafts/**"]
+"#,
+        );
+        let excluded = "---\ntitle: Idea\n---\n# Idea\n";
+        let book = book(&[
+            (
+                "Launch",
+                "blog/launch.md",
+                "---\ntitle: Launch\nsee-also: [blog/drafts/idea.md]\n---\n# Launch",
+            ),
+            ("Idea", "blog/drafts/idea.md", excluded),
+            ("Appendix", "appendix/a.md", excluded),
+        ]);
+
+        let book = Metadata::new(&ctx).run(&ctx, book).unwrap();
+        let contents = chapter_contents(&book);
 
         assert_eq!(
-            content_without_metadata, "Chapter content.",
-            "The content should exclude the metadata block."
+            contents[0].1,
+            "<meta name=\"see-also\" content=\"[blog/drafts/idea.md]\">\n\
+             <title>Launch</title>\n\
+             <link rel=\"related\" href=\"drafts/idea.html\">\n\n\
+             # Launch\n\n## See also\n\n- [Idea](drafts/idea.md)\n"
+        );
+        assert_eq!(contents[1].1, excluded);
+        assert_eq!(contents[2].1, excluded);
+    }
+
+    #[test]
+    fn run_honours_opt_out_keys() {
+        let chapters = [
+            (
+                "Custom",
+                "custom.md",
+                "---\ntitle: Custom\nmetadata: false\n---\n# Custom",
+            ),
+            (
+                "Skipped",
+                "skipped.md",
+                "---\nmdbook-metadata: skip\n---\n# Skipped",
+            ),
+            ("Guide", "guide.md", "---\nsee-also: [custom.md]\n---\n# Guide"),
+        ];
+
+        let ctx = context(
+            "[preprocessor.metadata]\ncontinue-on-error = false\ndefault-author = \"Jane\"\n",
+        );
+        let stripped = Metadata::new(&ctx).run(&ctx, book(&chapters)).unwrap();
+        let contents = chapter_contents(&stripped);
+        assert_eq!(contents[0].1, "# Custom");
+        assert_eq!(contents[1].1, "# Skipped");
+        assert!(contents[2].1.ends_with("## See also\n\n- [Custom](custom.md)\n"));
+
+        let ctx = context(
+            "[preprocessor.metadata]\ncontinue-on-error = false\nopt-out = \"untouched\"\n",
+        );
+        let untouched = Metadata::new(&ctx).run(&ctx, book(&chapters)).unwrap();
+        let contents = chapter_contents(&untouched);
+        assert_eq!(contents[0].1, chapters[0].2);
+        assert_eq!(contents[1].1, chapters[1].2);
+        assert!(contents[2].1.ends_with("- [Custom](custom.md)\n"));
+    }
+
+    #[test]
+    fn run_injects_raw_keys_unescaped() {
+        let ctx = context("[preprocessor.metadata]\nraw-keys = [\"head-extra\"]\n");
+        let book = book(&[(
+            "Intro",
+            "intro.md",
+            "---\nhead-extra: <link rel=\"me\" href=\"https://example.com\">\nnote: <b>\n---\n# Intro",
+        )]);
+
+        let book = Metadata::new(&ctx).run(&ctx, book).unwrap();
+
+        assert_eq!(
+            chapter_contents(&book)[0].1,
+            "<link rel=\"me\" href=\"https://example.com\">\n\
+             <meta name=\"note\" content=\"&lt;b&gt;\">\n\n# Intro"
         );
+    }
+
+    #[test]
+    fn run_sanitizes_extension_keys() {
+        let ctx = context("[preprocessor.metadata]\nsanitize-values = true\n");
+        let book = book(&[(
+            "Intro",
+            "intro.md",
+            "---\nx-note: <script>alert(1)</script>\n---\n# Intro",
+        )]);
+
+        let book = Metadata::new(&ctx).run(&ctx, book).unwrap();
+
+        assert_eq!(
+            chapter_contents(&book)[0].1,
+            "<meta name=\"x-note\" content=\"\">\n\n# Intro"
+        );
+    }
 
+    #[test]
+    fn run_emits_accessibility_json_ld() {
+        let ctx =
+            context("[preprocessor.metadata.accessibility]\nstatement = \"accessibility.md\"\n");
+        let book = book(&[
+            (
+                "Intro",
+                "guide/intro.md",
+                "---\ntitle: Intro\naccessibility-features: captions\n---\n# Intro",
+            ),
+            ("Plain", "plain.md", "# Plain"),
+        ]);
+
+        let book = Metadata::new(&ctx).run(&ctx, book).unwrap();
+
+        let contents = chapter_contents(&book);
+        assert_eq!(
+            contents[0].1,
+            "<link rel=\"accessibility-statement\" href=\"../accessibility.html\">\n\
+             <title>Intro</title>\n\
+             <meta name=\"accessibility-features\" content=\"captions\">\n\
+             <script type=\"application/ld+json\">{\"@context\":\"https://schema.org\",\"@type\":\"WebPage\",\"accessibilityFeature\":[\"captions\"],\"name\":\"Intro\"}</script>\n\n# Intro"
+        );
         assert_eq!(
-            metadata.get("title"),
-            Some(&"Second Title".to_string()),
-            "The 'title' key should reflect the last occurrence."
+            contents[1].1,
+            "<link rel=\"accessibility-statement\" href=\"accessibility.html\">\n\n# Plain"
         );
+    }
+
+    #[test]
+    fn run_emits_property_attributes() {
+        let chapters = [(
+            "Intro",
+            "intro.md",
+            "---\nog:title: Intro\ntwitter:card: summary\n---\n# Intro",
+        )];
+
+        let ctx = context("");
+        let defaults = Metadata::new(&ctx).run(&ctx, book(&chapters)).unwrap();
         assert_eq!(
-            metadata.get("keywords"),
-            Some(&"second, set".to_string()),
-            "The 'keywords' key should reflect the last occurrence."
+            chapter_contents(&defaults)[0].1,
+            "<meta property=\"og:title\" content=\"Intro\">\n\
+             <meta name=\"twitter:card\" content=\"summary\">\n\n# Intro"
         );
 
+        let ctx = context("[preprocessor.metadata]\nproperty-keys = [\"twitter:*\"]\n");
+        let configured = Metadata::new(&ctx).run(&ctx, book(&chapters)).unwrap();
         assert_eq!(
-            metadata.len(),
-            2,
-            "The metadata HashMap should only contain two entries, one for each unique key."
+            chapter_contents(&configured)[0].1,
+            "<meta property=\"og:title\" content=\"Intro\">\n\
+             <meta property=\"twitter:card\" content=\"summary\">\n\n# Intro"
         );
     }
 
     #[test]
-    fn test_metadata_to_html_basic() {
-        let mut metadata = HashMap::new();
-        metadata.insert("title".to_string(), "Example Title".to_string());
-        metadata.insert("keywords".to_string(), "rust, mdbook, testing".to_string());
-        metadata.insert("author".to_string(), "John Doe".to_string());
-
-        let html_output = metadata_to_html(&metadata, &HashMap::new());
+    fn run_emits_structured_links() {
+        let ctx = context("[preprocessor.metadata]\nvalid-tags = [\"title\"]\n");
+        let book = book(&[(
+            "Intro",
+            "intro.md",
+            "---\ntitle: Intro\nlinks: [{rel: license, href: LICENSE.html}, {rel: alternate, href: feed.xml, type: application/rss+xml}]\n---\n# Intro",
+        ), (
+            "Zola",
+            "zola.md",
+            "+++\n[[links]]\nrel = \"webmention\"\nhref = \"https://example.com/wm\"\n+++\n# Zola",
+        )]);
+
+        let book = Metadata::new(&ctx).run(&ctx, book).unwrap();
 
-        assert!(
-            html_output.contains("<title>Example Title</title>"),
-            "Output should contain title tag."
-        );
-        assert!(
-            html_output.contains("<meta name=\"keywords\" content=\"rust, mdbook, testing\">"),
-            "Output should contain keywords meta tag."
+        assert_eq!(
+            chapter_contents(&book)[0].1,
+            "<title>Intro</title>\n\
+             <link rel=\"license\" href=\"LICENSE.html\">\n\
+             <link rel=\"alternate\" href=\"feed.xml\" type=\"application/rss+xml\">\n\n# Intro"
         );
-        assert!(
-            html_output.contains("<meta name=\"author\" content=\"John Doe\">"),
-            "Output should contain author meta tag."
+        assert_eq!(
+            chapter_contents(&book)[1].1,
+            "<link rel=\"webmention\" href=\"https://example.com/wm\">\n\n# Zola"
         );
     }
 
     #[test]
-    fn test_metadata_to_html_empty() {
-        let metadata = HashMap::new();
+    fn run_emits_verification_tags_on_index() {
+        let ctx = context(
+            r#"
+[preprocessor.metadata.verification]
+google = "abc123"
+chapters = "index"
+"#,
+        );
+        let chapters = [
+            ("Home", "index.md", "# Home"),
+            ("Intro", "intro.md", "# Intro"),
+        ];
 
-        let html_output = metadata_to_html(&metadata, &HashMap::new());
+        let emitted = Metadata::new(&ctx).run(&ctx, book(&chapters)).unwrap();
+        let contents = chapter_contents(&emitted);
 
-        assert!(
-            html_output.is_empty(),
-            "HTML output should be empty for empty metadata."
+        assert_eq!(
+            contents[0].1,
+            "<meta name=\"google-site-verification\" content=\"abc123\">\n\n# Home"
         );
+        assert_eq!(contents[1].1, "# Intro");
+
+        // An excluded index doesn't hand its token to the next chapter
+        let ctx = context(
+            r#"
+[preprocessor.metadata]
+exclude = ["src/index.md"]
+
+[preprocessor.metadata.verification]
+google = "abc123"
+chapters = "index"
+"#,
+        );
+        let excluded = book(&[
+            ("Home", "index.md", "# Home"),
+            ("Intro", "intro.md", "---\ntitle: Intro\n---\n# Intro"),
+        ]);
+
+        let excluded = Metadata::new(&ctx).run(&ctx, excluded).unwrap();
+        let contents = chapter_contents(&excluded);
+
+        assert_eq!(contents[0].1, "# Home");
+        assert_eq!(contents[1].1, "<title>Intro</title>\n\n# Intro");
     }
 
     #[test]
-    fn test_metadata_to_html_complex() {
-        let metadata = HashMap::from([
-            (
-                "title".to_string(),
-                "Complex & <Special> 'Characters'".to_string(),
-            ),
-            (
-                "description".to_string(),
-                r#"Testing "quotes" and other <html> elements"#.to_string(),
-            ),
-            (
-                "keywords".to_string(),
-                r#"rust,mdbook,"special, characters",<html>"#.to_string(),
-            ),
-        ]);
+    fn run_emits_open_graph_article_tags() {
+        let ctx = context(
+            r#"
+[book]
+language = "en-GB"
 
-        let html_output = metadata_to_html(&metadata, &HashMap::new());
+[preprocessor.metadata]
+"default-og:type" = "article"
 
-        let expected_outputs = [
-            r#"<title>Complex &amp; &lt;Special&gt; &#x27;Characters&#x27;</title>"#,
-            r#"<meta name="description" content="Testing &quot;quotes&quot; and other &lt;html&gt; elements">"#,
-            r#"<meta name="keywords" content="rust,mdbook,&quot;special, characters&quot;,&lt;html&gt;">"#,
-        ];
+[preprocessor.metadata.open-graph]
+"#,
+        );
+        let book = book(&[(
+            "Intro",
+            "intro.md",
+            "---\nauthor: Jane\ndate: 2024-06-01\n---\n# Intro",
+        )]);
 
-        // Split the output by newline and collect into a set for order-independent comparison
-        let html_output_set: HashSet<_> = html_output.lines().collect();
-        let expected_output_set: HashSet<_> = expected_outputs.iter().cloned().collect();
+        let book = Metadata::new(&ctx).run(&ctx, book).unwrap();
 
         assert_eq!(
-        html_output_set, expected_output_set,
-        "The HTML output should correctly handle special characters and HTML content, order-independent."
-    );
+            chapter_contents(&book)[0].1,
+            "<meta name=\"author\" content=\"Jane\">\n\
+             <meta name=\"date\" content=\"2024-06-01\">\n\
+             <meta property=\"og:type\" content=\"article\">\n\
+             <meta property=\"og:locale\" content=\"en_GB\">\n\
+             <meta property=\"article:published_time\" content=\"2024-06-01\">\n\
+             <meta property=\"article:author\" content=\"Jane\">\n\n# Intro"
+        );
     }
 
     #[test]
-    fn test_metadata_to_html_xss_prevention() {
-        let metadata = HashMap::from([
-            ("title".to_string(), "Safe Title".to_string()),
+    fn run_emits_fediverse_creators() {
+        let ctx =
+            context("[preprocessor.metadata]\nfediverse-creator = \"@docs@mastodon.social\"\n");
+        let book = book(&[
             (
-                "script_injection".to_string(),
-                "<script>alert('XSS');</script>".to_string(),
+                "Intro",
+                "intro.md",
+                "---\ntitle: Intro\nfediverse: \"@jane@hachyderm.io\"\n---\n# Intro",
             ),
+            ("Setup", "setup.md", "---\ntitle: Setup\n---\n# Setup"),
         ]);
 
-        let html_output = metadata_to_html(&metadata, &HashMap::new());
-
-        // Expected outputs should escape the <, >, and other special HTML characters
-        let expected_outputs = [
-            r#"<title>Safe Title</title>"#,
-            // Include the encoded forward slash in the expected output
-            r#"<meta name="script_injection" content="&lt;script&gt;alert(&#x27;XSS&#x27;);&lt;&#x2F;script&gt;">"#,
-        ];
-
-        let html_output_set: HashSet<_> = html_output.lines().collect();
-        let expected_output_set: HashSet<_> = expected_outputs.iter().cloned().collect();
+        let book = Metadata::new(&ctx).run(&ctx, book).unwrap();
+        let contents = chapter_contents(&book);
 
         assert_eq!(
-            html_output_set, expected_output_set,
-            "The HTML output should escape potential XSS injection attempts, ensuring code safety."
+            contents[0].1,
+            "<title>Intro</title>\n\
+             <meta name=\"fediverse:creator\" content=\"@jane@hachyderm.io\">\n\n# Intro"
+        );
+        assert_eq!(
+            contents[1].1,
+            "<title>Setup</title>\n\
+             <meta name=\"fediverse:creator\" content=\"@docs@mastodon.social\">\n\n# Setup"
         );
     }
 
     #[test]
-    fn test_metadata_to_html_malicious_code() {
-        let metadata = HashMap::from([
-            ("title".to_string(), "Normal Title".to_string()),
-            // Attempted JavaScript injection
+    fn run_fails_on_unknown_keys_when_strict() {
+        let ctx = context("[preprocessor.metadata]\nvalid-tags = [\"title\"]\n");
+        let chapters = [(
+            "Intro",
+            "intro.md",
+            "---\ntitle: Intro\nstatus: draft\n---\n# Intro",
+        )];
+
+        assert!(Metadata::new(&ctx).run(&ctx, book(&chapters)).is_ok());
+        let error = Metadata::new(&ctx)
+            .with_strict(true)
+            .run(&ctx, book(&chapters))
+            .unwrap_err();
+        assert!(format!("{:#}", error).contains("Key 'status' isn't in valid-tags ('intro.md')"));
+    }
+
+    #[test]
+    fn run_fails_on_warnings_when_strict() {
+        let chapters = [
             (
-                "description".to_string(),
-                r#"<script>alert("malicious code");</script>"#.to_string(),
+                "Intro",
+                "intro.md",
+                "---\ntitle: Intro\ndefines: latency\n---\n# Intro",
+            ),
+            (
+                "Tuning",
+                "tuning.md",
+                "---\ntitle: Tuning\ndescription: Knobs\ndefines: latency\n---\n# Tuning",
             ),
-        ]);
-
-        let html_output = metadata_to_html(&metadata, &HashMap::new());
-
-        let expected_outputs = [
-            r#"<title>Normal Title</title>"#,
-            r#"<meta name="description" content="&lt;script&gt;alert(&quot;malicious code&quot;);&lt;&#x2F;script&gt;">"#,
         ];
-
-        let html_output_set: HashSet<_> = html_output.lines().collect();
-        let expected_output_set: HashSet<_> = expected_outputs.iter().cloned().collect();
+        let strict_error = |book_toml: &str| {
+            let ctx = context(book_toml);
+            assert!(Metadata::new(&ctx).run(&ctx, book(&chapters)).is_ok());
+            Metadata::new(&ctx)
+                .with_strict(true)
+                .run(&ctx, book(&chapters))
+                .unwrap_err()
+                .to_string()
+        };
 
         assert_eq!(
-            html_output_set, expected_output_set,
-            "The HTML output should escape potentially malicious code."
+            strict_error(
+                "[preprocessor.metadata]\nrequired-tags = [\"title\", \"description\"]\n\
+                 opt-out = \"keep\"\n"
+            ),
+            "Unknown opt-out 'keep', stripping the metadata block\n\
+             Missing required tag 'description' ('Intro')"
+        );
+        assert_eq!(
+            strict_error("[preprocessor.metadata.glossary]\n"),
+            "Glossary term 'latency' is defined in multiple chapters: intro.md, tuning.md"
         );
     }
 
     #[test]
-    fn test_metadata_to_html_complex_structures() {
-        use std::collections::BTreeMap;
+    fn run_fails_on_tag_synonyms_when_strict() {
+        let ctx = context("[preprocessor.metadata.tag-synonyms]\n\"rust-lang\" = \"rust\"\n");
+        let chapters = [(
+            "Retries",
+            "retries.md",
+            "---\ntags: [rust-lang]\n---\n# Retries",
+        )];
+
+        assert!(Metadata::new(&ctx).run(&ctx, book(&chapters)).is_ok());
+        let error = Metadata::new(&ctx)
+            .with_strict(true)
+            .run(&ctx, book(&chapters))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Tag 'rust-lang' is a synonym of 'rust' ('Retries')"
+        );
+    }
 
-        let mut nested_map = BTreeMap::new();
-        nested_map.insert("nested_key", vec!["value1", "value2"]);
+    #[test]
+    fn run_builds_only_chapters_matching_the_filter() {
+        let ctx = context("[preprocessor.metadata]\nfilter = \"audience=internal\"\n");
+        let chapters = [
+            (
+                "Quickstart",
+                "quickstart.md",
+                "---\naudience: [public, internal]\nstatus: stable\n---\n# Quickstart",
+            ),
+            (
+                "Internals",
+                "internals.md",
+                "---\naudience: internal\nstatus: draft\n---\n# Internals",
+            ),
+            ("Changelog", "changelog.md", "# Changelog"),
+        ];
 
-        let metadata = HashMap::from([
-            ("title".to_string(), "Complex Structures".to_string()),
-            ("complex".to_string(), format!("{:?}", nested_map)),
-        ]);
+        let built = Metadata::new(&ctx)
+            .with_filter(Some("audience=public && status=stable".to_string()))
+            .run(&ctx, book(&chapters))
+            .unwrap();
+        let names: Vec<String> = chapter_contents(&built)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, vec!["Quickstart"]);
+
+        let built = Metadata::new(&ctx).run(&ctx, book(&chapters)).unwrap();
+        assert_eq!(chapter_contents(&built).len(), 2);
+        assert!(Metadata::new(&ctx)
+            .with_filter(Some("audience=".to_string()))
+            .run(&ctx, book(&chapters))
+            .is_err());
+    }
 
-        let html_output = metadata_to_html(&metadata, &HashMap::new());
+    #[test]
+    fn run_filters_keys_per_renderer() {
+        let book_toml = "[preprocessor.metadata]\n\
+                         valid-tags = [\"title\", \"theme-color\"]\n\
+                         [preprocessor.metadata.per-renderer.epub]\n\
+                         valid-tags = [\"title\"]\n\
+                         emit = { meta = false }\n";
+        let chapters = [(
+            "Intro",
+            "intro.md",
+            "---\ntitle: Intro\ntheme-color: blue\n---\n# Intro",
+        )];
+
+        let html = context(book_toml);
+        let book_html = Metadata::new(&html).run(&html, book(&chapters)).unwrap();
+        let mut epub = context(book_toml);
+        epub.renderer = "epub".to_string();
+        let book_epub = Metadata::new(&epub).run(&epub, book(&chapters)).unwrap();
+
+        let html_content = &chapter_contents(&book_html)[0].1;
+        assert!(html_content.contains("<meta name=\"theme-color\" content=\"blue\">"));
+        assert!(html_content.contains("<title>Intro</title>"));
+        assert_eq!(chapter_contents(&book_epub)[0].1, "# Intro");
+    }
 
-        let expected_html_tags = vec![
-        "<title>Complex Structures</title>",
-        r#"<meta name="complex" content="{&quot;nested_key&quot;: [&quot;value1&quot;, &quot;value2&quot;]}">"#,
-    ].into_iter().map(String::from).collect::<HashSet<_>>();
+    #[test]
+    fn run_emits_preload_and_prefetch_hints() {
+        let ctx = context("[preprocessor.metadata.hints]\n");
+        let book = book(&[
+            (
+                "Intro",
+                "intro.md",
+                "---\npreload: [theme/big.css]\n---\n# Intro",
+            ),
+            ("Setup", "guide/setup.md", "# Setup"),
+        ]);
 
-        let output_html_tags = html_output
-            .lines()
-            .map(String::from)
-            .collect::<HashSet<_>>();
+        let book = Metadata::new(&ctx).run(&ctx, book).unwrap();
+        let contents = chapter_contents(&book);
 
         assert_eq!(
-            output_html_tags, expected_html_tags,
-            "The HTML output should correctly handle and escape complex structures."
+            contents[0].1,
+            "<link rel=\"prefetch\" href=\"guide/setup.html\">\n\
+             <link rel=\"preload\" href=\"theme/big.css\" as=\"style\">\n\n# Intro"
         );
+        assert_eq!(contents[1].1, "# Setup");
     }
 
     #[test]
-    fn test_metadata_to_html_large_volume() {
-        let mut metadata = HashMap::new();
-        for i in 0..1000 {
-            metadata.insert(format!("key_{}", i), format!("value_{}", i));
-        }
-
-        let html_output = metadata_to_html(&metadata, &HashMap::new());
-
-        for i in 0..1000 {
-            let expected_key = format!("key_{}", i);
-            let expected_value = format!("value_{}", i);
-            let expected_tag = format!(
-                "<meta name=\"{}\" content=\"{}\">",
-                expected_key, expected_value
-            );
+    fn run_rejects_malformed_config() {
+        let ctx = context("[preprocessor.metadata]\nvalid-tags = \"title\"\n");
 
-            assert!(
-                html_output.contains(&expected_tag),
-                "The HTML output should contain the metadata entry for key {}: {}",
-                expected_key,
-                expected_value
-            );
-        }
+        let error = Metadata::new(&ctx)
+            .run(&ctx, book(&[("Intro", "intro.md", "# Intro")]))
+            .unwrap_err();
 
-        assert!(
-            !html_output.contains("<title>"),
-            "The HTML output should not contain a title tag when not specified in the metadata."
+        assert_eq!(
+            error.to_string(),
+            "preprocessor.metadata.valid-tags: expected an array of strings, found a string"
         );
     }
 
     #[test]
-    fn metadata_empty_default_values_empty() {
-        let metadata = HashMap::new();
-        let default_values = HashMap::new();
+    fn run_applies_legacy_meta_version_rules() {
+        let ctx = context("[preprocessor.metadata]\nmeta-version = 2\n");
+        let book = book(&[
+            (
+                "Legacy",
+                "legacy.md",
+                "---\nmeta-version: 1\nog:title: Legacy\n---\n# Legacy",
+            ),
+            (
+                "Current",
+                "current.md",
+                "---\nog:title: Current\n---\n# Current",
+            ),
+        ]);
 
-        let html_output = metadata_to_html(&metadata, &default_values);
+        let book = Metadata::new(&ctx).run(&ctx, book).unwrap();
+        let contents = chapter_contents(&book);
 
-        assert!(html_output.is_empty(), "HTML output should be empty when both metadata and default values are empty.");
+        assert_eq!(
+            contents[0].1,
+            "<meta name=\"og\" content=\"title: Legacy\">\n\n# Legacy"
+        );
+        assert_eq!(
+            contents[1].1,
+            "<meta property=\"og:title\" content=\"Current\">\n\n# Current"
+        );
     }
 
     #[test]
-    fn metadata_empty_default_values_not_empty() {
-        let metadata = HashMap::new();
-        let mut default_values = HashMap::new();
-        default_values.insert("author".to_string(), "Jane Doe".to_string());
-
-        let html_output = metadata_to_html(&metadata, &default_values);
+    fn test_metadata_to_html_snapshot() {
+        let defaults = HashMap::from([
+            ("author".to_string(), "Default Author".to_string()),
+            ("keywords".to_string(), "mdbook".to_string()),
+        ]);
+        let metadata = HashMap::from([
+            ("title".to_string(), "Tags & \"Quotes\"".to_string()),
+            ("author".to_string(), "Jane Doe".to_string()),
+            ("description".to_string(), "<b>Bold</b> claims".to_string()),
+        ]);
 
-        assert!(html_output.contains("<meta name=\"author\" content=\"Jane Doe\">"), "HTML output should contain default values when metadata is empty.");
+        insta::assert_snapshot!(metadata_to_html(&metadata, &defaults));
     }
 
-    #[test]
-    fn metadata_not_empty_default_values_not_empty_conflicting_values() {
-        let mut metadata = HashMap::new();
-        metadata.insert("author".to_string(), "John Doe".to_string());
+    mod properties {
+        use super::*;
+        use proptest::collection::hash_map;
+        use proptest::prelude::*;
+
+        fn key() -> impl Strategy<Value = String> {
+            // Any single-line key the `key: value` syntax can express: trimmed
+            // like the parser does, without a colon followed by whitespace
+            "([^\r\n]|---){1,20}".prop_filter_map("inexpressible key", |k| {
+                let k = k.trim().to_string();
+                let splits = k
+                    .char_indices()
+                    .any(|(i, c)| c == ':' && k[i + 1..].starts_with(char::is_whitespace));
+                (!k.is_empty() && !splits).then_some(k)
+            })
+        }
 
-        let mut default_values = HashMap::new();
-        default_values.insert("author".to_string(), "Jane Doe".to_string());
+        fn value() -> impl Strategy<Value = String> {
+            // Arbitrary single-line text, trimmed like the parser does
+            "([^\r\n]|---){1,40}".prop_filter_map("blank value", |v| {
+                let v = v.trim().to_string();
+                (!v.is_empty()).then_some(v)
+            })
+        }
 
-        let html_output = metadata_to_html(&metadata, &default_values);
+        fn metadata() -> impl Strategy<Value = HashMap<String, String>> {
+            hash_map(key(), value(), 0..8)
+        }
 
-        assert!(html_output.contains("<meta name=\"author\" content=\"John Doe\">"), "Metadata values should override default values when both are present.");
-    }
+        fn body() -> impl Strategy<Value = String> {
+            "[a-zA-Z0-9 #*.\n-]{0,200}".prop_filter("body opening a metadata block", |b| {
+                b.trim_start().lines().next().map(str::trim_end) != Some("---")
+            })
+        }
 
-    #[test]
-    fn metadata_not_empty_default_values_not_empty_metadata_has_extra_tags() {
-        let mut metadata = HashMap::new();
-        metadata.insert("author".to_string(), "John Doe".to_string());
-        metadata.insert("keywords".to_string(), "rust, mdbook".to_string());
+        fn frontmatter(metadata: &HashMap<String, String>, body: &str) -> String {
+            let mut content = String::from("---\n");
+            for (key, value) in metadata {
+                content.push_str(&format!("{}: {}\n", key, value));
+            }
+            content.push_str("---\n");
+            content.push_str(body);
+            content
+        }
 
-        let mut default_values = HashMap::new();
-        default_values.insert("description".to_string(), "Sample book".to_string());
+        fn merged(
+            base: &HashMap<String, String>,
+            overrides: &HashMap<String, String>,
+        ) -> HashMap<String, String> {
+            let mut merged = base.clone();
+            merged.extend(overrides.clone());
+            merged
+        }
 
-        let html_output = metadata_to_html(&metadata, &default_values);
+        fn lines(html: &str) -> HashSet<String> {
+            html.lines().map(String::from).collect()
+        }
 
-        assert!(html_output.contains("<meta name=\"author\" content=\"John Doe\">") && html_output.contains("<meta name=\"keywords\" content=\"rust, mdbook\">") && html_output.contains("<meta name=\"description\" content=\"Sample book\">"), "HTML output should include all tags from both metadata and default values, with metadata taking precedence.");
-    }
+        proptest! {
+            #[test]
+            fn parse_round_trips_frontmatter(metadata in metadata(), body in body()) {
+                let content = frontmatter(&metadata, &body);
 
-    #[test]
-    fn default_values_has_tags_not_in_metadata() {
-        let mut metadata = HashMap::new();
-        metadata.insert("author".to_string(), "John Doe".to_string());
+                let (parsed, rest) = parse_metadata(&content, false).unwrap();
 
-        let mut default_values = HashMap::new();
-        default_values.insert("author".to_string(), "Jane Doe".to_string());
-        default_values.insert("keywords".to_string(), "rust, mdbook".to_string());
+                prop_assert_eq!(parsed, metadata);
+                prop_assert_eq!(rest.as_ref(), body.trim_start());
+            }
 
-        let html_output = metadata_to_html(&metadata, &default_values);
+            #[test]
+            fn stripping_is_idempotent(metadata in metadata(), body in body()) {
+                let content = frontmatter(&metadata, &body);
 
-        assert!(html_output.contains("<meta name=\"author\" content=\"John Doe\">") && html_output.contains("<meta name=\"keywords\" content=\"rust, mdbook\">"), "HTML output should include tags from metadata and default values not present in metadata, with metadata values taking precedence.");
-    }
+                let (_, once) = parse_metadata(&content, true).unwrap();
+                let (again, twice) = parse_metadata(&once, true).unwrap();
 
-    #[test]
-    fn special_characters_in_metadata() {
-        let mut metadata = HashMap::new();
-        metadata.insert("description".to_string(), "This & That <test>".to_string());
+                prop_assert!(again.is_empty());
+                prop_assert_eq!(once.as_ref(), twice.as_ref());
+            }
 
-        let default_values = HashMap::new(); // Empty default values for simplicity
+            #[test]
+            fn emitted_html_is_well_formed(
+                metadata in hash_map("[^\r\n:]{1,20}", "[^\r\n]{0,40}", 0..8),
+                defaults in metadata(),
+            ) {
+                let html = metadata_to_html(&metadata, &defaults);
+
+                for line in html.lines() {
+                    // Titles are text, so only markup has to be escaped; meta
+                    // attributes must not contain quotes either
+                    let (inner, forbidden): (String, &[char]) = if let Some(title) = line
+                        .strip_prefix("<title>")
+                        .and_then(|l| l.strip_suffix("</title>"))
+                    {
+                        (title.to_string(), &['<', '>'])
+                    } else {
+                        let attributes = line
+                            .strip_prefix("<meta name=\"")
+                            .and_then(|l| l.strip_suffix("\">"));
+                        prop_assert!(attributes.is_some(), "malformed tag: {}", line);
+                        (
+                            attributes.unwrap().replacen("\" content=\"", "", 1),
+                            &['<', '>', '"'],
+                        )
+                    };
+                    prop_assert!(!inner.contains(forbidden), "unescaped: {}", line);
+                }
+                // Keys that aren't tokens are skipped
+                let emitted = merged(&defaults, &metadata)
+                    .keys()
+                    .filter(|key| *key == "title" || tags::sanitize_name(key).is_some())
+                    .count();
+                prop_assert_eq!(html.lines().count(), emitted);
+            }
 
-        let html_output = metadata_to_html(&metadata, &default_values);
+            #[test]
+            fn merge_order_is_associative(a in metadata(), b in metadata(), c in metadata()) {
+                let left = metadata_to_html(&c, &merged(&a, &b));
+                let right = metadata_to_html(&merged(&b, &c), &a);
 
-        assert!(
-            html_output.contains("<meta name=\"description\" content=\"This &amp; That &lt;test&gt;\">"),
-            "HTML output should correctly escape special characters in metadata values."
-        );
+                prop_assert_eq!(lines(&left), lines(&right));
+            }
+        }
     }
 }
@@ -0,0 +1,157 @@
+use anyhow::Result;
+use log::{info, warn};
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use toml::value::Table;
+
+use crate::frontmatter::{self, Format};
+use crate::sources::load_config;
+
+/// Renamed metadata keys, `old-key = "new-key"`
+/// (`[preprocessor.metadata.migrate]`). `enabled` is the feature switch, not a
+/// key.
+#[derive(Debug, Clone, Default)]
+pub struct Migrate {
+    pub renames: BTreeMap<String, String>,
+}
+
+impl Migrate {
+    pub fn from_config(table: &Table) -> Self {
+        let renames = table
+            .iter()
+            .filter(|(old, _)| old.as_str() != "enabled")
+            .filter_map(|(old, new)| match new.as_str() {
+                Some(new) if !new.trim().is_empty() => Some((old.clone(), new.trim().to_string())),
+                _ => {
+                    warn!("Ignoring the migration of '{}': expected a key name", old);
+                    None
+                }
+            })
+            .collect();
+        Self { renames }
+    }
+
+    /// Reads the renamed keys of a book.
+    pub fn load(book_dir: &Path) -> Result<Self> {
+        let (config, _) = load_config(book_dir)?;
+        let table = config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("migrate"))
+            .and_then(|v| v.as_table())
+            .cloned()
+            .unwrap_or_default();
+        Ok(Self::from_config(&table))
+    }
+
+    /// Renames the legacy keys of a chapter's metadata. When a chapter sets
+    /// both names, the new one wins.
+    pub fn apply(&self, metadata: &mut HashMap<String, String>) {
+        for (old, new) in &self.renames {
+            let Some(value) = metadata.remove(old) else {
+                continue;
+            };
+            if metadata.contains_key(new) {
+                warn!("Ignoring legacy key '{}', '{}' is already set", old, new);
+            } else {
+                info!("Renamed legacy key '{}' to '{}'", old, new);
+                metadata.insert(new.clone(), value);
+            }
+        }
+    }
+
+    /// Renames the legacy keys of the body of a metadata block, leaving keys
+    /// whose new name is already set. Every other line is kept as is.
+    pub fn rename_in_body(&self, format: Format, body: &str) -> String {
+        let separator = match format {
+            Format::Yaml => ":",
+            Format::Toml => "=",
+        };
+        let mut lines: Vec<String> = body.split_inclusive('\n').map(String::from).collect();
+        // Keys of TOML tables belong to the table, not to the chapter
+        let top_level = match format {
+            Format::Yaml => lines.len(),
+            Format::Toml => lines
+                .iter()
+                .position(|l| l.trim_start().starts_with('['))
+                .unwrap_or(lines.len()),
+        };
+        let key_re = |key: &str| {
+            Regex::new(&format!(
+                r#"^(\s*)"?{}"?(\s*{})"#,
+                regex::escape(key),
+                separator
+            ))
+            .unwrap()
+        };
+
+        for (old, new) in &self.renames {
+            let (old_re, new_re) = (key_re(old), key_re(new));
+            if lines[..top_level].iter().any(|l| new_re.is_match(l)) {
+                continue;
+            }
+            let new = match format {
+                Format::Yaml => new.clone(),
+                Format::Toml => frontmatter::toml_key(new),
+            };
+            if let Some(line) = lines[..top_level].iter_mut().find(|l| old_re.is_match(l)) {
+                *line = old_re
+                    .replace(line, |caps: &regex::Captures| {
+                        format!("{}{}{}", &caps[1], new, &caps[2])
+                    })
+                    .into_owned();
+            }
+        }
+        lines.concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migrate() -> Migrate {
+        Migrate::from_config(
+            &toml::from_str("author = \"authors\"\n\"og-title\" = \"og:title\"").unwrap(),
+        )
+    }
+
+    #[test]
+    fn apply_renames_legacy_keys() {
+        let mut metadata = HashMap::from([
+            ("author".to_string(), "Jane".to_string()),
+            ("og-title".to_string(), "Old".to_string()),
+            ("og:title".to_string(), "New".to_string()),
+        ]);
+
+        migrate().apply(&mut metadata);
+
+        assert_eq!(
+            metadata,
+            HashMap::from([
+                ("authors".to_string(), "Jane".to_string()),
+                ("og:title".to_string(), "New".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn rename_in_body_keeps_the_rest_of_the_block() {
+        let migrate = migrate();
+        assert_eq!(
+            migrate.rename_in_body(Format::Yaml, "title: Intro\r\n  author: Jane # lead\r\n"),
+            "title: Intro\r\n  authors: Jane # lead\r\n"
+        );
+        assert_eq!(
+            migrate.rename_in_body(
+                Format::Toml,
+                "og-title = \"Intro\"\nauthor = \"Jane\"\n[extra]\nauthor = \"x\"\n"
+            ),
+            "\"og:title\" = \"Intro\"\nauthors = \"Jane\"\n[extra]\nauthor = \"x\"\n"
+        );
+        assert_eq!(
+            migrate.rename_in_body(Format::Yaml, "author: Jane\nauthors: Jane, John\n"),
+            "author: Jane\nauthors: Jane, John\n"
+        );
+    }
+}
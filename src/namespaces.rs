@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use toml::value::Table;
+
+use crate::metadata::MetadataError;
+use crate::tags::Tag;
+use crate::template;
+
+const DUBLIN_CORE_SCHEMA: &str = "http://purl.org/dc/elements/1.1/";
+
+/// The namespace of a metadata key, from its prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Namespace<'a> {
+    /// `x-` keys, private extensions exempt from `valid-tags`.
+    Extension,
+    /// `og:` keys, emitted as `<meta property>`.
+    OpenGraph,
+    /// `dc:` keys, emitted as Dublin Core `DC.*` tags.
+    DublinCore(&'a str),
+    /// `custom:` keys, rendered with a user template.
+    Custom(&'a str),
+    /// Keys without a known prefix.
+    None,
+}
+
+impl<'a> Namespace<'a> {
+    pub fn of(key: &'a str) -> Self {
+        if key.starts_with("x-") {
+            Namespace::Extension
+        } else if key.starts_with("og:") {
+            Namespace::OpenGraph
+        } else if let Some(name) = key.strip_prefix("dc:") {
+            Namespace::DublinCore(name)
+        } else if let Some(name) = key.strip_prefix("custom:") {
+            Namespace::Custom(name)
+        } else {
+            Namespace::None
+        }
+    }
+}
+
+/// Builds the tag of a key, dispatching on its namespace.
+pub fn tag(key: &str, value: &str) -> Tag {
+    match Namespace::of(key) {
+        Namespace::OpenGraph => Tag::property(key, value),
+        Namespace::DublinCore(name) => Tag::meta(&format!("DC.{}", name), value),
+        _ if key == "title" => Tag::title(value),
+        _ => Tag::meta(key, value),
+    }
+}
+
+/// The templates of `custom:` keys, `badge = "<meta itemprop=\"{name}\"
+/// content=\"{value}\">"` (`[preprocessor.metadata.namespaces.custom]`).
+#[derive(Debug, Clone, Default)]
+pub struct Namespaces {
+    pub custom: HashMap<String, String>,
+}
+
+impl Namespaces {
+    pub fn from_config(table: &Table) -> Self {
+        let custom = table
+            .get("custom")
+            .and_then(|v| v.as_table())
+            .map(|templates| {
+                templates
+                    .iter()
+                    .filter_map(|(name, template)| Some((name.clone(), template.as_str()?.into())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { custom }
+    }
+
+    /// Renders the `custom:` keys of a chapter with their templates, removing
+    /// them from the metadata, and links the Dublin Core schema when the
+    /// chapter has `dc:` keys. Templates can use the chapter's metadata,
+    /// `{name}` (the key without its prefix) and `{value}`, all escaped for
+    /// HTML.
+    pub fn apply(&self, metadata: &mut HashMap<String, String>) -> (Vec<Tag>, Vec<MetadataError>) {
+        let mut tags = Vec::new();
+        let mut errors = Vec::new();
+        let mut custom: Vec<(String, String)> = metadata
+            .iter()
+            .filter(|(key, _)| matches!(Namespace::of(key), Namespace::Custom(_)))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        custom.sort();
+        for (key, value) in custom {
+            metadata.remove(&key);
+            let name = &key["custom:".len()..];
+            let Some(template) = self.custom.get(name) else {
+                errors.push(MetadataError::InvalidValue {
+                    message: format!("no template in namespaces.custom.{}", name),
+                    key,
+                });
+                continue;
+            };
+            let mut vars = metadata.clone();
+            vars.insert("name".to_string(), name.to_string());
+            vars.insert("value".to_string(), value.trim().to_string());
+            match template::render_html(template, &vars) {
+                Ok(html) => tags.push(Tag::raw(&key, &html)),
+                Err(e) => errors.push(e),
+            }
+        }
+        if metadata
+            .keys()
+            .any(|key| matches!(Namespace::of(key), Namespace::DublinCore(_)))
+        {
+            tags.push(Tag::link("schema.DC", DUBLIN_CORE_SCHEMA));
+        }
+        (tags, errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::{self, TagKind};
+
+    #[test]
+    fn keys_dispatch_on_their_prefix() {
+        assert_eq!(tag("og:title", "Intro").kind, TagKind::Property);
+        assert_eq!(tag("dc:creator", "Jane").name, "DC.creator");
+        assert_eq!(tag("x-owner", "docs").name, "x-owner");
+        assert_eq!(tag("title", "Intro").kind, TagKind::Title);
+        assert_eq!(Namespace::of("custom:badge"), Namespace::Custom("badge"));
+    }
+
+    #[test]
+    fn custom_keys_render_their_template() {
+        let namespaces = Namespaces::from_config(
+            &toml::from_str(r#"custom.badge = "<meta itemprop=\"{name}\" content=\"{value}\">""#)
+                .unwrap(),
+        );
+        let mut metadata = HashMap::from([
+            (
+                "custom:badge".to_string(),
+                "\"new\" ' onload='alert(1)".to_string(),
+            ),
+            ("custom:other".to_string(), "x".to_string()),
+            ("dc:creator".to_string(), "Jane".to_string()),
+        ]);
+
+        let (emitted, errors) = namespaces.apply(&mut metadata);
+
+        assert_eq!(
+            tags::to_html(&emitted),
+            "<meta itemprop=\"badge\" content=\"&quot;new&quot; &#x27; onload=&#x27;alert(1)\">\n\
+             <link rel=\"schema.DC\" href=\"http://purl.org/dc/elements/1.1/\">\n"
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(metadata.len(), 1);
+    }
+}
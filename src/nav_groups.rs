@@ -0,0 +1,123 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use toml::value::Table;
+
+use crate::aggregate::ChapterEntry;
+use crate::metadata::parse_list;
+use crate::urls::Urls;
+
+/// Groups chapters by their `nav-group` key into a `nav-groups.json` file, for
+/// themes building mega-menus or landing grids
+/// (`[preprocessor.metadata.nav-groups]`).
+#[derive(Debug, Clone)]
+pub struct NavGroups {
+    pub key: String,
+    pub order_key: String,
+    pub file: String,
+}
+
+/// A chapter listed in a navigation group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NavItem {
+    pub title: String,
+    pub path: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl NavGroups {
+    pub fn from_config(table: &Table) -> Self {
+        let string = |key: &str, default: &str| {
+            table
+                .get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or(default)
+                .to_string()
+        };
+        Self {
+            key: string("key", "nav-group"),
+            order_key: string("order-key", "nav-order"),
+            file: string("file", "nav-groups.json"),
+        }
+    }
+
+    /// Maps each group to its chapters, ordered by their `nav-order` and then
+    /// in book order. A chapter can list several groups.
+    pub fn groups(&self, chapters: &[ChapterEntry], urls: &Urls) -> BTreeMap<String, Vec<NavItem>> {
+        let mut groups: BTreeMap<String, Vec<(Option<i64>, NavItem)>> = BTreeMap::new();
+        for entry in chapters {
+            let Some(names) = entry.metadata.get(&self.key) else {
+                continue;
+            };
+            let order = entry
+                .metadata
+                .get(&self.order_key)
+                .and_then(|o| o.trim().parse().ok());
+            let item = NavItem {
+                title: entry.title().to_string(),
+                path: entry.path.with_extension("html").display().to_string(),
+                url: urls
+                    .url(&entry.path)
+                    .unwrap_or_else(|| urls.path(&entry.path)),
+                description: entry.metadata.get("description").cloned(),
+            };
+            for name in parse_list(names) {
+                groups.entry(name).or_default().push((order, item.clone()));
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(name, mut items)| {
+                // Stable, so chapters without an order stay in book order, last
+                items.sort_by_key(|(order, _)| (order.is_none(), *order));
+                (name, items.into_iter().map(|(_, item)| item).collect())
+            })
+            .collect()
+    }
+
+    /// Returns the contents of `nav-groups.json`.
+    pub fn artifact(&self, chapters: &[ChapterEntry], urls: &Urls) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.groups(chapters, urls))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::named_entry;
+
+    #[test]
+    fn groups_list_chapters_by_order_then_book_order() {
+        let nav_groups = NavGroups::from_config(&Table::new());
+        let chapters = vec![
+            named_entry("Install", "install.md", &[("nav-group", "Guides")]),
+            named_entry(
+                "Overview",
+                "overview.md",
+                &[
+                    ("nav-group", "[Guides, Reference]"),
+                    ("nav-order", "1"),
+                    ("description", "Start here"),
+                ],
+            ),
+            named_entry("Untitled", "misc.md", &[]),
+        ];
+        let urls = Urls::default();
+
+        let groups = nav_groups.groups(&chapters, &urls);
+
+        assert_eq!(groups.keys().collect::<Vec<_>>(), ["Guides", "Reference"]);
+        let titles: Vec<&str> = groups["Guides"].iter().map(|i| i.title.as_str()).collect();
+        assert_eq!(titles, ["Overview", "Install"]);
+        assert_eq!(
+            serde_json::to_value(&groups["Reference"]).unwrap(),
+            serde_json::json!([{
+                "title": "Overview",
+                "path": "overview.html",
+                "url": "/overview.html",
+                "description": "Start here"
+            }])
+        );
+    }
+}
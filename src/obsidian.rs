@@ -0,0 +1,109 @@
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::aggregate::ChapterEntry;
+use crate::metadata::{parse_list, MetadataError};
+
+/// The key holding the other names of an Obsidian note.
+pub const ALIASES_KEY: &str = "aliases";
+
+lazy_static! {
+    // `[[Page]]`, `[[Page#Heading]]` and `[[Page|Label]]`
+    static ref WIKI_LINK_RE: Regex =
+        Regex::new(r"\[\[([^\[\]|#]+)(?:#[^\[\]|]*)?(?:\|[^\[\]]*)?\]\]").unwrap();
+}
+
+/// Replaces the wiki links in the metadata values of every chapter with the
+/// path of the chapter they name: its title, file name or one of its
+/// `aliases`, regardless of case. Returns the links no chapter answers to,
+/// which are left as they are.
+pub fn resolve_wiki_links(chapters: &mut [ChapterEntry]) -> Vec<MetadataError> {
+    let mut targets: HashMap<String, PathBuf> = HashMap::new();
+    for entry in chapters.iter() {
+        let names = [
+            Some(entry.name.clone()),
+            entry
+                .path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned()),
+            Some(entry.path.with_extension("").to_string_lossy().into_owned()),
+        ];
+        let aliases = entry
+            .metadata
+            .get(ALIASES_KEY)
+            .map(|v| parse_list(v))
+            .unwrap_or_default();
+        for name in names.into_iter().flatten().chain(aliases) {
+            targets
+                .entry(name.trim().to_lowercase())
+                .or_insert_with(|| entry.path.clone());
+        }
+    }
+
+    let mut errors = Vec::new();
+    for entry in chapters.iter_mut() {
+        for (key, value) in entry.metadata.iter_mut() {
+            if !value.contains("[[") {
+                continue;
+            }
+            let resolved = WIKI_LINK_RE.replace_all(value, |captures: &Captures| {
+                let name = captures[1].trim();
+                match targets.get(&name.to_lowercase()) {
+                    Some(path) => path.to_string_lossy().replace('\\', "/"),
+                    None => {
+                        errors.push(MetadataError::UnresolvedReference {
+                            chapter: entry.name.clone(),
+                            key: key.clone(),
+                            reference: name.to_string(),
+                        });
+                        captures[0].to_string()
+                    }
+                }
+            });
+            if let std::borrow::Cow::Owned(resolved) = resolved {
+                *value = resolved;
+            }
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::named_entry;
+
+    #[test]
+    fn wiki_links_resolve_to_chapter_paths() {
+        let mut chapters = vec![
+            named_entry(
+                "Intro",
+                "intro.md",
+                &[
+                    (
+                        "prerequisites",
+                        "[[[Setup Guide#Install]], [[retries|Retrying]]]",
+                    ),
+                    ("see-also", "[[Missing]]"),
+                ],
+            ),
+            named_entry(
+                "Setting up",
+                "guide/setup.md",
+                &[("aliases", "[Setup Guide]")],
+            ),
+            named_entry("Retries", "guide/retries.md", &[]),
+        ];
+
+        let errors = resolve_wiki_links(&mut chapters);
+
+        assert_eq!(
+            chapters[0].metadata["prerequisites"],
+            "[guide/setup.md, guide/retries.md]"
+        );
+        assert_eq!(chapters[0].metadata["see-also"], "[[Missing]]");
+        assert_eq!(errors.len(), 1);
+    }
+}
@@ -0,0 +1,218 @@
+use chrono::DateTime;
+use std::collections::HashMap;
+use std::path::Path;
+use toml::value::Table;
+
+use crate::dates::parse_date;
+use crate::metadata::parse_list;
+use crate::tags::Tag;
+
+/// Derived Open Graph tags (`[preprocessor.metadata.open-graph]`): `og:locale`,
+/// `og:url`, optionally `og:type` and, for articles, the `article:*` tags.
+#[derive(Debug, Clone)]
+pub struct OpenGraph {
+    pub locale: bool,
+    pub article: bool,
+    pub infer_type: bool,
+}
+
+impl OpenGraph {
+    pub fn from_config(table: &Table) -> Self {
+        let flag = |key: &str| table.get(key).and_then(|v| v.as_bool()).unwrap_or(true);
+        Self {
+            locale: flag("locale"),
+            article: flag("article"),
+            infer_type: table
+                .get("infer-type")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+        }
+    }
+
+    /// Infers the `og:type` of a chapter, with `infer-type`: `book` for the
+    /// root, the first chapter of the book, `website` for other index pages
+    /// (`index.md` and `README.md`) and `article` for chapters with a `date`
+    /// or `published` date. Chapters setting `og:type` keep theirs.
+    pub fn infer_type(
+        &self,
+        path: &Path,
+        is_root: bool,
+        metadata: &HashMap<String, String>,
+    ) -> Option<&'static str> {
+        if !self.infer_type || metadata.contains_key("og:type") {
+            return None;
+        }
+        let dated = ["date", "published"]
+            .iter()
+            .filter_map(|key| metadata.get(*key))
+            .any(|value| iso_time(value.trim()).is_some());
+        let stem = path.file_stem().and_then(|s| s.to_str());
+        if is_root {
+            Some("book")
+        } else if matches!(stem, Some("index" | "README")) {
+            Some("website")
+        } else if dated {
+            Some("article")
+        } else {
+            None
+        }
+    }
+
+    /// Returns the tags derived from a chapter's merged metadata. Tags the
+    /// chapter sets itself are left to it:
+    ///
+    /// - `og:locale` from the `locale` key or the book language (`en-US` becomes
+    ///   `en_US`);
+    /// - `og:url` from the chapter's URL, when there is a `site-url`;
+    /// - when `og:type` is `article`, `article:published_time` from `date` or
+    ///   `published`, `article:modified_time` from `updated`, `article:author`
+    ///   from `author` and one `article:tag` per `tags` (or `keywords`) item.
+    pub fn tags(
+        &self,
+        metadata: &HashMap<String, String>,
+        language: Option<&str>,
+        url: Option<&str>,
+    ) -> Vec<Tag> {
+        let mut tags = Vec::new();
+        let get = |key: &str| {
+            metadata
+                .get(key)
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+        };
+
+        if self.locale && get("og:locale").is_none() {
+            if let Some(locale) = get("locale").or(language) {
+                tags.push(Tag::property("og:locale", &locale.replace('-', "_")));
+            }
+        }
+
+        if let (Some(url), None) = (url, get("og:url")) {
+            tags.push(Tag::property("og:url", url));
+        }
+
+        if self.article && get("og:type") == Some("article") {
+            let times = [
+                ("article:published_time", get("date").or(get("published"))),
+                ("article:modified_time", get("updated")),
+            ];
+            for (name, value) in times {
+                if let Some(time) = value.and_then(iso_time) {
+                    if get(name).is_none() {
+                        tags.push(Tag::property(name, &time));
+                    }
+                }
+            }
+            if let (Some(author), None) = (get("author"), get("article:author")) {
+                tags.push(Tag::property("article:author", author));
+            }
+            if get("article:tag").is_none() {
+                for tag in get("tags")
+                    .or(get("keywords"))
+                    .map(parse_list)
+                    .unwrap_or_default()
+                {
+                    tags.push(Tag::property("article:tag", &tag));
+                }
+            }
+        }
+        tags
+    }
+}
+
+/// Returns an ISO 8601 time: RFC 3339 timestamps as they are, other dates as
+/// `YYYY-MM-DD`.
+fn iso_time(value: &str) -> Option<String> {
+    if DateTime::parse_from_rfc3339(value).is_ok() {
+        return Some(value.to_string());
+    }
+    parse_date(value).map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::metadata;
+    use crate::tags::to_html;
+
+    #[test]
+    fn tags_add_locale_and_article_tags() {
+        let og = OpenGraph::from_config(&Table::new());
+        let article = metadata(&[
+            ("og:type", "article"),
+            ("date", "2024-06-01T10:00:00Z"),
+            ("updated", "2024-06-03"),
+            ("author", "Jane Doe"),
+            ("tags", "[rust, mdbook]"),
+        ]);
+
+        assert_eq!(
+            to_html(&og.tags(
+                &article,
+                Some("en-US"),
+                Some("https://example.com/docs/intro/")
+            )),
+            "<meta property=\"og:locale\" content=\"en_US\">\n\
+             <meta property=\"og:url\" content=\"https://example.com/docs/intro/\">\n\
+             <meta property=\"article:published_time\" content=\"2024-06-01T10:00:00Z\">\n\
+             <meta property=\"article:modified_time\" content=\"2024-06-03\">\n\
+             <meta property=\"article:author\" content=\"Jane Doe\">\n\
+             <meta property=\"article:tag\" content=\"rust\">\n\
+             <meta property=\"article:tag\" content=\"mdbook\">\n"
+        );
+    }
+
+    #[test]
+    fn infer_type_follows_the_chapter_kind() {
+        let og = OpenGraph::from_config(&toml::from_str("infer-type = true").unwrap());
+        let dated = metadata(&[("date", "2024-06-01")]);
+
+        assert_eq!(
+            og.infer_type(Path::new("intro.md"), true, &dated),
+            Some("book")
+        );
+        assert_eq!(
+            og.infer_type(Path::new("guide/index.md"), false, &dated),
+            Some("website")
+        );
+        assert_eq!(
+            og.infer_type(Path::new("blog/launch.md"), false, &dated),
+            Some("article")
+        );
+        assert_eq!(
+            og.infer_type(Path::new("guide/setup.md"), false, &HashMap::new()),
+            None
+        );
+        assert_eq!(
+            og.infer_type(
+                Path::new("blog/launch.md"),
+                false,
+                &metadata(&[("date", "2024-06-01"), ("og:type", "website")])
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn tags_leave_explicit_values_and_other_types() {
+        let og = OpenGraph::from_config(&Table::new());
+        let page = metadata(&[
+            ("og:type", "website"),
+            ("locale", "fr-FR"),
+            ("author", "Jane Doe"),
+        ]);
+        let explicit = metadata(&[("og:locale", "de_DE"), ("og:url", "https://example.com")]);
+
+        assert_eq!(
+            to_html(&og.tags(&page, Some("en"), None)),
+            "<meta property=\"og:locale\" content=\"fr_FR\">\n"
+        );
+        assert!(og
+            .tags(
+                &explicit,
+                Some("en"),
+                Some("https://example.com/intro.html")
+            )
+            .is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+use html_escape::encode_safe;
+use mdbook::Config;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use toml::value::Table;
+
+/// Inline style of the overlay, so it works with any theme.
+const OVERLAY_STYLE: &str = "position: fixed; right: 1em; bottom: 1em; z-index: 100; \
+    max-width: 40em; max-height: 50vh; overflow: auto; padding: 0.5em 1em; \
+    font-size: 0.8em; background: var(--bg); color: var(--fg); \
+    border: 1px solid var(--sidebar-bg); border-radius: 4px;";
+
+/// Shows each chapter's metadata and warnings in a collapsible box while
+/// writing (`[preprocessor.metadata.overlay]`).
+#[derive(Debug, Clone)]
+pub struct Overlay {
+    pub always: bool,
+}
+
+impl Overlay {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            always: table.get("when").and_then(|v| v.as_str()) == Some("always"),
+        }
+    }
+
+    /// Whether the overlay is added to this build: only under `mdbook serve`,
+    /// unless configured with `when = "always"`.
+    pub fn enabled(&self, config: &Config) -> bool {
+        self.always || is_serving(config)
+    }
+
+    /// Renders the overlay of a chapter as a raw HTML block.
+    pub fn render(&self, metadata: &HashMap<String, String>, warnings: &[String]) -> String {
+        let mut html = format!(
+            "<details class=\"metadata-overlay\" style=\"{}\">\n<summary>Metadata: {} {}, {} {}</summary>\n",
+            OVERLAY_STYLE,
+            metadata.len(),
+            plural(metadata.len(), "key", "keys"),
+            warnings.len(),
+            plural(warnings.len(), "warning", "warnings"),
+        );
+        if !metadata.is_empty() {
+            html.push_str("<table>\n");
+            for (key, value) in metadata.iter().collect::<BTreeMap<_, _>>() {
+                let _ = writeln!(
+                    html,
+                    "<tr><th>{}</th><td>{}</td></tr>",
+                    encode_safe(key),
+                    encode_safe(value)
+                );
+            }
+            html.push_str("</table>\n");
+        }
+        if !warnings.is_empty() {
+            html.push_str("<ul class=\"metadata-overlay-warnings\">\n");
+            for warning in warnings {
+                let _ = writeln!(html, "<li>⚠️ {}</li>", encode_safe(warning));
+            }
+            html.push_str("</ul>\n");
+        }
+        html.push_str("</details>\n");
+        html
+    }
+}
+
+/// `mdbook serve` sets the live reload endpoint of the html renderer
+/// (`livereload-url` before mdbook 0.4.19).
+pub fn is_serving(config: &Config) -> bool {
+    config.get("output.html.live-reload-endpoint").is_some()
+        || config.get("output.html.livereload-url").is_some()
+}
+
+fn plural<'a>(count: usize, one: &'a str, many: &'a str) -> &'a str {
+    if count == 1 {
+        one
+    } else {
+        many
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn enabled_only_when_serving() {
+        let overlay = Overlay::from_config(&Table::new());
+        let mut config = Config::default();
+
+        assert!(!overlay.enabled(&config));
+        config
+            .set("output.html.live-reload-endpoint", "__livereload")
+            .unwrap();
+        assert!(overlay.enabled(&config));
+
+        let always = Overlay::from_config(&toml::from_str("when = \"always\"").unwrap());
+        assert!(always.enabled(&Config::from_str("").unwrap()));
+    }
+
+    #[test]
+    fn render_lists_metadata_and_warnings() {
+        let overlay = Overlay::from_config(&Table::new());
+        let metadata = HashMap::from([
+            ("title".to_string(), "Intro".to_string()),
+            ("author".to_string(), "<Jane>".to_string()),
+        ]);
+
+        let html = overlay.render(
+            &metadata,
+            &["Improperly formatted line: 'oops'".to_string()],
+        );
+
+        assert!(html.contains("<summary>Metadata: 2 keys, 1 warning</summary>"));
+        assert!(html.contains(
+            "<table>\n<tr><th>author</th><td>&lt;Jane&gt;</td></tr>\n\
+             <tr><th>title</th><td>Intro</td></tr>\n</table>"
+        ));
+        assert!(html.contains("<li>⚠️ Improperly formatted line: &#x27;oops&#x27;</li>"));
+        assert!(!html.contains("\n\n"));
+    }
+}
@@ -0,0 +1,173 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use toml::value::Table;
+
+use crate::diff::{self, Snapshot};
+use crate::metadata::parse_list;
+use crate::sources::load_config;
+
+/// Output formats of the ownership export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OwnersFormat {
+    Codeowners,
+    Json,
+}
+
+impl OwnersFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "codeowners" => Some(OwnersFormat::Codeowners),
+            "json" => Some(OwnersFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Settings of the ownership export (`[preprocessor.metadata.owners]`).
+#[derive(Debug, Clone)]
+pub struct Owners {
+    pub keys: Vec<String>,
+    pub prefix: String,
+}
+
+impl Owners {
+    /// Reads the settings; `src` is the book's `src` directory, which chapter
+    /// patterns start with unless `prefix` is set.
+    pub fn from_config(table: &Table, src: &Path) -> Self {
+        let src = src.to_string_lossy().replace('\\', "/");
+        Self {
+            keys: table
+                .get("keys")
+                .and_then(|v| v.as_array())
+                .map(|keys| {
+                    keys.iter()
+                        .filter_map(|k| k.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_else(|| vec!["owner".into(), "team".into()]),
+            prefix: table
+                .get("prefix")
+                .and_then(|v| v.as_str())
+                .map(String::from)
+                .unwrap_or_else(|| format!("/{}/", src.trim_matches('/'))),
+        }
+    }
+
+    /// Reads the export settings of a book.
+    pub fn load(book_dir: &Path) -> Result<Self> {
+        let (config, _) = load_config(book_dir)?;
+        let table = config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("owners"))
+            .and_then(|v| v.as_table())
+            .cloned()
+            .unwrap_or_default();
+        Ok(Self::from_config(&table, &config.book.src))
+    }
+
+    /// Maps the path pattern of every chapter with owners to the values of
+    /// its `keys`, in order and without duplicates. Chapters without owners
+    /// are left out.
+    pub fn mapping(&self, book: &Snapshot) -> BTreeMap<String, Vec<String>> {
+        book.iter()
+            .filter_map(|(chapter, metadata)| {
+                let mut owners: Vec<String> = Vec::new();
+                for value in self.keys.iter().filter_map(|key| metadata.get(key)) {
+                    for owner in parse_list(value) {
+                        if !owners.contains(&owner) {
+                            owners.push(owner);
+                        }
+                    }
+                }
+                (!owners.is_empty()).then(|| (format!("{}{}", self.prefix, chapter), owners))
+            })
+            .collect()
+    }
+
+    /// Reads the chapters of a book on disk and maps them to their owners.
+    pub fn rows(&self, book_dir: &Path) -> Result<BTreeMap<String, Vec<String>>> {
+        Ok(self.mapping(&diff::from_dir(book_dir)?))
+    }
+}
+
+/// Renders the mapping in a format. CODEOWNERS owners without an `@` are
+/// GitHub users or teams, and get one.
+pub fn render(mapping: &BTreeMap<String, Vec<String>>, format: OwnersFormat) -> Result<String> {
+    let mut out = String::new();
+    match format {
+        OwnersFormat::Json => {
+            out = serde_json::to_string_pretty(mapping)?;
+            out.push('\n');
+        }
+        OwnersFormat::Codeowners => {
+            out.push_str("# Generated by mdbook-metadata owners from chapter metadata\n");
+            for (pattern, owners) in mapping {
+                let owners: Vec<String> = owners
+                    .iter()
+                    .map(|owner| {
+                        if owner.contains('@') {
+                            owner.clone()
+                        } else {
+                            format!("@{}", owner)
+                        }
+                    })
+                    .collect();
+                let _ = writeln!(out, "{} {}", pattern.replace(' ', "\\ "), owners.join(" "));
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chapters_are_mapped_to_their_owners() {
+        let owners = Owners::from_config(&Table::new(), Path::new("src"));
+        let book: Snapshot = [
+            (
+                "guide/intro.md",
+                vec![("owner", "docs"), ("team", "acme/docs")],
+            ),
+            (
+                "api/my page.md",
+                vec![("owner", "[jane, docs@example.com]")],
+            ),
+            ("faq.md", vec![("title", "FAQ")]),
+        ]
+        .into_iter()
+        .map(|(chapter, metadata)| {
+            (
+                chapter.to_string(),
+                metadata
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            )
+        })
+        .collect();
+
+        let mapping = owners.mapping(&book);
+
+        assert_eq!(
+            render(&mapping, OwnersFormat::Codeowners).unwrap(),
+            "# Generated by mdbook-metadata owners from chapter metadata\n\
+             /src/api/my\\ page.md @jane docs@example.com\n\
+             /src/guide/intro.md @docs @acme/docs\n"
+        );
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(
+                &render(&mapping, OwnersFormat::Json).unwrap()
+            )
+            .unwrap(),
+            serde_json::json!({
+                "/src/api/my page.md": ["jane", "docs@example.com"],
+                "/src/guide/intro.md": ["docs", "acme/docs"]
+            })
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use mdbook::book::{Book, BookItem, Chapter};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use toml::value::Table;
+
+use crate::aggregate::ChapterEntry;
+
+/// Inherits part-level metadata from the intro chapter of each part
+/// (`[preprocessor.metadata.parts]`).
+#[derive(Debug, Clone)]
+pub struct Parts {
+    pub keys: Vec<String>,
+    pub intro_file: String,
+}
+
+/// The chapters of a single part, in book order.
+#[derive(Debug, Default)]
+struct Part {
+    intro: Option<PathBuf>,
+    chapters: Vec<PathBuf>,
+}
+
+impl Parts {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            keys: table
+                .get("keys")
+                .and_then(|v| v.as_array())
+                .map(|keys| {
+                    keys.iter()
+                        .filter_map(|k| k.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_else(|| vec!["section".into(), "maintainer".into()]),
+            intro_file: table
+                .get("intro-file")
+                .and_then(|v| v.as_str())
+                .unwrap_or("_part.md")
+                .to_string(),
+        }
+    }
+
+    /// Copies the inherited keys of each part's intro chapter into the other
+    /// chapters of the part, unless they set the key themselves. The intro is
+    /// the part's `_part.md` chapter, or else its first chapter. Chapters before
+    /// the first part title don't belong to any part.
+    pub fn apply(&self, book: &Book, chapters: &mut [ChapterEntry]) {
+        let index_of: HashMap<PathBuf, usize> = chapters
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (e.path.clone(), i))
+            .collect();
+
+        for part in self.parts(book) {
+            let Some(intro) = part.intro.and_then(|p| index_of.get(&p).copied()) else {
+                continue;
+            };
+            let inherited: Vec<(String, String)> = chapters[intro]
+                .metadata
+                .iter()
+                .filter(|(k, _)| self.inherits(k))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+
+            for path in &part.chapters {
+                let Some(&i) = index_of.get(path) else {
+                    continue;
+                };
+                for (key, value) in &inherited {
+                    chapters[i]
+                        .metadata
+                        .entry(key.clone())
+                        .or_insert_with(|| value.clone());
+                }
+            }
+        }
+    }
+
+    fn inherits(&self, key: &str) -> bool {
+        self.keys
+            .iter()
+            .any(|k| k == key || (k == "*" && key != "title"))
+    }
+
+    fn parts(&self, book: &Book) -> Vec<Part> {
+        let mut parts: Vec<Part> = Vec::new();
+        for item in &book.sections {
+            match item {
+                BookItem::PartTitle(_) => parts.push(Part::default()),
+                BookItem::Chapter(chap) => {
+                    if let Some(part) = parts.last_mut() {
+                        if part.intro.is_none() {
+                            part.intro = chap.path.clone();
+                        }
+                        collect_paths(chap, &mut part.chapters);
+                    }
+                }
+                BookItem::Separator => {}
+            }
+        }
+
+        // A dedicated intro file takes precedence over the first chapter
+        for part in &mut parts {
+            if let Some(intro) = part
+                .chapters
+                .iter()
+                .find(|p| p.file_name().and_then(|f| f.to_str()) == Some(self.intro_file.as_str()))
+            {
+                part.intro = Some(intro.clone());
+            }
+        }
+        parts
+    }
+}
+
+fn collect_paths(chapter: &Chapter, paths: &mut Vec<PathBuf>) {
+    if let Some(path) = &chapter.path {
+        paths.push(path.clone());
+    }
+    for item in &chapter.sub_items {
+        if let BookItem::Chapter(sub) = item {
+            collect_paths(sub, paths);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::entry;
+
+    fn chapter(path: &str) -> Chapter {
+        Chapter::new(path, String::new(), path, Vec::new())
+    }
+
+    #[test]
+    fn apply_inherits_from_part_intro() {
+        let mut book = Book::new();
+        book.push_item(chapter("prefix.md"));
+        book.push_item(BookItem::PartTitle("Guides".to_string()));
+        let mut intro = chapter("guides/intro.md");
+        intro
+            .sub_items
+            .push(BookItem::Chapter(chapter("guides/nested.md")));
+        book.push_item(intro);
+        book.push_item(chapter("guides/other.md"));
+        book.push_item(BookItem::PartTitle("Reference".to_string()));
+        book.push_item(chapter("reference/api.md"));
+        book.push_item(chapter("reference/_part.md"));
+
+        let mut chapters = vec![
+            entry("prefix.md", &[]),
+            entry(
+                "guides/intro.md",
+                &[
+                    ("title", "Intro"),
+                    ("section", "Guides"),
+                    ("maintainer", "docs"),
+                ],
+            ),
+            entry("guides/nested.md", &[]),
+            entry("guides/other.md", &[("maintainer", "platform")]),
+            entry("reference/api.md", &[]),
+            entry("reference/_part.md", &[("section", "Reference")]),
+        ];
+
+        Parts::from_config(&Table::new()).apply(&book, &mut chapters);
+
+        let value = |i: usize, key: &str| chapters[i].metadata.get(key).cloned();
+        assert_eq!(value(0, "section"), None);
+        assert_eq!(value(2, "section").as_deref(), Some("Guides"));
+        assert_eq!(value(2, "maintainer").as_deref(), Some("docs"));
+        assert_eq!(value(2, "title"), None);
+        assert_eq!(value(3, "maintainer").as_deref(), Some("platform"));
+        assert_eq!(value(4, "section").as_deref(), Some("Reference"));
+    }
+}
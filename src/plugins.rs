@@ -0,0 +1,214 @@
+#[cfg(not(feature = "wasm-plugins"))]
+use anyhow::bail;
+use anyhow::Result;
+use log::warn;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+use crate::tags;
+
+/// Fuel given to each call of a plugin, bounding runaway modules.
+const DEFAULT_FUEL: u64 = 100_000_000;
+
+/// A WebAssembly module emitting tags for a chapter
+/// (`[[preprocessor.metadata.plugins]]`). Plugins registered for `keys` receive
+/// only those keys, which are then no longer emitted as `<meta>` tags; plugins
+/// without keys receive all of the chapter's metadata.
+#[derive(Debug, Clone)]
+pub struct Plugin {
+    pub path: PathBuf,
+    pub keys: Vec<String>,
+    #[cfg_attr(not(feature = "wasm-plugins"), allow(dead_code))]
+    pub fuel: u64,
+}
+
+impl Plugin {
+    /// Reads a plugin, warning about and skipping plugins without a path.
+    pub fn from_config(value: &Value) -> Option<Self> {
+        let table = value.as_table()?;
+        let Some(path) = table.get("path").and_then(|v| v.as_str()) else {
+            warn!("Ignoring a metadata plugin without a path");
+            return None;
+        };
+        Some(Self {
+            path: path.into(),
+            keys: table
+                .get("keys")
+                .and_then(|v| v.as_array())
+                .map(|keys| {
+                    keys.iter()
+                        .filter_map(|k| k.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            fuel: table
+                .get("fuel")
+                .and_then(|v| v.as_integer())
+                .and_then(|v| u64::try_from(v).ok())
+                .unwrap_or(DEFAULT_FUEL),
+        })
+    }
+
+    /// Takes the metadata passed to the plugin: the registered keys, removed
+    /// from `metadata`, or a copy of all of it for global plugins.
+    pub fn take_input(&self, metadata: &mut HashMap<String, String>) -> HashMap<String, String> {
+        if self.keys.is_empty() {
+            return metadata.clone();
+        }
+        let keys: Vec<String> = metadata
+            .keys()
+            .filter(|k| tags::matches_key(&self.keys, k))
+            .cloned()
+            .collect();
+        keys.into_iter()
+            .filter_map(|k| metadata.remove_entry(&k))
+            .collect()
+    }
+
+    /// Compiles the module, whose path is relative to the book root.
+    #[cfg(feature = "wasm-plugins")]
+    pub fn load(&self, root: &Path) -> Result<Emitter> {
+        use anyhow::Context;
+
+        let path = root.join(&self.path);
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        let engine = wasmtime::Engine::new(&config)?;
+        let module = wasmtime::Module::from_file(&engine, &path)
+            .with_context(|| format!("Failed to load plugin '{}'", path.display()))?;
+        Ok(Emitter {
+            name: self.path.display().to_string(),
+            engine,
+            module,
+            fuel: self.fuel,
+        })
+    }
+
+    #[cfg(not(feature = "wasm-plugins"))]
+    pub fn load(&self, _root: &Path) -> Result<Emitter> {
+        bail!(
+            "Failed to load plugin '{}': mdbook-metadata was built without the `wasm-plugins` feature",
+            self.path.display()
+        )
+    }
+}
+
+/// Reads every plugin of the `plugins` array.
+pub fn plugins_from_config(value: &Value) -> Vec<Plugin> {
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Plugin::from_config)
+        .collect()
+}
+
+/// A compiled plugin. Each call runs in a fresh instance, so plugins can't
+/// keep state between chapters.
+///
+/// The module exports its `memory`, an `alloc(len: i32) -> i32` function and
+/// an `emit(ptr: i32, len: i32) -> i64` function. `emit` receives the metadata
+/// as a JSON object of strings and returns the HTML to inject, its pointer in
+/// the high 32 bits and its length in the low 32 bits.
+#[cfg(feature = "wasm-plugins")]
+pub struct Emitter {
+    pub name: String,
+    engine: wasmtime::Engine,
+    module: wasmtime::Module,
+    fuel: u64,
+}
+
+#[cfg(not(feature = "wasm-plugins"))]
+pub struct Emitter {
+    pub name: String,
+}
+
+impl Emitter {
+    #[cfg(feature = "wasm-plugins")]
+    pub fn emit(&self, metadata: &HashMap<String, String>) -> Result<String> {
+        use anyhow::{anyhow, Context};
+        use wasmtime::{Instance, Store};
+
+        let input = serde_json::to_vec(metadata)?;
+        let mut store = Store::new(&self.engine, ());
+        store.set_fuel(self.fuel)?;
+        let instance = Instance::new(&mut store, &self.module, &[])
+            .with_context(|| format!("Failed to instantiate plugin '{}'", self.name))?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow!("Plugin '{}' doesn't export its memory", self.name))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        let emit = instance.get_typed_func::<(i32, i32), i64>(&mut store, "emit")?;
+
+        let len = i32::try_from(input.len())?;
+        let ptr = alloc.call(&mut store, len)?;
+        memory.write(&mut store, ptr as u32 as usize, &input)?;
+        let packed = emit
+            .call(&mut store, (ptr, len))
+            .with_context(|| format!("Plugin '{}' failed", self.name))? as u64;
+
+        let (ptr, len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        let mut output = vec![0; len];
+        memory
+            .read(&store, ptr, &mut output)
+            .with_context(|| format!("Plugin '{}' returned an invalid pointer", self.name))?;
+        String::from_utf8(output)
+            .with_context(|| format!("Plugin '{}' returned invalid UTF-8", self.name))
+    }
+
+    #[cfg(not(feature = "wasm-plugins"))]
+    pub fn emit(&self, _metadata: &HashMap<String, String>) -> Result<String> {
+        unreachable!("plugins can't be loaded without the `wasm-plugins` feature")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn take_input_removes_registered_keys() {
+        let plugin = Plugin::from_config(
+            &toml::from_str("path = 'badges.wasm'\nkeys = ['badge:*']").unwrap(),
+        )
+        .unwrap();
+        let mut metadata = HashMap::from([
+            ("badge:status".to_string(), "beta".to_string()),
+            ("title".to_string(), "Intro".to_string()),
+        ]);
+
+        let input = plugin.take_input(&mut metadata);
+
+        assert_eq!(input.keys().collect::<Vec<_>>(), vec!["badge:status"]);
+        assert_eq!(metadata.keys().collect::<Vec<_>>(), vec!["title"]);
+    }
+
+    #[cfg(feature = "wasm-plugins")]
+    #[test]
+    fn emit_runs_the_module() {
+        // Echoes its input back
+        let dir = std::env::temp_dir().join("mdbook-metadata-plugins");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("echo.wat"),
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) i32.const 16)
+                (func (export "emit") (param i32 i32) (result i64)
+                    (i64.or
+                        (i64.shl (i64.extend_i32_u (local.get 0)) (i64.const 32))
+                        (i64.extend_i32_u (local.get 1)))))"#,
+        )
+        .unwrap();
+        let plugin = Plugin::from_config(&toml::from_str("path = 'echo.wat'").unwrap()).unwrap();
+
+        let output = plugin
+            .load(&dir)
+            .unwrap()
+            .emit(&HashMap::from([("badge".to_string(), "beta".to_string())]))
+            .unwrap();
+
+        assert_eq!(output, r#"{"badge":"beta"}"#);
+    }
+}
@@ -0,0 +1,59 @@
+use mdbook::book::Chapter;
+use std::collections::HashMap;
+
+/// Adds a chapter's section number (`4.2`, for numbered chapters), depth and
+/// 1-based `position` in the flattened book to its metadata. Keys the chapter
+/// sets itself win.
+pub fn add_position_keys(
+    metadata: &mut HashMap<String, String>,
+    chapter: &Chapter,
+    position: usize,
+) {
+    let depth = match &chapter.number {
+        Some(number) => number.len(),
+        None => chapter.parent_names.len() + 1,
+    };
+    let mut values = vec![
+        ("chapter-depth", depth.to_string()),
+        ("chapter-position", position.to_string()),
+    ];
+    if let Some(number) = &chapter.number {
+        let number = number
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(".");
+        values.insert(0, ("chapter-number", number));
+    }
+    for (key, value) in values {
+        metadata.entry(key.to_string()).or_insert(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mdbook::book::SectionNumber;
+
+    #[test]
+    fn numbered_chapters_get_their_section_number() {
+        let mut chapter = Chapter::new("Retries", String::new(), "guide/retries.md", Vec::new());
+        chapter.number = Some(SectionNumber(vec![4, 2]));
+        let mut metadata = HashMap::from([("chapter-position".to_string(), "1".to_string())]);
+
+        add_position_keys(&mut metadata, &chapter, 9);
+
+        assert_eq!(metadata["chapter-number"], "4.2");
+        assert_eq!(metadata["chapter-depth"], "2");
+        assert_eq!(metadata["chapter-position"], "1");
+
+        let mut metadata = HashMap::new();
+        add_position_keys(
+            &mut metadata,
+            &Chapter::new("Intro", String::new(), "intro.md", Vec::new()),
+            1,
+        );
+        assert_eq!(metadata.get("chapter-number"), None);
+        assert_eq!(metadata["chapter-depth"], "1");
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt::Write;
+use toml::value::Table;
+
+use crate::aggregate::{relative_link, resolve_references, ChapterEntry};
+use crate::metadata::MetadataError;
+
+/// Renders the `prerequisites` key as a "Before you read this" box
+/// (`[preprocessor.metadata.prerequisites]`).
+#[derive(Debug, Clone)]
+pub struct Prerequisites {
+    pub title: String,
+}
+
+impl Prerequisites {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            title: table
+                .get("title")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Before you read this")
+                .to_string(),
+        }
+    }
+
+    /// Returns the box for `entry`, or an error for every prerequisite that
+    /// doesn't resolve to a chapter of the book.
+    pub fn render(
+        &self,
+        entry: &ChapterEntry,
+        chapters: &[ChapterEntry],
+    ) -> Result<Option<String>, Vec<MetadataError>> {
+        let targets = resolve_references(entry, "prerequisites", chapters)?;
+        if targets.is_empty() {
+            return Ok(None);
+        }
+
+        let mut block = format!("> **{}**\n>\n", self.title);
+        for target in targets {
+            let _ = writeln!(
+                block,
+                "> - [{}]({})",
+                target.title(),
+                relative_link(&entry.path, &target.path)
+            );
+        }
+        Ok(Some(block))
+    }
+}
+
+/// Inserts a markdown block right after the chapter's leading `# ` heading, or
+/// at the top when the chapter doesn't start with one.
+pub fn insert_after_title(content: &str, block: &str) -> String {
+    let trimmed = content.trim_start();
+    if trimmed.starts_with("# ") {
+        let offset = content.len() - trimmed.len();
+        let end = trimmed
+            .find('\n')
+            .map(|i| offset + i + 1)
+            .unwrap_or(content.len());
+        let (heading, rest) = content.split_at(end);
+        let heading = heading.trim_end_matches('\n');
+        format!("{}\n\n{}\n{}", heading, block, rest)
+    } else {
+        format!("{}\n{}", block, content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::entry;
+
+    #[test]
+    fn render_resolves_prerequisites() {
+        let prerequisites = Prerequisites::from_config(&Table::new());
+        let chapters = vec![
+            entry("basics/install.md", &[("title", "Installing")]),
+            entry(
+                "advanced/tuning.md",
+                &[("prerequisites", "[basics/install]")],
+            ),
+        ];
+
+        let block = prerequisites.render(&chapters[1], &chapters).unwrap();
+
+        assert_eq!(
+            block.as_deref(),
+            Some("> **Before you read this**\n>\n> - [Installing](../basics/install.md)\n")
+        );
+        assert_eq!(prerequisites.render(&chapters[0], &chapters).unwrap(), None);
+    }
+
+    #[test]
+    fn render_reports_unresolved_prerequisites() {
+        let prerequisites = Prerequisites::from_config(&Table::new());
+        let chapters = vec![entry("tuning.md", &[("prerequisites", "missing, gone.md")])];
+
+        let errors = prerequisites.render(&chapters[0], &chapters).unwrap_err();
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0].to_string(),
+            "Chapter 'tuning.md' references unknown chapter 'missing' in 'prerequisites'"
+        );
+    }
+
+    #[test]
+    fn insert_after_title_places_block_below_heading() {
+        assert_eq!(
+            insert_after_title("# Title\nBody", "> box\n"),
+            "# Title\n\n> box\n\nBody"
+        );
+        assert_eq!(insert_after_title("Body", "> box\n"), "> box\n\nBody");
+    }
+}
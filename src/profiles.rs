@@ -0,0 +1,150 @@
+use globset::{GlobBuilder, GlobMatcher};
+use log::warn;
+use std::collections::HashMap;
+use std::path::Path;
+use toml::Value;
+
+use crate::metadata::MetadataError;
+use crate::versions::META_VERSION_KEY;
+
+/// The keys chapters matching a glob must have, and may have
+/// (`[[preprocessor.metadata.profiles]]`).
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub pattern: String,
+    pub glob: GlobMatcher,
+    pub required: Vec<String>,
+    pub allowed: Option<Vec<String>>,
+}
+
+impl Profile {
+    /// Reads a profile, warning about and skipping profiles without a valid
+    /// glob.
+    pub fn from_config(value: &Value) -> Option<Self> {
+        let table = value.as_table()?;
+        let Some(pattern) = table.get("glob").and_then(|v| v.as_str()) else {
+            warn!("Ignoring a metadata profile without a glob");
+            return None;
+        };
+        let glob = match GlobBuilder::new(pattern).literal_separator(true).build() {
+            Ok(glob) => glob.compile_matcher(),
+            Err(e) => {
+                warn!("Ignoring metadata profile '{}': {}", pattern, e);
+                return None;
+            }
+        };
+        let keys = |name: &str| -> Option<Vec<String>> {
+            table.get(name).and_then(|v| v.as_array()).map(|keys| {
+                keys.iter()
+                    .filter_map(|k| k.as_str().map(String::from))
+                    .collect()
+            })
+        };
+        Some(Self {
+            pattern: pattern.to_string(),
+            glob,
+            required: keys("required").unwrap_or_default(),
+            allowed: keys("allowed"),
+        })
+    }
+
+    /// Returns the problems of a chapter's metadata: missing required keys,
+    /// and keys that are neither required nor allowed when `allowed` is set.
+    pub fn check(&self, metadata: &HashMap<String, String>) -> Vec<MetadataError> {
+        let mut problems: Vec<String> = self
+            .required
+            .iter()
+            .filter(|key| metadata.get(*key).map_or(true, |v| v.trim().is_empty()))
+            .map(|key| format!("missing required key '{}'", key))
+            .collect();
+        if let Some(allowed) = &self.allowed {
+            let mut unknown: Vec<&String> = metadata
+                .keys()
+                .filter(|key| {
+                    !allowed.contains(key)
+                        && !self.required.contains(key)
+                        && key.as_str() != META_VERSION_KEY
+                })
+                .collect();
+            unknown.sort();
+            problems.extend(
+                unknown
+                    .into_iter()
+                    .map(|key| format!("key '{}' isn't allowed", key)),
+            );
+        }
+        problems
+            .into_iter()
+            .map(|message| MetadataError::Profile {
+                profile: self.pattern.clone(),
+                message,
+            })
+            .collect()
+    }
+}
+
+/// Reads the `profiles` array, skipping invalid profiles.
+pub fn profiles_from_config(value: &Value) -> Vec<Profile> {
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Profile::from_config)
+        .collect()
+}
+
+/// Checks a chapter's metadata against every profile matching its `path`
+/// (relative to the book root).
+pub fn check(
+    profiles: &[Profile],
+    path: &Path,
+    metadata: &HashMap<String, String>,
+) -> Vec<MetadataError> {
+    profiles
+        .iter()
+        .filter(|profile| profile.glob.is_match(path))
+        .flat_map(|profile| profile.check(metadata))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_check_the_chapters_they_match() {
+        let profiles = profiles_from_config(
+            &toml::from_str::<toml::value::Table>(
+                r#"
+                [[profiles]]
+                glob = "src/blog/**"
+                required = ["title", "date"]
+                allowed = ["tags"]
+
+                [[profiles]]
+                glob = "src/api/**"
+                required = ["since"]
+                "#,
+            )
+            .unwrap()["profiles"],
+        );
+        let metadata = HashMap::from([
+            ("title".to_string(), "Launch".to_string()),
+            ("summary".to_string(), "We launched".to_string()),
+        ]);
+
+        let errors: Vec<String> = check(&profiles, Path::new("src/blog/launch.md"), &metadata)
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+
+        assert_eq!(
+            errors,
+            [
+                "Chapter doesn't follow profile 'src/blog/**': missing required key 'date'",
+                "Chapter doesn't follow profile 'src/blog/**': key 'summary' isn't allowed",
+            ]
+        );
+        assert!(check(&profiles, Path::new("src/guide/intro.md"), &metadata).is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+use anyhow::{bail, Context, Result};
+use log::{info, warn};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use toml::value::Table;
+
+/// Environment variable skipping the `url` of remote sources, like `--offline`.
+pub const OFFLINE_VAR: &str = "MDBOOK_METADATA_OFFLINE";
+
+/// Which metadata wins when a chapter and the remote source set the same key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precedence {
+    Chapter,
+    Remote,
+}
+
+/// Additional metadata supplied by a JSON object keyed by chapter path or slug
+/// (`[preprocessor.metadata.remote]`), fetched from a `url` or read from a
+/// local `file`. Fetched data is cached on disk.
+#[derive(Debug, Clone)]
+pub struct Remote {
+    pub url: Option<String>,
+    pub file: Option<PathBuf>,
+    pub cache: PathBuf,
+    pub max_age: Duration,
+    pub timeout: Duration,
+    pub precedence: Precedence,
+    pub offline: bool,
+}
+
+/// The loaded remote metadata.
+#[derive(Debug, Clone, Default)]
+pub struct RemoteData {
+    entries: Map<String, Value>,
+}
+
+impl Remote {
+    pub fn from_config(table: &Table) -> Self {
+        let seconds = |key: &str, default: u64| {
+            let seconds = table
+                .get(key)
+                .and_then(|v| v.as_integer())
+                .and_then(|v| u64::try_from(v).ok())
+                .unwrap_or(default);
+            Duration::from_secs(seconds)
+        };
+        let precedence = match table.get("precedence").and_then(|v| v.as_str()) {
+            Some("remote") => Precedence::Remote,
+            Some("chapter") | None => Precedence::Chapter,
+            Some(other) => {
+                warn!("Unknown remote precedence '{}', chapters win", other);
+                Precedence::Chapter
+            }
+        };
+        Self {
+            url: table.get("url").and_then(|v| v.as_str()).map(String::from),
+            file: table
+                .get("file")
+                .and_then(|v| v.as_str())
+                .map(PathBuf::from),
+            cache: table
+                .get("cache")
+                .and_then(|v| v.as_str())
+                .unwrap_or(".metadata-cache/remote.json")
+                .into(),
+            max_age: seconds("max-age", 3600),
+            timeout: seconds("timeout", 10),
+            precedence,
+            offline: table
+                .get("offline")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+                || std::env::var_os(OFFLINE_VAR).is_some_and(|v| !v.is_empty() && v != "0"),
+        }
+    }
+
+    /// Loads the metadata, relative paths starting from the book root. A `url`
+    /// is fetched unless the cache is younger than `max-age`; offline, or when
+    /// fetching fails, the cache is used whatever its age.
+    pub fn load(&self, root: &Path) -> Result<RemoteData> {
+        let body = match (&self.url, &self.file) {
+            (Some(url), _) => self.fetch(url, &root.join(&self.cache))?,
+            (None, Some(file)) => fs::read_to_string(root.join(file))
+                .with_context(|| format!("Failed to read '{}'", file.display()))?,
+            (None, None) => bail!("The remote metadata source needs a url or a file"),
+        };
+        match serde_json::from_str(&body).context("Invalid remote metadata")? {
+            Value::Object(entries) => Ok(RemoteData { entries }),
+            _ => bail!("Invalid remote metadata: expected a JSON object"),
+        }
+    }
+
+    fn fetch(&self, url: &str, cache: &Path) -> Result<String> {
+        let cached = || {
+            fs::read_to_string(cache)
+                .with_context(|| format!("No cached remote metadata in '{}'", cache.display()))
+        };
+        if self.offline {
+            info!("Offline, using the cached remote metadata");
+            return cached();
+        }
+        let age = fs::metadata(cache)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+        if age.is_some_and(|age| age < self.max_age) {
+            return cached();
+        }
+
+        let response = ureq::get(url)
+            .timeout(self.timeout)
+            .call()
+            .map_err(anyhow::Error::from)
+            .and_then(|response| Ok(response.into_string()?));
+        match response {
+            Ok(body) => {
+                if let Some(dir) = cache.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                fs::write(cache, &body)
+                    .with_context(|| format!("Failed to write '{}'", cache.display()))?;
+                Ok(body)
+            }
+            Err(e) => {
+                warn!("Failed to fetch '{}': {:#}, using the cache", url, e);
+                cached()
+            }
+        }
+    }
+}
+
+impl RemoteData {
+    /// Returns the remote metadata of a chapter, found by its path (`guide/intro.md`,
+    /// with or without the extension) or else by its `slug`. Non-string values
+    /// are kept as their JSON text.
+    pub fn lookup(&self, path: &Path, slug: Option<&str>) -> HashMap<String, String> {
+        let path = path.to_string_lossy().replace('\\', "/");
+        let keys = [
+            Some(path.as_str()),
+            path.strip_suffix(".md"),
+            slug.map(str::trim),
+        ];
+        let Some(Value::Object(values)) =
+            keys.into_iter().flatten().find_map(|k| self.entries.get(k))
+        else {
+            return HashMap::new();
+        };
+        values
+            .iter()
+            .filter(|(_, value)| !value.is_null())
+            .map(|(key, value)| match value {
+                Value::String(s) => (key.clone(), s.clone()),
+                value => (key.clone(), value.to_string()),
+            })
+            .collect()
+    }
+}
+
+/// Merges the remote metadata of a chapter into its metadata, following the
+/// precedence.
+pub fn merge(
+    metadata: &mut HashMap<String, String>,
+    remote: HashMap<String, String>,
+    precedence: Precedence,
+) {
+    for (key, value) in remote {
+        match precedence {
+            Precedence::Chapter => {
+                metadata.entry(key).or_insert(value);
+            }
+            Precedence::Remote => {
+                metadata.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_reads_the_cache_offline() {
+        let dir =
+            std::env::temp_dir().join(format!("mdbook-metadata-remote-{}", std::process::id()));
+        fs::create_dir_all(dir.join(".metadata-cache")).unwrap();
+        fs::write(
+            dir.join(".metadata-cache/remote.json"),
+            r#"{"guide/intro.md": {"owner": "platform", "tier": 1}, "faq": {"owner": "support"}}"#,
+        )
+        .unwrap();
+        let remote = Remote::from_config(
+            &toml::from_str("url = \"https://cms.invalid/metadata.json\"\noffline = true").unwrap(),
+        );
+
+        let data = remote.load(&dir).unwrap();
+
+        assert_eq!(
+            data.lookup(Path::new("guide/intro.md"), None),
+            HashMap::from([
+                ("owner".to_string(), "platform".to_string()),
+                ("tier".to_string(), "1".to_string()),
+            ])
+        );
+        let mut metadata = HashMap::from([("owner".to_string(), "docs".to_string())]);
+        merge(
+            &mut metadata,
+            data.lookup(Path::new("help.md"), Some("faq")),
+            Precedence::Chapter,
+        );
+        assert_eq!(metadata["owner"], "docs");
+        merge(
+            &mut metadata,
+            data.lookup(Path::new("help.md"), Some("faq")),
+            Precedence::Remote,
+        );
+        assert_eq!(metadata["owner"], "support");
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(remote.load(&dir).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::fmt::{self, Write};
+use std::fs;
+use std::path::Path;
+use toml::value::Table;
+
+use crate::dates::parse_date;
+use crate::metadata::parse_metadata;
+use crate::sources::{chapter_files, chapter_name, load_config};
+
+/// Output formats of the ownership report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Table,
+    Json,
+    Markdown,
+}
+
+impl ReportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "table" => Some(ReportFormat::Table),
+            "json" => Some(ReportFormat::Json),
+            "markdown" => Some(ReportFormat::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// Why a chapter needs attention.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "problem", rename_all = "kebab-case")]
+pub enum Problem {
+    Unowned,
+    NeverReviewed,
+    Overdue { days: i64 },
+    InvalidDate { value: String },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Unowned => write!(f, "no owner"),
+            Problem::NeverReviewed => write!(f, "never reviewed"),
+            Problem::Overdue { days } => write!(f, "review overdue by {} days", days),
+            Problem::InvalidDate { value } => write!(f, "invalid review date '{}'", value),
+        }
+    }
+}
+
+/// A chapter that is unowned or overdue for review.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReportRow {
+    pub chapter: String,
+    pub owner: Option<String>,
+    pub reviewed: Option<String>,
+    pub problems: Vec<Problem>,
+}
+
+/// Settings of the ownership report (`[preprocessor.metadata.report]`).
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub owner_key: String,
+    pub reviewed_key: String,
+    pub max_age: i64,
+}
+
+impl Report {
+    pub fn from_config(table: &Table) -> Self {
+        let key = |name: &str, default: &str| {
+            table
+                .get(name)
+                .and_then(|v| v.as_str())
+                .unwrap_or(default)
+                .to_string()
+        };
+        Self {
+            owner_key: key("owner-key", "owner"),
+            reviewed_key: key("reviewed-key", "reviewed"),
+            max_age: table
+                .get("max-age")
+                .and_then(|v| v.as_integer())
+                .unwrap_or(180),
+        }
+    }
+
+    /// Reads the report settings of a book.
+    pub fn load(book_dir: &Path) -> Result<Self> {
+        let (config, _) = load_config(book_dir)?;
+        let table = config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("report"))
+            .and_then(|v| v.as_table())
+            .cloned()
+            .unwrap_or_default();
+        Ok(Self::from_config(&table))
+    }
+
+    /// Returns the problems of a chapter's metadata, as of `today`. Reviews
+    /// older than `max-age` days are overdue.
+    pub fn problems(
+        &self,
+        owner: Option<&str>,
+        reviewed: Option<&str>,
+        today: NaiveDate,
+    ) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        if owner.map_or(true, |o| o.trim().is_empty()) {
+            problems.push(Problem::Unowned);
+        }
+        match reviewed.map(|r| (r, parse_date(r))) {
+            None => problems.push(Problem::NeverReviewed),
+            Some((value, None)) => problems.push(Problem::InvalidDate {
+                value: value.trim().to_string(),
+            }),
+            Some((_, Some(date))) => {
+                let days = (today - date).num_days() - self.max_age;
+                if days > 0 {
+                    problems.push(Problem::Overdue { days });
+                }
+            }
+        }
+        problems
+    }
+
+    /// Lists the chapters of a book that are unowned or overdue for review.
+    pub fn rows(&self, book_dir: &Path, today: NaiveDate) -> Result<Vec<ReportRow>> {
+        let (_, src_dir) = load_config(book_dir)?;
+        let mut rows = Vec::new();
+        for path in chapter_files(&src_dir) {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read '{}'", path.display()))?;
+            let metadata = parse_metadata(&content, true)
+                .map(|(metadata, _)| metadata)
+                .unwrap_or_default();
+            let owner = metadata.get(&self.owner_key).cloned();
+            let reviewed = metadata.get(&self.reviewed_key).cloned();
+            let problems = self.problems(owner.as_deref(), reviewed.as_deref(), today);
+            if !problems.is_empty() {
+                rows.push(ReportRow {
+                    chapter: chapter_name(&src_dir, &path),
+                    owner,
+                    reviewed,
+                    problems,
+                });
+            }
+        }
+        Ok(rows)
+    }
+}
+
+/// Renders the report rows in a format.
+pub fn render(rows: &[ReportRow], format: ReportFormat) -> Result<String> {
+    let cells = |row: &ReportRow| {
+        [
+            row.chapter.clone(),
+            row.owner.clone().unwrap_or_else(|| "-".to_string()),
+            row.reviewed.clone().unwrap_or_else(|| "-".to_string()),
+            row.problems
+                .iter()
+                .map(Problem::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ]
+    };
+    let header = ["Chapter", "Owner", "Reviewed", "Problems"].map(String::from);
+    let mut out = String::new();
+    match format {
+        ReportFormat::Json => {
+            out = serde_json::to_string_pretty(rows)?;
+            out.push('\n');
+        }
+        ReportFormat::Table => {
+            let lines: Vec<[String; 4]> = std::iter::once(header)
+                .chain(rows.iter().map(cells))
+                .collect();
+            let widths: Vec<usize> = (0..4)
+                .map(|i| {
+                    lines
+                        .iter()
+                        .map(|l| l[i].chars().count())
+                        .max()
+                        .unwrap_or(0)
+                })
+                .collect();
+            for line in &lines {
+                let padded: Vec<String> = line
+                    .iter()
+                    .zip(&widths)
+                    .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                    .collect();
+                let _ = writeln!(out, "{}", padded.join("  ").trim_end());
+            }
+        }
+        ReportFormat::Markdown => {
+            let _ = writeln!(out, "| {} |", header.join(" | "));
+            let _ = writeln!(out, "|---|---|---|---|");
+            for row in rows {
+                let row = cells(row).map(|cell| cell.replace('|', "\\|"));
+                let _ = writeln!(out, "| {} |", row.join(" | "));
+            }
+        }
+    }
+    if format != ReportFormat::Json {
+        let _ = writeln!(out, "\nChapters needing attention: {}", rows.len());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn problems_flag_unowned_and_overdue_chapters() {
+        let report = Report::from_config(&toml::from_str("max-age = 30").unwrap());
+        let today = NaiveDate::from_ymd_opt(2024, 6, 30).unwrap();
+
+        assert_eq!(
+            report.problems(Some("docs"), Some("2024-06-01"), today),
+            vec![]
+        );
+        assert_eq!(
+            report.problems(None, Some("2024-05-01"), today),
+            vec![Problem::Unowned, Problem::Overdue { days: 30 }]
+        );
+        assert_eq!(
+            report.problems(Some("docs"), None, today),
+            vec![Problem::NeverReviewed]
+        );
+
+        let rows = vec![ReportRow {
+            chapter: "guide/intro.md".to_string(),
+            owner: None,
+            reviewed: Some("2024-05-01".to_string()),
+            problems: report.problems(None, Some("2024-05-01"), today),
+        }];
+        assert_eq!(
+            render(&rows, ReportFormat::Markdown).unwrap(),
+            "| Chapter | Owner | Reviewed | Problems |\n\
+             |---|---|---|---|\n\
+             | guide/intro.md | - | 2024-05-01 | no owner, review overdue by 30 days |\n\
+             \nChapters needing attention: 1\n"
+        );
+        assert_eq!(
+            serde_json::to_value(&rows).unwrap()[0]["problems"],
+            serde_json::json!([{ "problem": "unowned" }, { "problem": "overdue", "days": 30 }])
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use toml::value::Table;
+
+use crate::aggregate::ChapterEntry;
+use crate::links::parse_maps;
+use crate::metadata::MetadataError;
+use crate::tags::Tag;
+
+/// Metadata keys handled by the reviews emitter instead of the generic one.
+pub const REVIEW_KEYS: [&str; 2] = ["rating", "review-of"];
+
+/// Emits chapters reviewing tools or libraries as schema.org `Review` JSON-LD,
+/// with the `AggregateRating` of every review of the same item
+/// (`[preprocessor.metadata.reviews]`).
+#[derive(Debug, Clone)]
+pub struct Reviews {
+    pub worst: f64,
+    pub best: f64,
+    pub item_type: String,
+}
+
+/// The review of a chapter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Review {
+    pub name: String,
+    pub item_type: Option<String>,
+    pub url: Option<String>,
+    pub rating: Option<f64>,
+}
+
+/// The ratings of every review of an item.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Ratings {
+    pub sum: f64,
+    pub count: usize,
+}
+
+impl Reviews {
+    pub fn from_config(table: &Table) -> Self {
+        let number = |key: &str, default: f64| {
+            table
+                .get(key)
+                .and_then(|v| v.as_float().or_else(|| v.as_integer().map(|i| i as f64)))
+                .unwrap_or(default)
+        };
+        Self {
+            worst: number("worst", 1.0),
+            best: number("best", 5.0),
+            item_type: table
+                .get("item-type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("SoftwareApplication")
+                .to_string(),
+        }
+    }
+
+    /// Reads the review of a chapter with a `review-of` key: the name of the
+    /// item, or a map with its `name`, `type` and `url`. `rating` needs
+    /// `review-of`, and must be within the scale.
+    pub fn review(
+        &self,
+        metadata: &HashMap<String, String>,
+    ) -> Option<Result<Review, MetadataError>> {
+        let rating = metadata.get("rating");
+        let Some(item) = metadata.get("review-of") else {
+            return rating.map(|_| Err(invalid("rating", "ratings need a review-of item")));
+        };
+        Some(self.read(item, rating))
+    }
+
+    fn read(&self, item: &str, rating: Option<&String>) -> Result<Review, MetadataError> {
+        let item = item.trim();
+        let mut review = if item.starts_with('{') {
+            let mut maps = parse_maps("review-of", item)?;
+            let (Some(mut map), true) = (maps.pop(), maps.is_empty()) else {
+                return Err(invalid("review-of", "expected a name or a single map"));
+            };
+            Review {
+                name: map
+                    .remove("name")
+                    .ok_or_else(|| invalid("review-of", "the reviewed item needs a name"))?,
+                item_type: map.remove("type"),
+                url: map.remove("url"),
+                rating: None,
+            }
+        } else {
+            Review {
+                name: item.to_string(),
+                item_type: None,
+                url: None,
+                rating: None,
+            }
+        };
+        if let Some(rating) = rating {
+            let value = rating
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|r| (self.worst..=self.best).contains(r))
+                .ok_or_else(|| {
+                    invalid(
+                        "rating",
+                        &format!(
+                            "'{}' isn't a number between {} and {}",
+                            rating.trim(),
+                            self.worst,
+                            self.best
+                        ),
+                    )
+                })?;
+            review.rating = Some(value);
+        }
+        Ok(review)
+    }
+
+    /// Sums the valid ratings of every reviewed item. Invalid reviews are left
+    /// out; they are reported with the chapter's tags.
+    pub fn ratings(&self, chapters: &[ChapterEntry]) -> HashMap<String, Ratings> {
+        let mut ratings: HashMap<String, Ratings> = HashMap::new();
+        for entry in chapters {
+            if let Some(Ok(Review {
+                name,
+                rating: Some(rating),
+                ..
+            })) = self.review(&entry.metadata)
+            {
+                let item = ratings.entry(name).or_default();
+                item.sum += rating;
+                item.count += 1;
+            }
+        }
+        ratings
+    }
+
+    /// Returns the `Review` JSON-LD of a chapter, written by `author`, with the
+    /// aggregate rating of the item when it has several reviews.
+    pub fn tag(
+        &self,
+        title: &str,
+        review: &Review,
+        author: Option<&str>,
+        ratings: &HashMap<String, Ratings>,
+    ) -> Tag {
+        let mut item = serde_json::json!({
+            "@type": review.item_type.as_deref().unwrap_or(&self.item_type),
+            "name": review.name,
+        });
+        if let Some(url) = &review.url {
+            item["url"] = url.as_str().into();
+        }
+        if let Some(ratings) = ratings.get(&review.name).filter(|r| r.count > 1) {
+            let average = (ratings.sum / ratings.count as f64 * 10.0).round() / 10.0;
+            item["aggregateRating"] = serde_json::json!({
+                "@type": "AggregateRating",
+                "ratingValue": average,
+                "reviewCount": ratings.count,
+                "worstRating": self.worst,
+                "bestRating": self.best,
+            });
+        }
+
+        let mut data = serde_json::json!({
+            "@context": "https://schema.org",
+            "@type": "Review",
+            "name": title,
+            "itemReviewed": item,
+        });
+        if let Some(rating) = review.rating {
+            data["reviewRating"] = serde_json::json!({
+                "@type": "Rating",
+                "ratingValue": rating,
+                "worstRating": self.worst,
+                "bestRating": self.best,
+            });
+        }
+        if let Some(author) = author {
+            data["author"] = serde_json::json!({ "@type": "Person", "name": author });
+        }
+        Tag::script("application/ld+json", &data)
+    }
+}
+
+fn invalid(key: &str, message: &str) -> MetadataError {
+    MetadataError::InvalidValue {
+        key: key.to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::entry;
+
+    #[test]
+    fn reviews_are_aggregated_by_item() {
+        let reviews = Reviews::from_config(&toml::from_str("best = 10").unwrap());
+        let chapters = vec![
+            entry(
+                "reviews/rg.md",
+                &[
+                    (
+                        "review-of",
+                        "{name: ripgrep, url: \"https://github.com/BurntSushi/ripgrep\"}",
+                    ),
+                    ("rating", "9"),
+                ],
+            ),
+            entry(
+                "reviews/rg-2.md",
+                &[("review-of", "ripgrep"), ("rating", "8")],
+            ),
+            entry("reviews/fd.md", &[("review-of", "fd"), ("rating", "11")]),
+        ];
+
+        let ratings = reviews.ratings(&chapters);
+        let review = reviews.review(&chapters[0].metadata).unwrap().unwrap();
+        let tag = reviews.tag("ripgrep review", &review, Some("Jane Doe"), &ratings);
+
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&tag.content).unwrap(),
+            serde_json::json!({
+                "@context": "https://schema.org",
+                "@type": "Review",
+                "name": "ripgrep review",
+                "author": {"@type": "Person", "name": "Jane Doe"},
+                "itemReviewed": {
+                    "@type": "SoftwareApplication",
+                    "name": "ripgrep",
+                    "url": "https://github.com/BurntSushi/ripgrep",
+                    "aggregateRating": {
+                        "@type": "AggregateRating",
+                        "ratingValue": 8.5,
+                        "reviewCount": 2,
+                        "worstRating": 1.0,
+                        "bestRating": 10.0
+                    }
+                },
+                "reviewRating": {
+                    "@type": "Rating",
+                    "ratingValue": 9.0,
+                    "worstRating": 1.0,
+                    "bestRating": 10.0
+                }
+            })
+        );
+        assert!(reviews.review(&chapters[2].metadata).unwrap().is_err());
+        assert!(reviews
+            .review(&HashMap::from([("rating".to_string(), "4".to_string())]))
+            .unwrap()
+            .is_err());
+    }
+}
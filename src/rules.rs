@@ -0,0 +1,182 @@
+use chrono::NaiveDate;
+use globset::{GlobBuilder, GlobMatcher};
+use log::warn;
+use std::collections::HashMap;
+use std::path::Path;
+use toml::Value;
+
+use crate::frontmatter::flatten_table;
+use crate::metadata::MetadataError;
+use crate::script;
+
+/// Metadata set on every chapter whose path matches a glob and whose metadata
+/// matches a `when` expression (`[[preprocessor.metadata.rules]]`).
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub glob: Option<GlobMatcher>,
+    pub when: Option<String>,
+    pub set: Vec<(String, String)>,
+}
+
+impl Rule {
+    /// Reads a rule, warning about and skipping rules without a valid glob or
+    /// a `when` condition.
+    pub fn from_config(value: &Value) -> Option<Self> {
+        let table = value.as_table()?;
+        let when = table.get("when").and_then(|v| v.as_str()).map(String::from);
+        let glob = match table.get("glob").and_then(|v| v.as_str()) {
+            Some(pattern) => match GlobBuilder::new(pattern).literal_separator(true).build() {
+                Ok(glob) => Some(glob.compile_matcher()),
+                Err(e) => {
+                    warn!("Ignoring metadata rule '{}': {}", pattern, e);
+                    return None;
+                }
+            },
+            None if when.is_some() => None,
+            None => {
+                warn!("Ignoring a metadata rule without a glob or a condition");
+                return None;
+            }
+        };
+        let set = table
+            .get("set")
+            .and_then(|v| v.as_table())
+            .map(flatten_table)
+            .unwrap_or_default();
+        Some(Self { glob, when, set })
+    }
+
+    /// Returns whether the rule applies to a chapter. Conditions see the
+    /// chapter's own metadata, before any rule is applied.
+    fn matches(
+        &self,
+        path: &Path,
+        metadata: &HashMap<String, String>,
+        today: NaiveDate,
+    ) -> Result<bool, MetadataError> {
+        if self.glob.as_ref().is_some_and(|glob| !glob.is_match(path)) {
+            return Ok(false);
+        }
+        match &self.when {
+            Some(when) => script::condition(when, metadata, today),
+            None => Ok(true),
+        }
+    }
+}
+
+/// Reads every rule of the `rules` array.
+pub fn rules_from_config(value: &Value) -> Vec<Rule> {
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Rule::from_config)
+        .collect()
+}
+
+/// Adds the keys of every rule matching `path` (relative to the book root) to
+/// `metadata`. Keys already set by the chapter win over the rules, and later
+/// rules win over earlier ones. Rules whose condition fails are skipped and
+/// their errors returned.
+pub fn apply(
+    rules: &[Rule],
+    path: &Path,
+    metadata: &mut HashMap<String, String>,
+    today: NaiveDate,
+) -> Vec<MetadataError> {
+    let mut values = HashMap::new();
+    let mut errors = Vec::new();
+    for rule in rules {
+        match rule.matches(path, metadata, today) {
+            Ok(true) => values.extend(rule.set.iter().cloned()),
+            Ok(false) => {}
+            Err(e) => errors.push(e),
+        }
+    }
+    for (key, value) in values {
+        metadata.entry(key).or_insert(value);
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 11).unwrap()
+    }
+
+    #[test]
+    fn apply_merges_matching_rules_under_frontmatter() {
+        let config: toml::value::Table = toml::from_str(
+            r#"
+[[rules]]
+glob = "src/reference/**"
+set = { layout = "api", noindex = true, owner = "platform" }
+
+[[rules]]
+glob = "src/reference/legacy/*.md"
+set = { owner = "archive" }
+
+[[rules]]
+glob = "src/[broken"
+set = { layout = "broken" }
+"#,
+        )
+        .unwrap();
+        let rules = rules_from_config(&config["rules"]);
+        assert_eq!(rules.len(), 2);
+
+        let mut metadata = HashMap::from([("layout".to_string(), "guide".to_string())]);
+        apply(
+            &rules,
+            Path::new("src/reference/legacy/v1.md"),
+            &mut metadata,
+            today(),
+        );
+        let mut untouched = HashMap::new();
+        apply(&rules, Path::new("src/intro.md"), &mut untouched, today());
+
+        assert_eq!(
+            metadata,
+            HashMap::from([
+                ("layout".to_string(), "guide".to_string()),
+                ("noindex".to_string(), "true".to_string()),
+                ("owner".to_string(), "archive".to_string()),
+            ])
+        );
+        assert!(untouched.is_empty());
+    }
+
+    #[test]
+    fn apply_checks_conditions() {
+        let config: toml::value::Table = toml::from_str(
+            r#"
+[[rules]]
+when = 'status == "draft" || date > now()'
+set = { noindex = true }
+
+[[rules]]
+glob = "src/guide/**"
+when = "level"
+set = { reviewed = false }
+"#,
+        )
+        .unwrap();
+        let rules = rules_from_config(&config["rules"]);
+        let path = Path::new("src/guide/intro.md");
+
+        let mut draft = HashMap::from([("status".to_string(), "draft".to_string())]);
+        let errors = apply(&rules, path, &mut draft, today());
+        let mut scheduled = HashMap::from([("date".to_string(), "2024-07-01".to_string())]);
+        apply(&rules, Path::new("src/intro.md"), &mut scheduled, today());
+        let mut published = HashMap::from([("status".to_string(), "final".to_string())]);
+        apply(&rules, Path::new("src/intro.md"), &mut published, today());
+
+        assert_eq!(draft.get("noindex").map(String::as_str), Some("true"));
+        assert_eq!(errors.len(), 1); // `level` is undefined
+        assert_eq!(scheduled.get("noindex").map(String::as_str), Some("true"));
+        assert_eq!(published.get("noindex"), None);
+    }
+}
@@ -0,0 +1,80 @@
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use log::warn;
+use std::path::Path;
+use toml::Value;
+
+/// The chapters the preprocessor works on, from the `include` and `exclude`
+/// globs. Paths are relative to the book root, e.g. `src/blog/**`.
+#[derive(Debug, Clone)]
+pub struct Scope {
+    pub include: Option<GlobSet>,
+    pub exclude: GlobSet,
+}
+
+impl Scope {
+    pub fn from_config(include: Option<&Value>, exclude: Option<&Value>) -> Self {
+        Self {
+            include: include.map(glob_set),
+            exclude: exclude.map(glob_set).unwrap_or_else(GlobSet::empty),
+        }
+    }
+
+    /// Returns whether the chapter at `path` should be processed: it matches
+    /// one of the `include` globs (if any) and none of the `exclude` globs.
+    pub fn contains(&self, path: &Path) -> bool {
+        self.include.as_ref().map_or(true, |g| g.is_match(path)) && !self.exclude.is_match(path)
+    }
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Self::from_config(None, None)
+    }
+}
+
+/// Builds a glob set from a string or an array of strings, warning about and
+/// skipping invalid globs. `*` doesn't match `/`; use `**` to match directories.
+fn glob_set(value: &Value) -> GlobSet {
+    let patterns: Vec<&str> = match value {
+        Value::String(pattern) => vec![pattern.as_str()],
+        value => value
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str())
+            .collect(),
+    };
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        match GlobBuilder::new(pattern).literal_separator(true).build() {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(e) => warn!("Ignoring glob '{}': {}", pattern, e),
+        }
+    }
+    builder.build().unwrap_or_else(|e| {
+        warn!("Ignoring globs: {}", e);
+        GlobSet::empty()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contains_applies_include_then_exclude() {
+        let scope = Scope::from_config(
+            Some(&Value::Array(vec!["src/blog/**".into(), "src/*.md".into()])),
+            Some(&Value::String("src/blog/drafts/**".into())),
+        );
+
+        assert!(scope.contains(Path::new("src/blog/2024/launch.md")));
+        assert!(scope.contains(Path::new("src/intro.md")));
+        assert!(!scope.contains(Path::new("src/blog/drafts/idea.md")));
+        assert!(!scope.contains(Path::new("src/appendix/a.md")));
+        assert!(Scope::default().contains(Path::new("src/appendix/a.md")));
+    }
+}
@@ -0,0 +1,124 @@
+use chrono::NaiveDate;
+use rhai::{Dynamic, Engine, Map, Scope};
+use std::collections::{HashMap, HashSet};
+
+use crate::metadata::MetadataError;
+
+/// Operations allowed per expression, bounding runaway loops.
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// Evaluates an [rhai](https://rhai.rs) expression against a chapter's
+/// metadata. Keys that are identifiers are variables (`status`), and every key
+/// is in the `meta` map (`meta["book.title"]`); keys the chapter doesn't set
+/// are `()`, which is equal to no string. `now()` returns today's date as
+/// `YYYY-MM-DD`, so it compares with ISO dates. Returns `None` when the
+/// expression evaluates to `()`.
+pub fn evaluate(
+    expression: &str,
+    vars: &HashMap<String, String>,
+    today: NaiveDate,
+) -> Result<Option<String>, MetadataError> {
+    let value = eval(expression, vars, today)?;
+    Ok((!value.is_unit()).then(|| value.to_string()))
+}
+
+/// Evaluates an expression that must return a boolean, such as the `when`
+/// condition of a rule.
+pub fn condition(
+    expression: &str,
+    vars: &HashMap<String, String>,
+    today: NaiveDate,
+) -> Result<bool, MetadataError> {
+    eval(expression, vars, today)?
+        .as_bool()
+        .map_err(|type_name| {
+            expression_error(expression, &format!("expected a bool, found {}", type_name))
+        })
+}
+
+fn eval(
+    expression: &str,
+    vars: &HashMap<String, String>,
+    today: NaiveDate,
+) -> Result<Dynamic, MetadataError> {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    let now = today.format("%Y-%m-%d").to_string();
+    engine.register_fn("now", move || now.clone());
+
+    let known: HashSet<String> = vars.keys().cloned().collect();
+    #[allow(deprecated)] // `on_var` is only flagged as volatile
+    engine.on_var(move |name, _, _| {
+        Ok((name != "meta" && !known.contains(name)).then_some(Dynamic::UNIT))
+    });
+
+    let mut scope = Scope::new();
+    let mut meta = Map::new();
+    for (key, value) in vars {
+        if is_identifier(key) {
+            scope.push_constant(key.as_str(), value.clone());
+        }
+        meta.insert(key.as_str().into(), value.clone().into());
+    }
+    scope.push_constant("meta", meta);
+
+    engine
+        .eval_expression_with_scope::<Dynamic>(&mut scope, expression)
+        .map_err(|e| expression_error(expression, &e.to_string()))
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn expression_error(expression: &str, message: &str) -> MetadataError {
+    MetadataError::Expression {
+        expression: expression.to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> HashMap<String, String> {
+        HashMap::from([
+            ("status".to_string(), "draft".to_string()),
+            ("date".to_string(), "2024-06-01".to_string()),
+            ("reading-time".to_string(), "7".to_string()),
+        ])
+    }
+
+    #[test]
+    fn evaluate_exposes_the_metadata() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 11).unwrap();
+
+        let evaluate = |expression| evaluate(expression, &vars(), today).unwrap();
+
+        assert_eq!(
+            evaluate(r#"status == "draft" || date > now()"#).as_deref(),
+            Some("true")
+        );
+        assert_eq!(
+            evaluate(r#"meta["reading-time"].parse_int() * 60"#).as_deref(),
+            Some("420")
+        );
+        assert_eq!(evaluate(r#"if status == "final" { "index" }"#), None);
+        assert!(!condition(r#"date > now()"#, &vars(), today).unwrap());
+    }
+
+    #[test]
+    fn evaluate_reports_invalid_expressions() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 11).unwrap();
+
+        assert!(evaluate("status ==", &vars(), today).is_err());
+        assert!(evaluate("missing + 1", &vars(), today).is_err());
+        assert!(condition("status", &vars(), today).is_err());
+        assert!(evaluate("loop {}", &vars(), today).is_err());
+    }
+}
@@ -0,0 +1,376 @@
+use lazy_static::lazy_static;
+use log::warn;
+use mdbook::book::{Book, BookItem, Chapter};
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use toml::value::Table;
+
+use crate::aggregate::ChapterEntry;
+use crate::metadata::parse_list;
+use crate::urls::Urls;
+
+lazy_static! {
+    static ref IMAGE_RE: Regex = Regex::new(r"!\[[^\]]*\]\([^)]*\)").unwrap();
+    static ref LINK_RE: Regex = Regex::new(r"\[([^\]]*)\]\([^)]*\)").unwrap();
+    static ref HTML_TAG_RE: Regex = Regex::new(r"<[^>]+>").unwrap();
+    static ref DIRECTIVE_RE: Regex = Regex::new(r"\{\{#[^}]*\}\}").unwrap();
+}
+
+/// Default length of content excerpts, in characters.
+const EXCERPT_LENGTH: usize = 300;
+
+/// Exports one Algolia DocSearch record per chapter, with its hierarchy,
+/// content excerpt and metadata facets (`[preprocessor.metadata.algolia]`).
+#[derive(Debug, Clone)]
+pub struct Algolia {
+    pub facets: Vec<String>,
+    pub excerpt_length: usize,
+    pub file: String,
+}
+
+impl Algolia {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            facets: table
+                .get("facets")
+                .and_then(|v| v.as_array())
+                .map(|keys| {
+                    keys.iter()
+                        .filter_map(|k| k.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_else(|| vec!["version".into(), "audience".into()]),
+            excerpt_length: table
+                .get("excerpt-length")
+                .and_then(|v| v.as_integer())
+                .and_then(|v| usize::try_from(v).ok())
+                .unwrap_or(EXCERPT_LENGTH),
+            file: table
+                .get("file")
+                .and_then(|v| v.as_str())
+                .unwrap_or("algolia-records.json")
+                .to_string(),
+        }
+    }
+
+    /// Returns the records of the indexed chapters. The hierarchy starts with
+    /// the book title (`lvl0`), followed by the chapter's parents and the
+    /// chapter itself; `keywords` become the record's `_tags`.
+    pub fn records(
+        &self,
+        book: &Book,
+        book_title: Option<&str>,
+        chapters: &[ChapterEntry],
+        urls: &Urls,
+    ) -> Vec<Value> {
+        let contents = chapter_contents(book);
+        indexed(chapters)
+            .map(|entry| {
+                let chapter = contents.get(&entry.path);
+                let mut levels: Vec<&str> = vec![book_title.unwrap_or("Documentation")];
+                levels.extend(
+                    chapter
+                        .into_iter()
+                        .flat_map(|c| c.parent_names.iter().map(String::as_str)),
+                );
+                levels.push(entry.title());
+                let mut hierarchy = Map::new();
+                for level in 0..7 {
+                    let value = levels.get(level).map_or(Value::Null, |&l| l.into());
+                    hierarchy.insert(format!("lvl{}", level), value);
+                }
+
+                let url = urls
+                    .url(&entry.path)
+                    .unwrap_or_else(|| urls.path(&entry.path));
+                let mut record = Map::new();
+                record.insert(
+                    "objectID".to_string(),
+                    entry.path.display().to_string().into(),
+                );
+                record.insert("url".to_string(), url.clone().into());
+                record.insert("url_without_anchor".to_string(), url.into());
+                record.insert("type".to_string(), "content".into());
+                record.insert("hierarchy".to_string(), Value::Object(hierarchy));
+                let content = chapter
+                    .map(|c| excerpt(&c.content, self.excerpt_length))
+                    .unwrap_or_default();
+                record.insert("content".to_string(), content.into());
+                let tags = entry
+                    .metadata
+                    .get("keywords")
+                    .map(|v| parse_list(v))
+                    .unwrap_or_default();
+                record.insert("_tags".to_string(), tags.into());
+                for key in &self.facets {
+                    if let Some(value) = entry.metadata.get(key) {
+                        record.insert(key.clone(), facet(value));
+                    }
+                }
+                Value::Object(record)
+            })
+            .collect()
+    }
+
+    /// Returns the records file.
+    pub fn artifact(
+        &self,
+        book: &Book,
+        book_title: Option<&str>,
+        chapters: &[ChapterEntry],
+        urls: &Urls,
+    ) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.records(book, book_title, chapters, urls))
+    }
+}
+
+/// Documents for self-hosted search engines, in their import format
+/// (`search-export`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchExport {
+    Meilisearch, // A JSON array, for the documents route
+    Typesense,   // JSON lines, for the import endpoint
+}
+
+impl SearchExport {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "meilisearch" => Some(SearchExport::Meilisearch),
+            "typesense" => Some(SearchExport::Typesense),
+            other => {
+                warn!("Unknown search-export '{}', skipping it", other);
+                None
+            }
+        }
+    }
+
+    /// Returns a document per indexed chapter, with an `id` derived from its
+    /// path, its URL path, `title`, `description`, `keywords` as `tags`, and
+    /// an excerpt of its text as `content`.
+    pub fn documents(&self, book: &Book, chapters: &[ChapterEntry], urls: &Urls) -> Vec<Value> {
+        let contents = chapter_contents(book);
+        indexed(chapters)
+            .map(|entry| {
+                let id: String = entry
+                    .path
+                    .with_extension("")
+                    .to_string_lossy()
+                    .chars()
+                    .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '-' })
+                    .collect();
+                let content = contents
+                    .get(&entry.path)
+                    .map(|c| excerpt(&c.content, EXCERPT_LENGTH))
+                    .unwrap_or_default();
+                serde_json::json!({
+                    "id": id,
+                    "path": urls.path(&entry.path),
+                    "title": entry.title(),
+                    "description": entry.metadata.get("description").map_or("", |v| v.trim()),
+                    "tags": entry.metadata.get("keywords").map(|v| parse_list(v)).unwrap_or_default(),
+                    "content": content,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the name and contents of the documents file.
+    pub fn artifact(
+        &self,
+        book: &Book,
+        chapters: &[ChapterEntry],
+        urls: &Urls,
+    ) -> serde_json::Result<(&'static str, String)> {
+        let documents = self.documents(book, chapters, urls);
+        Ok(match self {
+            SearchExport::Meilisearch => (
+                "search-documents.json",
+                serde_json::to_string_pretty(&documents)?,
+            ),
+            SearchExport::Typesense => {
+                let mut lines = String::new();
+                for document in &documents {
+                    lines.push_str(&serde_json::to_string(document)?);
+                    lines.push('\n');
+                }
+                ("search-documents.jsonl", lines)
+            }
+        })
+    }
+}
+
+/// Returns the chapters to index, leaving out those with `search: false` or
+/// `noindex: true`.
+fn indexed(chapters: &[ChapterEntry]) -> impl Iterator<Item = &ChapterEntry> {
+    chapters.iter().filter(|entry| {
+        let flag = |key: &str| entry.metadata.get(key).map(|v| v.trim());
+        flag("search") != Some("false") && flag("noindex") != Some("true")
+    })
+}
+
+/// Maps the path of every chapter of the book to the chapter.
+fn chapter_contents(book: &Book) -> HashMap<PathBuf, &Chapter> {
+    book.iter()
+        .filter_map(|item| match item {
+            BookItem::Chapter(chap) => Some((chap.path.clone()?, chap)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A facet value: a list for `[a, b]` and comma-separated values, else a string.
+fn facet(value: &str) -> Value {
+    let values = parse_list(value);
+    if values.len() > 1 || value.trim().starts_with('[') {
+        Value::from(values)
+    } else {
+        Value::from(value.trim())
+    }
+}
+
+/// Returns the first `length` characters of the text of a chapter, cut at a
+/// word boundary. Code blocks, HTML tags, images and mdBook directives are
+/// left out, and links and emphasis are reduced to their text.
+pub fn excerpt(markdown: &str, length: usize) -> String {
+    let mut in_code = false;
+    let mut words = Vec::new();
+    for line in markdown.lines() {
+        let line = line.trim();
+        if line.starts_with("```") || line.starts_with("~~~") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        let line = line.trim_start_matches(['#', '>', '-', '*', '+', '|']);
+        let line = IMAGE_RE.replace_all(line, "");
+        let line = LINK_RE.replace_all(&line, "$1");
+        let line = HTML_TAG_RE.replace_all(&line, "");
+        let line = DIRECTIVE_RE.replace_all(&line, "");
+        let line = line.replace(['*', '`', '|'], "");
+        words.extend(line.split_whitespace().map(String::from));
+    }
+
+    let mut text = String::new();
+    for word in words {
+        if text.chars().count() + word.chars().count() + 1 > length {
+            text.push('…');
+            break;
+        }
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        text.push_str(&word);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn excerpt_keeps_the_text() {
+        let markdown = "# Intro\n\nRead the [*guide*](guide.md) first.\n\n\
+                        ```rust\nfn main() {}\n```\n\n![logo](logo.png)<br>\n{{#include x.md}}\n\
+                        - Install `mdbook` now";
+
+        assert_eq!(
+            excerpt(markdown, 100),
+            "Intro Read the guide first. Install mdbook now"
+        );
+        assert_eq!(excerpt(markdown, 20), "Intro Read the guide…");
+    }
+
+    #[test]
+    fn typesense_documents_are_json_lines() {
+        let chapter = Chapter::new("Intro", "Welcome!".to_string(), "intro.md", Vec::new());
+        let metadata = HashMap::from([
+            ("description".to_string(), "Start here".to_string()),
+            ("keywords".to_string(), "[rust, mdbook]".to_string()),
+        ]);
+        let chapters = vec![ChapterEntry::new(
+            &chapter,
+            std::path::Path::new("guide/intro.md"),
+            metadata,
+        )];
+        let mut book = Book::new();
+        book.push_item(BookItem::Chapter(Chapter::new(
+            "Intro",
+            "Welcome!".to_string(),
+            "guide/intro.md",
+            Vec::new(),
+        )));
+
+        let (name, contents) = SearchExport::from_name("typesense")
+            .unwrap()
+            .artifact(&book, &chapters, &Urls::default())
+            .unwrap();
+
+        assert_eq!(name, "search-documents.jsonl");
+        assert_eq!(
+            contents,
+            "{\"content\":\"Welcome!\",\"description\":\"Start here\",\"id\":\"guide-intro\",\
+             \"path\":\"/guide/intro.html\",\"tags\":[\"rust\",\"mdbook\"],\"title\":\"Intro\"}\n"
+        );
+        assert_eq!(SearchExport::from_name("solr"), None);
+    }
+
+    #[test]
+    fn records_have_hierarchy_and_facets() {
+        let mut parent = Chapter::new("Guide", String::new(), "guide/index.md", Vec::new());
+        let child = Chapter::new(
+            "Install",
+            "Run cargo install.".to_string(),
+            "guide/install.md",
+            vec!["Guide".to_string()],
+        );
+        let metadata: HashMap<String, String> = [
+            ("version", "2.0"),
+            ("audience", "admins, developers"),
+            ("keywords", "setup"),
+            ("owner", "docs"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let chapters = vec![
+            ChapterEntry::new(
+                &parent,
+                std::path::Path::new("guide/index.md"),
+                HashMap::new(),
+            ),
+            ChapterEntry::new(&child, std::path::Path::new("guide/install.md"), metadata),
+        ];
+        parent.sub_items.push(BookItem::Chapter(child));
+        let mut book = Book::new();
+        book.push_item(BookItem::Chapter(parent));
+        let urls =
+            Urls::from_config(&toml::from_str("site-url = \"https://example.com\"").unwrap());
+
+        let records =
+            Algolia::from_config(&Table::new()).records(&book, Some("Manual"), &chapters, &urls);
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            records[1],
+            serde_json::json!({
+                "objectID": "guide/install.md",
+                "url": "https://example.com/guide/install.html",
+                "url_without_anchor": "https://example.com/guide/install.html",
+                "type": "content",
+                "hierarchy": {
+                    "lvl0": "Manual", "lvl1": "Guide", "lvl2": "Install",
+                    "lvl3": null, "lvl4": null, "lvl5": null, "lvl6": null
+                },
+                "content": "Run cargo install.",
+                "_tags": ["setup"],
+                "version": "2.0",
+                "audience": ["admins", "developers"]
+            })
+        );
+    }
+}
@@ -0,0 +1,285 @@
+use anyhow::{bail, Context, Result};
+use mdbook::book::{Book, BookItem};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::value::Table;
+
+use crate::aggregate::ChapterEntry;
+use crate::metadata::parse_list;
+use crate::sources::load_config;
+
+/// Script adding a tag filter to mdBook's search, for `additional-js`. The
+/// search index is loaded lazily, so the tags are collected once results show.
+const SEARCH_FILTER_JS: &str = r##"(function () {
+    var tagsByPage = null;
+    var select = document.createElement("select");
+    select.id = "search-tag-filter";
+    select.innerHTML = '<option value="">All tags</option>';
+
+    function collect() {
+        if (tagsByPage || !window.search || !window.search.doc_urls) {
+            return;
+        }
+        tagsByPage = {};
+        var docs = window.search.index.documentStore.docs;
+        var all = {};
+        window.search.doc_urls.forEach(function (url, ref) {
+            var page = url.split("#")[0];
+            var tags = (docs[ref] && docs[ref].tags) || [];
+            tagsByPage[page] = (tagsByPage[page] || []).concat(tags);
+            tags.forEach(function (tag) { all[tag] = true; });
+        });
+        Object.keys(all).sort().forEach(function (tag) {
+            var option = document.createElement("option");
+            option.value = option.textContent = tag;
+            select.appendChild(option);
+        });
+    }
+
+    function filter() {
+        collect();
+        var results = document.getElementById("searchresults");
+        if (!tagsByPage || !results) {
+            return;
+        }
+        var root = new URL(typeof path_to_root === "undefined" ? "" : path_to_root, document.baseURI);
+        results.querySelectorAll("li").forEach(function (item) {
+            var link = item.querySelector("a[href]");
+            var url = link ? new URL(link.getAttribute("href"), document.baseURI) : null;
+            var page = url ? decodeURIComponent(url.pathname.slice(root.pathname.length)) : "";
+            var tags = tagsByPage[page] || [];
+            item.hidden = select.value !== "" && tags.indexOf(select.value) === -1;
+        });
+    }
+
+    function setup() {
+        var bar = document.getElementById("searchbar");
+        var results = document.getElementById("searchresults");
+        if (!bar || !results) {
+            return;
+        }
+        bar.parentNode.insertBefore(select, bar.nextSibling);
+        select.addEventListener("change", filter);
+        new MutationObserver(filter).observe(results, { childList: true });
+    }
+    if (document.readyState === "loading") {
+        document.addEventListener("DOMContentLoaded", setup);
+    } else {
+        setup();
+    }
+})();
+"##;
+
+/// Adds chapter metadata to the documents of mdBook's search index
+/// (`[preprocessor.metadata.search-index]`). The preprocessor writes the data,
+/// and `enrich-search` merges it into the index once the book is built.
+#[derive(Debug, Clone)]
+pub struct SearchIndex {
+    pub keys: Vec<String>,
+}
+
+impl SearchIndex {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            keys: table
+                .get("keys")
+                .and_then(|v| v.as_array())
+                .map(|keys| {
+                    keys.iter()
+                        .filter_map(|k| k.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_else(|| vec!["keywords".into(), "section".into(), "version".into()]),
+        }
+    }
+
+    /// Maps each chapter's rendered HTML path to its search fields. `keywords`
+    /// become a `tags` list, and `section` defaults to the chapter's top-level
+    /// parent, or the chapter itself.
+    pub fn data(&self, book: &Book, chapters: &[ChapterEntry]) -> BTreeMap<String, Value> {
+        let parents: HashMap<&PathBuf, &str> = book
+            .iter()
+            .filter_map(|item| match item {
+                BookItem::Chapter(chap) => {
+                    Some((chap.path.as_ref()?, chap.parent_names.first()?.as_str()))
+                }
+                _ => None,
+            })
+            .collect();
+        chapters
+            .iter()
+            .map(|entry| {
+                let mut fields = Map::new();
+                for key in &self.keys {
+                    let value = entry.metadata.get(key).map(|v| v.trim());
+                    match (key.as_str(), value) {
+                        ("keywords", Some(value)) => {
+                            fields.insert("tags".to_string(), parse_list(value).into());
+                        }
+                        ("section", None) => {
+                            let section = parents.get(&entry.path).copied();
+                            fields.insert(
+                                "section".to_string(),
+                                section.unwrap_or(entry.title()).into(),
+                            );
+                        }
+                        (_, Some(value)) => {
+                            fields.insert(key.clone(), value.into());
+                        }
+                        (_, None) => {}
+                    }
+                }
+                (
+                    entry.path.with_extension("html").display().to_string(),
+                    Value::Object(fields),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the `search-meta.json` data file and the `search-filter.js` script.
+    pub fn artifacts(
+        &self,
+        book: &Book,
+        chapters: &[ChapterEntry],
+    ) -> serde_json::Result<Vec<(&'static str, String)>> {
+        Ok(vec![
+            (
+                "search-meta.json",
+                serde_json::to_string_pretty(&self.data(book, chapters))?,
+            ),
+            ("search-filter.js", SEARCH_FILTER_JS.to_string()),
+        ])
+    }
+}
+
+/// Adds the fields of `data` to the documents of an mdBook search index, and
+/// returns the number of documents enriched. Every heading of a chapter is a
+/// document, and they all get the chapter's fields.
+pub fn enrich(index: &mut Value, data: &Map<String, Value>) -> usize {
+    let pages: Vec<String> = index["doc_urls"]
+        .as_array()
+        .map(|urls| {
+            urls.iter()
+                .map(|url| {
+                    url.as_str()
+                        .unwrap_or_default()
+                        .split('#')
+                        .next()
+                        .unwrap_or_default()
+                        .to_string()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let Some(docs) = index["index"]["documentStore"]["docs"].as_object_mut() else {
+        return 0;
+    };
+    let mut enriched = 0;
+    for (doc_ref, doc) in docs.iter_mut() {
+        let fields = doc_ref
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| pages.get(i))
+            .and_then(|page| data.get(page))
+            .and_then(Value::as_object);
+        if let (Some(fields), Some(doc)) = (fields, doc.as_object_mut()) {
+            doc.extend(fields.clone());
+            enriched += 1;
+        }
+    }
+    enriched
+}
+
+/// Merges the `search-meta.json` of a built book into its `searchindex.json`
+/// and `searchindex.js`, in the build directory or its `html` directory when
+/// the book has several outputs. Returns the number of documents enriched.
+pub fn enrich_build(book_dir: &Path) -> Result<usize> {
+    let (config, _) = load_config(book_dir)?;
+    let artifacts_dir = config
+        .get_preprocessor("metadata")
+        .and_then(|p| p.get("artifacts-dir"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("book-metadata");
+    let data_path = book_dir.join(artifacts_dir).join("search-meta.json");
+    let data: Map<String, Value> = serde_json::from_str(
+        &fs::read_to_string(&data_path)
+            .with_context(|| format!("Failed to read '{}'", data_path.display()))?,
+    )?;
+
+    let build_dir = book_dir.join(&config.build.build_dir);
+    let Some(dir) = [build_dir.clone(), build_dir.join("html")]
+        .into_iter()
+        .find(|dir| dir.join("searchindex.json").is_file())
+    else {
+        bail!("No searchindex.json in '{}'", build_dir.display());
+    };
+    let mut index: Value =
+        serde_json::from_str(&fs::read_to_string(dir.join("searchindex.json"))?)?;
+    let enriched = enrich(&mut index, &data);
+    let json = serde_json::to_string(&index)?;
+    fs::write(dir.join("searchindex.json"), &json)?;
+    if dir.join("searchindex.js").is_file() {
+        fs::write(
+            dir.join("searchindex.js"),
+            format!("Object.assign(window.search, {});", json),
+        )?;
+    }
+    Ok(enriched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mdbook::book::Chapter;
+
+    #[test]
+    fn enrich_adds_chapter_fields_to_every_heading() {
+        let guide = Chapter::new("Guide", String::new(), "guide/index.md", Vec::new());
+        let install = Chapter::new(
+            "Install",
+            String::new(),
+            "guide/install.md",
+            vec!["Guide".to_string()],
+        );
+        let chapters = vec![
+            ChapterEntry::new(&guide, Path::new("guide/index.md"), HashMap::new()),
+            ChapterEntry::new(
+                &install,
+                Path::new("guide/install.md"),
+                HashMap::from([
+                    ("keywords".to_string(), "setup, cli".to_string()),
+                    ("version".to_string(), "2".to_string()),
+                ]),
+            ),
+        ];
+        let mut book = Book::new();
+        book.push_item(BookItem::Chapter(install));
+        let data = SearchIndex::from_config(&Table::new()).data(&book, &chapters);
+        let mut index = serde_json::json!({
+            "doc_urls": ["guide/index.html#guide", "guide/install.html#install", "guide/install.html#linux"],
+            "index": { "documentStore": { "docs": {
+                "0": { "id": "0", "title": "Guide" },
+                "1": { "id": "1", "title": "Install" },
+                "2": { "id": "2", "title": "Linux" }
+            } } }
+        });
+
+        let enriched = enrich(&mut index, &data.into_iter().collect());
+
+        assert_eq!(enriched, 3);
+        let docs = &index["index"]["documentStore"]["docs"];
+        assert_eq!(
+            docs["0"],
+            serde_json::json!({ "id": "0", "title": "Guide", "section": "Guide" })
+        );
+        assert_eq!(
+            docs["2"],
+            serde_json::json!({
+                "id": "2", "title": "Linux", "section": "Guide", "tags": ["setup", "cli"], "version": "2"
+            })
+        );
+    }
+}
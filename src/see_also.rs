@@ -0,0 +1,103 @@
+use std::fmt::Write;
+use toml::value::Table;
+
+use crate::aggregate::{relative_href, relative_link, resolve_references, ChapterEntry};
+use crate::metadata::MetadataError;
+use crate::tags::Tag;
+
+/// Renders the `see-also` key as a "See also" section and `<link rel="related">`
+/// tags (`[preprocessor.metadata.see-also]`).
+#[derive(Debug, Clone)]
+pub struct SeeAlso {
+    pub title: String,
+}
+
+/// The rendered output of a chapter's `see-also` references.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedSeeAlso {
+    pub section: String,
+    pub link_tags: Vec<Tag>,
+}
+
+impl SeeAlso {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            title: table
+                .get("title")
+                .and_then(|v| v.as_str())
+                .unwrap_or("See also")
+                .to_string(),
+        }
+    }
+
+    /// Returns the section and link tags for `entry`, or an error for every
+    /// reference that doesn't resolve to a chapter of the book.
+    pub fn render(
+        &self,
+        entry: &ChapterEntry,
+        chapters: &[ChapterEntry],
+    ) -> Result<Option<RenderedSeeAlso>, Vec<MetadataError>> {
+        let targets = resolve_references(entry, "see-also", chapters)?;
+        if targets.is_empty() {
+            return Ok(None);
+        }
+
+        let mut section = format!("## {}\n\n", self.title);
+        let mut link_tags = Vec::new();
+        for target in targets {
+            let _ = writeln!(
+                section,
+                "- [{}]({})",
+                target.title(),
+                relative_link(&entry.path, &target.path)
+            );
+            link_tags.push(Tag::link(
+                "related",
+                &relative_href(&entry.path, &target.path),
+            ));
+        }
+
+        Ok(Some(RenderedSeeAlso { section, link_tags }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::entry;
+
+    #[test]
+    fn render_builds_section_and_link_tags() {
+        let see_also = SeeAlso::from_config(&Table::new());
+        let chapters = vec![
+            entry("guide/retries.md", &[("see-also", "[queues, faq-page]")]),
+            entry("guide/queues.md", &[("title", "Queues")]),
+            entry("faq.md", &[("slug", "faq-page")]),
+        ];
+
+        let rendered = see_also.render(&chapters[0], &chapters).unwrap().unwrap();
+
+        assert_eq!(
+            rendered.section,
+            "## See also\n\n- [Queues](queues.md)\n- [faq.md](../faq.md)\n"
+        );
+        assert_eq!(
+            crate::tags::to_html(&rendered.link_tags),
+            "<link rel=\"related\" href=\"queues.html\">\n\
+             <link rel=\"related\" href=\"../faq.html\">\n"
+        );
+    }
+
+    #[test]
+    fn render_reports_unresolved_references() {
+        let see_also = SeeAlso::from_config(&Table::new());
+        let chapters = vec![entry("a.md", &[("see-also", "renamed-chapter")])];
+
+        let errors = see_also.render(&chapters[0], &chapters).unwrap_err();
+
+        assert_eq!(
+            errors[0].to_string(),
+            "Chapter 'a.md' references unknown chapter 'renamed-chapter' in 'see-also'"
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use mdbook::book::{Book, BookItem};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use toml::value::Table;
+
+use crate::aggregate::ChapterEntry;
+
+/// Shows a metadata value instead of the chapter name in the navigation, so
+/// long titles get a shorter sidebar entry without renaming files or editing
+/// `SUMMARY.md` (`[preprocessor.metadata.short-title]`).
+#[derive(Debug, Clone)]
+pub struct ShortTitle {
+    pub key: String,
+}
+
+impl ShortTitle {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            key: table
+                .get("key")
+                .and_then(|v| v.as_str())
+                .unwrap_or("short-title")
+                .to_string(),
+        }
+    }
+
+    /// Renames every chapter setting the key, keeping the `parent_names` of
+    /// nested chapters in sync with their renamed ancestors.
+    pub fn apply(&self, book: &mut Book, chapters: &[ChapterEntry]) {
+        let names: HashMap<&PathBuf, &str> = chapters
+            .iter()
+            .filter_map(|entry| {
+                let name = entry.metadata.get(&self.key)?.trim();
+                (!name.is_empty()).then_some((&entry.path, name))
+            })
+            .collect();
+        if !names.is_empty() {
+            rename_items(&mut book.sections, &[], &names);
+        }
+    }
+}
+
+fn rename_items(items: &mut [BookItem], parent_names: &[String], names: &HashMap<&PathBuf, &str>) {
+    for item in items {
+        if let BookItem::Chapter(chap) = item {
+            if let Some(name) = chap.path.as_ref().and_then(|p| names.get(p)) {
+                chap.name = name.to_string();
+            }
+            chap.parent_names = parent_names.to_vec();
+
+            let mut parent_names = parent_names.to_vec();
+            parent_names.push(chap.name.clone());
+            rename_items(&mut chap.sub_items, &parent_names, names);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mdbook::book::Chapter;
+    use std::path::Path;
+
+    #[test]
+    fn chapters_are_renamed_from_their_short_title() {
+        let mut parent = Chapter::new(
+            "Configuring retries and timeouts",
+            String::new(),
+            "retries.md",
+            Vec::new(),
+        );
+        parent.sub_items.push(BookItem::Chapter(Chapter::new(
+            "Backoff",
+            String::new(),
+            "backoff.md",
+            vec![parent.name.clone()],
+        )));
+        let mut book = Book::new();
+        book.push_item(parent.clone());
+        let chapters = vec![ChapterEntry::new(
+            &parent,
+            Path::new("retries.md"),
+            HashMap::from([("short-title".to_string(), " Retries ".to_string())]),
+        )];
+
+        ShortTitle::from_config(&Table::new()).apply(&mut book, &chapters);
+
+        let BookItem::Chapter(parent) = &book.sections[0] else {
+            panic!("expected a chapter");
+        };
+        assert_eq!(parent.name, "Retries");
+        let BookItem::Chapter(child) = &parent.sub_items[0] else {
+            panic!("expected a chapter");
+        };
+        assert_eq!(child.name, "Backoff");
+        assert_eq!(child.parent_names, vec!["Retries".to_string()]);
+    }
+}
@@ -0,0 +1,131 @@
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use toml::value::Table;
+
+use crate::aggregate::ChapterEntry;
+
+/// Script decorating the sidebar links, appended after the embedded data.
+const SIDEBAR_HOOK_JS: &str = r#"
+(function () {
+    function decorate() {
+        var root = new URL(typeof path_to_root === "undefined" ? "" : path_to_root, document.baseURI);
+        document.querySelectorAll(".sidebar a[href]").forEach(function (link) {
+            var url = new URL(link.getAttribute("href"), document.baseURI);
+            var meta = SIDEBAR_META[decodeURIComponent(url.pathname.slice(root.pathname.length))];
+            if (!meta || link.dataset.metaDecorated) {
+                return;
+            }
+            link.dataset.metaDecorated = "true";
+            Object.keys(meta).forEach(function (key) {
+                link.setAttribute("data-meta-" + key, meta[key]);
+                var badge = document.createElement("span");
+                badge.className = "meta-badge meta-badge-" + key;
+                badge.textContent = meta[key];
+                link.appendChild(badge);
+            });
+        });
+    }
+    if (document.readyState === "loading") {
+        document.addEventListener("DOMContentLoaded", decorate);
+    } else {
+        decorate();
+    }
+})();
+"#;
+
+/// Publishes per-chapter badge data for custom themes
+/// (`[preprocessor.metadata.sidebar]`).
+#[derive(Debug, Clone)]
+pub struct Sidebar {
+    pub keys: Vec<String>,
+}
+
+impl Sidebar {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            keys: table
+                .get("keys")
+                .and_then(|v| v.as_array())
+                .map(|keys| {
+                    keys.iter()
+                        .filter_map(|k| k.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_else(|| vec!["status".into(), "level".into(), "updated".into()]),
+        }
+    }
+
+    /// Maps each chapter's rendered HTML path to its badge values. Chapters
+    /// without any of the keys are left out.
+    pub fn data(&self, chapters: &[ChapterEntry]) -> BTreeMap<String, Map<String, Value>> {
+        chapters
+            .iter()
+            .filter_map(|entry| {
+                let values: Map<String, Value> = self
+                    .keys
+                    .iter()
+                    .filter_map(|k| Some((k.clone(), entry.metadata.get(k)?.clone().into())))
+                    .collect();
+                (!values.is_empty()).then(|| {
+                    (
+                        entry.path.with_extension("html").display().to_string(),
+                        values,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the `sidebar-meta.json` data file and the `sidebar-meta.js` hook,
+    /// which embeds the same data so it can be added through `additional-js`.
+    pub fn artifacts(
+        &self,
+        chapters: &[ChapterEntry],
+    ) -> serde_json::Result<Vec<(&'static str, String)>> {
+        let data = self.data(chapters);
+        let script = format!(
+            "var SIDEBAR_META = {};\n{}",
+            serde_json::to_string(&data)?,
+            SIDEBAR_HOOK_JS.trim_start()
+        );
+        Ok(vec![
+            ("sidebar-meta.json", serde_json::to_string_pretty(&data)?),
+            ("sidebar-meta.js", script),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::entry;
+
+    #[test]
+    fn data_maps_html_paths_to_badges() {
+        let sidebar = Sidebar::from_config(&Table::new());
+        let chapters = vec![
+            entry(
+                "guide/intro.md",
+                &[
+                    ("status", "draft"),
+                    ("level", "beginner"),
+                    ("author", "Jane"),
+                ],
+            ),
+            entry("plain.md", &[("author", "Jane")]),
+        ];
+
+        let artifacts = sidebar.artifacts(&chapters).unwrap();
+
+        assert_eq!(artifacts[0].0, "sidebar-meta.json");
+        assert_eq!(
+            serde_json::from_str::<Value>(&artifacts[0].1).unwrap(),
+            serde_json::json!({
+                "guide/intro.html": { "status": "draft", "level": "beginner" }
+            })
+        );
+        assert!(artifacts[1].1.starts_with(
+            "var SIDEBAR_META = {\"guide/intro.html\":{\"level\":\"beginner\",\"status\":\"draft\"}};\n"
+        ));
+    }
+}
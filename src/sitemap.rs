@@ -0,0 +1,221 @@
+use html_escape::encode_text;
+use log::warn;
+use mdbook::book::{Book, BookItem};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+use toml::value::Table;
+
+use crate::aggregate::ChapterEntry;
+use crate::dates::parse_date;
+use crate::metadata::MetadataError;
+use crate::urls::Urls;
+
+/// Keys used by the sitemap, which aren't emitted as meta tags.
+pub const SITEMAP_KEYS: [&str; 2] = ["sitemap-priority", "sitemap-changefreq"];
+
+const CHANGE_FREQUENCIES: [&str; 7] = [
+    "always", "hourly", "daily", "weekly", "monthly", "yearly", "never",
+];
+
+/// Generates `sitemap.xml` (`[preprocessor.metadata.sitemap]`). Its own
+/// `site-url` takes precedence over the top-level one.
+#[derive(Debug, Clone)]
+pub struct Sitemap {
+    pub site_url: Option<String>,
+}
+
+impl Sitemap {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            site_url: table
+                .get("site-url")
+                .and_then(|v| v.as_str())
+                .map(String::from),
+        }
+    }
+
+    /// Renders the sitemap, along with an error for every invalid
+    /// `sitemap-priority` or `sitemap-changefreq` value (the default is used
+    /// instead). Chapters with `sitemap: false` or `noindex: true` are left out.
+    ///
+    /// Priorities default to 1.0 for top-level chapters, minus 0.2 per level of
+    /// nesting down to 0.4; frequencies to `weekly` for top-level chapters and
+    /// `monthly` for nested ones. `lastmod` comes from `updated` or `date`.
+    pub fn render(
+        &self,
+        book: &Book,
+        chapters: &[ChapterEntry],
+        urls: &Urls,
+    ) -> (String, Vec<MetadataError>) {
+        let urls = self.urls(urls);
+        let mut depths = HashMap::new();
+        collect_depths(&book.sections, 0, &mut depths);
+
+        let mut errors = Vec::new();
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+        );
+        for entry in chapters {
+            let flag = |key: &str| entry.metadata.get(key).map(|v| v.trim());
+            if flag("sitemap") == Some("false") || flag("noindex") == Some("true") {
+                continue;
+            }
+            let depth = depths.get(&entry.path).copied().unwrap_or_default();
+
+            let priority = match flag("sitemap-priority") {
+                Some(value) => match value.parse::<f32>() {
+                    Ok(priority) if (0.0..=1.0).contains(&priority) => Some(priority),
+                    _ => {
+                        errors.push(invalid(
+                            "sitemap-priority",
+                            entry,
+                            value,
+                            "a number between 0.0 and 1.0",
+                        ));
+                        None
+                    }
+                },
+                None => None,
+            }
+            .unwrap_or_else(|| (1.0 - 0.2 * depth as f32).max(0.4));
+
+            let changefreq = match flag("sitemap-changefreq") {
+                Some(value) if CHANGE_FREQUENCIES.contains(&value) => Some(value),
+                Some(value) => {
+                    errors.push(invalid(
+                        "sitemap-changefreq",
+                        entry,
+                        value,
+                        &CHANGE_FREQUENCIES.join(", "),
+                    ));
+                    None
+                }
+                None => None,
+            }
+            .unwrap_or(if depth == 0 { "weekly" } else { "monthly" });
+
+            let url = urls.url(&entry.path).unwrap_or_default();
+            let _ = writeln!(xml, "  <url>\n    <loc>{}</loc>", encode_text(&url));
+            if let Some(date) = flag("updated")
+                .or_else(|| flag("date"))
+                .and_then(parse_date)
+            {
+                let _ = writeln!(xml, "    <lastmod>{}</lastmod>", date.format("%Y-%m-%d"));
+            }
+            let _ = writeln!(
+                xml,
+                "    <changefreq>{}</changefreq>\n    <priority>{:.1}</priority>\n  </url>",
+                changefreq, priority
+            );
+        }
+        xml.push_str("</urlset>\n");
+        (xml, errors)
+    }
+
+    /// Returns the sitemap, or `None` without a `site-url`, as sitemaps need
+    /// absolute URLs.
+    pub fn artifact(
+        &self,
+        book: &Book,
+        chapters: &[ChapterEntry],
+        urls: &Urls,
+    ) -> Option<(String, Vec<MetadataError>)> {
+        if self.urls(urls).site_url.is_none() {
+            warn!("The sitemap needs a site-url, skipping it");
+            return None;
+        }
+        Some(self.render(book, chapters, urls))
+    }
+
+    fn urls(&self, urls: &Urls) -> Urls {
+        match &self.site_url {
+            Some(site_url) => urls.with_site_url(site_url),
+            None => urls.clone(),
+        }
+    }
+}
+
+fn collect_depths(items: &[BookItem], depth: usize, depths: &mut HashMap<PathBuf, usize>) {
+    for item in items {
+        if let BookItem::Chapter(chap) = item {
+            if let Some(path) = &chap.path {
+                depths.insert(path.clone(), depth);
+            }
+            collect_depths(&chap.sub_items, depth + 1, depths);
+        }
+    }
+}
+
+fn invalid(key: &str, entry: &ChapterEntry, value: &str, expected: &str) -> MetadataError {
+    MetadataError::InvalidValue {
+        key: key.to_string(),
+        message: format!(
+            "'{}' in chapter '{}', expected {}",
+            value, entry.name, expected
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mdbook::book::Chapter;
+
+    fn entry(chapter: &Chapter, metadata: &[(&str, &str)]) -> ChapterEntry {
+        let metadata = metadata
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        ChapterEntry::new(chapter, chapter.path.as_deref().unwrap(), metadata)
+    }
+
+    #[test]
+    fn render_uses_keys_and_depth_defaults() {
+        let mut guide = Chapter::new("Guide", String::new(), "guide/index.md", Vec::new());
+        let setup = Chapter::new("Setup", String::new(), "guide/setup.md", Vec::new());
+        guide.sub_items.push(BookItem::Chapter(setup.clone()));
+        let hidden = Chapter::new("Hidden", String::new(), "hidden.md", Vec::new());
+        let mut book = Book::new();
+        book.push_item(guide.clone());
+        book.push_item(hidden.clone());
+        let chapters = vec![
+            entry(
+                &guide,
+                &[("updated", "2024-06-01"), ("sitemap-changefreq", "daily")],
+            ),
+            entry(
+                &setup,
+                &[("sitemap-priority", "2"), ("sitemap-changefreq", "often")],
+            ),
+            entry(&hidden, &[("noindex", "true")]),
+        ];
+        let table: Table = toml::from_str("site-url = \"https://example.com/book\"").unwrap();
+
+        let (xml, errors) = Sitemap::from_config(&table).render(&book, &chapters, &Urls::default());
+
+        assert_eq!(
+            xml,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n\
+             \x20 <url>\n\
+             \x20   <loc>https://example.com/book/guide/index.html</loc>\n\
+             \x20   <lastmod>2024-06-01</lastmod>\n\
+             \x20   <changefreq>daily</changefreq>\n\
+             \x20   <priority>1.0</priority>\n\
+             \x20 </url>\n\
+             \x20 <url>\n\
+             \x20   <loc>https://example.com/book/guide/setup.html</loc>\n\
+             \x20   <changefreq>monthly</changefreq>\n\
+             \x20   <priority>0.8</priority>\n\
+             \x20 </url>\n\
+             </urlset>\n"
+        );
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0].to_string(),
+            "Invalid value of 'sitemap-priority': '2' in chapter 'Setup', expected a number between 0.0 and 1.0"
+        );
+    }
+}
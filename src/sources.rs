@@ -0,0 +1,40 @@
+use anyhow::{Context, Result};
+use mdbook::Config;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::config;
+
+/// Reads the `book.toml` of a book directory, with its `extends` applied, and
+/// returns it with the book's `src` directory.
+pub fn load_config(book_dir: &Path) -> Result<(Config, PathBuf)> {
+    let mut config = Config::from_disk(book_dir.join("book.toml"))
+        .with_context(|| format!("Failed to read book.toml in '{}'", book_dir.display()))?;
+    config::apply_extends(book_dir, &mut config)?;
+    let src_dir = book_dir.join(&config.book.src);
+    Ok((config, src_dir))
+}
+
+/// Returns the markdown chapter files under `src_dir`, sorted, leaving out
+/// `SUMMARY.md`.
+pub fn chapter_files(src_dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = WalkDir::new(src_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.path().extension() == Some("md".as_ref()))
+        .filter(|e| e.file_name() != "SUMMARY.md")
+        .map(|e| e.into_path())
+        .collect();
+    files.sort();
+    files
+}
+
+/// Formats a chapter file path relative to `src_dir`, with `/` separators.
+pub fn chapter_name(src_dir: &Path, path: &Path) -> String {
+    path.strip_prefix(src_dir)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,114 @@
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::path::Path;
+use toml::value::Table;
+
+use crate::aggregate::ChapterEntry;
+use crate::dates::{git_dates, parse_date};
+use crate::metadata::MetadataError;
+use crate::template;
+
+/// The key opting a chapter out of the stale-content check.
+pub const STALE_CHECK_KEY: &str = "stale-check";
+
+/// Flags chapters last updated more than `stale-after-days` ago with a banner
+/// and a `stale` meta tag.
+#[derive(Debug, Clone)]
+pub struct Stale {
+    pub after_days: i64,
+    pub template: String,
+}
+
+impl Stale {
+    /// Reads the `stale-after-days` and `stale-template` options, returning
+    /// `None` when the check is off.
+    pub fn from_config(table: &Table) -> Option<Self> {
+        let after_days = table.get("stale-after-days")?.as_integer()?;
+        Some(Self {
+            after_days,
+            template: table
+                .get("stale-template")
+                .and_then(|v| v.as_str())
+                .unwrap_or(
+                    "> **Last updated {months} months ago.** Some of this page may be out of date.",
+                )
+                .to_string(),
+        })
+    }
+
+    /// Returns the date a chapter was last updated, and whether it's stale as
+    /// of `today`. Dates come from `updated` or `date`, falling back to the
+    /// last commit of the chapter file (relative to `src_dir`).
+    pub fn check(
+        &self,
+        entry: &ChapterEntry,
+        src_dir: &Path,
+        today: NaiveDate,
+    ) -> Option<NaiveDate> {
+        if entry.metadata.get(STALE_CHECK_KEY).map(|v| v.trim()) == Some("false") {
+            return None;
+        }
+        let updated = entry
+            .metadata
+            .get("updated")
+            .or_else(|| entry.metadata.get("date"))
+            .and_then(|d| parse_date(d))
+            .or_else(|| Some(git_dates(src_dir, entry.source_path.as_ref()?)?.modified))?;
+        ((today - updated).num_days() > self.after_days).then_some(updated)
+    }
+
+    /// Renders the banner of a chapter last updated on `updated`. The template
+    /// can use the chapter's metadata, `{updated}`, `{days}` and `{months}`.
+    pub fn banner(
+        &self,
+        metadata: &HashMap<String, String>,
+        updated: NaiveDate,
+        today: NaiveDate,
+    ) -> Result<String, MetadataError> {
+        let days = (today - updated).num_days();
+        let mut vars = metadata.clone();
+        vars.insert(
+            "updated".to_string(),
+            updated.format("%Y-%m-%d").to_string(),
+        );
+        vars.insert("days".to_string(), days.to_string());
+        vars.insert("months".to_string(), (days / 30).to_string());
+        template::render(&self.template, &vars)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::named_entry;
+
+    #[test]
+    fn check_flags_chapters_updated_long_ago() {
+        let stale = Stale::from_config(&toml::from_str("stale-after-days = 365").unwrap()).unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let entry = |metadata: &[(&str, &str)]| named_entry("Intro", "intro.md", metadata);
+
+        let old = entry(&[("date", "2022-01-01"), ("updated", "2023-03-01")]);
+        let updated = stale.check(&old, Path::new("."), today);
+        assert_eq!(updated, NaiveDate::from_ymd_opt(2023, 3, 1));
+        assert_eq!(
+            stale
+                .banner(&old.metadata, updated.unwrap(), today)
+                .unwrap(),
+            "> **Last updated 15 months ago.** Some of this page may be out of date."
+        );
+        assert_eq!(
+            stale.check(&entry(&[("updated", "2024-01-01")]), Path::new("."), today),
+            None
+        );
+        assert_eq!(
+            stale.check(
+                &entry(&[("updated", "2020-01-01"), ("stale-check", "false")]),
+                Path::new("."),
+                today
+            ),
+            None
+        );
+        assert!(Stale::from_config(&Table::new()).is_none());
+    }
+}
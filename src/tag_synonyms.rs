@@ -0,0 +1,102 @@
+use log::warn;
+use std::collections::HashMap;
+use toml::value::Table;
+
+use crate::metadata::parse_list;
+
+/// Keys holding the tags of a chapter.
+pub const TAG_KEYS: [&str; 2] = ["tags", "keywords"];
+
+/// Canonical spellings of tags, `"rust-lang" = "rust"`
+/// (`[preprocessor.metadata.tag-synonyms]`). Synonyms match regardless of
+/// case; `enabled` is the feature switch, not a synonym.
+#[derive(Debug, Clone, Default)]
+pub struct TagSynonyms {
+    pub synonyms: HashMap<String, String>,
+}
+
+impl TagSynonyms {
+    pub fn from_config(table: &Table) -> Self {
+        let synonyms = table
+            .iter()
+            .filter(|(synonym, _)| synonym.as_str() != "enabled")
+            .filter_map(|(synonym, tag)| match tag.as_str() {
+                Some(tag) if !tag.trim().is_empty() => {
+                    Some((synonym.trim().to_lowercase(), tag.trim().to_string()))
+                }
+                _ => {
+                    warn!("Ignoring the tag synonym '{}': expected a tag", synonym);
+                    None
+                }
+            })
+            .collect();
+        Self { synonyms }
+    }
+
+    /// Rewrites the synonyms in the tag keys of a chapter's metadata, dropping
+    /// tags that become duplicates. Returns the rewrites, `(synonym, tag)`.
+    pub fn apply(&self, metadata: &mut HashMap<String, String>) -> Vec<(String, String)> {
+        let mut rewrites = Vec::new();
+        for key in TAG_KEYS {
+            let Some(value) = metadata.get_mut(key) else {
+                continue;
+            };
+            let mut tags: Vec<String> = Vec::new();
+            let mut changed = false;
+            for tag in parse_list(value) {
+                let canonical = match self.synonyms.get(&tag.to_lowercase()) {
+                    Some(canonical) if *canonical != tag => {
+                        rewrites.push((tag, canonical.clone()));
+                        changed = true;
+                        canonical.clone()
+                    }
+                    _ => tag,
+                };
+                if tags.contains(&canonical) {
+                    changed = true;
+                } else {
+                    tags.push(canonical);
+                }
+            }
+            if changed {
+                *value = if value.trim_start().starts_with('[') {
+                    format!("[{}]", tags.join(", "))
+                } else {
+                    tags.join(", ")
+                };
+            }
+        }
+        rewrites
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synonyms_are_rewritten_to_their_tag() {
+        let synonyms = TagSynonyms::from_config(
+            &toml::from_str("\"rust-lang\" = \"rust\"\nk8s = \"kubernetes\"").unwrap(),
+        );
+        let mut metadata = HashMap::from([
+            (
+                "tags".to_string(),
+                "[Rust-Lang, rust, networking]".to_string(),
+            ),
+            ("keywords".to_string(), "k8s, helm".to_string()),
+        ]);
+
+        let rewrites = synonyms.apply(&mut metadata);
+
+        assert_eq!(metadata["tags"], "[rust, networking]");
+        assert_eq!(metadata["keywords"], "kubernetes, helm");
+        assert_eq!(
+            rewrites,
+            vec![
+                ("Rust-Lang".to_string(), "rust".to_string()),
+                ("k8s".to_string(), "kubernetes".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use lazy_static::lazy_static;
+use log::warn;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+lazy_static! {
+    // Scripts and styles with their content, comments, doctypes, and any other
+    // opening or closing tag, including one left unterminated. `<a@b.c>` isn't
+    // a tag
+    static ref HTML_RE: Regex = Regex::new(
+        r"(?is)<script\b.*?(</script\s*>|$)|<style\b.*?(</style\s*>|$)|<!--.*?(-->|$)|<!\w[^>]*>?|</?[a-z][a-z0-9-]*(\s[^>]*)?/?(>|$)"
+    )
+    .unwrap();
+}
+
+/// The HTML element a tag is emitted as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagKind {
+    Title,
+    Meta,
+    Property,
+    Link,
+    Script,
+    Raw,
+}
+
+/// A single tag injected into a chapter. For links, `name` is the `rel`,
+/// `content` the `href` and `attributes` any others, like `type`; for scripts,
+/// `name` is the `type` and `content` the JSON data; raw tags are the unescaped
+/// `content` of the `name` key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Tag {
+    pub name: String,
+    pub content: String,
+    pub kind: TagKind,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl Tag {
+    pub fn new(kind: TagKind, name: &str, content: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            content: content.to_string(),
+            kind,
+            attributes: BTreeMap::new(),
+        }
+    }
+
+    pub fn title(content: &str) -> Self {
+        Self::new(TagKind::Title, "title", content)
+    }
+
+    pub fn meta(name: &str, content: &str) -> Self {
+        Self::new(TagKind::Meta, name, content)
+    }
+
+    /// A `<meta property>` tag, as required by Open Graph.
+    pub fn property(name: &str, content: &str) -> Self {
+        Self::new(TagKind::Property, name, content)
+    }
+
+    pub fn link(rel: &str, href: &str) -> Self {
+        Self::new(TagKind::Link, rel, href)
+    }
+
+    /// A `<script>` holding JSON data, such as JSON-LD.
+    pub fn script(script_type: &str, data: &serde_json::Value) -> Self {
+        Self::new(TagKind::Script, script_type, &data.to_string())
+    }
+
+    /// A tag injected verbatim. Only for trusted values (`raw-keys`).
+    pub fn raw(name: &str, html: &str) -> Self {
+        Self::new(TagKind::Raw, name, html)
+    }
+
+    pub fn to_html(&self) -> String {
+        let name = match self.kind {
+            TagKind::Meta | TagKind::Property | TagKind::Link => match sanitize_name(&self.name) {
+                Some(name) => name,
+                None => {
+                    warn!(
+                        "Skipping the tag of key '{}': keys can only contain letters, digits, ':', '.', '_' and '-'",
+                        self.name
+                    );
+                    return String::new();
+                }
+            },
+            _ => String::new(),
+        };
+        match self.kind {
+            TagKind::Title => format!("<title>{}</title>\n", encode_text(&self.content)),
+            TagKind::Meta => format!(
+                "<meta name=\"{}\" content=\"{}\">\n",
+                name,
+                attribute(&self.content)
+            ),
+            TagKind::Property => format!(
+                "<meta property=\"{}\" content=\"{}\">\n",
+                name,
+                attribute(&self.content)
+            ),
+            TagKind::Link => {
+                let mut html = format!(
+                    "<link rel=\"{}\" href=\"{}\"",
+                    name,
+                    attribute(&self.content)
+                );
+                for (name, value) in self.attributes.iter().filter(|(n, _)| is_attribute_name(n)) {
+                    html.push_str(&format!(" {}=\"{}\"", name, attribute(value)));
+                }
+                html + ">\n"
+            }
+            TagKind::Script => {
+                let mut html = format!("<script type=\"{}\"", attribute(&self.name));
+                for (name, value) in self.attributes.iter().filter(|(n, _)| is_attribute_name(n)) {
+                    html.push_str(&format!(" {}=\"{}\"", name, attribute(value)));
+                }
+                // Escaped in JSON strings, so the data can't close the element
+                let data = self
+                    .content
+                    .replace('<', "\\u003c")
+                    .replace('>', "\\u003e")
+                    .replace('&', "\\u0026");
+                format!("{}>{}</script>\n", html, data)
+            }
+            TagKind::Raw => format!("{}\n", self.content.trim_end()),
+        }
+    }
+}
+
+/// Returns whether `key` matches one of `patterns`: a key, or a prefix ending
+/// with `*` (`og:*`).
+pub fn matches_key(patterns: &[String], key: &str) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => key.starts_with(prefix),
+            None => pattern == key,
+        })
+}
+
+/// Returns whether `name` can be used as an attribute name as-is: lowercase
+/// ASCII letters, digits and `-`, starting with a letter.
+pub fn is_attribute_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Escapes a value for a double-quoted attribute. Line breaks are encoded too,
+/// so every tag stays on a single line.
+fn attribute(value: &str) -> String {
+    encode_double_quoted_attribute(value)
+        .replace('\n', "&#10;")
+        .replace('\r', "&#13;")
+}
+
+/// Turns a metadata key into a `name`/`rel`/`property` token: runs of
+/// whitespace and control characters become a single `-`, and surrounding ones
+/// are dropped. Returns `None` for keys with other characters than
+/// `[A-Za-z0-9:._-]`, such as quotes and brackets.
+pub fn sanitize_name(name: &str) -> Option<String> {
+    let mut sanitized = String::with_capacity(name.len());
+    for c in name.trim().chars() {
+        if c.is_whitespace() || c.is_control() {
+            if !sanitized.ends_with('-') {
+                sanitized.push('-');
+            }
+        } else {
+            sanitized.push(c);
+        }
+    }
+    let token = sanitized
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, ':' | '.' | '_' | '-'));
+    (!sanitized.is_empty() && token).then_some(sanitized)
+}
+
+/// Removes the HTML of a value (`sanitize-values`): every tag, comment and
+/// doctype, and scripts and styles with their content. Text is kept, and a `<`
+/// not opening a tag, as in `a < b`, too.
+pub fn strip_html(value: &str) -> String {
+    HTML_RE.replace_all(value, "").trim().to_string()
+}
+
+/// Renders tags as HTML, one per line.
+pub fn to_html(tags: &[Tag]) -> String {
+    tags.iter().map(Tag::to_html).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_html_keeps_only_text() {
+        assert_eq!(
+            strip_html("<b>Fast</b> retries<script>alert(1)</script> for a < b"),
+            "Fast retries for a < b"
+        );
+        assert_eq!(
+            strip_html("Intro<!-- note --><style>p{}</style><img src=x onerror=alert(1)"),
+            "Intro"
+        );
+        assert_eq!(
+            strip_html("Jane <jane@example.com><br/>"),
+            "Jane <jane@example.com>"
+        );
+    }
+
+    #[test]
+    fn sanitize_name_keeps_only_token_characters() {
+        assert_eq!(
+            sanitize_name(" review\t date ").as_deref(),
+            Some("review-date")
+        );
+        assert_eq!(sanitize_name("DC.creator").as_deref(), Some("DC.creator"));
+        assert_eq!(sanitize_name(r#"x" onload="alert(1)"#), None);
+        assert_eq!(Tag::link("<stylesheet>", "a.css").to_html(), "");
+    }
+
+    #[test]
+    fn to_html_escapes_each_kind() {
+        let tags = [
+            Tag::title("A & B"),
+            Tag::meta("x.y", "<b>"),
+            Tag::property("og:title", "Intro"),
+            Tag::link("related", "a b/\"c\".html"),
+            Tag {
+                attributes: BTreeMap::from([(
+                    "type".to_string(),
+                    "application/rss+xml".to_string(),
+                )]),
+                ..Tag::link("alternate", "feed.xml")
+            },
+            Tag::script(
+                "application/ld+json",
+                &serde_json::json!({ "name": "</script><b>" }),
+            ),
+            Tag::raw(
+                "head-extra",
+                "<link rel=\"me\" href=\"https://example.com\">",
+            ),
+        ];
+
+        assert_eq!(
+            to_html(&tags),
+            "<title>A &amp; B</title>\n\
+             <meta name=\"x.y\" content=\"&lt;b&gt;\">\n\
+             <meta property=\"og:title\" content=\"Intro\">\n\
+             <link rel=\"related\" href=\"a b/&quot;c&quot;.html\">\n\
+             <link rel=\"alternate\" href=\"feed.xml\" type=\"application/rss+xml\">\n\
+             <script type=\"application/ld+json\">{\"name\":\"\\u003c/script\\u003e\\u003cb\\u003e\"}</script>\n\
+             <link rel=\"me\" href=\"https://example.com\">\n"
+        );
+        assert_eq!(
+            serde_json::to_string(&tags[3]).unwrap(),
+            r#"{"name":"related","content":"a b/\"c\".html","kind":"link"}"#
+        );
+    }
+
+    #[test]
+    fn matches_key_supports_prefixes() {
+        let patterns = vec!["og:*".to_string(), "fb:app_id".to_string()];
+
+        assert!(matches_key(&patterns, "og:image:alt"));
+        assert!(matches_key(&patterns, "fb:app_id"));
+        assert!(!matches_key(&patterns, "fb:admins"));
+        assert!(!matches_key(&patterns, "description"));
+    }
+
+    #[test]
+    fn to_html_blocks_injection_through_keys() {
+        let tags = [
+            Tag::meta("x\" onload=\"alert(1)", "a\"b"),
+            Tag::meta("owner\n<script>alert(1)</script>", "x"),
+            Tag::meta(" review\t date ", "2024-06-01"),
+            Tag::meta("owner", "a\"b\r\nbreak"),
+        ];
+
+        assert_eq!(
+            to_html(&tags),
+            "<meta name=\"review-date\" content=\"2024-06-01\">\n\
+             <meta name=\"owner\" content=\"a&quot;b&#13;&#10;break\">\n"
+        );
+    }
+}
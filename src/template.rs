@@ -0,0 +1,252 @@
+use html_escape::encode_quoted_attribute;
+use mdbook::config::BookConfig;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::dates::parse_date;
+use crate::metadata::MetadataError;
+
+/// Renders a template such as `{title | upper} - {book.title}`. Each `{...}`
+/// expression names a variable optionally followed by `|`-separated functions:
+/// `upper()`, `lower()`, `truncate(n)`, `date("%Y-%m-%d")` and `slugify()` (the
+/// parentheses are optional for functions without arguments). Missing variables
+/// render as an empty string; `{{` and `}}` produce literal braces.
+pub fn render(template: &str, vars: &HashMap<String, String>) -> Result<String, MetadataError> {
+    render_with(template, vars, |value| value)
+}
+
+/// Renders an HTML template, escaping the value of every expression for text
+/// and both single- and double-quoted attributes.
+pub fn render_html(
+    template: &str,
+    vars: &HashMap<String, String>,
+) -> Result<String, MetadataError> {
+    render_with(template, vars, |value| {
+        encode_quoted_attribute(&value).into_owned()
+    })
+}
+
+fn render_with(
+    template: &str,
+    vars: &HashMap<String, String>,
+    escape: fn(String) -> String,
+) -> Result<String, MetadataError> {
+    let mut output = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                output.push('}');
+            }
+            '{' => {
+                let mut expression = String::new();
+                let mut in_quotes = false;
+                loop {
+                    match chars.next() {
+                        Some('"') => {
+                            in_quotes = !in_quotes;
+                            expression.push('"');
+                        }
+                        Some('}') if !in_quotes => break,
+                        Some(c) => expression.push(c),
+                        None => {
+                            return Err(template_error(template, "unclosed '{'"));
+                        }
+                    }
+                }
+                output.push_str(&escape(evaluate(&expression, vars, template)?));
+            }
+            '}' => return Err(template_error(template, "unmatched '}'")),
+            c => output.push(c),
+        }
+    }
+
+    Ok(output)
+}
+
+/// Returns whether `value` contains template expressions.
+pub fn is_template(value: &str) -> bool {
+    value.contains('{')
+}
+
+/// Builds the variables available to templates: the chapter's metadata plus the
+/// `book.title`, `book.description`, `book.authors` and `book.language` settings.
+pub fn variables(metadata: &HashMap<String, String>, book: &BookConfig) -> HashMap<String, String> {
+    let mut vars = metadata.clone();
+    let book_vars = [
+        ("book.title", book.title.clone()),
+        ("book.description", book.description.clone()),
+        ("book.authors", Some(book.authors.join(", "))),
+        ("book.language", book.language.clone()),
+    ];
+    for (key, value) in book_vars {
+        if let Some(value) = value {
+            vars.insert(key.to_string(), value);
+        }
+    }
+    vars
+}
+
+fn evaluate(
+    expression: &str,
+    vars: &HashMap<String, String>,
+    template: &str,
+) -> Result<String, MetadataError> {
+    let mut parts = split_pipes(expression).into_iter();
+    let variable = parts.next().unwrap_or_default();
+    let mut value = vars.get(variable.trim()).cloned().unwrap_or_default();
+
+    for function in parts {
+        value = apply(function.trim(), value, template)?;
+    }
+    Ok(value)
+}
+
+fn split_pipes(expression: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+    for (i, c) in expression.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '|' if !in_quotes => {
+                parts.push(&expression[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&expression[start..]);
+    parts
+}
+
+fn apply(function: &str, value: String, template: &str) -> Result<String, MetadataError> {
+    let (name, argument) = match function.find('(') {
+        Some(open) if function.ends_with(')') => (
+            function[..open].trim(),
+            function[open + 1..function.len() - 1].trim(),
+        ),
+        Some(_) => return Err(template_error(template, "unclosed '(' in function call")),
+        None => (function, ""),
+    };
+    let argument = argument.trim_matches('"');
+
+    match name {
+        "upper" => Ok(value.to_uppercase()),
+        "lower" => Ok(value.to_lowercase()),
+        "slugify" => Ok(slugify(&value)),
+        "truncate" => {
+            let length: usize = argument.parse().map_err(|_| {
+                template_error(template, "truncate() expects a number of characters")
+            })?;
+            Ok(value.chars().take(length).collect())
+        }
+        "date" => {
+            let format = if argument.is_empty() {
+                "%Y-%m-%d"
+            } else {
+                argument
+            };
+            // Values that aren't dates are passed through unchanged
+            let Some(date) = parse_date(&value) else {
+                return Ok(value);
+            };
+            let mut formatted = String::new();
+            write!(formatted, "{}", date.format(format))
+                .map_err(|_| template_error(template, "invalid date() format"))?;
+            Ok(formatted)
+        }
+        _ => Err(template_error(
+            template,
+            &format!("unknown function '{}'", name),
+        )),
+    }
+}
+
+/// Converts a value into a lowercase, dash-separated slug.
+pub fn slugify(value: &str) -> String {
+    let mut slug = String::with_capacity(value.len());
+    for c in value.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+fn template_error(template: &str, message: &str) -> MetadataError {
+    MetadataError::Template {
+        template: template.to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> HashMap<String, String> {
+        HashMap::from([
+            ("title".to_string(), "Hello, World!".to_string()),
+            ("date".to_string(), "2024-06-01".to_string()),
+            ("book.title".to_string(), "My Book".to_string()),
+        ])
+    }
+
+    #[test]
+    fn render_interpolates_variables() {
+        assert_eq!(
+            render("{title} | {book.title}", &vars()).unwrap(),
+            "Hello, World! | My Book"
+        );
+        assert_eq!(render("{missing}!", &vars()).unwrap(), "!");
+        assert_eq!(render("{{literal}}", &vars()).unwrap(), "{literal}");
+    }
+
+    #[test]
+    fn render_applies_functions() {
+        assert_eq!(render("{title | upper}", &vars()).unwrap(), "HELLO, WORLD!");
+        assert_eq!(render("{title|lower()}", &vars()).unwrap(), "hello, world!");
+        assert_eq!(render("{title | truncate(5)}", &vars()).unwrap(), "Hello");
+        assert_eq!(
+            render("{title | slugify | upper}", &vars()).unwrap(),
+            "HELLO-WORLD"
+        );
+        assert_eq!(
+            render("{date | date(\"%d/%m|%Y\")}", &vars()).unwrap(),
+            "01/06|2024"
+        );
+    }
+
+    #[test]
+    fn render_reports_invalid_templates() {
+        assert!(render("{title", &vars()).is_err());
+        assert!(render("title}", &vars()).is_err());
+        assert!(render("{title | shout}", &vars()).is_err());
+        assert!(render("{title | truncate(x)}", &vars()).is_err());
+        assert!(render("{date | date(\"%Q\")}", &vars()).is_err());
+    }
+
+    #[test]
+    fn render_html_escapes_every_expression() {
+        let vars = HashMap::from([("title".to_string(), "x'><script>\"".to_string())]);
+        assert_eq!(
+            render_html("<meta content='{title | upper}'>", &vars).unwrap(),
+            "<meta content='X&#x27;&gt;&lt;SCRIPT&gt;&quot;'>"
+        );
+    }
+
+    #[test]
+    fn slugify_collapses_separators() {
+        assert_eq!(slugify("  Hello,  World! "), "hello-world");
+        assert_eq!(slugify("Überblick & Co"), "überblick-co");
+    }
+}
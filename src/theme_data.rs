@@ -0,0 +1,146 @@
+use serde_json::{Map, Value};
+use toml::value::Table;
+
+use crate::aggregate::ChapterEntry;
+use crate::metadata::parse_list;
+
+/// Publishes the metadata of every chapter for custom handlebars themes
+/// (`[preprocessor.metadata.theme-data]`).
+#[derive(Debug, Clone)]
+pub struct ThemeData {
+    pub keys: Option<Vec<String>>,
+    pub list_keys: Vec<String>,
+    pub file: String,
+}
+
+impl ThemeData {
+    pub fn from_config(table: &Table) -> Self {
+        let strings = |key: &str| {
+            table.get(key).and_then(|v| v.as_array()).map(|keys| {
+                keys.iter()
+                    .filter_map(|k| k.as_str().map(String::from))
+                    .collect::<Vec<_>>()
+            })
+        };
+        Self {
+            keys: strings("keys"),
+            list_keys: strings("list-keys").unwrap_or_else(|| vec!["keywords".into()]),
+            file: table
+                .get("file")
+                .and_then(|v| v.as_str())
+                .unwrap_or("theme-data.json")
+                .to_string(),
+        }
+    }
+
+    /// Maps each chapter's path, as in the `{{path}}` template variable, to its
+    /// metadata. Dotted keys become nested objects (`taxonomies.tags` is
+    /// `{{taxonomies.tags}}`), and `[a, b]` values and the `list-keys` become
+    /// arrays for `{{#each}}`. Every chapter has a `title`.
+    pub fn data(&self, chapters: &[ChapterEntry]) -> Map<String, Value> {
+        chapters
+            .iter()
+            .map(|entry| {
+                let mut values = Map::new();
+                values.insert("title".to_string(), entry.title().into());
+                let mut keys: Vec<&String> = entry
+                    .metadata
+                    .keys()
+                    .filter(|k| self.keys.as_ref().map_or(true, |keys| keys.contains(k)))
+                    .collect();
+                keys.sort();
+                for key in keys {
+                    let value = &entry.metadata[key];
+                    let value = if self.list_keys.contains(key) || is_list(value) {
+                        Value::from(parse_list(value))
+                    } else {
+                        Value::from(value.as_str())
+                    };
+                    insert_nested(&mut values, key, value);
+                }
+                (entry.path.display().to_string(), Value::Object(values))
+            })
+            .collect()
+    }
+
+    /// Returns the data file, with the chapters under a `chapters` object.
+    pub fn artifact(&self, chapters: &[ChapterEntry]) -> serde_json::Result<String> {
+        let mut data = Map::new();
+        data.insert("chapters".to_string(), Value::Object(self.data(chapters)));
+        serde_json::to_string_pretty(&data)
+    }
+}
+
+fn is_list(value: &str) -> bool {
+    let value = value.trim();
+    value.starts_with('[') && value.ends_with(']')
+}
+
+/// Inserts `value` under a dotted `key`. A key that is both a value and a
+/// parent (`a` and `a.b`) keeps its value.
+fn insert_nested(object: &mut Map<String, Value>, key: &str, value: Value) {
+    match key.split_once('.') {
+        Some((parent, rest)) if !parent.is_empty() && !rest.is_empty() => {
+            let child = object
+                .entry(parent)
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(child) = child {
+                insert_nested(child, rest, value);
+            }
+        }
+        _ => {
+            object.insert(key.to_string(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::named_entry;
+    use handlebars::Handlebars;
+
+    #[test]
+    fn data_feeds_the_example_partial() {
+        let theme_data = ThemeData::from_config(&Table::new());
+        let chapters = vec![named_entry(
+            "Intro",
+            "guide/intro.md",
+            &[
+                ("author", "Jane"),
+                ("date", "2024-06-01"),
+                ("keywords", "rust, mdbook"),
+                ("taxonomies.level", "beginner"),
+            ],
+        )];
+
+        let data = theme_data.data(&chapters);
+        assert_eq!(
+            data["guide/intro.md"],
+            serde_json::json!({
+                "title": "Intro",
+                "author": "Jane",
+                "date": "2024-06-01",
+                "keywords": ["rust", "mdbook"],
+                "taxonomies": { "level": "beginner" }
+            })
+        );
+
+        let mut handlebars = Handlebars::new();
+        handlebars
+            .register_template_string(
+                "chapter-meta",
+                include_str!("../examples/theme/chapter-meta.hbs"),
+            )
+            .unwrap();
+        let html = handlebars
+            .render(
+                "chapter-meta",
+                &serde_json::json!({ "path": "guide/intro.md", "theme_data": { "chapters": data } }),
+            )
+            .unwrap();
+        assert!(html.contains("By Jane"));
+        assert!(html.contains("<time datetime=\"2024-06-01\">"));
+        assert!(html.contains("<span class=\"meta-badge\">mdbook</span>"));
+    }
+}
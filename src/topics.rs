@@ -0,0 +1,177 @@
+use html_escape::encode_text;
+use mdbook::book::Chapter;
+use mdbook::utils::unique_id_from_content;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+use toml::value::Table;
+
+use crate::aggregate::{generated_chapter, relative_link, Position};
+use crate::book_metadata::BookMetadata;
+use crate::metadata::MetadataError;
+use crate::template;
+
+const DEFAULT_TEMPLATE: &str =
+    "<a class=\"topic topic-{level}\" href=\"#{anchor}\" style=\"font-size: {size}em\">{keyword}</a>";
+
+const DEFAULT_CSS: &str = ".topic-cloud { line-height: 2; text-align: center; }\n\
+                           .topic-cloud .topic { margin: 0 0.4em; white-space: nowrap; }";
+
+/// Configuration of the generated "Topics" chapter, a keyword cloud with the
+/// chapters of every keyword (`[preprocessor.metadata.topics]`).
+#[derive(Debug, Clone)]
+pub struct Topics {
+    pub title: String,
+    pub path: PathBuf,
+    pub position: Position,
+    pub keys: Vec<String>,
+    pub min_size: f64,
+    pub max_size: f64,
+    pub template: String,
+    pub css: String,
+}
+
+impl Topics {
+    pub fn from_config(table: &Table) -> Self {
+        let string = |key: &str, default: &str| {
+            table
+                .get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or(default)
+                .to_string()
+        };
+        let size = |key: &str, default: f64| {
+            table
+                .get(key)
+                .and_then(|v| v.as_float().or_else(|| v.as_integer().map(|i| i as f64)))
+                .filter(|size| *size > 0.0)
+                .unwrap_or(default)
+        };
+        Self {
+            title: string("title", "Topics"),
+            path: string("path", "topics.md").into(),
+            position: table
+                .get("position")
+                .and_then(Position::from_toml)
+                .unwrap_or(Position::End),
+            keys: table
+                .get("keys")
+                .and_then(|v| v.as_array())
+                .map(|keys| {
+                    keys.iter()
+                        .filter_map(|k| k.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_else(|| vec!["keywords".into(), "tags".into()]),
+            min_size: size("min-size", 0.8),
+            max_size: size("max-size", 2.0),
+            template: string("template", DEFAULT_TEMPLATE),
+            css: string("css", DEFAULT_CSS),
+        }
+    }
+
+    /// Builds the "Topics" chapter: a cloud of every keyword of the `keys`,
+    /// sized by the number of chapters using it, then the chapters of each
+    /// keyword. Keywords differing only in case are the same topic.
+    pub fn chapter(&self, chapters: &BookMetadata) -> Result<Chapter, MetadataError> {
+        // Keyed by lowercase keyword, with the first spelling seen
+        let topics = chapters.terms(&self.keys);
+
+        let mut content = format!("# {}\n", self.title);
+        if topics.is_empty() {
+            content.push_str("\nNo topics.\n");
+            return Ok(generated_chapter(&self.title, content, &self.path));
+        }
+
+        // Anchors as mdBook gives them to the headings below
+        let mut ids = HashMap::new();
+        unique_id_from_content(&self.title, &mut ids);
+        let anchors: Vec<String> = topics
+            .values()
+            .map(|(keyword, _)| unique_id_from_content(keyword, &mut ids))
+            .collect();
+
+        let counts = topics.values().map(|(_, entries)| entries.len());
+        let (min, max) = (counts.clone().min().unwrap_or(1), counts.max().unwrap_or(1));
+        if !self.css.trim().is_empty() {
+            let _ = write!(content, "\n<style>\n{}\n</style>\n", self.css.trim());
+        }
+        content.push_str("\n<div class=\"topic-cloud\">\n");
+        for ((keyword, entries), anchor) in topics.values().zip(&anchors) {
+            let weight = if max == min {
+                0.5
+            } else {
+                (entries.len() - min) as f64 / (max - min) as f64
+            };
+            let size = self.min_size + (self.max_size - self.min_size) * weight;
+            let vars = HashMap::from([
+                ("keyword".to_string(), encode_text(keyword).into_owned()),
+                ("anchor".to_string(), anchor.clone()),
+                ("count".to_string(), entries.len().to_string()),
+                ("size".to_string(), format!("{:.2}", size)),
+                (
+                    "level".to_string(),
+                    (1 + (weight * 4.0).round() as usize).to_string(),
+                ),
+            ]);
+            let _ = writeln!(content, "{}", template::render(&self.template, &vars)?);
+        }
+        content.push_str("</div>\n");
+
+        for (keyword, entries) in topics.values() {
+            let _ = write!(content, "\n## {}\n\n", keyword);
+            for entry in entries {
+                let _ = writeln!(
+                    content,
+                    "- [{}]({})",
+                    entry.title(),
+                    relative_link(&self.path, &entry.path)
+                );
+            }
+        }
+
+        Ok(generated_chapter(&self.title, content, &self.path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::named_entry;
+    use crate::aggregate::ChapterEntry;
+
+    fn entry(name: &str, path: &str, keywords: &str) -> ChapterEntry {
+        named_entry(name, path, &[("keywords", keywords)])
+    }
+
+    #[test]
+    fn topics_are_sized_by_frequency() {
+        let topics = Topics::from_config(
+            &toml::from_str(
+                "css = \"\"\ntemplate = \"<a href='#{anchor}' style='font-size: {size}em'>{keyword}</a>\"",
+            )
+            .unwrap(),
+        );
+        let chapters = BookMetadata::new(vec![
+            entry("Retries", "guide/retries.md", "rust, networking"),
+            entry("Queues", "guide/queues.md", "[Rust, async]"),
+            entry("Intro", "intro.md", "rust"),
+        ]);
+
+        let chapter = topics.chapter(&chapters).unwrap();
+
+        assert_eq!(chapter.path, Some(PathBuf::from("topics.md")));
+        assert_eq!(
+            chapter.content,
+            "# Topics\n\n\
+             <div class=\"topic-cloud\">\n\
+             <a href='#async' style='font-size: 0.80em'>async</a>\n\
+             <a href='#networking' style='font-size: 0.80em'>networking</a>\n\
+             <a href='#rust' style='font-size: 2.00em'>rust</a>\n\
+             </div>\n\
+             \n## async\n\n- [Queues](guide/queues.md)\n\
+             \n## networking\n\n- [Retries](guide/retries.md)\n\
+             \n## rust\n\n- [Retries](guide/retries.md)\n- [Queues](guide/queues.md)\n- [Intro](intro.md)\n"
+        );
+    }
+}
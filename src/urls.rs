@@ -0,0 +1,152 @@
+use log::warn;
+use std::path::Path;
+use toml::value::Table;
+
+/// How the URL of a rendered chapter ends (`trailing-slash`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingSlash {
+    #[default]
+    Html, // `guide/intro.html`, as mdBook renders it
+    Always, // `guide/intro/`, for servers mapping directories to pages
+    Never,  // `guide/intro`, for servers resolving extensionless URLs
+}
+
+/// Builds the URLs of chapters for every feature producing them, from the
+/// `site-url`, `path-prefix` and `trailing-slash` options.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Urls {
+    pub site_url: Option<String>,
+    pub path_prefix: String,
+    pub trailing_slash: TrailingSlash,
+}
+
+impl Urls {
+    /// Reads the URL options of the `[preprocessor.metadata]` table.
+    pub fn from_config(table: &Table) -> Self {
+        let trailing_slash = match table.get("trailing-slash").and_then(|v| v.as_str()) {
+            Some("always") => TrailingSlash::Always,
+            Some("never") => TrailingSlash::Never,
+            Some("html") | None => TrailingSlash::Html,
+            Some(other) => {
+                warn!("Unknown trailing-slash '{}', keeping .html URLs", other);
+                TrailingSlash::Html
+            }
+        };
+        let path_prefix = table
+            .get("path-prefix")
+            .and_then(|v| v.as_str())
+            .map(|p| p.trim_matches('/'))
+            .filter(|p| !p.is_empty())
+            .map(|p| format!("/{}", p))
+            .unwrap_or_default();
+        Self {
+            site_url: table
+                .get("site-url")
+                .and_then(|v| v.as_str())
+                .map(|url| url.trim_end_matches('/').to_string()),
+            path_prefix,
+            trailing_slash,
+        }
+    }
+
+    /// Returns the builder with another site URL, e.g. a feature's own.
+    pub fn with_site_url(&self, site_url: &str) -> Self {
+        Self {
+            site_url: Some(site_url.trim_end_matches('/').to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// Returns the absolute path of the page of a chapter, such as
+    /// `/docs/guide/intro.html`. `index.md` chapters are their directory with
+    /// `trailing-slash = "always"` or `"never"`.
+    pub fn path(&self, chapter: &Path) -> String {
+        let page = chapter.with_extension("");
+        let page = page.to_string_lossy().replace('\\', "/");
+        let page = match self.trailing_slash {
+            TrailingSlash::Html => format!("{}.html", page),
+            TrailingSlash::Always | TrailingSlash::Never => {
+                let page = match page.strip_suffix("index") {
+                    Some(dir) if dir.is_empty() || dir.ends_with('/') => dir.trim_end_matches('/'),
+                    _ => page.as_str(),
+                };
+                match (self.trailing_slash, page.is_empty()) {
+                    (_, true) => String::new(),
+                    (TrailingSlash::Always, false) => format!("{}/", page),
+                    _ => page.to_string(),
+                }
+            }
+        };
+        let path = format!("{}/{}", self.path_prefix, page).replace(' ', "%20");
+        match self.trailing_slash {
+            TrailingSlash::Never if path.len() > 1 => path.trim_end_matches('/').to_string(),
+            _ => path,
+        }
+    }
+
+    /// Returns the absolute URL of the page of a chapter, or `None` without a
+    /// `site-url`.
+    pub fn url(&self, chapter: &Path) -> Option<String> {
+        let site_url = self.site_url.as_ref()?;
+        Some(format!("{}{}", site_url, self.path(chapter)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(config: &str) -> Urls {
+        Urls::from_config(&toml::from_str(config).unwrap())
+    }
+
+    #[test]
+    fn path_follows_prefix_and_trailing_slash() {
+        let paths = |urls: &Urls| {
+            ["guide/intro.md", "guide/index.md", "index.md", "my page.md"]
+                .map(|p| urls.path(Path::new(p)))
+        };
+
+        assert_eq!(
+            paths(&urls("")),
+            [
+                "/guide/intro.html",
+                "/guide/index.html",
+                "/index.html",
+                "/my%20page.html"
+            ]
+        );
+        assert_eq!(
+            paths(&urls(
+                "path-prefix = \"docs/\"\ntrailing-slash = \"always\""
+            )),
+            [
+                "/docs/guide/intro/",
+                "/docs/guide/",
+                "/docs/",
+                "/docs/my%20page/"
+            ]
+        );
+        assert_eq!(
+            paths(&urls("path-prefix = \"/docs\"\ntrailing-slash = \"never\"")),
+            [
+                "/docs/guide/intro",
+                "/docs/guide",
+                "/docs",
+                "/docs/my%20page"
+            ]
+        );
+        assert_eq!(paths(&urls("trailing-slash = \"never\""))[2], "/");
+    }
+
+    #[test]
+    fn url_needs_a_site_url() {
+        assert_eq!(urls("").url(Path::new("intro.md")), None);
+        assert_eq!(
+            urls("site-url = \"https://example.com/\"\npath-prefix = \"/docs\"")
+                .url(Path::new("intro.md"))
+                .as_deref(),
+            Some("https://example.com/docs/intro.html")
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use toml::value::Table;
+
+use crate::tags::Tag;
+
+/// The meta tag name of each supported service.
+const SERVICES: [(&str, &str); 5] = [
+    ("google", "google-site-verification"),
+    ("bing", "msvalidate.01"),
+    ("yandex", "yandex-verification"),
+    ("pinterest", "p:domain_verify"),
+    ("facebook", "facebook-domain-verification"),
+];
+
+/// Site verification tags (`[preprocessor.metadata.verification]`).
+#[derive(Debug, Clone)]
+pub struct Verification {
+    pub tags: Vec<Tag>,
+    pub index_only: bool,
+}
+
+impl Verification {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            tags: SERVICES
+                .iter()
+                .filter_map(|(service, name)| {
+                    let token = table.get(*service)?.as_str()?;
+                    Some(Tag::meta(name, token))
+                })
+                .collect(),
+            index_only: table.get("chapters").and_then(|v| v.as_str()) == Some("index"),
+        }
+    }
+
+    /// Returns the tags of a chapter; with `chapters = "index"`, only the first
+    /// chapter of the book, rendered as `index.html`, gets them.
+    pub fn tags(&self, is_index: bool) -> &[Tag] {
+        if self.index_only && !is_index {
+            &[]
+        } else {
+            &self.tags
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::to_html;
+
+    #[test]
+    fn tags_follow_the_chapters_setting() {
+        let table: Table = toml::from_str(
+            r#"
+google = "abc123"
+bing = "DEF456"
+chapters = "index"
+"#,
+        )
+        .unwrap();
+        let verification = Verification::from_config(&table);
+
+        assert_eq!(
+            to_html(verification.tags(true)),
+            "<meta name=\"google-site-verification\" content=\"abc123\">\n\
+             <meta name=\"msvalidate.01\" content=\"DEF456\">\n"
+        );
+        assert!(verification.tags(false).is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+use crate::metadata::MetadataError;
+
+/// The key declaring the metadata conventions a chapter follows.
+pub const META_VERSION_KEY: &str = "meta-version";
+
+/// The latest metadata conventions:
+///
+/// 1. `key: value` lines split on the first `:`, and every key is emitted as
+///    `<meta name>`;
+/// 2. lines split on the first `: `, so keys can contain colons (`og:title`),
+///    and `property-keys` are emitted as `<meta property>`.
+pub const LATEST_META_VERSION: u32 = 2;
+
+/// Returns the version a chapter declares, or `default` when it declares none.
+pub fn chapter_version(
+    metadata: &HashMap<String, String>,
+    default: u32,
+) -> Result<u32, MetadataError> {
+    match metadata.get(META_VERSION_KEY) {
+        None => Ok(default),
+        Some(value) => match value.trim().parse::<u32>() {
+            Ok(version) if (1..=LATEST_META_VERSION).contains(&version) => Ok(version),
+            _ => Err(MetadataError::InvalidValue {
+                key: META_VERSION_KEY.to_string(),
+                message: format!(
+                    "'{}', expected a version from 1 to {}",
+                    value, LATEST_META_VERSION
+                ),
+            }),
+        },
+    }
+}
+
+/// Applies the version 1 parsing rules to keys parsed with the latest ones:
+/// `og:title: Intro` was read as the key `og` with the value `title: Intro`.
+pub fn legacy_keys(metadata: HashMap<String, String>) -> HashMap<String, String> {
+    metadata
+        .into_iter()
+        .map(|(key, value)| match key.split_once(':') {
+            Some((legacy_key, rest)) if key != META_VERSION_KEY => (
+                legacy_key.trim().to_string(),
+                format!("{}: {}", rest.trim(), value),
+            ),
+            _ => (key, value),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::metadata;
+
+    #[test]
+    fn chapter_version_validates_the_key() {
+        assert_eq!(chapter_version(&metadata(&[]), 1).unwrap(), 1);
+        assert_eq!(
+            chapter_version(&metadata(&[("meta-version", "2")]), 1).unwrap(),
+            2
+        );
+        assert!(chapter_version(&metadata(&[("meta-version", "3")]), 2).is_err());
+        assert!(chapter_version(&metadata(&[("meta-version", "v1")]), 2).is_err());
+    }
+
+    #[test]
+    fn legacy_keys_split_on_the_first_colon() {
+        assert_eq!(
+            legacy_keys(metadata(&[
+                ("og:title", "Intro"),
+                ("author", "Jane"),
+                ("meta-version", "1"),
+            ])),
+            metadata(&[
+                ("og", "title: Intro"),
+                ("author", "Jane"),
+                ("meta-version", "1"),
+            ])
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use log::warn;
+use std::collections::HashMap;
+
+use crate::dates::parse_date;
+use crate::metadata::{parse_list, MetadataError};
+use crate::tags::{self, Tag};
+
+/// How the values of a vocabulary key are checked and emitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+    /// Emitted as it is.
+    Text,
+    /// An absolute `http(s)` URL.
+    Url,
+    /// A date, emitted in the given `chrono` format.
+    Date(&'static str),
+    /// A list, emitted as one tag per item.
+    Repeated,
+}
+
+/// A bundled set of keys for a standard use case (`vocab = ["seo"]`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Vocabulary {
+    pub name: &'static str,
+    pub keys: &'static [(&'static str, KeyType)],
+    /// Links emitted on chapters using the vocabulary, `(rel, href)`.
+    pub links: &'static [(&'static str, &'static str)],
+}
+
+const VOCABULARIES: &[Vocabulary] = &[
+    Vocabulary {
+        name: "seo",
+        keys: &[
+            ("title", KeyType::Text),
+            ("description", KeyType::Text),
+            ("keywords", KeyType::Text),
+            ("author", KeyType::Text),
+            ("robots", KeyType::Text),
+            ("googlebot", KeyType::Text),
+            ("theme-color", KeyType::Text),
+        ],
+        links: &[],
+    },
+    Vocabulary {
+        name: "opengraph",
+        keys: &[
+            ("og:title", KeyType::Text),
+            ("og:description", KeyType::Text),
+            ("og:type", KeyType::Text),
+            ("og:url", KeyType::Url),
+            ("og:image", KeyType::Url),
+            ("og:image:alt", KeyType::Text),
+            ("og:site_name", KeyType::Text),
+            ("og:locale", KeyType::Text),
+            ("article:published_time", KeyType::Date("%Y-%m-%d")),
+            ("article:modified_time", KeyType::Date("%Y-%m-%d")),
+            ("article:author", KeyType::Repeated),
+            ("article:section", KeyType::Text),
+            ("article:tag", KeyType::Repeated),
+        ],
+        links: &[],
+    },
+    Vocabulary {
+        name: "dublin-core",
+        keys: &[
+            ("DC.title", KeyType::Text),
+            ("DC.creator", KeyType::Repeated),
+            ("DC.subject", KeyType::Repeated),
+            ("DC.description", KeyType::Text),
+            ("DC.publisher", KeyType::Text),
+            ("DC.contributor", KeyType::Repeated),
+            ("DC.date", KeyType::Date("%Y-%m-%d")),
+            ("DC.type", KeyType::Text),
+            ("DC.format", KeyType::Text),
+            ("DC.identifier", KeyType::Text),
+            ("DC.language", KeyType::Text),
+            ("DC.rights", KeyType::Text),
+        ],
+        links: &[("schema.DC", "http://purl.org/dc/elements/1.1/")],
+    },
+    Vocabulary {
+        name: "scholar",
+        keys: &[
+            ("citation_title", KeyType::Text),
+            ("citation_author", KeyType::Repeated),
+            ("citation_publication_date", KeyType::Date("%Y/%m/%d")),
+            ("citation_journal_title", KeyType::Text),
+            ("citation_conference_title", KeyType::Text),
+            ("citation_volume", KeyType::Text),
+            ("citation_issue", KeyType::Text),
+            ("citation_firstpage", KeyType::Text),
+            ("citation_lastpage", KeyType::Text),
+            ("citation_doi", KeyType::Text),
+            ("citation_pdf_url", KeyType::Url),
+            ("citation_abstract_html_url", KeyType::Url),
+        ],
+        links: &[],
+    },
+];
+
+/// The vocabularies selected with `vocab`.
+#[derive(Debug, Clone, Default)]
+pub struct Vocabularies {
+    pub selected: Vec<Vocabulary>,
+}
+
+impl Vocabularies {
+    /// Selects vocabularies by name, skipping unknown ones with a warning.
+    pub fn from_names(names: &[String]) -> Self {
+        let selected = names
+            .iter()
+            .filter_map(|name| {
+                let vocabulary = VOCABULARIES.iter().find(|v| v.name == name.as_str());
+                if vocabulary.is_none() {
+                    let known: Vec<&str> = VOCABULARIES.iter().map(|v| v.name).collect();
+                    warn!(
+                        "Ignoring unknown vocabulary '{}', expected one of {}",
+                        name,
+                        known.join(", ")
+                    );
+                }
+                vocabulary.copied()
+            })
+            .collect();
+        Self { selected }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.selected.is_empty()
+    }
+
+    /// The keys of every selected vocabulary, to add to `valid-tags`.
+    pub fn keys(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.selected
+            .iter()
+            .flat_map(|v| v.keys.iter().map(|(key, _)| *key))
+    }
+
+    fn key_type(&self, key: &str) -> Option<KeyType> {
+        self.selected
+            .iter()
+            .flat_map(|v| v.keys.iter())
+            .find(|(k, _)| *k == key)
+            .map(|(_, key_type)| *key_type)
+    }
+
+    /// Checks and normalizes the vocabulary keys of a chapter's metadata.
+    /// Dates are rewritten in the vocabulary's format; repeated keys are taken
+    /// out and returned as one tag per item, as `<meta property>` when they
+    /// match `property_keys`. Links of the vocabularies in use come first.
+    pub fn apply(
+        &self,
+        metadata: &mut HashMap<String, String>,
+        property_keys: &[String],
+    ) -> (Vec<Tag>, Vec<MetadataError>) {
+        let mut emitted = Vec::new();
+        let mut errors = Vec::new();
+        for vocabulary in &self.selected {
+            if vocabulary
+                .keys
+                .iter()
+                .any(|(key, _)| metadata.contains_key(*key))
+            {
+                emitted.extend(
+                    vocabulary
+                        .links
+                        .iter()
+                        .map(|(rel, href)| Tag::link(rel, href)),
+                );
+            }
+        }
+
+        let mut keys: Vec<String> = metadata.keys().cloned().collect();
+        keys.sort();
+        for key in keys {
+            let Some(key_type) = self.key_type(&key) else {
+                continue;
+            };
+            let value = metadata[&key].trim().to_string();
+            match key_type {
+                KeyType::Text => {}
+                KeyType::Url => {
+                    if !value.starts_with("https://") && !value.starts_with("http://") {
+                        metadata.remove(&key);
+                        errors.push(invalid(&key, &format!("'{}' isn't an absolute URL", value)));
+                    }
+                }
+                KeyType::Date(format) => match parse_date(&value) {
+                    Some(date) => {
+                        metadata.insert(key, date.format(format).to_string());
+                    }
+                    None => {
+                        metadata.remove(&key);
+                        errors.push(invalid(&key, &format!("'{}' isn't a date", value)));
+                    }
+                },
+                KeyType::Repeated => {
+                    metadata.remove(&key);
+                    for item in parse_list(&value) {
+                        emitted.push(if tags::matches_key(property_keys, &key) {
+                            Tag::property(&key, &item)
+                        } else {
+                            Tag::meta(&key, &item)
+                        });
+                    }
+                }
+            }
+        }
+        (emitted, errors)
+    }
+}
+
+fn invalid(key: &str, message: &str) -> MetadataError {
+    MetadataError::InvalidValue {
+        key: key.to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vocabulary_keys_are_checked_and_emitted() {
+        let vocab = Vocabularies::from_names(&["scholar".into(), "dublin-core".into()]);
+        let mut metadata = HashMap::from([
+            ("citation_title".to_string(), "Retries".to_string()),
+            (
+                "citation_author".to_string(),
+                "[Jane Doe, Richard Roe]".to_string(),
+            ),
+            (
+                "citation_publication_date".to_string(),
+                "2024-06-01".to_string(),
+            ),
+            ("citation_pdf_url".to_string(), "paper.pdf".to_string()),
+            ("DC.date".to_string(), "June 1".to_string()),
+        ]);
+
+        let (emitted, errors) = vocab.apply(&mut metadata, &[]);
+
+        assert_eq!(
+            tags::to_html(&emitted),
+            "<link rel=\"schema.DC\" href=\"http://purl.org/dc/elements/1.1/\">\n\
+             <meta name=\"citation_author\" content=\"Jane Doe\">\n\
+             <meta name=\"citation_author\" content=\"Richard Roe\">\n"
+        );
+        assert_eq!(
+            metadata,
+            HashMap::from([
+                ("citation_title".to_string(), "Retries".to_string()),
+                (
+                    "citation_publication_date".to_string(),
+                    "2024/06/01".to_string()
+                ),
+            ])
+        );
+        assert_eq!(errors.len(), 2);
+        assert!(vocab.keys().any(|k| k == "DC.creator"));
+    }
+}
@@ -0,0 +1,415 @@
+use anyhow::Result;
+use mdbook::book::Chapter;
+use serde_json::{json, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::aggregate::{resolve_references, ChapterEntry};
+use crate::metadata::{key_line, located_metadata_errors, parse_metadata};
+use crate::profiles::{self, Profile};
+use crate::sources::{chapter_files, chapter_name, load_config};
+use crate::versions::META_VERSION_KEY;
+
+/// Keys listing other chapters, checked by [`Validator::check`].
+const REFERENCE_KEYS: [&str; 2] = ["prerequisites", "see-also"];
+
+/// A validation problem of a chapter, with the line it was found on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+/// How diagnostics are printed: `file:line: message` lines, GitHub Actions
+/// workflow commands, or a GitLab code quality report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    Human,
+    Github,
+    Gitlab,
+}
+
+impl ErrorFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "human" => Some(ErrorFormat::Human),
+            "github" => Some(ErrorFormat::Github),
+            "gitlab" => Some(ErrorFormat::Gitlab),
+            _ => None,
+        }
+    }
+}
+
+/// Validates the chapters of a book on disk, for the `check` and `watch`
+/// subcommands.
+#[derive(Debug, Clone)]
+pub struct Validator {
+    pub src_dir: PathBuf,
+    pub book_src: PathBuf,
+    pub valid_tags: Option<Vec<String>>,
+    pub profiles: Vec<Profile>,
+}
+
+impl Validator {
+    /// Reads the `src` directory, `valid-tags` and `profiles` from the book's
+    /// `book.toml`.
+    pub fn load(book_dir: &Path) -> Result<Self> {
+        let (config, src_dir) = load_config(book_dir)?;
+        let profiles = config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("profiles"))
+            .map(profiles::profiles_from_config)
+            .unwrap_or_default();
+        let valid_tags = config
+            .get_preprocessor("metadata")
+            .and_then(|p| p.get("valid-tags"))
+            .and_then(|v| v.as_array())
+            .map(|tags| {
+                tags.iter()
+                    .filter_map(|t| t.as_str().map(String::from))
+                    .collect()
+            });
+        Ok(Self {
+            src_dir,
+            book_src: config.book.src,
+            valid_tags,
+            profiles,
+        })
+    }
+
+    /// Returns the diagnostics of a single chapter's content.
+    pub fn validate(&self, content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics: Vec<Diagnostic> = located_metadata_errors(content)
+            .into_iter()
+            .map(|(line, e)| Diagnostic {
+                line: Some(line),
+                message: e.to_string(),
+            })
+            .collect();
+        if let (Ok((metadata, _)), Some(valid_tags)) =
+            (parse_metadata(content, true), &self.valid_tags)
+        {
+            let mut invalid: Vec<&String> = metadata
+                .keys()
+                .filter(|k| !valid_tags.contains(k) && k.as_str() != META_VERSION_KEY)
+                .collect();
+            invalid.sort();
+            for key in invalid {
+                diagnostics.push(Diagnostic {
+                    line: key_line(content, key),
+                    message: format!("Key '{}' isn't in valid-tags", key),
+                });
+            }
+        }
+        diagnostics
+    }
+
+    /// Like [`Validator::validate`], also checking the chapter file at `path`
+    /// against the profiles matching it.
+    pub fn validate_file(&self, path: &Path, content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = self.validate(content);
+        if let Ok((metadata, _)) = parse_metadata(content, true) {
+            let path = self.book_src.join(chapter_name(&self.src_dir, path));
+            diagnostics.extend(
+                profiles::check(&self.profiles, &path, &metadata)
+                    .into_iter()
+                    .map(|e| Diagnostic {
+                        line: None,
+                        message: e.to_string(),
+                    }),
+            );
+        }
+        diagnostics
+    }
+
+    /// Validates every chapter once, returning the chapters with problems.
+    /// Unlike [`Validator::validate_file`], also reports the references to
+    /// chapters that don't exist.
+    pub fn check(&self) -> Vec<(PathBuf, Vec<Diagnostic>)> {
+        let files: Vec<(PathBuf, std::io::Result<String>)> = chapter_files(&self.src_dir)
+            .into_iter()
+            .map(|path| {
+                let content = fs::read_to_string(&path);
+                (path, content)
+            })
+            .collect();
+        let chapters: Vec<ChapterEntry> = files
+            .iter()
+            .map(|(path, content)| {
+                let name = chapter_name(&self.src_dir, path);
+                let metadata = content
+                    .as_ref()
+                    .ok()
+                    .and_then(|content| parse_metadata(content, true).ok())
+                    .map(|(metadata, _)| metadata)
+                    .unwrap_or_default();
+                let chapter = Chapter::new(&name, String::new(), &name, Vec::new());
+                ChapterEntry::new(&chapter, Path::new(&name), metadata)
+            })
+            .collect();
+
+        files
+            .iter()
+            .zip(&chapters)
+            .filter_map(|((path, content), entry)| {
+                let diagnostics = match content {
+                    Ok(content) => {
+                        let mut diagnostics = self.validate_file(path, content);
+                        for key in REFERENCE_KEYS {
+                            let Err(errors) = resolve_references(entry, key, &chapters) else {
+                                continue;
+                            };
+                            diagnostics.extend(errors.into_iter().map(|e| Diagnostic {
+                                line: key_line(content, key),
+                                message: e.to_string(),
+                            }));
+                        }
+                        diagnostics
+                    }
+                    Err(e) => vec![Diagnostic {
+                        line: None,
+                        message: format!("Failed to read: {}", e),
+                    }],
+                };
+                (!diagnostics.is_empty()).then(|| (path.clone(), diagnostics))
+            })
+            .collect()
+    }
+
+    /// Returns the modification time of every chapter file under `src`.
+    fn scan(&self) -> BTreeMap<PathBuf, SystemTime> {
+        chapter_files(&self.src_dir)
+            .into_iter()
+            .filter_map(|path| {
+                let modified = fs::metadata(&path).ok()?.modified().ok()?;
+                Some((path, modified))
+            })
+            .collect()
+    }
+
+    fn report(&self, path: &Path, format: ErrorFormat) {
+        let diagnostics = match fs::read_to_string(path) {
+            Ok(content) => self.validate_file(path, &content),
+            Err(e) => vec![Diagnostic {
+                line: None,
+                message: format!("Failed to read: {}", e),
+            }],
+        };
+        if diagnostics.is_empty() && format == ErrorFormat::Human {
+            println!("{}: ok", chapter_name(&self.src_dir, path));
+        }
+        print!("{}", self.render(path, &diagnostics, format));
+    }
+
+    /// Renders the diagnostics of a chapter as lines. GitLab reports are
+    /// rendered by [`Validator::gitlab_report`] instead.
+    pub fn render(&self, path: &Path, diagnostics: &[Diagnostic], format: ErrorFormat) -> String {
+        let name = match format {
+            ErrorFormat::Human => chapter_name(&self.src_dir, path),
+            _ => display_path(path),
+        };
+        diagnostics
+            .iter()
+            .map(|d| match (format, d.line) {
+                (ErrorFormat::Github, Some(line)) => format!(
+                    "::error file={},line={}::{}\n",
+                    name,
+                    line,
+                    escape_github(&d.message)
+                ),
+                (ErrorFormat::Github, None) => {
+                    format!("::error file={}::{}\n", name, escape_github(&d.message))
+                }
+                (_, Some(line)) => format!("{}:{}: {}\n", name, line, d.message),
+                (_, None) => format!("{}: {}\n", name, d.message),
+            })
+            .collect()
+    }
+
+    /// Builds a GitLab code quality report of the diagnostics of every chapter.
+    pub fn gitlab_report(&self, results: &[(PathBuf, Vec<Diagnostic>)]) -> Value {
+        let issues: Vec<Value> = results
+            .iter()
+            .flat_map(|(path, diagnostics)| {
+                let path = display_path(path);
+                diagnostics.iter().map(move |d| {
+                    let mut hasher = DefaultHasher::new();
+                    (&path, d.line, &d.message).hash(&mut hasher);
+                    json!({
+                        "description": d.message,
+                        "check_name": "mdbook-metadata",
+                        "fingerprint": format!("{:016x}", hasher.finish()),
+                        "severity": "major",
+                        "location": { "path": path, "lines": { "begin": d.line.unwrap_or(1) } },
+                    })
+                })
+            })
+            .collect();
+        Value::Array(issues)
+    }
+
+    /// Validates every chapter, then polls `src` and revalidates the files that
+    /// change. Runs until interrupted.
+    pub fn watch(&self, interval: Duration, format: ErrorFormat) -> ! {
+        let mut files = self.scan();
+        for path in files.keys() {
+            self.report(path, format);
+        }
+        println!(
+            "Watching {} chapters in '{}'",
+            files.len(),
+            self.src_dir.display()
+        );
+
+        loop {
+            thread::sleep(interval);
+            let current = self.scan();
+            for (path, modified) in &current {
+                if files.get(path) != Some(modified) {
+                    self.report(path, format);
+                }
+            }
+            for path in files.keys().filter(|p| !current.contains_key(*p)) {
+                println!("{}: removed", chapter_name(&self.src_dir, path));
+            }
+            files = current;
+        }
+    }
+}
+
+/// Displays a path relative to the working directory without a `./` prefix.
+fn display_path(path: &Path) -> String {
+    path.strip_prefix(".")
+        .unwrap_or(path)
+        .display()
+        .to_string()
+        .replace('\\', "/")
+}
+
+/// Escapes a message for a GitHub Actions workflow command.
+fn escape_github(message: &str) -> String {
+    message
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_reports_malformed_lines_and_invalid_keys() {
+        let validator = Validator {
+            src_dir: PathBuf::from("src"),
+            book_src: PathBuf::from("src"),
+            valid_tags: Some(vec!["title".to_string()]),
+            profiles: Vec::new(),
+        };
+
+        assert_eq!(
+            validator.validate("---\ntitle: Intro\nnot a pair\nstatus: draft\n---\n# Intro"),
+            vec![
+                Diagnostic {
+                    line: Some(3),
+                    message: "Improperly formatted metadata line: 'not a pair'".to_string(),
+                },
+                Diagnostic {
+                    line: Some(4),
+                    message: "Key 'status' isn't in valid-tags".to_string(),
+                },
+            ]
+        );
+        assert!(validator.validate("# No metadata").is_empty());
+        assert_eq!(
+            validator.validate("\n+++\ntitle = \"Intro\"\nbroken =\n+++\n")[0].line,
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn check_reports_unresolved_references() {
+        let dir =
+            std::env::temp_dir().join(format!("mdbook-metadata-check-{}", std::process::id()));
+        let src_dir = dir.join("src");
+        fs::create_dir_all(&src_dir).unwrap();
+        fs::write(src_dir.join("basics.md"), "# Basics").unwrap();
+        fs::write(
+            src_dir.join("tuning.md"),
+            "---\ntitle: Tuning\nprerequisites: [basics, install]\nsee-also: [faq]\n---\n# Tuning",
+        )
+        .unwrap();
+        let validator = Validator {
+            src_dir: src_dir.clone(),
+            book_src: PathBuf::from("src"),
+            valid_tags: None,
+            profiles: Vec::new(),
+        };
+
+        let problems = validator.check();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            problems,
+            vec![(
+                src_dir.join("tuning.md"),
+                vec![
+                    Diagnostic {
+                        line: Some(3),
+                        message: "Chapter 'tuning.md' references unknown chapter 'install' in \
+                                  'prerequisites'"
+                            .to_string(),
+                    },
+                    Diagnostic {
+                        line: Some(4),
+                        message: "Chapter 'tuning.md' references unknown chapter 'faq' in \
+                                  'see-also'"
+                            .to_string(),
+                    },
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn render_formats_ci_annotations() {
+        let validator = Validator {
+            src_dir: PathBuf::from("./src"),
+            book_src: PathBuf::from("src"),
+            valid_tags: None,
+            profiles: Vec::new(),
+        };
+        let path = PathBuf::from("./src/guide/intro.md");
+        let diagnostics = vec![
+            Diagnostic {
+                line: Some(3),
+                message: "Key 'status' isn't in valid-tags".to_string(),
+            },
+            Diagnostic {
+                line: None,
+                message: "50% done\nmore".to_string(),
+            },
+        ];
+
+        assert_eq!(
+            validator.render(&path, &diagnostics, ErrorFormat::Human),
+            "guide/intro.md:3: Key 'status' isn't in valid-tags\nguide/intro.md: 50% done\nmore\n"
+        );
+        assert_eq!(
+            validator.render(&path, &diagnostics, ErrorFormat::Github),
+            "::error file=src/guide/intro.md,line=3::Key 'status' isn't in valid-tags\n\
+             ::error file=src/guide/intro.md::50%25 done%0Amore\n"
+        );
+        let report = validator.gitlab_report(&[(path, diagnostics)]);
+        assert_eq!(
+            report[0]["location"],
+            json!({ "path": "src/guide/intro.md", "lines": { "begin": 3 } })
+        );
+        assert_eq!(report[1]["location"]["lines"]["begin"], 1);
+    }
+}
@@ -0,0 +1,184 @@
+use chrono::NaiveDate;
+use mdbook::book::Chapter;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use toml::value::Table;
+
+use crate::aggregate::{generated_chapter, relative_link, ChapterEntry, Position};
+use crate::dates::{git_dates, parse_date};
+
+/// How a chapter changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Updated,
+}
+
+/// Configuration of the generated "What's new" chapter
+/// (`[preprocessor.metadata.whats-new]`).
+#[derive(Debug, Clone)]
+pub struct WhatsNew {
+    pub title: String,
+    pub path: PathBuf,
+    pub position: Position,
+    pub limit: usize,
+    pub git_dates: bool,
+}
+
+impl WhatsNew {
+    pub fn from_config(table: &Table) -> Self {
+        Self {
+            title: table
+                .get("title")
+                .and_then(|v| v.as_str())
+                .unwrap_or("What's new")
+                .to_string(),
+            path: table
+                .get("path")
+                .and_then(|v| v.as_str())
+                .unwrap_or("whats-new.md")
+                .into(),
+            position: table
+                .get("position")
+                .and_then(Position::from_toml)
+                .unwrap_or(Position::End),
+            limit: table
+                .get("limit")
+                .and_then(|v| v.as_integer())
+                .map(|l| l.max(0) as usize)
+                .unwrap_or(20),
+            git_dates: table
+                .get("git-dates")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+        }
+    }
+
+    /// Builds the chapter listing the most recent changes, grouped by month.
+    pub fn chapter(&self, chapters: &[ChapterEntry], src_dir: &Path) -> Chapter {
+        let mut changes: Vec<(NaiveDate, Change, &ChapterEntry)> = chapters
+            .iter()
+            .filter_map(|entry| {
+                self.change_for(entry, src_dir)
+                    .map(|(date, change)| (date, change, entry))
+            })
+            .collect();
+        changes.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.2.title().cmp(b.2.title())));
+        if self.limit > 0 {
+            changes.truncate(self.limit);
+        }
+
+        let mut content = format!("# {}\n", self.title);
+        let mut current_month = String::new();
+        for (date, change, entry) in changes {
+            let month = date.format("%B %Y").to_string();
+            if month != current_month {
+                let _ = write!(content, "\n## {}\n\n", month);
+                current_month = month;
+            }
+            let label = match change {
+                Change::Added => "added",
+                Change::Updated => "updated",
+            };
+            let _ = writeln!(
+                content,
+                "- {} — [{}]({}) *({})*",
+                date.format("%Y-%m-%d"),
+                entry.title(),
+                relative_link(&self.path, &entry.path),
+                label
+            );
+        }
+
+        generated_chapter(&self.title, content, &self.path)
+    }
+
+    fn change_for(&self, entry: &ChapterEntry, src_dir: &Path) -> Option<(NaiveDate, Change)> {
+        let date = entry.metadata.get("date").and_then(|d| parse_date(d));
+        let updated = entry.metadata.get("updated").and_then(|u| parse_date(u));
+
+        match (date, updated) {
+            (_, Some(updated)) if Some(updated) != date => Some((updated, Change::Updated)),
+            (Some(date), _) => Some((date, Change::Added)),
+            _ if self.git_dates => {
+                let dates = git_dates(src_dir, entry.source_path.as_ref()?)?;
+                if dates.created == dates.modified {
+                    Some((dates.created, Change::Added))
+                } else {
+                    Some((dates.modified, Change::Updated))
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::named_entry;
+
+    #[test]
+    fn whats_new_groups_changes_by_month() {
+        let whats_new = WhatsNew::from_config(&Table::new());
+        let chapters = vec![
+            named_entry("Intro", "intro.md", &[("date", "2024-05-03")]),
+            named_entry(
+                "Guide",
+                "guide/setup.md",
+                &[
+                    ("title", "Setup"),
+                    ("date", "2024-01-01"),
+                    ("updated", "2024-06-10"),
+                ],
+            ),
+            named_entry("Undated", "undated.md", &[]),
+        ];
+
+        let chapter = whats_new.chapter(&chapters, Path::new("."));
+
+        assert_eq!(chapter.path, Some(PathBuf::from("whats-new.md")));
+        assert_eq!(chapter.source_path, None);
+        assert_eq!(
+            chapter.content,
+            "# What's new\n\
+             \n## June 2024\n\n\
+             - 2024-06-10 — [Setup](guide/setup.md) *(updated)*\n\
+             \n## May 2024\n\n\
+             - 2024-05-03 — [Intro](intro.md) *(added)*\n"
+        );
+    }
+
+    #[test]
+    fn whats_new_respects_limit() {
+        let mut table = Table::new();
+        table.insert("limit".to_string(), toml::Value::Integer(1));
+        let whats_new = WhatsNew::from_config(&table);
+        let chapters = vec![
+            named_entry("Old", "old.md", &[("date", "2023-01-01")]),
+            named_entry("New", "new.md", &[("date", "2024-01-01")]),
+        ];
+
+        let chapter = whats_new.chapter(&chapters, Path::new("."));
+
+        assert!(chapter.content.contains("[New](new.md)"));
+        assert!(!chapter.content.contains("[Old](old.md)"));
+    }
+
+    #[test]
+    fn whats_new_page_snapshot() {
+        let whats_new = WhatsNew::from_config(&Table::new());
+        let chapters = vec![
+            named_entry("Intro", "intro.md", &[("date", "2024-05-03")]),
+            named_entry(
+                "Setup",
+                "guide/setup.md",
+                &[("date", "2024-01-01"), ("updated", "2024-06-10")],
+            ),
+            named_entry("Usage", "guide/usage.md", &[("date", "2024-06-02")]),
+            named_entry("Old", "old.md", &[("date", "2023-12-24")]),
+        ];
+
+        insta::assert_snapshot!(whats_new.chapter(&chapters, Path::new(".")).content);
+    }
+}
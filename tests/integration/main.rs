@@ -0,0 +1,94 @@
+//! Runs the preprocessor binary over the fixture books in `fixtures/`, feeding
+//! it the same JSON input mdbook sends, and compares the processed book with
+//! its insta snapshot in `snapshots/`.
+
+use mdbook::book::{Book, BookItem};
+use mdbook::MDBook;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+fn fixture_dir(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/integration/fixtures")
+        .join(name)
+}
+
+/// Pipes the fixture book through the preprocessor, as `mdbook build` would.
+fn preprocess(root: &Path) -> Book {
+    let md = MDBook::load(root).expect("Failed to load fixture book");
+    let ctx = serde_json::json!({
+        "root": root,
+        "config": md.config,
+        "renderer": "html",
+        "mdbook_version": mdbook::MDBOOK_VERSION,
+    });
+    let input = serde_json::to_vec(&serde_json::json!([ctx, md.book])).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mdbook-metadata"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to run the preprocessor");
+    child.stdin.take().unwrap().write_all(&input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(
+        output.status.success(),
+        "Preprocessor failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).expect("Failed to parse the processed book")
+}
+
+/// Renders the book structure and every chapter's content as plain text.
+fn snapshot(book: &Book) -> String {
+    fn write_items(out: &mut String, items: &[BookItem], depth: usize) {
+        for item in items {
+            match item {
+                BookItem::Chapter(chap) => {
+                    let path = chap
+                        .path
+                        .as_ref()
+                        .map(|p| p.display().to_string().replace('\\', "/"))
+                        .unwrap_or_else(|| "draft".to_string());
+                    let _ = writeln!(out, "{}=== {} ({})", "  ".repeat(depth), chap.name, path);
+                    out.push_str(&chap.content);
+                    if !chap.content.ends_with('\n') {
+                        out.push('\n');
+                    }
+                    write_items(out, &chap.sub_items, depth + 1);
+                }
+                BookItem::PartTitle(title) => {
+                    let _ = writeln!(out, "### {}", title);
+                }
+                BookItem::Separator => out.push_str("---\n"),
+            }
+        }
+    }
+
+    let mut out = String::new();
+    write_items(&mut out, &book.sections, 0);
+    out
+}
+
+fn check(name: &str) {
+    let book = preprocess(&fixture_dir(name));
+    insta::assert_snapshot!(name, snapshot(&book));
+}
+
+#[test]
+fn basic() {
+    check("basic");
+}
+
+#[test]
+fn generated() {
+    check("generated");
+}
+
+#[test]
+fn links() {
+    check("links");
+}